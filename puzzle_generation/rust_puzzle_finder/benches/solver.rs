@@ -0,0 +1,166 @@
+//! Solver and search benchmarks.
+//!
+//! Run with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline before` then `cargo bench -- --baseline before`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+use rayon::prelude::*;
+
+use puzzle_finder::{
+    all_tiles, cyclic_latin_square, puzzle_constraints, solve_constraints, CombinationIterator, Constraints, Kernel,
+    PropagationThreshold, SolveHooks, SolverOptions,
+};
+
+/// Same seeded random combinations of `placed` tiles on every run.
+fn random_combinations(size: usize, placed: usize, count: usize) -> Vec<Vec<(usize, usize)>> {
+    let tiles = all_tiles(size);
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..count)
+        .map(|_| {
+            let mut combination: Vec<_> = tiles.choose_multiple(&mut rng, placed).cloned().collect();
+            combination.sort();
+            combination
+        })
+        .collect()
+}
+
+/// Check whether the puzzle has exactly one solution, as the search does.
+fn check_uniqueness(constraints: &Constraints) -> usize {
+    check_uniqueness_with(constraints, &SolverOptions::default())
+}
+
+fn check_uniqueness_with(constraints: &Constraints, options: &SolverOptions) -> usize {
+    solve_constraints(constraints, Some(2), options, SolveHooks::default()).0.len()
+}
+
+/// Complete a square without any clue.
+fn empty_square(c: &mut Criterion) {
+    let mut group = c.benchmark_group("empty_square");
+    for size in [5, 7, 9] {
+        let constraints = Constraints::new(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &constraints, |b, constraints| {
+            b.iter(|| {
+                solve_constraints(black_box(constraints), Some(1), &SolverOptions::default(), SolveHooks::default())
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Uniqueness checks of puzzles known to have a single solution.
+fn unique_puzzles(c: &mut Criterion) {
+    let puzzles: [(usize, &[(usize, usize)]); 2] = [
+        (5, &[(0, 0), (0, 1), (1, 0), (2, 2)]),
+        (6, &[(0, 0), (0, 1), (1, 0), (3, 5), (4, 4), (4, 5)]),
+    ];
+    let mut group = c.benchmark_group("unique_puzzle");
+    for (size, tiles) in puzzles {
+        let constraints = puzzle_constraints(tiles, &cyclic_latin_square(size), &all_tiles(size));
+        assert_eq!(check_uniqueness(&constraints), 1);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &constraints, |b, constraints| {
+            b.iter(|| check_uniqueness(black_box(constraints)))
+        });
+    }
+    group.finish();
+}
+
+/// Uniqueness checks of 100 random combinations of N tiles, the clue count
+/// where unique puzzles start to appear.
+fn random_puzzles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_puzzles");
+    group.sample_size(20);
+    for size in 5..=9 {
+        let grid = cyclic_latin_square(size);
+        let tiles = all_tiles(size);
+        let puzzles: Vec<Constraints> = random_combinations(size, size, 100)
+            .iter()
+            .map(|combination| puzzle_constraints(combination, &grid, &tiles))
+            .collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &puzzles, |b, puzzles| {
+            b.iter(|| puzzles.iter().map(check_uniqueness).sum::<usize>())
+        });
+    }
+    group.finish();
+}
+
+/// The random combinations of `random_puzzles` checked with propagation
+/// after every decision and only once half the cells are filled, the
+/// measurements behind `auto_propagation_threshold`.
+fn propagation_thresholds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("propagation_threshold");
+    group.sample_size(20);
+    for size in 5..=9 {
+        let grid = cyclic_latin_square(size);
+        let tiles = all_tiles(size);
+        let puzzles: Vec<Constraints> = random_combinations(size, size, 100)
+            .iter()
+            .map(|combination| puzzle_constraints(combination, &grid, &tiles))
+            .collect();
+        for threshold in [50, 100].map(PropagationThreshold::Percent) {
+            let options = SolverOptions::builder().propagation_threshold(threshold).build();
+            let id = BenchmarkId::new(format!("{}x{}", size, size), threshold);
+            group.bench_with_input(id, &puzzles, |b, puzzles| {
+                b.iter(|| puzzles.iter().map(|constraints| check_uniqueness_with(constraints, &options)).sum::<usize>())
+            });
+        }
+    }
+    group.finish();
+}
+
+/// The random combinations of `random_puzzles` checked with the general
+/// kernel and with the kernel specialized for their size.
+fn kernels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kernel");
+    group.sample_size(20);
+    for size in 5..=9 {
+        let grid = cyclic_latin_square(size);
+        let tiles = all_tiles(size);
+        let puzzles: Vec<Constraints> = random_combinations(size, size, 100)
+            .iter()
+            .map(|combination| puzzle_constraints(combination, &grid, &tiles))
+            .collect();
+        for (name, kernel) in [("general", Kernel::General), ("specialized", Kernel::Specialized)] {
+            let options = SolverOptions::builder().kernel(kernel).build();
+            let id = BenchmarkId::new(format!("{}x{}", size, size), name);
+            group.bench_with_input(id, &puzzles, |b, puzzles| {
+                b.iter(|| puzzles.iter().map(|constraints| check_uniqueness_with(constraints, &options)).sum::<usize>())
+            });
+        }
+    }
+    group.finish();
+}
+
+/// A batch of 10k combinations checked in parallel, like one batch of the
+/// exhaustive search.
+fn search_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search_batch");
+    group.sample_size(10);
+    for (size, placed) in [(5, 4), (6, 6)] {
+        let grid = cyclic_latin_square(size);
+        let tiles = all_tiles(size);
+        let batch: Vec<_> = CombinationIterator::new(tiles.clone(), placed).take(10_000).collect();
+        group.bench_with_input(BenchmarkId::new(format!("{}x{}", size, size), placed), &batch, |b, batch| {
+            b.iter(|| {
+                batch
+                    .par_iter()
+                    .filter(|combination| check_uniqueness(&puzzle_constraints(combination, &grid, &tiles)) == 1)
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    empty_square,
+    unique_puzzles,
+    random_puzzles,
+    propagation_thresholds,
+    kernels,
+    search_batch
+);
+criterion_main!(benches);
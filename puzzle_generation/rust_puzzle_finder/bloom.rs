@@ -0,0 +1,110 @@
+//! Bloom filter of the combinations tried by a random search.
+//!
+//! Keeping every combination drawn in a hash set takes dozens of bytes per
+//! combination, gigabytes for hundreds of millions of tries. The filter takes
+//! a few bits each, at the price of false positives: a combination never
+//! drawn is taken for a tried one, and skipped, with a small probability set
+//! when the filter is created. Combinations are keyed by their tile masks.
+
+use std::f64::consts::LN_2;
+
+use crate::mask::TileMask;
+
+/// Share of untried combinations skipped by default.
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 1e-4;
+
+/// Fewest bits of a filter. The positions of a key are an arithmetic
+/// progression modulo the number of bits, and with only a few hundred bits
+/// the keys of a small filter often share most of their positions.
+const MIN_BITS: usize = 1024;
+
+/// Set of tile masks with false positives and no false negatives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    words: Vec<u64>,
+    hashes: u32,
+    items: u64,
+}
+
+impl BloomFilter {
+    /// A filter sized so that, once `capacity` keys are inserted, an absent
+    /// key is reported present with probability `false_positive_rate`.
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let bits = (-capacity * false_positive_rate.ln() / (LN_2 * LN_2)).ceil().max(MIN_BITS as f64) as usize;
+        let hashes = (bits as f64 / capacity * LN_2).round().clamp(1.0, 32.0) as u32;
+        Self { words: vec![0; bits.div_ceil(64)], hashes, items: 0 }
+    }
+
+    /// A filter from the fields of a saved one.
+    pub fn from_parts(words: Vec<u64>, hashes: u32, items: u64) -> Self {
+        assert!(!words.is_empty() && hashes > 0, "a filter has bits and hash functions");
+        Self { words, hashes, items }
+    }
+
+    /// Bits of the filter, 64 per word.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Number of bits set for each key.
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// Bit positions of `key`, by double hashing.
+    fn positions(&self, key: TileMask) -> impl Iterator<Item = usize> {
+        let [low, high] = key.halves();
+        // Keys whose high half is zero hash like their low 128 bits, so that
+        // filters saved by older versions stay valid
+        let high = if high == 0 { 0 } else { mix(high as u64 ^ mix((high >> 64) as u64)) };
+        let first = mix(low as u64 ^ mix((low >> 64) as u64 ^ high));
+        let step = mix(first ^ 0x9E37_79B9_7F4A_7C15) | 1;
+        let bits = self.words.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| (first.wrapping_add(i.wrapping_mul(step)) % bits) as usize)
+    }
+
+    /// Insert `key`, returning whether it was absent (or reported absent).
+    pub fn insert(&mut self, key: TileMask) -> bool {
+        let mut added = false;
+        for position in self.positions(key) {
+            let bit = 1 << (position % 64);
+            added |= self.words[position / 64] & bit == 0;
+            self.words[position / 64] |= bit;
+        }
+        self.items += added as u64;
+        added
+    }
+
+    pub fn contains(&self, key: TileMask) -> bool {
+        self.positions(key).all(|position| self.words[position / 64] & 1 << (position % 64) != 0)
+    }
+
+    /// Number of keys inserted that were reported absent.
+    pub fn len(&self) -> u64 {
+        self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items == 0
+    }
+
+    /// Probability that an absent key is reported present, given the keys
+    /// inserted so far.
+    pub fn false_positive_rate(&self) -> f64 {
+        let bits = self.words.len() as f64 * 64.0;
+        (1.0 - (-(self.hashes as f64) * self.items as f64 / bits).exp()).powi(self.hashes as i32)
+    }
+
+    /// Memory taken by the bits, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.words.len() * 8
+    }
+}
+
+/// Finalizer of SplitMix64, spreading every input bit over the output.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,175 @@
+//! Provable lower bounds on the placed tiles of unique puzzles of a base grid.
+//!
+//! With [`ClueSemantics::GivensOnly`] and the rules of Latin squares, two
+//! arguments bound the tiles of a unique clue set:
+//!
+//! - Empty lines: swapping two rows, two columns or two values of the base
+//!   grid gives another Latin square, so a unique clue set leaves at most one
+//!   row, one column and one value without a tile, and places at least N - 1.
+//! - Disjoint trades: a clue set placing no tile on a cycle of
+//!   [`crate::cycles`] (an intercalate, or any longer cycle of a pair of
+//!   rows, columns or values) leaves a second solution, so a unique clue set
+//!   places a tile on each of a set of pairwise disjoint cycles. The largest
+//!   such set is found by branch and bound; if the search runs out of nodes,
+//!   the largest set found is still a valid bound.
+//!
+//! With the game's semantics the base grid itself is not a solution, and the
+//! rules of variants rule out some of the trades, so neither argument holds
+//! and no bound is given.
+
+use crate::cycles::{self, Cycle, LineKind};
+use crate::{ClueSemantics, Grid, Variant, MAX_SIZE};
+
+/// Default number of nodes of the search for the largest set of disjoint trades.
+pub const DEFAULT_NODE_BUDGET: usize = 1_000_000;
+
+/// A lower bound on the placed tiles of the unique puzzles of a base grid.
+#[derive(Debug, Clone)]
+pub struct LowerBound {
+    /// Fewest placed tiles a unique puzzle can have: the larger of N - 1 and
+    /// the number of disjoint trades.
+    pub tiles: usize,
+    /// Pairwise disjoint cycles of the base grid, each of which a unique
+    /// puzzle places a tile on.
+    pub trades: Vec<Cycle>,
+    /// Whether no larger set of disjoint trades exists, rather than the
+    /// search running out of nodes first.
+    pub exact: bool,
+}
+
+/// Cells of the grid, as a bitmask of `row * size + col`.
+type Cells = [u64; MAX_SIZE * MAX_SIZE / 64];
+
+fn count(cells: &Cells) -> usize {
+    cells.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+fn contains(outer: &Cells, inner: &Cells) -> bool {
+    outer.iter().zip(inner).all(|(outer, inner)| inner & !outer == 0)
+}
+
+fn without(outer: &Cells, inner: &Cells) -> Cells {
+    let mut cells = *outer;
+    for (word, inner) in cells.iter_mut().zip(inner) {
+        *word &= !inner;
+    }
+    cells
+}
+
+/// The lowest cell of `cells`, if any.
+fn first(cells: &Cells) -> Option<usize> {
+    let word = cells.iter().position(|&word| word != 0)?;
+    Some(word * 64 + cells[word].trailing_zeros() as usize)
+}
+
+/// Branch and bound search for the largest set of pairwise disjoint trades.
+struct Packing {
+    masks: Vec<Cells>,
+    /// Indices of the trades covering each cell, shortest first.
+    covering: Vec<Vec<usize>>,
+    /// Fewest cells of a trade, bounding the trades that fit in free cells.
+    min_cells: usize,
+    chosen: Vec<usize>,
+    best: Vec<usize>,
+    nodes: usize,
+    budget: usize,
+}
+
+impl Packing {
+    /// Pack trades into `free`, branching on its lowest cell: on each trade
+    /// covering it that fits, then on leaving it free. Returns false once the
+    /// budget is spent.
+    fn search(&mut self, mut free: Cells) -> bool {
+        self.nodes += 1;
+        if self.nodes > self.budget {
+            return false;
+        }
+        loop {
+            if self.chosen.len() + count(&free) / self.min_cells <= self.best.len() {
+                return true;
+            }
+            let Some(cell) = first(&free) else {
+                self.best = self.chosen.clone();
+                return true;
+            };
+            let fitting: Vec<usize> =
+                self.covering[cell].iter().copied().filter(|&t| contains(&free, &self.masks[t])).collect();
+            free[cell / 64] &= !(1 << (cell % 64));
+            if fitting.is_empty() {
+                continue;
+            }
+            for trade in fitting {
+                self.chosen.push(trade);
+                let done = self.search(without(&free, &self.masks[trade]));
+                self.chosen.pop();
+                if !done {
+                    return false;
+                }
+            }
+            return self.search(free);
+        }
+    }
+}
+
+/// The largest set of pairwise disjoint cycles of `grid` found within
+/// `budget` nodes, and whether the search finished.
+pub fn disjoint_trades(grid: &Grid, budget: usize) -> (Vec<Cycle>, bool) {
+    let size = grid.size();
+    let mut trades: Vec<Cycle> = [LineKind::Row, LineKind::Column, LineKind::Value]
+        .into_iter()
+        .flat_map(|kind| cycles::cycles(grid, kind))
+        .collect();
+    trades.sort_by_key(Cycle::len);
+    let masks: Vec<Cells> = trades
+        .iter()
+        .map(|trade| {
+            let mut cells = [0; MAX_SIZE * MAX_SIZE / 64];
+            for &(r, c) in &trade.cells {
+                let cell = r * size + c;
+                cells[cell / 64] |= 1 << (cell % 64);
+            }
+            cells
+        })
+        .collect();
+    let mut covering = vec![Vec::new(); size * size];
+    for (index, trade) in trades.iter().enumerate() {
+        for &(r, c) in &trade.cells {
+            covering[r * size + c].push(index);
+        }
+    }
+    // Start from the greedy packing of `cycles::givens_lower_bound`
+    let mut used = [0; MAX_SIZE * MAX_SIZE / 64];
+    let mut greedy = Vec::new();
+    for (index, mask) in masks.iter().enumerate() {
+        if used.iter().zip(mask).all(|(used, mask)| used & mask == 0) {
+            used.iter_mut().zip(mask).for_each(|(used, mask)| *used |= mask);
+            greedy.push(index);
+        }
+    }
+    let mut all = [0; MAX_SIZE * MAX_SIZE / 64];
+    for cell in 0..size * size {
+        all[cell / 64] |= 1 << (cell % 64);
+    }
+    let mut packing = Packing {
+        min_cells: masks.iter().map(count).min().unwrap_or(1),
+        masks,
+        covering,
+        chosen: Vec::new(),
+        best: greedy,
+        nodes: 0,
+        budget,
+    };
+    let exact = packing.search(all);
+    let best = packing.best.iter().map(|&index| trades[index].clone()).collect();
+    (best, exact)
+}
+
+/// The lower bound on the placed tiles of unique puzzles of `grid` with
+/// `semantics` and `variant`, or `None` if no argument applies.
+pub fn lower_bound(grid: &Grid, semantics: ClueSemantics, variant: Variant, budget: usize) -> Option<LowerBound> {
+    if semantics != ClueSemantics::GivensOnly || variant != Variant::Latin {
+        return None;
+    }
+    let (trades, exact) = disjoint_trades(grid, budget);
+    Some(LowerBound { tiles: trades.len().max(grid.size() - 1), trades, exact })
+}
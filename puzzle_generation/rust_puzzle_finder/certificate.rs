@@ -0,0 +1,217 @@
+//! Certificates of uniqueness for the puzzles that singles solve alone.
+//!
+//! A certificate lists the cells of the solution in the order in which naked
+//! and hidden singles fill them, each with the single that forces it. Every
+//! step follows from the clues and the steps before it, so a solution has no
+//! other choice at any step: when they fill the grid, the puzzle has exactly
+//! one solution. Checking a certificate replays its steps, which takes
+//! microseconds where a solve may search.
+//!
+//! Candidates only account for the rows, columns and known wrong values, which
+//! every variant requires, so the steps also hold under the rules of a
+//! variant; the filled grid is checked against them at the end.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::validate_latin_square;
+use crate::{Constraints, Grid};
+
+/// The single forcing the value of a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduction {
+    /// The value is the last candidate of its cell.
+    NakedSingle,
+    /// The cell is the last of its row where the value is a candidate.
+    HiddenSingleRow,
+    /// The cell is the last of its column where the value is a candidate.
+    HiddenSingleColumn,
+}
+
+impl Deduction {
+    /// Letter of the deduction in the encoding of certificates.
+    pub fn letter(self) -> char {
+        match self {
+            Deduction::NakedSingle => 'n',
+            Deduction::HiddenSingleRow => 'r',
+            Deduction::HiddenSingleColumn => 'c',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'n' => Some(Deduction::NakedSingle),
+            'r' => Some(Deduction::HiddenSingleRow),
+            'c' => Some(Deduction::HiddenSingleColumn),
+            _ => None,
+        }
+    }
+}
+
+/// A cell filled by a certificate, with its value from 1 to N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub cell: (usize, usize),
+    pub value: u8,
+    pub deduction: Deduction,
+}
+
+/// The steps filling the empty cells of a puzzle, encoded as `row:col=value`
+/// followed by the letter of their deduction and separated by semicolons,
+/// e.g. `0:2=3n;1:4=2r`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Certificate {
+    pub steps: Vec<Step>,
+}
+
+impl Certificate {
+    pub fn encode(&self) -> String {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| format!("{}:{}={}{}", step.cell.0, step.cell.1, step.value, step.deduction.letter()))
+            .collect();
+        steps.join(";")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parse_step = |step: &str| -> Option<Step> {
+            let (cell, value) = step.split_once('=')?;
+            let (row, col) = cell.split_once(':')?;
+            let letter = value.chars().last()?;
+            let deduction = Deduction::from_letter(letter)?;
+            let value = value[..value.len() - letter.len_utf8()].parse().ok()?;
+            Some(Step { cell: (row.parse().ok()?, col.parse().ok()?), value, deduction })
+        };
+        let steps = text
+            .split(';')
+            .filter(|step| !step.is_empty())
+            .map(|step| parse_step(step).ok_or_else(|| format!("invalid certificate step '{}'", step)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+}
+
+impl From<Certificate> for String {
+    fn from(certificate: Certificate) -> Self {
+        certificate.encode()
+    }
+}
+
+impl TryFrom<String> for Certificate {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        Self::parse(&text)
+    }
+}
+
+/// A puzzle being filled, with the candidates of its empty cells.
+struct Board<'a> {
+    constraints: &'a Constraints,
+    grid: Grid,
+}
+
+impl<'a> Board<'a> {
+    fn new(constraints: &'a Constraints) -> Self {
+        Self { constraints, grid: constraints.known.clone() }
+    }
+
+    /// Values left for `cell`, as a bitmask: none if it is filled.
+    fn candidates(&self, (i, j): (usize, usize)) -> u32 {
+        let size = self.constraints.size;
+        if self.grid[(i, j)] != 0 {
+            return 0;
+        }
+        let used = (0..size)
+            .flat_map(|k| [self.grid[(i, k)], self.grid[(k, j)]])
+            .filter(|&value| value != 0)
+            .fold(0, |used, value| used | 1 << (value - 1));
+        ((1u32 << size) - 1) & !used & !self.constraints.wrong[i * size + j]
+    }
+
+    /// Whether the deduction of `step` forces its value.
+    fn forces(&self, step: Step) -> bool {
+        let size = self.constraints.size;
+        let (i, j) = step.cell;
+        if i >= size || j >= size || !(1..=size).contains(&(step.value as usize)) {
+            return false;
+        }
+        let bit = 1u32 << (step.value - 1);
+        let candidates = self.candidates(step.cell);
+        candidates & bit != 0
+            && match step.deduction {
+                Deduction::NakedSingle => candidates == bit,
+                Deduction::HiddenSingleRow => (0..size).all(|k| k == j || self.candidates((i, k)) & bit == 0),
+                Deduction::HiddenSingleColumn => (0..size).all(|k| k == i || self.candidates((k, j)) & bit == 0),
+            }
+    }
+
+    /// A step forcing a value: a naked single, or else a hidden single.
+    fn next_step(&self) -> Option<Step> {
+        let size = self.constraints.size;
+        let candidates: Vec<u32> = (0..size * size).map(|cell| self.candidates((cell / size, cell % size))).collect();
+        let step = |cell: usize, bit: u32, deduction| Step {
+            cell: (cell / size, cell % size),
+            value: bit.trailing_zeros() as u8 + 1,
+            deduction,
+        };
+        if let Some(cell) = (0..size * size).find(|&cell| candidates[cell].count_ones() == 1) {
+            return Some(step(cell, candidates[cell], Deduction::NakedSingle));
+        }
+        let rows = (0..size).map(|i| (Deduction::HiddenSingleRow, i * size, 1));
+        let cols = (0..size).map(|j| (Deduction::HiddenSingleColumn, j, size));
+        rows.chain(cols).find_map(|(deduction, first, stride)| {
+            (0..size).find_map(|value| {
+                let bit = 1u32 << value;
+                let mut cells = (0..size).map(|k| first + k * stride).filter(|&cell| candidates[cell] & bit != 0);
+                match (cells.next(), cells.next()) {
+                    (Some(cell), None) => Some(step(cell, bit, deduction)),
+                    _ => None,
+                }
+            })
+        })
+    }
+
+    /// Whether the grid is full and satisfies every constraint.
+    fn is_solution(&self) -> bool {
+        let size = self.constraints.size;
+        self.grid.empty_cells() == 0
+            && validate_latin_square(&self.grid).is_ok()
+            && (0..size * size).all(|cell| {
+                self.constraints.wrong[cell] & 1 << (self.grid[(cell / size, cell % size)] - 1) == 0
+            })
+            && self.constraints.variant.allows(&self.grid)
+    }
+}
+
+/// The certificate of the puzzle, if singles fill its grid with a solution.
+pub fn find(constraints: &Constraints) -> Option<Certificate> {
+    let mut board = Board::new(constraints);
+    let mut steps = Vec::new();
+    while let Some(step) = board.next_step() {
+        board.grid[step.cell] = step.value;
+        steps.push(step);
+    }
+    board.is_solution().then_some(Certificate { steps })
+}
+
+/// Replay `certificate` on the puzzle: the unique solution it proves, or the
+/// first step that does not follow.
+pub fn check(constraints: &Constraints, certificate: &Certificate) -> Result<Grid, String> {
+    let mut board = Board::new(constraints);
+    for (index, &step) in certificate.steps.iter().enumerate() {
+        if !board.forces(step) {
+            return Err(format!(
+                "step {} ({}) does not follow",
+                index + 1,
+                Certificate { steps: vec![step] }.encode()
+            ));
+        }
+        board.grid[step.cell] = step.value;
+    }
+    if !board.is_solution() {
+        return Err("the steps do not fill the grid with a solution".to_string());
+    }
+    Ok(board.grid)
+}
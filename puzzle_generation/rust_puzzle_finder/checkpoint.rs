@@ -0,0 +1,119 @@
+//! Checkpoints of random searches, so that an interrupted run can continue
+//! sampling new clue sets instead of trying the same ones again.
+//!
+//! Random combinations are drawn batch by batch, batch `b` from a generator
+//! seeded with the run's sampling seed and `b`, so the state of the sampling
+//! is the seed and the number of batches drawn. A checkpoint stores it with
+//! the Bloom filter of the combinations already tried, in a binary file:
+//!
+//! ```text
+//! magic "SDCP", version (u8), size (u8), placed (u16), generator (u8),
+//! seed (u64), batches (u64), tries (u64),
+//! filter hashes (u32), filter items (u64), filter words (u64), the words (u64)
+//! ```
+//!
+//! Integers are little-endian. Combinations are keyed in the filter by their
+//! tile masks, see [`crate::mask::TileMask`].
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::bloom::BloomFilter;
+use crate::generators::Generator;
+
+const MAGIC: &[u8; 4] = b"SDCP";
+const VERSION: u8 = 2;
+
+/// State of a random search after some batches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub size: usize,
+    pub placed: usize,
+    pub generator: Generator,
+    /// Seed of the random combinations of the run.
+    pub seed: u64,
+    /// Batches of random combinations drawn so far.
+    pub batches: u64,
+    /// Tries used so far (constructor runs, or new combinations drawn).
+    pub tries: usize,
+    /// Combinations tried so far.
+    pub tried: BloomFilter,
+}
+
+impl Checkpoint {
+    /// Read the checkpoint at `path`, or `None` if there is no such file.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_bytes::<1>(&mut reader)?[0] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a search checkpoint"));
+        }
+        let size = read_bytes::<1>(&mut reader)?[0] as usize;
+        let placed = u16::from_le_bytes(read_bytes(&mut reader)?) as usize;
+        let generator = match read_bytes::<1>(&mut reader)?[0] {
+            0 => Generator::BottomUp,
+            1 => Generator::TopDown,
+            2 => Generator::Anneal,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown generator")),
+        };
+        let seed = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let batches = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let tries = u64::from_le_bytes(read_bytes(&mut reader)?) as usize;
+        let hashes = u32::from_le_bytes(read_bytes(&mut reader)?);
+        let items = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let count = u64::from_le_bytes(read_bytes(&mut reader)?) as usize;
+        if count == 0 || hashes == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty filter"));
+        }
+        let words = (0..count)
+            .map(|_| read_bytes(&mut reader).map(u64::from_le_bytes))
+            .collect::<io::Result<_>>()?;
+        let tried = BloomFilter::from_parts(words, hashes, items);
+        Ok(Some(Self { size, placed, generator, seed, batches, tries, tried }))
+    }
+
+    /// Write the checkpoint to `path`, through a temporary file so that an
+    /// interruption while writing leaves the previous checkpoint intact.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temporary = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.size as u8])?;
+        writer.write_all(&(self.placed as u16).to_le_bytes())?;
+        writer.write_all(&[self.generator as u8])?;
+        for value in [self.seed, self.batches, self.tries as u64] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&self.tried.hashes().to_le_bytes())?;
+        for value in [self.tried.len(), self.tried.words().len() as u64] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for word in self.tried.words() {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temporary, path)
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Random number generator of batch `batch` of a run sampling with `seed`.
+pub fn batch_rng(seed: u64, batch: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ batch.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
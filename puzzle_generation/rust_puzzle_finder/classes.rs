@@ -0,0 +1,177 @@
+//! Main classes of Latin squares, to compare the cyclic square with every
+//! other secret the game could use.
+//!
+//! Two squares are in the same main class when one is turned into the other
+//! by permuting rows, columns and values, and by swapping the roles of rows,
+//! columns and values (conjugates). These operations map the unique puzzles
+//! of a square to unique puzzles of the other with as many placed tiles, so
+//! the fewest tiles a unique puzzle needs is a property of the class.
+//!
+//! The classes are found by enumerating the reduced squares (first row and
+//! column in order) of a size and gathering them by class. Each class is
+//! represented by its smallest reduced square, row by row. There are 1, 1,
+//! 1, 2, 2 and 12 classes of sizes 1 to 6, and 147 of size 7, too many
+//! reduced squares to enumerate: sizes are limited to [`MAX_CLASS_SIZE`].
+
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use crate::{
+    all_tiles, puzzle_constraints_with, solve_constraints, ClueSemantics, CombinationIterator, Constraints, Grid,
+    SolveHooks, SolverOptions,
+};
+
+/// Largest size whose main classes are enumerated.
+pub const MAX_CLASS_SIZE: usize = 6;
+
+/// Combinations of tiles tested together by [`min_unique_placed`].
+const CHUNK: usize = 100_000;
+
+/// Representatives of the main classes of Latin squares of `size`, in
+/// increasing order. Panics above [`MAX_CLASS_SIZE`].
+pub fn main_classes(size: usize, solver: &SolverOptions) -> Vec<Grid> {
+    assert!(size <= MAX_CLASS_SIZE, "main classes are only enumerated up to size {}", MAX_CLASS_SIZE);
+    let mut reduced = Constraints::new(size);
+    for k in 0..size {
+        reduced.set_known((0, k), k + 1);
+        reduced.set_known((k, 0), k + 1);
+    }
+    let (squares, _) = solve_constraints(&reduced, None, solver, SolveHooks::default());
+    let mut seen = HashSet::new();
+    let mut classes = Vec::new();
+    for square in squares {
+        if seen.contains(&cells(&square)) {
+            continue;
+        }
+        let orbit = reduced_main_class(&square);
+        classes.push(orbit.iter().min().expect("a square is in its own class").clone());
+        seen.extend(orbit);
+    }
+    classes.sort();
+    classes.into_iter().map(|cells| grid(size, &cells)).collect()
+}
+
+/// The representative of the main class of `square`: the smallest reduced
+/// square of the class, row by row.
+pub fn main_class_representative(square: &Grid) -> Grid {
+    let orbit = reduced_main_class(square);
+    grid(square.size(), orbit.iter().min().expect("a square is in its own class"))
+}
+
+/// The reduced squares of the main class of `square`, as their cells.
+fn reduced_main_class(square: &Grid) -> HashSet<Vec<u8>> {
+    let size = square.size();
+    let permutations = permutations(size);
+    let mut orbit = HashSet::new();
+    for roles in [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]] {
+        let conjugate = conjugate(square, roles);
+        for first_row in 0..size {
+            for columns in &permutations {
+                orbit.insert(reduced_isotope(&conjugate, first_row, columns));
+            }
+        }
+    }
+    orbit
+}
+
+/// The square whose entry `(t[roles[0]], t[roles[1]])` is `t[roles[2]]` for
+/// each triple `t` of row, column and value (from 0) of `square`.
+fn conjugate(square: &Grid, roles: [usize; 3]) -> Grid {
+    let size = square.size();
+    let mut conjugate = Grid::new(size);
+    for (i, j) in (0..size).flat_map(|i| (0..size).map(move |j| (i, j))) {
+        let triple = [i, j, usize::from(square[(i, j)]) - 1];
+        conjugate[(triple[roles[0]], triple[roles[1]])] = triple[roles[2]] as u8 + 1;
+    }
+    conjugate
+}
+
+/// The reduced square isotopic to `square` with row `first_row` first and
+/// its columns in the order of `columns`: values are relabelled so that the
+/// first row reads 1 to N, then rows are sorted by their first value.
+fn reduced_isotope(square: &Grid, first_row: usize, columns: &[usize]) -> Vec<u8> {
+    let size = square.size();
+    let mut labels = vec![0u8; size + 1];
+    for (k, &column) in columns.iter().enumerate() {
+        labels[usize::from(square[(first_row, column)])] = k as u8 + 1;
+    }
+    let mut cells = vec![0u8; size * size];
+    for i in 0..size {
+        let row = usize::from(labels[usize::from(square[(i, columns[0])])]) - 1;
+        for (k, &column) in columns.iter().enumerate() {
+            cells[row * size + k] = labels[usize::from(square[(i, column)])];
+        }
+    }
+    cells
+}
+
+/// Every permutation of `0..n`.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut all = Vec::new();
+    for shorter in permutations(n - 1) {
+        for position in 0..n {
+            let mut permutation = shorter.clone();
+            permutation.insert(position, n - 1);
+            all.push(permutation);
+        }
+    }
+    all
+}
+
+fn cells(grid: &Grid) -> Vec<u8> {
+    grid.rows().flatten().copied().collect()
+}
+
+fn grid(size: usize, cells: &[u8]) -> Grid {
+    let mut grid = Grid::new(size);
+    for (index, &value) in cells.iter().enumerate() {
+        grid[(index / size, index % size)] = value;
+    }
+    grid
+}
+
+/// Outcome of [`min_unique_placed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimumSearch {
+    /// The first unique puzzle with the fewest placed tiles, in the order of
+    /// [`CombinationIterator`], or `None` if there is none up to the limit.
+    pub tiles: Option<Vec<(usize, usize)>>,
+    /// Clue sets tested.
+    pub tested: u64,
+}
+
+/// The smallest unique puzzle placing tiles of `grid`, testing every clue
+/// set of each number of tiles from 1 up to `max_placed`.
+pub fn min_unique_placed(
+    grid: &Grid,
+    semantics: ClueSemantics,
+    solver: &SolverOptions,
+    max_placed: usize,
+) -> MinimumSearch {
+    let size = grid.size();
+    let tile_coordinates = all_tiles(size);
+    let mut tested = 0;
+    for placed in 1..=max_placed.min(size * size) {
+        let mut combinations = CombinationIterator::new(tile_coordinates.clone(), placed);
+        loop {
+            let chunk: Vec<Vec<(usize, usize)>> = combinations.by_ref().take(CHUNK).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            let unique = chunk.par_iter().position_first(|tiles| {
+                let constraints = puzzle_constraints_with(tiles, grid, &tile_coordinates, semantics);
+                solve_constraints(&constraints, Some(2), solver, SolveHooks::default()).0.len() == 1
+            });
+            tested += unique.map_or(chunk.len(), |position| position + 1) as u64;
+            if let Some(position) = unique {
+                return MinimumSearch { tiles: Some(chunk[position].clone()), tested };
+            }
+        }
+        tracing::info!(placed, tested, "no unique puzzle");
+    }
+    MinimumSearch { tiles: None, tested }
+}
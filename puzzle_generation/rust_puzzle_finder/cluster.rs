@@ -0,0 +1,446 @@
+//! Searches spread over the processes of a cluster: a coordinator splits a
+//! search into ranges of combinations (or of constructor runs) and hands
+//! them out over TCP to worker processes, which search them and send back
+//! the puzzles found.
+//!
+//! Messages are JSON objects, one per line. A worker sends `ready` to get a
+//! range, with the settings of the search, searches it and sends `done`
+//! with its puzzles, until the coordinator answers `finished`. A range is
+//! handed out again if its worker disconnects before returning it, or has
+//! not returned it when its lease times out; the first result of a range is
+//! kept and later ones are dropped.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::certificate::Certificate;
+use crate::generators::Generator;
+use crate::mask::TileMask;
+use crate::output::{self, PuzzleSink};
+use crate::{
+    binomial, find_single_solution_puzzles, BaseGrid, ClueSemantics, FoundPuzzle, SearchOptions, SolveStats,
+    SolverOptions,
+};
+
+/// Attempts of a worker to connect to its coordinator, two seconds apart,
+/// so that workers may start first.
+const CONNECT_ATTEMPTS: usize = 30;
+
+/// Settings of a search spread over workers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub size: usize,
+    pub placed: usize,
+    #[serde(with = "value_name")]
+    pub semantics: ClueSemantics,
+    /// Square whose tiles the puzzles place, the cyclic one if left out.
+    #[serde(default, with = "value_name")]
+    pub base_grid: BaseGrid,
+    #[serde(with = "value_name")]
+    pub generator: Generator,
+    pub generator_seed: u64,
+    /// Constructor runs to spread, or `None` to try every combination.
+    pub runs: Option<u64>,
+    pub require_singles_solvable: bool,
+    /// Rules of the puzzles searched, see [`output::RULES_VERSION`].
+    #[serde(default = "output::first_rules_version")]
+    pub rules_version: u32,
+}
+
+impl Job {
+    /// Combinations (or runs) of the search, saturating at `u64::MAX`.
+    pub fn total(&self) -> u64 {
+        match self.runs {
+            Some(runs) => runs,
+            None => binomial(self.size * self.size, self.placed).try_into().unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Settings of a coordinator.
+#[derive(Debug, Clone)]
+pub struct CoordinatorOptions {
+    pub job: Job,
+    /// Combinations (or runs) in each range handed out.
+    pub range_size: u64,
+    /// Time after which a range not returned is handed out again.
+    pub lease_timeout: Duration,
+}
+
+/// Outcome of a search spread over workers.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterSummary {
+    pub ranges: u64,
+    pub combinations: u64,
+    pub unique_puzzles: usize,
+    /// Ranges handed out again after their worker failed or timed out.
+    pub reissued: u64,
+    /// Connections of workers.
+    pub workers: usize,
+    pub wall_time: Duration,
+}
+
+/// Names of the values of clap enums, as used on the command line.
+mod value_name {
+    use clap::ValueEnum;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: ValueEnum, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.to_possible_value().expect("no skipped values").get_name())
+    }
+
+    pub fn deserialize<'de, T: ValueEnum, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        T::from_str(&name, false).map_err(D::Error::custom)
+    }
+}
+
+/// A puzzle found by a worker, without its solution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PuzzleRecord {
+    tiles: Vec<(usize, usize)>,
+    backtrack_nodes: usize,
+    guesses: usize,
+    max_depth: usize,
+    forced_cells: usize,
+    propagation_passes: usize,
+    solve_time_us: u64,
+    singles_solvable: bool,
+    #[serde(default)]
+    certificate: Option<Certificate>,
+}
+
+impl PuzzleRecord {
+    fn new(puzzle: &FoundPuzzle) -> Self {
+        Self {
+            tiles: puzzle.tiles.clone(),
+            backtrack_nodes: puzzle.stats.backtrack_nodes,
+            guesses: puzzle.stats.guesses,
+            max_depth: puzzle.stats.max_depth,
+            forced_cells: puzzle.stats.forced_cells,
+            propagation_passes: puzzle.stats.propagation_passes,
+            solve_time_us: puzzle.solve_time.as_micros() as u64,
+            singles_solvable: puzzle.singles_solvable,
+            certificate: puzzle.certificate.clone(),
+        }
+    }
+
+    fn puzzle(self) -> FoundPuzzle {
+        FoundPuzzle {
+            tiles: self.tiles,
+            solution: None,
+            stats: SolveStats {
+                backtrack_nodes: self.backtrack_nodes,
+                guesses: self.guesses,
+                max_depth: self.max_depth,
+                forced_cells: self.forced_cells,
+                propagation_passes: self.propagation_passes,
+                timed_out: false,
+            },
+            solve_time: Duration::from_micros(self.solve_time_us),
+            singles_solvable: self.singles_solvable,
+            certificate: self.certificate,
+            expected_guesses: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WorkerMessage {
+    Ready,
+    Done { start: u64, combinations: usize, puzzles: Vec<PuzzleRecord> },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CoordinatorMessage {
+    Work { job: Job, start: u64, end: u64 },
+    Finished,
+}
+
+/// A range handed out and not returned yet.
+struct Lease {
+    end: u64,
+    issued: Instant,
+}
+
+#[derive(Default)]
+struct CoordinatorState {
+    /// Start of the first range never handed out.
+    next_start: u64,
+    /// Starts of the leased ranges to hand out again.
+    reissue: VecDeque<u64>,
+    leases: BTreeMap<u64, Lease>,
+    ranges: u64,
+    combinations: u64,
+    reissued: u64,
+}
+
+/// State shared by the connections of the workers.
+struct Coordinator {
+    options: CoordinatorOptions,
+    total: u64,
+    state: Mutex<CoordinatorState>,
+    /// Signalled when a range is returned or is to be handed out again.
+    changed: Condvar,
+}
+
+impl Coordinator {
+    /// The next range to search, waiting while every range is leased, or
+    /// `None` once every range is searched.
+    fn next_range(&self) -> Option<Range<u64>> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let CoordinatorState { leases, reissue, reissued, .. } = &mut *state;
+            for (&start, lease) in leases.iter_mut() {
+                if now.duration_since(lease.issued) > self.options.lease_timeout {
+                    tracing::warn!(start, end = lease.end, "lease timed out, handing the range out again");
+                    lease.issued = now;
+                    reissue.push_back(start);
+                    *reissued += 1;
+                }
+            }
+            while let Some(start) = state.reissue.pop_front() {
+                if let Some(lease) = state.leases.get_mut(&start) {
+                    lease.issued = now;
+                    return Some(start..lease.end);
+                }
+            }
+            if state.next_start < self.total {
+                let start = state.next_start;
+                let end = start.saturating_add(self.options.range_size).min(self.total);
+                state.leases.insert(start, Lease { end, issued: now });
+                state.next_start = end;
+                return Some(start..end);
+            }
+            if state.leases.is_empty() {
+                return None;
+            }
+            state = self.changed.wait_timeout(state, Duration::from_secs(1)).unwrap().0;
+        }
+    }
+
+    /// Hand the range starting at `start` out again, its worker being gone.
+    fn release(&self, start: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.leases.contains_key(&start) {
+            state.reissue.push_back(start);
+            state.reissued += 1;
+            self.changed.notify_all();
+        }
+    }
+
+    /// Record the result of the range starting at `start`, unless another
+    /// worker returned it first.
+    fn complete(&self, start: u64, combinations: usize, puzzles: Vec<FoundPuzzle>, results: &Sender<Vec<FoundPuzzle>>) {
+        let mut state = self.state.lock().unwrap();
+        if state.leases.remove(&start).is_some() {
+            state.ranges += 1;
+            state.combinations += combinations as u64;
+            // Sent under the lock, so that the puzzles of every range are sent once all are done
+            let _ = results.send(puzzles);
+        }
+        self.changed.notify_all();
+    }
+
+    fn finished(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.next_start >= self.total && state.leases.is_empty()
+    }
+
+    /// Answer the messages of a worker until it disconnects.
+    fn serve_worker(&self, stream: TcpStream, results: &Sender<Vec<FoundPuzzle>>) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let mut lease = None;
+        let outcome = (|| {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Ok(());
+                }
+                let message: WorkerMessage = serde_json::from_str(&line).map_err(io::Error::other)?;
+                let reply = match message {
+                    WorkerMessage::Ready => match self.next_range() {
+                        Some(range) => {
+                            lease = Some(range.start);
+                            CoordinatorMessage::Work {
+                                job: self.options.job.clone(),
+                                start: range.start,
+                                end: range.end,
+                            }
+                        }
+                        None => CoordinatorMessage::Finished,
+                    },
+                    WorkerMessage::Done { start, combinations, puzzles } => {
+                        lease = None;
+                        let puzzles = puzzles.into_iter().map(PuzzleRecord::puzzle).collect();
+                        self.complete(start, combinations, puzzles, results);
+                        continue;
+                    }
+                };
+                writeln!(writer, "{}", serde_json::to_string(&reply).map_err(io::Error::other)?)?;
+                if matches!(reply, CoordinatorMessage::Finished) {
+                    return Ok(());
+                }
+            }
+        })();
+        if let Some(start) = lease {
+            tracing::warn!(start, "worker left without returning its range, handing it out again");
+            self.release(start);
+        }
+        outcome
+    }
+}
+
+/// Serve the ranges of `options.job` to the workers connecting to `addr`
+/// until every range is searched, writing the puzzles they find to `sinks`.
+pub fn coordinate(
+    addr: &str,
+    options: CoordinatorOptions,
+    sinks: &mut [Box<dyn PuzzleSink>],
+) -> io::Result<ClusterSummary> {
+    let start_time = Instant::now();
+    let listener = TcpListener::bind(addr)?;
+    // Poll for connections, to notice when the search is over
+    listener.set_nonblocking(true)?;
+    let total = options.job.total();
+    let range_size = options.range_size.max(1);
+    tracing::info!(addr = %listener.local_addr()?, total, ranges = total.div_ceil(range_size), "coordinating");
+    let coordinator = Coordinator {
+        options: CoordinatorOptions { range_size, ..options },
+        total,
+        state: Mutex::new(CoordinatorState::default()),
+        changed: Condvar::new(),
+    };
+    let (sender, receiver) = mpsc::channel::<Vec<FoundPuzzle>>();
+    let mut seen = HashSet::new();
+    let mut summary = ClusterSummary::default();
+    let mut last_report = Instant::now();
+    std::thread::scope(|scope| {
+        loop {
+            let finished = coordinator.finished();
+            for puzzles in receiver.try_iter() {
+                let size = coordinator.options.job.size;
+                let new: Vec<FoundPuzzle> =
+                    puzzles.into_iter().filter(|puzzle| seen.insert(TileMask::of_tiles(size, &puzzle.tiles))).collect();
+                for sink in sinks.iter_mut() {
+                    sink.write_puzzles(&new)?;
+                }
+                summary.unique_puzzles += new.len();
+            }
+            if finished {
+                break;
+            }
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    stream.set_nonblocking(false)?;
+                    summary.workers += 1;
+                    tracing::info!(%peer, "worker connected");
+                    let sender = sender.clone();
+                    let coordinator = &coordinator;
+                    scope.spawn(move || {
+                        if let Err(e) = coordinator.serve_worker(stream, &sender) {
+                            tracing::warn!(%peer, error = %e, "worker connection failed");
+                        }
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(100)),
+                Err(e) => tracing::warn!(error = %e, "cannot accept connection"),
+            }
+            if last_report.elapsed() >= Duration::from_secs(60) {
+                last_report = Instant::now();
+                let state = coordinator.state.lock().unwrap();
+                tracing::info!(
+                    ranges = state.ranges,
+                    leased = state.leases.len(),
+                    combinations = state.combinations,
+                    found = summary.unique_puzzles,
+                    "progress"
+                );
+            }
+        }
+        io::Result::Ok(())
+    })?;
+    for sink in sinks.iter_mut() {
+        sink.finish()?;
+    }
+    let state = coordinator.state.into_inner().unwrap();
+    Ok(ClusterSummary {
+        ranges: state.ranges,
+        combinations: state.combinations,
+        reissued: state.reissued,
+        wall_time: start_time.elapsed(),
+        ..summary
+    })
+}
+
+/// Search the ranges handed out by the coordinator at `addr` until it has
+/// none left, with the solver settings `solver`, and return the number of
+/// ranges searched and puzzles found.
+pub fn work(addr: &str, solver: SolverOptions) -> io::Result<(usize, usize)> {
+    let mut attempt = 1;
+    let stream = loop {
+        match TcpStream::connect(addr) {
+            Ok(stream) => break stream,
+            Err(e) if attempt < CONNECT_ATTEMPTS => {
+                tracing::info!(addr, error = %e, "coordinator not reachable yet, retrying");
+                attempt += 1;
+                std::thread::sleep(Duration::from_secs(2));
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let (mut ranges, mut found) = (0, 0);
+    let mut line = String::new();
+    loop {
+        writeln!(writer, "{}", serde_json::to_string(&WorkerMessage::Ready).map_err(io::Error::other)?)?;
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the coordinator closed the connection"));
+        }
+        let (job, start, end) = match serde_json::from_str(&line).map_err(io::Error::other)? {
+            CoordinatorMessage::Work { job, start, end } => (job, start, end),
+            CoordinatorMessage::Finished => return Ok((ranges, found)),
+        };
+        output::check_rules_version(job.rules_version, "the job of the coordinator")
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if job.base_grid.grid(job.size).is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the job of the coordinator has no base grid"));
+        }
+        let _span = tracing::info_span!("range", start, end).entered();
+        let options = SearchOptions {
+            random_tries: job.runs.map(|_| (end - start) as usize),
+            solver,
+            require_singles_solvable: job.require_singles_solvable,
+            generator: job.generator,
+            generator_seed: job.generator_seed,
+            semantics: job.semantics,
+            base_grid: job.base_grid,
+            no_solutions: true,
+            range: Some(start..end),
+            ..SearchOptions::default()
+        };
+        let result = find_single_solution_puzzles(job.size, job.placed, options, Vec::new(), HashSet::new());
+        ranges += 1;
+        found += result.puzzles.len();
+        let done = WorkerMessage::Done {
+            start,
+            combinations: result.summary.combinations_processed,
+            puzzles: result.puzzles.iter().map(PuzzleRecord::new).collect(),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&done).map_err(io::Error::other)?)?;
+    }
+}
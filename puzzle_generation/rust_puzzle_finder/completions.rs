@@ -0,0 +1,255 @@
+//! Counting the completions of a partial square without listing them.
+//!
+//! The solver can only count completions by finding them one by one, which is
+//! too slow for sparse clue sets and is cut short by `--max-solutions`.
+//! [`count_exact`] fills the square row by row instead, merging the partial
+//! squares whose columns hold the same sets of values: their completions are
+//! the same, so they are counted once. The last row of each partial square
+//! is not enumerated: its completions are the permanent of the matrix of the
+//! values each cell can still take, which Ryser's formula computes directly.
+//!
+//! Larger squares with few clues have too many completions for either, and
+//! [`estimate_completions`] estimates their number by random probing instead.
+//! The same probes give random completions: [`sample_completions`] weighs
+//! the completions reached by the inverse of their chance of being reached,
+//! and draws among them, which is close to uniform with enough probes.
+
+use std::collections::HashMap;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::{Constraints, Grid, Variant};
+
+/// Probes of [`sample_completions`] reaching a completion, for each completion drawn.
+pub const PROBES_PER_SAMPLE: usize = 32;
+/// Rounds of probes of [`sample_completions`] before it settles for the
+/// completions reached so far.
+const SAMPLE_ROUNDS: usize = 16;
+
+/// Largest size [`count_exact`] handles: the values used in each column of a
+/// partial square of this size fit in one `u64` key.
+pub const MAX_EXACT_SIZE: usize = 6;
+
+/// Number of completions of `constraints`, or `None` if the square is larger
+/// than [`MAX_EXACT_SIZE`] or the puzzle is of a variant, whose rules the
+/// count (tracking the values used in each column) cannot follow.
+pub fn count_exact(constraints: &Constraints) -> Option<u64> {
+    let size = constraints.size;
+    if size > MAX_EXACT_SIZE || constraints.variant != Variant::Latin {
+        return None;
+    }
+    let mut states: HashMap<u64, u64> = HashMap::from([(0, 1)]);
+    for row in 0..size - 1 {
+        let allowed = row_candidates(constraints, row);
+        let mut next: HashMap<u64, u64> = HashMap::new();
+        for (&columns, &count) in &states {
+            fill_row(size, &allowed, columns, 0, 0, &mut |filled| {
+                *next.entry(filled).or_insert(0) += count;
+            });
+        }
+        states = next;
+    }
+    let last = row_candidates(constraints, size - 1);
+    Some(
+        states
+            .iter()
+            .map(|(&columns, &count)| {
+                let matrix: Vec<u32> =
+                    (0..size).map(|col| last[col] & !column_values(size, columns, col)).collect();
+                count * permanent(&matrix)
+            })
+            .sum(),
+    )
+}
+
+/// Values each cell of `row` can hold given its clues, as bitmasks.
+fn row_candidates(constraints: &Constraints, row: usize) -> Vec<u32> {
+    let size = constraints.size;
+    (0..size)
+        .map(|col| match constraints.known[(row, col)] {
+            0 => ((1u32 << size) - 1) & !constraints.wrong[row * size + col],
+            known => 1 << (known - 1),
+        })
+        .collect()
+}
+
+/// Values used in column `col` of the state `columns`.
+fn column_values(size: usize, columns: u64, col: usize) -> u32 {
+    (columns >> (col * size)) as u32 & ((1 << size) - 1)
+}
+
+/// Call `visit` with the state of every way of filling a row from column
+/// `col` on with values allowed in its cells, unused in their columns and not
+/// in `used`.
+fn fill_row(size: usize, allowed: &[u32], columns: u64, col: usize, used: u32, visit: &mut impl FnMut(u64)) {
+    if col == size {
+        visit(columns);
+        return;
+    }
+    let mut candidates = allowed[col] & !column_values(size, columns, col) & !used;
+    while candidates != 0 {
+        let value = candidates & candidates.wrapping_neg();
+        candidates &= candidates - 1;
+        fill_row(size, allowed, columns | (value as u64) << (col * size), col + 1, used | value, visit);
+    }
+}
+
+/// Permanent of the 0/1 matrix whose row `i` is the bitmask `matrix[i]`, by
+/// Ryser's formula: the number of ways to give each row a distinct column.
+pub fn permanent(matrix: &[u32]) -> u64 {
+    let n = matrix.len();
+    let mut total: i64 = 0;
+    for subset in 1u32..1 << n {
+        let product: i64 = matrix.iter().map(|&row| (row & subset).count_ones() as i64).product();
+        let sign = if (n - subset.count_ones() as usize) & 1 == 0 { 1 } else { -1 };
+        total += sign * product;
+    }
+    total as u64
+}
+
+/// Estimates of the number of completions of a partial square and of the
+/// size of its search tree, from random probes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompletionEstimate {
+    pub probes: usize,
+    /// Mean of the probe estimates of the number of completions.
+    pub completions: f64,
+    /// Standard error of `completions`.
+    pub completions_std_error: f64,
+    /// Mean of the probe estimates of the number of nodes of the search tree.
+    pub tree_nodes: f64,
+    /// Standard error of `tree_nodes`.
+    pub tree_nodes_std_error: f64,
+    /// Share of the probes that reached a completion.
+    pub success_rate: f64,
+}
+
+/// Estimate the number of completions of `constraints` with Knuth's method:
+/// each probe follows one random branch of a search filling the cell with
+/// the fewest candidates first, and the product of the numbers of candidates
+/// met along the way is an unbiased estimate of the number of leaves (0 for
+/// branches ending at a dead end), and their running sums of the tree size.
+///
+/// Probe `p` uses its own random numbers derived from `seed`, so estimates
+/// don't depend on the number of threads.
+pub fn estimate_completions(constraints: &Constraints, probes: usize, seed: u64) -> CompletionEstimate {
+    let samples: Vec<(f64, f64)> = (0..probes as u64)
+        .into_par_iter()
+        .map(|probe| {
+            let mut rng = StdRng::seed_from_u64(seed ^ probe.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let (leaves, nodes, _) = probe_tree(constraints, &mut rng);
+            (leaves, nodes)
+        })
+        .collect();
+    let (completions, completions_std_error) = mean_and_std_error(samples.iter().map(|&(leaves, _)| leaves));
+    let (tree_nodes, tree_nodes_std_error) = mean_and_std_error(samples.iter().map(|&(_, nodes)| nodes));
+    CompletionEstimate {
+        probes,
+        completions,
+        completions_std_error,
+        tree_nodes,
+        tree_nodes_std_error,
+        success_rate: samples.iter().filter(|&&(leaves, _)| leaves > 0.0).count() as f64 / probes.max(1) as f64,
+    }
+}
+
+/// `count` completions of `constraints` drawn independently and close to
+/// uniformly at random, or none if it has none.
+///
+/// Probes follow random branches of the search tree as in
+/// [`estimate_completions`], and are repeated until [`PROBES_PER_SAMPLE`]
+/// times `count` of them reached a completion (or after a few rounds). A
+/// probe reaches a completion with a chance inversely proportional to its
+/// estimate of the number of completions, so drawing the completions
+/// reached with that estimate as weight corrects the bias of the probes;
+/// the draws get closer to uniform as more probes reach a completion.
+pub fn sample_completions(constraints: &Constraints, count: usize, seed: u64) -> Vec<Grid> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let target = count * PROBES_PER_SAMPLE;
+    let mut reached: Vec<(f64, Grid)> = Vec::new();
+    for round in 0..SAMPLE_ROUNDS as u64 {
+        let first = round * target as u64;
+        reached.par_extend((first..first + target as u64).into_par_iter().filter_map(|probe| {
+            let mut rng = StdRng::seed_from_u64(seed ^ probe.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let (leaves, _, grid) = probe_tree(constraints, &mut rng);
+            (leaves > 0.0).then_some((leaves, grid))
+        }));
+        if reached.len() >= target {
+            break;
+        }
+    }
+    let Ok(weights) = WeightedIndex::new(reached.iter().map(|&(leaves, _)| leaves)) else {
+        return Vec::new();
+    };
+    let mut rng = StdRng::seed_from_u64(seed ^ u64::MAX);
+    (0..count).map(|_| reached[weights.sample(&mut rng)].1.clone()).collect()
+}
+
+/// Follow one random branch of the search tree of `constraints` and return
+/// its estimates of the numbers of completions and of nodes, and the square
+/// it filled (a completion if the first estimate is not 0).
+fn probe_tree(constraints: &Constraints, rng: &mut impl Rng) -> (f64, f64, Grid) {
+    let size = constraints.size;
+    let mut grid = constraints.known.clone();
+    let mut row_used = vec![0u32; size];
+    let mut col_used = vec![0u32; size];
+    let mut empty = Vec::new();
+    for (i, j) in (0..size).flat_map(|i| (0..size).map(move |j| (i, j))) {
+        match constraints.known[(i, j)] {
+            0 => empty.push((i, j)),
+            known => {
+                let value = 1 << (known - 1);
+                if (row_used[i] | col_used[j]) & value != 0 {
+                    return (0.0, 1.0, grid);
+                }
+                row_used[i] |= value;
+                col_used[j] |= value;
+            }
+        }
+    }
+    let all = (1u32 << size) - 1;
+    let candidates = |(i, j): (usize, usize), row_used: &[u32], col_used: &[u32]| {
+        all & !row_used[i] & !col_used[j] & !constraints.wrong[i * size + j]
+    };
+    let (mut weight, mut nodes) = (1.0, 1.0);
+    while !empty.is_empty() {
+        let (index, &cell) = empty
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &cell)| candidates(cell, &row_used, &col_used).count_ones())
+            .expect("there are empty cells");
+        let options = candidates(cell, &row_used, &col_used);
+        if options == 0 {
+            return (0.0, nodes, grid);
+        }
+        weight *= options.count_ones() as f64;
+        nodes += weight;
+        let pick = rng.gen_range(0..options.count_ones());
+        let value = (0..size as u32).map(|bit| 1 << bit).filter(|&value| options & value != 0).nth(pick as usize);
+        let value = value.expect("the pick is among the options");
+        row_used[cell.0] |= value;
+        col_used[cell.1] |= value;
+        grid[cell] = value.trailing_zeros() as u8 + 1;
+        empty.swap_remove(index);
+    }
+    (weight, nodes, grid)
+}
+
+/// Mean of `samples` and its standard error.
+fn mean_and_std_error(samples: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let count = samples.clone().count() as f64;
+    if count == 0.0 {
+        return (0.0, 0.0);
+    }
+    let mean = samples.clone().sum::<f64>() / count;
+    if count < 2.0 {
+        return (mean, 0.0);
+    }
+    let variance = samples.map(|sample| (sample - mean).powi(2)).sum::<f64>() / (count - 1.0);
+    (mean, (variance / count).sqrt())
+}
@@ -0,0 +1,88 @@
+//! Critical sets of the cyclic square: clue sets that force it as the unique
+//! completion when only the placed tiles are given, and from which no tile
+//! can be removed without losing uniqueness. They are the minimal classic
+//! puzzles of the square.
+//!
+//! The smallest critical sets are found exactly, by testing every clue set
+//! of each number of tiles from the bound of [`crate::cycles`] up, for sizes
+//! up to [`MAX_EXACT_SIZE`]. A smallest unique clue set is always critical,
+//! as any unique subset of it would be smaller. Larger squares have too many
+//! clue sets for that, and the greedy removals of [`generators::top_down`]
+//! give critical sets that are small but not always the smallest.
+
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+use rayon::prelude::*;
+
+use crate::nogoods::NogoodSet;
+use crate::{
+    all_tiles, cycles, cyclic_latin_square, generators, puzzle_constraints_with, solve_constraints, ClueSemantics,
+    CombinationIterator, SolveHooks, SolverOptions, Variant,
+};
+
+/// Largest size whose smallest critical sets are found exactly by default.
+pub const MAX_EXACT_SIZE: usize = 5;
+
+/// Critical sets of the cyclic square of one size.
+#[derive(Debug, Clone)]
+pub struct CriticalSets {
+    /// Number of tiles of the smallest sets found.
+    pub tiles: usize,
+    /// Critical sets with that number of tiles, each sorted, in increasing order.
+    pub sets: Vec<Vec<(usize, usize)>>,
+    /// Whether no smaller critical set exists.
+    pub exact: bool,
+}
+
+/// The smallest critical sets of the cyclic square of size `size`, up to
+/// `limit` of them, testing every clue set of each number of tiles.
+///
+/// Clue sets placing no tile on the cells of a second solution found earlier
+/// are skipped, as with `search --nogoods`.
+pub fn smallest_critical_sets(size: usize, limit: usize, solver: &SolverOptions) -> CriticalSets {
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let nogoods = RwLock::new(NogoodSet::new(size));
+    for tiles in cycles::givens_lower_bound(&grid)..=size * size {
+        let combinations: Vec<Vec<(usize, usize)>> = CombinationIterator::new(tile_coordinates.clone(), tiles).collect();
+        tracing::info!(tiles, combinations = combinations.len(), "testing clue sets");
+        let mut sets: Vec<Vec<(usize, usize)>> = combinations
+            .into_par_iter()
+            .filter(|combination| {
+                if nogoods.read().unwrap().rules_out(combination) {
+                    return false;
+                }
+                let constraints =
+                    puzzle_constraints_with(combination, &grid, &tile_coordinates, ClueSemantics::GivensOnly);
+                let (solutions, _) = solve_constraints(&constraints, Some(2), solver, SolveHooks::default());
+                if solutions.len() > 1 {
+                    let mut nogoods = nogoods.write().unwrap();
+                    for solution in &solutions {
+                        nogoods.record(solution, &grid);
+                    }
+                }
+                solutions.len() == 1
+            })
+            .collect();
+        if !sets.is_empty() {
+            sets.sort();
+            sets.truncate(limit);
+            return CriticalSets { tiles, sets, exact: true };
+        }
+    }
+    unreachable!("the full grid is a unique clue set")
+}
+
+/// Critical sets of the cyclic square of size `size` from `runs` greedy
+/// removal runs of `seed`, keeping the distinct sets with the fewest tiles.
+pub fn greedy_critical_sets(size: usize, runs: usize, seed: u64, limit: usize, solver: &SolverOptions) -> CriticalSets {
+    let grid = cyclic_latin_square(size);
+    let found: BTreeSet<Vec<(usize, usize)>> = (0..runs as u64)
+        .into_par_iter()
+        .filter_map(|run| generators::top_down(&grid, 0, run, seed, solver, ClueSemantics::GivensOnly, Variant::Latin))
+        .collect();
+    let tiles = found.iter().map(Vec::len).min().unwrap_or(0);
+    let sets: Vec<Vec<(usize, usize)>> = found.into_iter().filter(|set| set.len() == tiles).take(limit).collect();
+    CriticalSets { tiles, sets, exact: false }
+}
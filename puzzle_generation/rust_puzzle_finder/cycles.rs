@@ -0,0 +1,185 @@
+//! Cycle structure of a Latin square, and the lower bound it gives on the
+//! number of tiles of classic puzzles.
+//!
+//! Two rows of a Latin square hold the same values, in an order that maps
+//! each column to the column holding its value in the other row. The cycles
+//! of this permutation split the cells of the two rows into trades: swapping
+//! the values of the two rows along a cycle gives another Latin square (a
+//! 2-cycle is an intercalate). The same holds for pairs of columns, and for
+//! pairs of values, whose cells form cycles of alternating values.
+//!
+//! With [`crate::ClueSemantics::GivensOnly`], a clue set placing no tile on
+//! the cells of a cycle leaves both squares as solutions, so a unique clue
+//! set places a tile on every cycle, and needs at least as many tiles as
+//! there are pairwise disjoint cycles. With the game's semantics the square
+//! itself is not a solution, and its cycles give no bound.
+
+use std::collections::BTreeMap;
+
+use crate::Grid;
+
+/// Lines of the pairs whose cycles are taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LineKind {
+    Row,
+    Column,
+    Value,
+}
+
+/// A cycle of a pair of lines, as the cells of its trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub kind: LineKind,
+    /// The two lines, 0-based (values are counted from 0 too).
+    pub lines: (usize, usize),
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Cycle {
+    /// Number of cells of each line of the pair in the cycle.
+    pub fn len(&self) -> usize {
+        self.cells.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// The cycles of every pair of lines of `kind` of `grid`.
+pub fn cycles(grid: &Grid, kind: LineKind) -> Vec<Cycle> {
+    let size = grid.size();
+    // Read the square as `value_at[line][position]`, with `cell` mapping back
+    let mut value_at = vec![vec![0; size]; size];
+    for r in 0..size {
+        for c in 0..size {
+            let v = grid[(r, c)] as usize - 1;
+            match kind {
+                LineKind::Row => value_at[r][c] = v,
+                LineKind::Column => value_at[c][r] = v,
+                LineKind::Value => value_at[v][r] = c,
+            }
+        }
+    }
+    let cell = |line: usize, position: usize| match kind {
+        LineKind::Row => (line, position),
+        LineKind::Column => (position, line),
+        LineKind::Value => (position, value_at[line][position]),
+    };
+    let mut found = Vec::new();
+    for first in 0..size {
+        for second in first + 1..size {
+            let mut position_of = vec![0; size];
+            for (position, &value) in value_at[second].iter().enumerate() {
+                position_of[value] = position;
+            }
+            let mut visited = vec![false; size];
+            for start in 0..size {
+                let mut cells = Vec::new();
+                let mut position = start;
+                while !visited[position] {
+                    visited[position] = true;
+                    cells.push(cell(first, position));
+                    cells.push(cell(second, position));
+                    position = position_of[value_at[first][position]];
+                }
+                if !cells.is_empty() {
+                    found.push(Cycle { kind, lines: (first, second), cells });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Number of pairs of lines of `kind` with each cycle type (the lengths of
+/// their cycles, in decreasing order).
+pub fn cycle_types(grid: &Grid, kind: LineKind) -> BTreeMap<Vec<usize>, usize> {
+    let mut lengths: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    for cycle in cycles(grid, kind) {
+        lengths.entry(cycle.lines).or_default().push(cycle.len());
+    }
+    let mut types = BTreeMap::new();
+    for (_, mut cycle_type) in lengths {
+        cycle_type.sort_unstable_by(|a, b| b.cmp(a));
+        *types.entry(cycle_type).or_insert(0) += 1;
+    }
+    types
+}
+
+/// Number of intercalates of `grid` (2×2 subsquares).
+pub fn intercalates(grid: &Grid) -> usize {
+    cycles(grid, LineKind::Row).iter().filter(|cycle| cycle.len() == 2).count()
+}
+
+/// Lower bound on the tiles of a unique classic clue set on `grid`: the size
+/// of a set of pairwise disjoint cycles, picked greedily shortest first.
+/// Every such set gives a valid bound, even if a larger one exists.
+pub fn givens_lower_bound(grid: &Grid) -> usize {
+    let size = grid.size();
+    let mut all: Vec<Cycle> = [LineKind::Row, LineKind::Column, LineKind::Value]
+        .into_iter()
+        .flat_map(|kind| cycles(grid, kind))
+        .collect();
+    all.sort_by_key(Cycle::len);
+    let mut used = vec![false; size * size];
+    let mut disjoint = 0;
+    for cycle in all {
+        if cycle.cells.iter().all(|&(r, c)| !used[r * size + c]) {
+            for &(r, c) in &cycle.cells {
+                used[r * size + c] = true;
+            }
+            disjoint += 1;
+        }
+    }
+    disjoint
+}
+
+/// How two Latin squares differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difference {
+    /// They differ on the four cells of an intercalate, swapped.
+    Intercalate,
+    /// They differ on a cycle of length 3 of a pair of rows, columns or values.
+    ThreeCycle,
+    /// They differ on a longer cycle of a pair of lines.
+    LongerCycle,
+    /// The cells where they differ are not a single cycle of a pair of lines.
+    Other,
+}
+
+/// How `second` differs from `first`, two distinct squares of the same size.
+pub fn difference(first: &Grid, second: &Grid) -> Difference {
+    let size = first.size();
+    let mut differing: Vec<(usize, usize)> =
+        (0..size).flat_map(|r| (0..size).map(move |c| (r, c))).filter(|&cell| first[cell] != second[cell]).collect();
+    differing.sort_unstable();
+    // A cycle of `first` swapped gives another square, which must be `second`
+    let swapped = [LineKind::Row, LineKind::Column, LineKind::Value].into_iter().find_map(|kind| {
+        cycles(first, kind).into_iter().find(|cycle| {
+            let mut cells = cycle.cells.clone();
+            cells.sort_unstable();
+            cells == differing
+        })
+    });
+    match swapped.map(|cycle| cycle.len()) {
+        Some(2) => Difference::Intercalate,
+        Some(3) => Difference::ThreeCycle,
+        Some(_) => Difference::LongerCycle,
+        None => Difference::Other,
+    }
+}
+
+/// How the two closest of `solutions` (those differing on the fewest cells)
+/// differ, or `None` if there are fewer than two.
+pub fn closest_difference(solutions: &[Grid]) -> Option<Difference> {
+    let distance = |first: &Grid, second: &Grid| {
+        let size = first.size();
+        (0..size).flat_map(|r| (0..size).map(move |c| (r, c))).filter(|&cell| first[cell] != second[cell]).count()
+    };
+    let pairs = solutions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, first)| solutions[i + 1..].iter().map(move |second| (first, second)));
+    pairs.min_by_key(|(first, second)| distance(first, second)).map(|(first, second)| difference(first, second))
+}
@@ -0,0 +1,166 @@
+//! SQLite storage for discovered puzzles.
+//!
+//! Puzzles are keyed by their size and canonical (sorted) clue set, so the same
+//! puzzle found by several runs is only stored once.
+
+use std::io;
+
+use rusqlite::{params, params_from_iter, Connection};
+
+use crate::certificate::Certificate;
+use crate::output::{decode_clues, encode_clues, FileHeader, PuzzleSink, RULES_VERSION};
+use crate::{standardize_tile_tuple, FoundPuzzle, Grid};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS puzzles (
+        id INTEGER PRIMARY KEY,
+        size INTEGER NOT NULL,
+        placed INTEGER NOT NULL,
+        clues TEXT NOT NULL,
+        solution TEXT NOT NULL,
+        difficulty INTEGER NOT NULL,
+        backtrack_nodes INTEGER NOT NULL,
+        max_depth INTEGER NOT NULL DEFAULT 0,
+        forced_cells INTEGER NOT NULL DEFAULT 0,
+        propagation_passes INTEGER NOT NULL,
+        solve_time_us INTEGER NOT NULL,
+        singles_solvable INTEGER NOT NULL DEFAULT 0,
+        content_id TEXT,
+        rules_version INTEGER NOT NULL DEFAULT 1,
+        certificate TEXT,
+        UNIQUE (size, clues)
+    );
+    CREATE INDEX IF NOT EXISTS puzzles_size_placed_difficulty
+        ON puzzles (size, placed, difficulty);
+";
+
+/// Columns added after the initial schema, created on databases that predate them.
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("max_depth", "INTEGER NOT NULL DEFAULT 0"),
+    ("forced_cells", "INTEGER NOT NULL DEFAULT 0"),
+    ("singles_solvable", "INTEGER NOT NULL DEFAULT 0"),
+    ("content_id", "TEXT"),
+    // Rows written before the rules version was recorded follow version 1
+    ("rules_version", "INTEGER NOT NULL DEFAULT 1"),
+    ("certificate", "TEXT"),
+];
+
+/// Criteria used to sample puzzles from the database.
+pub struct PuzzleQuery {
+    pub size: usize,
+    pub placed: Option<usize>,
+    pub min_difficulty: Option<usize>,
+    pub max_difficulty: Option<usize>,
+    pub limit: usize,
+}
+
+/// A SQLite database of puzzles, created on first use.
+pub struct PuzzleDb {
+    conn: Connection,
+    header: FileHeader,
+}
+
+impl PuzzleDb {
+    /// Open (or create) the database at `path`, recording the puzzles
+    /// described by `header` (whose content IDs are stored with them).
+    pub fn open(path: &str, header: &FileHeader) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        migrate(&conn)?;
+        Ok(Self { conn, header: header.clone() })
+    }
+
+    /// Insert puzzles, ignoring those already present. Returns the number of new rows.
+    pub fn insert_puzzles(&mut self, puzzles: &[FoundPuzzle]) -> rusqlite::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO puzzles
+                    (size, placed, clues, solution, difficulty, backtrack_nodes, max_depth, forced_cells,
+                     propagation_passes, solve_time_us, singles_solvable, content_id, rules_version, certificate)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+            for puzzle in puzzles {
+                inserted += stmt.execute(params![
+                    self.header.size as i64,
+                    puzzle.tiles.len() as i64,
+                    encode_clues(&standardize_tile_tuple(&puzzle.tiles)),
+                    // Left empty for searches that don't keep solutions
+                    puzzle.solution.as_ref().map_or_else(String::new, encode_solution),
+                    puzzle.stats.difficulty() as i64,
+                    puzzle.stats.backtrack_nodes as i64,
+                    puzzle.stats.max_depth as i64,
+                    puzzle.stats.forced_cells as i64,
+                    puzzle.stats.propagation_passes as i64,
+                    puzzle.solve_time.as_micros() as i64,
+                    puzzle.singles_solvable,
+                    self.header.content_id(&puzzle.tiles),
+                    RULES_VERSION,
+                    puzzle.certificate.as_ref().map(Certificate::encode),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+}
+
+impl PuzzleSink for PuzzleDb {
+    fn write_puzzles(&mut self, puzzles: &[FoundPuzzle]) -> io::Result<()> {
+        self.insert_puzzles(puzzles).map(|_| ()).map_err(io::Error::other)
+    }
+}
+
+/// Add the columns of [`ADDED_COLUMNS`] missing from an existing puzzles table.
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    for (name, definition) in ADDED_COLUMNS {
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('puzzles') WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            conn.execute_batch(&format!("ALTER TABLE puzzles ADD COLUMN {} {}", name, definition))?;
+        }
+    }
+    Ok(())
+}
+
+/// Randomly sample up to `query.limit` puzzles matching the query from the database at `path`.
+///
+/// Returns the placed tiles of each sampled puzzle. Puzzles of another
+/// [`RULES_VERSION`] are left out.
+pub fn sample_puzzles(path: &str, query: &PuzzleQuery) -> rusqlite::Result<Vec<Vec<(usize, usize)>>> {
+    let conn = Connection::open(path)?;
+    migrate(&conn)?;
+    let mut sql = String::from("SELECT clues FROM puzzles WHERE size = ? AND rules_version = ?");
+    let mut values = vec![query.size as i64, RULES_VERSION as i64];
+    if let Some(placed) = query.placed {
+        sql.push_str(" AND placed = ?");
+        values.push(placed as i64);
+    }
+    if let Some(min) = query.min_difficulty {
+        sql.push_str(" AND difficulty >= ?");
+        values.push(min as i64);
+    }
+    if let Some(max) = query.max_difficulty {
+        sql.push_str(" AND difficulty <= ?");
+        values.push(max as i64);
+    }
+    sql.push_str(" ORDER BY RANDOM() LIMIT ?");
+    values.push(query.limit as i64);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        let clues: String = row.get(0)?;
+        Ok(decode_clues(&clues))
+    })?;
+    rows.collect()
+}
+
+/// Encode a solution as its rows of values separated by slashes, e.g. `123/231/312`,
+/// see [`crate::output::format_grid`].
+fn encode_solution(solution: &Grid) -> String {
+    crate::output::format_grid(solution)
+}
@@ -0,0 +1,55 @@
+//! Set of the clue sets of the puzzles found so far, shared by the workers of
+//! a search so that each worker deduplicates its puzzles as soon as it finds
+//! them instead of leaving it to the main thread at the end of the batch.
+//!
+//! Clue sets are keyed by their tile masks, see [`TileMask`]. The keys are
+//! spread over independently locked shards, so that workers rarely wait for
+//! each other. The set is not lock-free: a worker takes the lock of one shard
+//! for the time of a hash set insertion, once per unique puzzle, which is
+//! rare next to the solves that find it. With 64 shards, contention stays
+//! negligible, as it would with a concurrent map such as `DashSet`, which
+//! also shards its keys behind locks.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::mask::TileMask;
+
+/// Number of independently locked parts of the set.
+const SHARDS: usize = 64;
+
+/// Concurrent set of clue sets of an N×N grid.
+#[derive(Debug)]
+pub struct ConcurrentTileSet {
+    size: usize,
+    shards: Vec<Mutex<HashSet<TileMask>>>,
+}
+
+impl ConcurrentTileSet {
+    pub fn new(size: usize) -> Self {
+        Self { size, shards: (0..SHARDS).map(|_| Mutex::new(HashSet::new())).collect() }
+    }
+
+    fn shard(&self, mask: TileMask) -> &Mutex<HashSet<TileMask>> {
+        &self.shards[mask.shard(SHARDS)]
+    }
+
+    /// Add the clue set `tiles`, returning whether it was new.
+    pub fn insert(&self, tiles: &[(usize, usize)]) -> bool {
+        let mask = TileMask::of_tiles(self.size, tiles);
+        self.shard(mask).lock().unwrap().insert(mask)
+    }
+
+    pub fn contains(&self, tiles: &[(usize, usize)]) -> bool {
+        let mask = TileMask::of_tiles(self.size, tiles);
+        self.shard(mask).lock().unwrap().contains(&mask)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,302 @@
+//! Dancing links, an exact cover search engine for the solver, enabled with
+//! [`crate::SolverOptions::dlx`].
+//!
+//! Completing a puzzle is an exact cover problem: each option places a value
+//! in a cell, and covers the items of that cell and of the value in its row,
+//! its column, and its box or broken diagonals in the variants that have
+//! them. A solution picks options covering every item exactly once. Options
+//! the clues rule out are left out from the start. Knuth's Algorithm X then
+//! searches on the item with the fewest options left, trying each of them in
+//! turn, and the lists of options are unlinked and relinked in place as it
+//! goes ("dancing links").
+//!
+//! Values next to each other in the non-consecutive variant are not an exact
+//! cover constraint: options consecutive to a value placed next to them are
+//! skipped when their turn comes instead.
+
+use std::ops::ControlFlow;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use crate::trace::{Reason, SearchObserver};
+use crate::{
+    box_of, box_shape, consecutive, neighbours, CancellationToken, Constraints, Grid, SolveHooks, SolveStats,
+    SolverOptions, Variant,
+};
+
+/// The items and options of a puzzle, as circular doubly linked lists: node
+/// 0 is the root of the list of items left, nodes 1 to `items` their headers,
+/// and the following ones the items of each option, which link up each option
+/// and each item's options.
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Item of each node, the headers being their own.
+    item: Vec<usize>,
+    /// Options of each item still linked.
+    len: Vec<usize>,
+    /// Option of each node, as `cell * size + value - 1`.
+    option: Vec<usize>,
+}
+
+impl Links {
+    fn new(items: usize) -> Self {
+        let nodes = items + 1;
+        Self {
+            left: (0..nodes).map(|node| (node + nodes - 1) % nodes).collect(),
+            right: (0..nodes).map(|node| (node + 1) % nodes).collect(),
+            up: (0..nodes).collect(),
+            down: (0..nodes).collect(),
+            item: (0..nodes).collect(),
+            len: vec![0; nodes],
+            option: vec![usize::MAX; nodes],
+        }
+    }
+
+    /// Add the option covering `items`.
+    fn add_option(&mut self, option: usize, items: &[usize]) {
+        let first = self.item.len();
+        for (k, &item) in items.iter().enumerate() {
+            let node = first + k;
+            self.left.push(if k == 0 { first + items.len() - 1 } else { node - 1 });
+            self.right.push(if k + 1 == items.len() { first } else { node + 1 });
+            self.up.push(self.up[item]);
+            self.down.push(item);
+            self.down[self.up[item]] = node;
+            self.up[item] = node;
+            self.item.push(item);
+            self.option.push(option);
+            self.len[item] += 1;
+        }
+    }
+
+    /// Take `item` out of the list of items, and the options covering it out of
+    /// the lists of their other items.
+    fn cover(&mut self, item: usize) {
+        let (left, right) = (self.left[item], self.right[item]);
+        self.right[left] = right;
+        self.left[right] = left;
+        let mut row = self.down[item];
+        while row != item {
+            let mut node = self.right[row];
+            while node != row {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.len[self.item[node]] -= 1;
+                node = self.right[node];
+            }
+            row = self.down[row];
+        }
+    }
+
+    /// Undo [`Links::cover`] of `item`, in the reverse order.
+    fn uncover(&mut self, item: usize) {
+        let mut row = self.up[item];
+        while row != item {
+            let mut node = self.left[row];
+            while node != row {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.down[up] = node;
+                self.up[down] = node;
+                self.len[self.item[node]] += 1;
+                node = self.left[node];
+            }
+            row = self.up[row];
+        }
+        let (left, right) = (self.left[item], self.right[item]);
+        self.right[left] = item;
+        self.left[right] = item;
+    }
+
+    /// The item left with the fewest options, the first one on ties, or `None`
+    /// if every item is covered.
+    fn fewest_options(&self) -> Option<usize> {
+        let mut best = None;
+        let mut item = self.right[0];
+        while item != 0 {
+            if best.is_none_or(|best| self.len[item] < self.len[best]) {
+                best = Some(item);
+            }
+            item = self.right[item];
+        }
+        best
+    }
+}
+
+/// The exact cover problem of `constraints`.
+fn encode(constraints: &Constraints) -> Links {
+    let size = constraints.size;
+    let cells = size * size;
+    // Cells, then the values of the rows and of the columns, then those of the
+    // boxes, or of the diagonals and anti-diagonals
+    let lines = match constraints.variant {
+        Variant::Latin | Variant::NonConsecutive => 2,
+        Variant::Sudoku => 3,
+        Variant::Pandiagonal => 4,
+    };
+    let mut links = Links::new(cells * (lines + 1));
+    let boxes = box_shape(size);
+    let mut items = Vec::with_capacity(lines + 1);
+    for i in 0..size {
+        for j in 0..size {
+            let cell = i * size + j;
+            let known = constraints.known[(i, j)] as usize;
+            for value in 0..size {
+                if (known != 0 && known != value + 1) || constraints.wrong[cell] & 1 << value != 0 {
+                    continue;
+                }
+                items.clear();
+                items.extend([cell, i * size + value, j * size + value]);
+                match constraints.variant {
+                    Variant::Latin | Variant::NonConsecutive => {}
+                    Variant::Sudoku => items.push(box_of(size, boxes, i, j) * size + value),
+                    Variant::Pandiagonal => {
+                        items.push((j + size - i) % size * size + value);
+                        items.push((i + j) % size * size + value);
+                    }
+                }
+                // Items are numbered from 1, one block of N² for the cells and each kind of line
+                for (block, item) in items.iter_mut().enumerate() {
+                    *item += block * cells + 1;
+                }
+                links.add_option(cell * size + value, &items);
+            }
+        }
+    }
+    links
+}
+
+/// State of a running search.
+struct Search<'a, O: SearchObserver + ?Sized> {
+    links: Links,
+    size: usize,
+    non_consecutive: bool,
+    /// Values placed so far, 0 where none is.
+    grid: Grid,
+    options: &'a SolverOptions,
+    hooks: SolveHooks<'a>,
+    observer: &'a O,
+    on_solution: &'a mut dyn FnMut(&Grid) -> ControlFlow<()>,
+    stats: SolveStats,
+    found: usize,
+}
+
+impl<O: SearchObserver + ?Sized> Search<'_, O> {
+    /// Whether placing `value` in `cell` puts it next to a consecutive value.
+    fn next_to_consecutive(&self, (i, j): (usize, usize), value: usize) -> bool {
+        let values = consecutive(1 << (value - 1), self.size);
+        neighbours(self.size, i, j).any(|cell| self.grid[cell] != 0 && values & 1 << (self.grid[cell] - 1) != 0)
+    }
+
+    /// Solutions found so far, by this search or all those sharing the count.
+    fn limit_reached(&self) -> bool {
+        let found = self.hooks.shared_solutions.map_or(self.found, |shared| shared.load(Ordering::Relaxed));
+        self.options.max_solutions.is_some_and(|max| found >= max)
+    }
+
+    /// Search the node at `depth`, breaking when the search must stop.
+    fn search(&mut self, depth: usize) -> ControlFlow<()> {
+        self.stats.backtrack_nodes += 1;
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        // Reading the clock is comparatively slow, so only check the deadline now and then
+        if (self.stats.backtrack_nodes.is_multiple_of(64)
+            && self.hooks.deadline.is_some_and(|deadline| Instant::now() >= deadline))
+            || self.options.node_budget.is_some_and(|budget| self.stats.backtrack_nodes > budget)
+        {
+            self.stats.timed_out = true;
+            return ControlFlow::Break(());
+        }
+        let Some(item) = self.links.fewest_options() else {
+            // Every item is covered: a solution
+            self.observer.solution(&self.grid);
+            if let Some(shared) = self.hooks.shared_solutions {
+                shared.fetch_add(1, Ordering::Relaxed);
+            }
+            self.found += 1;
+            if (self.on_solution)(&self.grid).is_break() || self.limit_reached() {
+                return ControlFlow::Break(());
+            }
+            return ControlFlow::Continue(());
+        };
+        let choices = self.links.len[item];
+        if choices == 0 {
+            // An empty cell, or a value with no place left in a line
+            let cells = self.size * self.size;
+            self.observer.contradiction(if item <= cells { Reason::NoCandidates } else { Reason::Contradiction });
+            return ControlFlow::Continue(());
+        }
+        let reason = if choices > 1 { Reason::Guess } else { Reason::Forced };
+        if choices > 1 {
+            self.stats.guesses += 1;
+        } else {
+            self.stats.forced_cells += 1;
+        }
+        self.links.cover(item);
+        let mut flow = ControlFlow::Continue(());
+        let mut row = self.links.down[item];
+        while row != item {
+            if self.limit_reached() || self.hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+                flow = ControlFlow::Break(());
+                break;
+            }
+            let option = self.links.option[row];
+            let (cell, value) = (option / self.size, option % self.size + 1);
+            let cell = (cell / self.size, cell % self.size);
+            if self.non_consecutive && self.next_to_consecutive(cell, value) {
+                self.observer.contradiction(Reason::Contradiction);
+                row = self.links.down[row];
+                continue;
+            }
+            self.observer.enter_node(cell, value, depth + 1, reason);
+            self.grid[cell] = value as u8;
+            let mut node = self.links.right[row];
+            while node != row {
+                self.links.cover(self.links.item[node]);
+                node = self.links.right[node];
+            }
+            flow = self.search(depth + 1);
+            let mut node = self.links.left[row];
+            while node != row {
+                self.links.uncover(self.links.item[node]);
+                node = self.links.left[node];
+            }
+            self.grid[cell] = 0;
+            self.observer.exit_node(depth + 1);
+            if flow.is_break() {
+                break;
+            }
+            row = self.links.down[row];
+        }
+        self.links.uncover(item);
+        flow
+    }
+}
+
+/// Search the solutions of `constraints` with dancing links, passing each one
+/// to `on_solution`, in the way of [`crate::for_each_solution`].
+pub fn search<O: SearchObserver + ?Sized>(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+    observer: &O,
+    on_solution: &mut dyn FnMut(&Grid) -> ControlFlow<()>,
+) -> SolveStats {
+    let size = constraints.size;
+    let mut search = Search {
+        links: encode(constraints),
+        size,
+        non_consecutive: constraints.variant == Variant::NonConsecutive,
+        grid: Grid::new(size),
+        options,
+        hooks,
+        observer,
+        on_solution,
+        stats: SolveStats::default(),
+        found: 0,
+    };
+    let _ = search.search(0);
+    search.stats
+}
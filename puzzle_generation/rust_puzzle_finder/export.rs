@@ -0,0 +1,201 @@
+//! Export of puzzles as models for other constraint solvers, to compare them
+//! with the solver of this crate and check its solution counts.
+//!
+//! MiniZinc and FlatZinc models use 1-based rows and columns, as these
+//! languages do, and print their solutions in the row-by-row format of
+//! [`crate::output::format_grid`]. XCSP3 instances index their array from 0,
+//! and [`parse_xcsp3_solution`] reads back the solutions XCSP3 solvers print.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::{output, ClueSemantics, Constraints, Grid, MAX_SIZE};
+
+/// Language of an exported model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ModelFormat {
+    /// A MiniZinc model (`.mzn`), compiled for any MiniZinc solver.
+    #[default]
+    Minizinc,
+    /// A FlatZinc model (`.fzn`), read directly by FlatZinc solvers such as
+    /// Gecode or Chuffed. Row and column constraints are decomposed into
+    /// pairwise disequalities, and the clues become the domains of the cells.
+    Flatzinc,
+    /// An XCSP3 instance (`.xml`), the format of the XCSP constraint solver
+    /// competitions.
+    Xcsp3,
+}
+
+/// Model of the puzzle of `constraints` in `format`, with a comment describing
+/// how its clues were read.
+pub fn export_model(constraints: &Constraints, semantics: ClueSemantics, format: ModelFormat) -> String {
+    let semantics = match semantics {
+        ClueSemantics::Sudodle => "tiles that are not placed are wrong where they stand",
+        ClueSemantics::GivensOnly => "only the placed tiles are given",
+    };
+    match format {
+        ModelFormat::Minizinc => minizinc_model(constraints, semantics),
+        ModelFormat::Flatzinc => flatzinc_model(constraints, semantics),
+        ModelFormat::Xcsp3 => xcsp3_instance(constraints, semantics),
+    }
+}
+
+/// Values that cell `(i, j)` can hold given its clues, in increasing order.
+fn cell_domain(constraints: &Constraints, (i, j): (usize, usize)) -> Vec<usize> {
+    let size = constraints.size;
+    match constraints.known[(i, j)] {
+        0 => (1..=size).filter(|&value| constraints.wrong[i * size + j] & 1 << (value - 1) == 0).collect(),
+        known => vec![known.into()],
+    }
+}
+
+fn minizinc_model(constraints: &Constraints, semantics: &str) -> String {
+    let size = constraints.size;
+    let mut model = String::new();
+    let _ = writeln!(model, "% Sudodle puzzle of size {} ({})", size, semantics);
+    let _ = writeln!(model, "include \"alldifferent.mzn\";\n");
+    let _ = writeln!(model, "int: n = {};", size);
+    let _ = writeln!(model, "array[1..n, 1..n] of var 1..n: grid;\n");
+    let _ = writeln!(model, "constraint forall(i in 1..n)(alldifferent([grid[i, j] | j in 1..n]));");
+    let _ = writeln!(model, "constraint forall(j in 1..n)(alldifferent([grid[i, j] | i in 1..n]));\n");
+    let _ = writeln!(model, "% Placed tiles");
+    for (i, j) in cells(size).filter(|&tile| constraints.known[tile] != 0) {
+        let _ = writeln!(model, "constraint grid[{}, {}] = {};", i + 1, j + 1, constraints.known[(i, j)]);
+    }
+    let _ = writeln!(model, "\n% Values known to be wrong");
+    for (i, j) in cells(size) {
+        for value in (1..=size).filter(|&value| constraints.wrong[i * size + j] & 1 << (value - 1) != 0) {
+            let _ = writeln!(model, "constraint grid[{}, {}] != {};", i + 1, j + 1, value);
+        }
+    }
+    let _ = writeln!(model, "\nsolve satisfy;\n");
+    let _ = writeln!(
+        model,
+        "output [show(grid[i, j]) ++ if j < n then \"\" elseif i < n then \"/\" else \"\\n\" endif | i, j in 1..n];"
+    );
+    model
+}
+
+fn flatzinc_model(constraints: &Constraints, semantics: &str) -> String {
+    let size = constraints.size;
+    let name = |(i, j): (usize, usize)| format!("x_{}_{}", i + 1, j + 1);
+    let mut model = String::new();
+    let _ = writeln!(model, "% Sudodle puzzle of size {} ({})", size, semantics);
+    for tile in cells(size) {
+        let domain = cell_domain(constraints, tile).iter().map(usize::to_string).collect::<Vec<_>>();
+        let _ = writeln!(model, "var {{{}}}: {};", domain.join(","), name(tile));
+    }
+    let names = cells(size).map(name).collect::<Vec<_>>();
+    let _ = writeln!(
+        model,
+        "array [1..{}] of var int: grid :: output_array([1..{}, 1..{}]) = [{}];",
+        size * size,
+        size,
+        size,
+        names.join(", ")
+    );
+    for a in cells(size) {
+        for b in cells(size).filter(|&b| b > a && (b.0 == a.0 || b.1 == a.1)) {
+            let _ = writeln!(model, "constraint int_ne({}, {});", name(a), name(b));
+        }
+    }
+    let _ = writeln!(model, "solve satisfy;");
+    model
+}
+
+fn xcsp3_instance(constraints: &Constraints, semantics: &str) -> String {
+    let size = constraints.size;
+    let mut instance = String::new();
+    let _ = writeln!(instance, "<instance format=\"XCSP3\" type=\"CSP\">");
+    let _ = writeln!(instance, "  <!-- Sudodle puzzle of size {} ({}) -->", size, semantics);
+    let _ = writeln!(instance, "  <variables>");
+    let _ = writeln!(instance, "    <array id=\"grid\" size=\"[{}][{}]\"> 1..{} </array>", size, size, size);
+    let _ = writeln!(instance, "  </variables>");
+    let _ = writeln!(instance, "  <constraints>");
+    let _ = writeln!(instance, "    <allDifferent>");
+    let _ = writeln!(instance, "      <matrix> grid[][] </matrix>");
+    let _ = writeln!(instance, "    </allDifferent>");
+    let placed: Vec<(usize, usize)> = cells(size).filter(|&tile| constraints.known[tile] != 0).collect();
+    if !placed.is_empty() {
+        let list = placed.iter().map(|&(i, j)| format!("grid[{}][{}]", i, j)).collect::<Vec<_>>();
+        let values = placed.iter().map(|&tile| constraints.known[tile].to_string()).collect::<Vec<_>>();
+        let _ = writeln!(instance, "    <instantiation>");
+        let _ = writeln!(instance, "      <list> {} </list>", list.join(" "));
+        let _ = writeln!(instance, "      <values> {} </values>", values.join(" "));
+        let _ = writeln!(instance, "    </instantiation>");
+    }
+    for (i, j) in cells(size) {
+        let wrong: Vec<String> = (1..=size)
+            .filter(|&value| constraints.wrong[i * size + j] & 1 << (value - 1) != 0)
+            .map(|value| value.to_string())
+            .collect();
+        if !wrong.is_empty() {
+            let _ = writeln!(instance, "    <extension>");
+            let _ = writeln!(instance, "      <list> grid[{}][{}] </list>", i, j);
+            let _ = writeln!(instance, "      <conflicts> {} </conflicts>", wrong.join(" "));
+            let _ = writeln!(instance, "    </extension>");
+        }
+    }
+    let _ = writeln!(instance, "  </constraints>");
+    let _ = writeln!(instance, "</instance>");
+    instance
+}
+
+/// Read the grid of a solution printed by an XCSP3 solver for an exported
+/// instance: the `<instantiation>` of its `v` lines (or of a solution file).
+///
+/// The list of variables can be `grid[][]`, for every cell row by row, or the
+/// cells one by one in any order.
+pub fn parse_xcsp3_solution(text: &str) -> Result<Grid, String> {
+    let text: String = text
+        .lines()
+        .map(|line| line.trim_start().strip_prefix("v ").unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let element = |tag: &str| -> Result<&str, String> {
+        let start = text.find(&format!("<{}>", tag)).ok_or_else(|| format!("no <{}> in the solution", tag))?;
+        let content = &text[start + tag.len() + 2..];
+        let end = content.find(&format!("</{}>", tag)).ok_or_else(|| format!("no </{}> in the solution", tag))?;
+        Ok(&content[..end])
+    };
+    let values: Vec<usize> = element("values")?
+        .split_whitespace()
+        .map(|value| value.parse().map_err(|_| format!("'{}' is not a value", value)))
+        .collect::<Result<_, _>>()?;
+    let size = (1..=MAX_SIZE)
+        .find(|size| size * size == values.len())
+        .ok_or_else(|| format!("expected the N×N values of a grid, got {} values", values.len()))?;
+    let list: Vec<&str> = element("list")?.split_whitespace().collect();
+    let tiles: Vec<(usize, usize)> = if list == ["grid[][]"] {
+        cells(size).collect()
+    } else {
+        list.iter().map(|variable| parse_cell(variable, size)).collect::<Result<_, _>>()?
+    };
+    if tiles.len() != values.len() {
+        return Err(format!("{} variables but {} values", tiles.len(), values.len()));
+    }
+    let mut rows = vec![vec!['0'; size]; size];
+    for (&(i, j), &value) in tiles.iter().zip(&values) {
+        if !(1..=size).contains(&value) {
+            return Err(format!("{} is not a value", value));
+        }
+        rows[i][j] = output::value_char(value as u8);
+    }
+    output::parse_grid(&rows.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("/"))
+}
+
+/// Cell of a variable `grid[i][j]` of an instance of size `size`.
+fn parse_cell(variable: &str, size: usize) -> Result<(usize, usize), String> {
+    let indices = variable.strip_prefix("grid[").and_then(|rest| rest.strip_suffix(']'));
+    let (i, j) = indices.and_then(|indices| indices.split_once("][")).ok_or_else(|| format!("unknown variable {}", variable))?;
+    match (i.parse(), j.parse()) {
+        (Ok(i), Ok(j)) if i < size && j < size => Ok((i, j)),
+        _ => Err(format!("unknown variable {}", variable)),
+    }
+}
+
+/// Every cell of an N×N grid, row by row.
+fn cells(size: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..size).flat_map(move |i| (0..size).map(move |j| (i, j)))
+}
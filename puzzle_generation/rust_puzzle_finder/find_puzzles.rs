@@ -1,10 +1,49 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::OpenOptions;
-use std::io::{BufWriter, Write};
-use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use clap::Parser;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use clap::parser::ValueSource;
+use clap::{Args as ClapArgs, ArgMatches, CommandFactory, Parser, Subcommand, ValueEnum};
 use rand::prelude::*;
+use rayon::prelude::*;
+use tracing_subscriber::filter::LevelFilter;
+
+use puzzle_finder::bounds;
+use puzzle_finder::certificate;
+use puzzle_finder::checkpoint::Checkpoint;
+use puzzle_finder::classes;
+use puzzle_finder::cluster::{self, CoordinatorOptions, Job};
+#[cfg(feature = "sqlite")]
+use puzzle_finder::db;
+use puzzle_finder::completions;
+use puzzle_finder::critical;
+use puzzle_finder::cycles::{self, LineKind};
+use puzzle_finder::export::{self, ModelFormat};
+use puzzle_finder::first_guess::{self, FirstGuessSettings, FirstGuessTable};
+use puzzle_finder::generators::{self, AnnealOptions, Generator};
+use puzzle_finder::hints::{self, Hint, TileHint};
+use puzzle_finder::index::ResultsIndex;
+use puzzle_finder::neighbors::{self, Neighbor};
+use puzzle_finder::output::{self, Compression, OutputFormat, PuzzleSink};
+use puzzle_finder::render::{self, RenderOptions};
+use puzzle_finder::repair::{self, RepairOptions};
+use puzzle_finder::robustness;
+use puzzle_finder::pool::{PoolOptions, PuzzlePool};
+use puzzle_finder::profile::{self, Profile};
+use puzzle_finder::qcp::{self, Family};
+use puzzle_finder::server;
+use puzzle_finder::sheet::{self, SheetPuzzle};
+use puzzle_finder::simulate::{self, GuessCounts, SecretSource, SimulationOptions, Strategy};
+use puzzle_finder::summary::{CombinedSummary, RunSummary};
+use puzzle_finder::superset::SupersetFilter;
+use puzzle_finder::symmetry;
+use puzzle_finder::techniques::{self, TechniqueSet};
+use puzzle_finder::trace::{self, SearchObserver, Tracer};
+use puzzle_finder::witness;
+use puzzle_finder::*;
 
 #[derive(Parser)]
 #[command(
@@ -13,13 +52,54 @@ use rand::prelude::*;
     version = "1.0"
 )]
 struct Args {
-    /// Size of the Latin square (5-9)
-    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=9))]
-    size: u8,
-    
-    /// Number of tiles to place as correct values
+    #[command(subcommand)]
+    command: Command,
+
+    /// Most verbose log messages to print: off, error, warn, info, debug or trace
+    #[arg(long, global = true, default_value = "info")]
+    log_level: LevelFilter,
+
+    /// Format of the log messages written to standard error
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(ClapArgs)]
+struct SearchArgs {
+    /// TOML file of search settings keyed by flag name, e.g. `random-tries = 1000`
+    /// (flags given on the command line take precedence)
     #[arg(long)]
-    placed: usize,
+    config: Option<String>,
+
+    /// Size of the Latin square (3-16; grids above 9 write the values 10 to 16 as A to G)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64),
+          required_unless_present_any = ["sizes", "worker"])]
+    size: Option<u8>,
+
+    /// Search several sizes one after the other instead, separated by commas, writing one
+    /// output file per size (e.g. puzzles.s5.txt for --out-file puzzles.txt)
+    #[arg(long, value_delimiter = ',', conflicts_with = "size",
+          value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    sizes: Vec<u8>,
+    
+    /// Number of tiles to place as correct values (with the top-down generator, the
+    /// fewest tiles a run may stop at); with --sizes, one value for every size or one
+    /// per size, separated by commas
+    #[arg(long, value_delimiter = ',', required_unless_present_any = ["worker", "find_minimum"])]
+    placed: Vec<usize>,
+
+    /// Look for the fewest placed tiles of a unique puzzle instead: start from the smallest
+    /// of --random-tries top-down runs, then try one fewer tile at a time with as many
+    /// annealing runs, printing an example puzzle for each count found (and writing them to
+    /// --out-file) until a count has none or the lower bound of `analyze` is reached
+    #[arg(long, conflicts_with_all = ["sizes", "placed", "worker", "generator"])]
+    find_minimum: bool,
+
+    /// Search the ranges handed out by the `coordinate` process at this address instead,
+    /// until it has none left; the settings of the search come from the coordinator, only
+    /// --processors and the solver settings apply
+    #[arg(long, value_name = "HOST:PORT", conflicts_with_all = ["size", "sizes"])]
+    worker: Option<String>,
     
     /// Optional output file path
     #[arg(long)]
@@ -29,869 +109,3336 @@ struct Args {
     #[arg(long, default_value = "10")]
     processors: usize,
     
-    /// Number of random tile combinations to try (alternative to exhaustive search)
+    /// Number of random tile combinations to try (alternative to exhaustive search); sizes
+    /// above 9 default to 20 top-down runs
     #[arg(long)]
     random_tries: Option<usize>,
+
+    /// Format of the records written to the output file
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Compress the output file (line-based formats only)
+    #[arg(long, value_enum)]
+    compress: Option<Compression>,
+
+    /// Optional path to write the end-of-run summary to, as JSON
+    #[arg(long)]
+    summary: Option<String>,
+
+    /// Stop as soon as one single-solution puzzle is found
+    #[arg(long)]
+    first_only: bool,
+
+    /// Write the puzzles in the order of their combinations instead of as the threads find
+    /// them, with zero solve times, so that runs with the same settings write the same files
+    /// (random bottom-up searches then draw their combinations from --generator-seed)
+    #[arg(long)]
+    deterministic_output: bool,
+
+    /// Append to the output file instead of overwriting it, skipping puzzles it already contains
+    #[arg(long)]
+    append: bool,
+
+    /// Random mode only: save the state of the sampling to this file every few batches,
+    /// and continue from it if it exists, never drawing a combination tried before
+    #[arg(long, value_name = "FILE", requires = "random_tries")]
+    checkpoint: Option<String>,
+
+    /// Random mode only: share of the combinations never drawn that the Bloom filter of
+    /// tried combinations mistakes for tried ones and skips (smaller takes more memory)
+    #[arg(long, value_name = "RATE", requires = "random_tries")]
+    tried_false_positive_rate: Option<f64>,
+
+    /// Rewrite the counters of the search (combinations per second, solver nodes, puzzles
+    /// found) to this file after every batch, in the Prometheus text format
+    #[arg(long, value_name = "FILE")]
+    metrics_file: Option<PathBuf>,
+
+    /// Keep the approximate memory of the search under this many megabytes, by moving the
+    /// puzzles found out of memory (to a spill file without --out-file or --out-db) and
+    /// shrinking the batches
+    #[arg(long, value_name = "MB")]
+    max_memory_mb: Option<usize>,
+
+    /// Only keep the tiles of the puzzles found, not their solutions, to save memory
+    /// (solutions are solved again where needed, e.g. by --cross-check)
+    #[arg(long, conflicts_with = "out_db")]
+    no_solutions: bool,
+
+    /// Optional SQLite database to insert puzzles into (deduplicated across runs)
+    #[arg(long)]
+    out_db: Option<String>,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+
+    /// Only keep puzzles that can be solved with naked and hidden singles alone
+    #[arg(long)]
+    require_singles_solvable: bool,
+
+    /// Abandon (and log) any combination whose uniqueness check takes longer than this
+    #[arg(long)]
+    per_puzzle_timeout_ms: Option<u64>,
+
+    /// Exhaustive search only: enumerate combinations so that successive ones differ by
+    /// a single tile and update the clues in place instead of rebuilding them
+    #[arg(long, conflicts_with = "random_tries")]
+    incremental: bool,
+
+    /// Skip the combinations containing a puzzle of this file, e.g. the unique puzzles
+    /// found with fewer placed tiles (can be repeated)
+    #[arg(long, value_name = "FILE")]
+    drop_supersets_of: Vec<String>,
+
+    /// Random mode only: draw combinations with at most this many tiles in any row or column
+    #[arg(long, requires = "random_tries")]
+    max_per_line: Option<usize>,
+
+    /// How candidate clue sets are built (constructors other than bottom-up run once per
+    /// random try); defaults to bottom-up, or top-down above size 9, whose combinations are
+    /// too many to test
+    #[arg(long, value_enum)]
+    generator: Option<Generator>,
+
+    /// Seed of the constructor runs
+    #[arg(long, default_value = "0")]
+    generator_seed: u64,
+
+    /// Re-check this many of the puzzles found, picked at random, with the slow reference
+    /// solver, and fail if any of them is not confirmed
+    #[arg(long, value_name = "SAMPLE")]
+    cross_check: Option<usize>,
+
+    /// What the tiles that are not placed say: wrong where they stand, as in the game, or
+    /// nothing, for classic puzzles where only the placed tiles are given; defaults to the
+    /// game's, or givens-only above size 9, where unique puzzles of the game are too rare
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Latin square whose tiles the puzzles place: the cyclic square of the game, a
+    /// row-complete square (even sizes only), a symmetric square, an idempotent
+    /// square (every size but 2), a pandiagonal square (sizes 5 and 7), a
+    /// non-consecutive square (sizes 5 up) or a Sudoku solution
+    #[arg(long, value_enum, default_value_t = BaseGrid::Cyclic)]
+    base_grid: BaseGrid,
+
+    /// Rules the solutions follow on top of those of Latin squares: none, each value once
+    /// on every broken diagonal and anti-diagonal, no consecutive values in orthogonally
+    /// adjacent cells, or each value once in every box, e.g. 4x4 boxes for 16x16 grids
+    /// (needs a base grid following them, such as the matching --base-grid)
+    #[arg(long, value_enum, default_value_t = Variant::Latin, conflicts_with = "no_solutions")]
+    variant: Variant,
+
+    /// Play this many simulated games of each puzzle found with the random-consistent
+    /// strategy, and record the mean, median and 90th percentile of the guesses they take
+    #[arg(long, value_name = "GAMES")]
+    expected_guesses: Option<usize>,
+
+    /// With --semantics givens-only: record the cells where a second solution differs
+    /// from the base grid, and skip the combinations placing no tile on them
+    #[arg(long)]
+    nogoods: bool,
+
+    /// Count the solutions of the combinations with two, up to 4, and classify how the
+    /// closest two differ (an intercalate, a 3-cycle or a longer cycle of two lines, or
+    /// something else) in the summary, to see which trades pre-filters could rule out
+    #[arg(long)]
+    duplicate_structure: bool,
+
+    /// Skip the combinations recorded in this file as evaluated by previous runs of the
+    /// same search, unique or not, and record there each combination this run solves
+    /// (created if missing)
+    #[arg(long, value_name = "FILE")]
+    results_index: Option<PathBuf>,
+
+    #[command(flatten)]
+    anneal: AnnealArgs,
+}
+
+/// Settings of `--generator anneal`.
+#[derive(ClapArgs)]
+struct AnnealArgs {
+    /// Clue swaps tried per annealing run
+    #[arg(long, default_value_t = AnnealOptions::default().steps)]
+    anneal_steps: usize,
+
+    /// Temperature of the first annealing step, in units of log solution count
+    #[arg(long, default_value_t = AnnealOptions::default().start_temperature)]
+    anneal_start_temperature: f64,
+
+    /// Temperature of the last annealing step
+    #[arg(long, default_value_t = AnnealOptions::default().end_temperature)]
+    anneal_end_temperature: f64,
+
+    /// Count the solutions of each clue set up to this many
+    #[arg(long, default_value_t = AnnealOptions::default().max_solutions)]
+    anneal_max_solutions: usize,
+}
+
+/// Largest size searched by default like the game's puzzles, bottom-up. Larger
+/// grids have too many combinations to test, and the game's semantics make
+/// too few of them unique (none of thousands of random 16x16 Sudoku clue
+/// sets): their searches build classic puzzles top-down instead.
+const MAX_BOTTOM_UP_SIZE: usize = 9;
+
+/// Top-down runs of the searches above [`MAX_BOTTOM_UP_SIZE`] by default
+/// (each takes minutes for a 16x16 grid).
+const DEFAULT_TOP_DOWN_RUNS: usize = 20;
+
+impl SearchArgs {
+    /// The generator of the search of `size`, see [`MAX_BOTTOM_UP_SIZE`].
+    fn generator(&self, size: usize) -> Generator {
+        self.generator.unwrap_or(if size > MAX_BOTTOM_UP_SIZE { Generator::TopDown } else { Generator::BottomUp })
+    }
+
+    /// The semantics of the search of `size`, see [`MAX_BOTTOM_UP_SIZE`].
+    fn semantics(&self, size: usize) -> ClueSemantics {
+        let default = if size > MAX_BOTTOM_UP_SIZE { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        self.semantics.unwrap_or(default)
+    }
+
+    /// The random tries (or constructor runs) of the search of `size`.
+    fn random_tries(&self, size: usize) -> Option<usize> {
+        let default_runs = self.generator.is_none() && size > MAX_BOTTOM_UP_SIZE;
+        self.random_tries.or(default_runs.then_some(DEFAULT_TOP_DOWN_RUNS))
+    }
+}
+
+impl AnnealArgs {
+    fn options(&self) -> AnnealOptions {
+        AnnealOptions {
+            steps: self.anneal_steps,
+            start_temperature: self.anneal_start_temperature,
+            end_temperature: self.anneal_end_temperature,
+            max_solutions: self.anneal_max_solutions,
+        }
+    }
+}
+
+/// Format of the log messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per message, with the fields of its spans.
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search for puzzles with a unique solution
+    Search(Box<SearchArgs>),
+    /// Solve a single puzzle and report its solver statistics
+    Solve(SolveArgs),
+    /// Check that every puzzle of a file has a unique solution
+    Verify(VerifyArgs),
+    /// Find the fewest tiles to add to a puzzle so that it has a unique solution
+    Repair(RepairArgs),
+    /// Classify the clue sets within a few added or removed tiles of a puzzle, keeping the unique ones
+    Neighbors(NeighborsArgs),
+    /// Check which moves of each tile along its row and column keep the puzzles of a file unique
+    Robustness(RobustnessArgs),
+    /// Count the completions of a single clue set
+    Stats(StatsArgs),
+    /// Solve every puzzle of a file and write its solver metrics as CSV
+    Rate(RateArgs),
+    /// Solve every puzzle of a file, timing the solver by search depth and by technique
+    Profile(ProfileArgs),
+    /// Pack the puzzles of files into the web game's puzzle list, with difficulty levels
+    Pack(PackArgs),
+    /// Write the puzzles of files as the web game's puzzle data, by size and level
+    ExportWeb(ExportWebArgs),
+    /// Merge puzzle files from several runs, keeping each puzzle once
+    Dedupe(DedupeArgs),
+    /// Print the puzzle of the day from a puzzle file
+    Daily(DailyArgs),
+    /// Answer uniqueness checks over HTTP
+    Serve(ServeArgs),
+    /// Hand out the ranges of a search to `search --worker` processes and collect their puzzles
+    Coordinate(CoordinateArgs),
+    /// Play a puzzle in the terminal
+    Play(PlayArgs),
+    /// Compare solver strategies on random tile combinations
+    Bench(BenchArgs),
+    /// Solve random quasigroup completion benchmark instances (QCP or QWH) and report how the solver does
+    BenchQcp(BenchQcpArgs),
+    /// Sample puzzles from a SQLite puzzle database
+    Query(QueryArgs),
+    /// Probe each number of placed tiles to see whether unique puzzles exist
+    Table(TableArgs),
+    /// Convert a solver trace written by `solve --trace` to a Graphviz DOT graph
+    TraceDot(TraceDotArgs),
+    /// Find minimal sets of mixed hints (correct and wrong tiles) for the puzzles of a file
+    ///
+    /// There are no hints that a value belongs elsewhere in its row: every value belongs in every
+    /// row of a Latin square, so such a hint would say no more than a wrong tile.
+    Hints(HintsArgs),
+    /// Play simulated games with a guessing strategy and report how many guesses they take
+    Simulate(SimulateArgs),
+    /// Print the opening guess whose feedback tells the most about the secret, for each size
+    FirstGuess(FirstGuessArgs),
+    /// Draw a puzzle, and optionally its solution, as an SVG or PNG image
+    Render(RenderArgs),
+    /// Lay out the puzzles of a file on printable PDF pages, followed by their answers
+    Print(PrintArgs),
+    /// Write a puzzle as a MiniZinc, FlatZinc or XCSP3 model for other constraint solvers
+    Export(ExportArgs),
+    /// Read the solution of an exported XCSP3 instance back into a grid
+    Import(ImportArgs),
+    /// Describe the cycles of the pairs of rows, columns and values of a base grid, and the
+    /// fewest placed tiles they prove a unique classic puzzle needs
+    Analyze(AnalyzeArgs),
+    /// Find the smallest critical sets of the cyclic square: minimal classic puzzles
+    CriticalSets(CriticalSetsArgs),
+    /// Find the fewest placed tiles of a unique puzzle for every main class of Latin squares of a size
+    Classes(ClassesArgs),
+}
+
+#[derive(ClapArgs)]
+struct ClassesArgs {
+    /// Size of the Latin squares
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=classes::MAX_CLASS_SIZE as i64))]
+    size: u8,
+
+    /// Give up on a class beyond this many placed tiles [default: every tile]
+    #[arg(long)]
+    max_placed: Option<usize>,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct CriticalSetsArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Test every clue set even above size 5, instead of greedy removal runs
+    #[arg(long, conflicts_with = "greedy")]
+    exact: bool,
+
+    /// Use greedy removal runs even up to size 5, which may miss the smallest sets
+    #[arg(long)]
+    greedy: bool,
+
+    /// Number of greedy removal runs
+    #[arg(long, default_value = "1000")]
+    runs: usize,
+
+    /// Seed of the order in which greedy runs remove tiles
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Most critical sets to write
+    #[arg(long, default_value = "100")]
+    limit: usize,
+
+    /// Optional path to write the critical sets to, in the text format [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct AnalyzeArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Latin square to analyze (see `search --help`)
+    #[arg(long, value_enum, default_value_t = BaseGrid::Cyclic)]
+    base_grid: BaseGrid,
+
+    /// Nodes of the search for the largest set of disjoint cycles, past which the
+    /// largest set found gives the bound
+    #[arg(long, default_value_t = bounds::DEFAULT_NODE_BUDGET)]
+    node_budget: usize,
+}
+
+#[derive(ClapArgs)]
+struct ImportArgs {
+    /// Output of the XCSP3 solver, or a file holding its solution
+    file: String,
+
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Placed tiles of the exported puzzle, to check that the solution completes it
+    #[arg(long)]
+    tiles: Option<String>,
+
+    /// What the tiles that are not placed say, as given to `export`
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+}
+
+#[derive(ClapArgs)]
+struct RenderArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Placed tiles, in the format of the text output, e.g. "(0,1), (2,3)"
+    #[arg(long)]
+    tiles: String,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Draw the solution in the cells that are not placed
+    #[arg(long)]
+    solution: bool,
+
+    /// Width and height of a cell, in pixels
+    #[arg(long, default_value_t = RenderOptions::default().cell_size, value_parser = clap::value_parser!(u32).range(10..))]
+    cell_size: u32,
+
+    /// Optional path to write the image to, a PNG image if it ends in .png [default: SVG on standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct PrintArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    #[arg(long)]
+    input: String,
+
+    /// Size of the Latin square [default: from the header of the file]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// What the tiles that are not placed say [default: from the header of the file]
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Number of puzzles on each page
+    #[arg(long, default_value = "6", value_parser = clap::value_parser!(u32).range(1..=30))]
+    per_page: u32,
+
+    /// Print only the first puzzles of the file
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Leave out the pages of answers
+    #[arg(long)]
+    no_answers: bool,
+
+    /// Path of the PDF document
+    #[arg(long)]
+    output: String,
+}
+
+#[derive(ClapArgs)]
+struct ExportArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Placed tiles, in the format of the text output, e.g. "(0,1), (2,3)"
+    #[arg(long)]
+    tiles: String,
+
+    /// Language of the model
+    #[arg(long, value_enum, default_value_t = ModelFormat::Minizinc)]
+    format: ModelFormat,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Optional path to write the model to [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct FirstGuessArgs {
+    /// Sizes of the Latin square, separated by commas
+    #[arg(long, value_delimiter = ',', default_values_t = [4, 5, 6],
+          value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    sizes: Vec<u8>,
+
+    /// Reduced Latin squares to try as opening guesses
+    #[arg(long, default_value_t = FirstGuessSettings::default().candidates)]
+    candidates: usize,
+
+    /// Secrets to rate the guesses against: every Latin square of the size if there are
+    /// at most this many, otherwise this many random ones
+    #[arg(long, default_value_t = FirstGuessSettings::default().secrets)]
+    secrets: usize,
+
+    /// Seed of the random secrets
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// JSON file caching the best guesses found, read and updated
+    #[arg(long, default_value = "first_guesses.json")]
+    cache: String,
+
+    /// Search again even if the cache holds the sizes for these settings
+    #[arg(long)]
+    refresh: bool,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct SimulateArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Secret grid, row by row with `/` between rows, e.g. "123/231/312"
+    /// [default: a random secret for each game]
+    #[arg(long)]
+    secret: Option<String>,
+
+    /// Draw the random secrets nearly uniformly among the Latin squares of the size, instead
+    /// of completing an empty square with a random value order (quicker, but favouring some)
+    #[arg(long, conflicts_with = "secret")]
+    uniform_secrets: bool,
+
+    /// How the simulated player picks its guesses
+    #[arg(long, value_enum, default_value_t = Strategy::RandomConsistent)]
+    strategy: Strategy,
+
+    /// Number of games to play
+    #[arg(long, default_value = "1000")]
+    trials: usize,
+
+    /// Consistent grids drawn at each guess by the entropy-greedy and human-heuristic strategies
+    #[arg(long, default_value_t = SimulationOptions::default().samples)]
+    samples: usize,
+
+    /// Play the relaxed variant: guesses need not be Latin squares, and are only consistent
+    /// with the feedback row by row
+    #[arg(long)]
+    relaxed: bool,
+
+    /// Seed of the games
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Optional path to write the guesses of every game to, as JSON lines
+    #[arg(long, conflicts_with = "hardest")]
+    transcripts: Option<String>,
+
+    /// Look for the secrets taking the most guesses on average instead, and print this
+    /// many of them (each candidate secret is played --trials times)
+    #[arg(long, conflicts_with = "secret")]
+    hardest: Option<usize>,
+
+    /// Number of candidate secrets with --hardest
+    #[arg(long, default_value = "100", requires = "hardest")]
+    candidates: usize,
+
+    /// Where the candidate secrets of --hardest come from
+    #[arg(long, value_enum, default_value_t = SecretSource::Random, requires = "hardest")]
+    secrets: SecretSource,
+
+    /// Optional path to write the secrets found with --hardest to, as JSON lines
+    #[arg(long, requires = "hardest")]
+    out_file: Option<String>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct HintsArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed), whose
+    /// solutions are the secrets to give hints about
+    file: String,
+
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Hint sets to build for each puzzle, dropping hints in a different random order each time
+    #[arg(long, default_value = "10")]
+    runs: usize,
+
+    /// Seed of the order in which hints are dropped
+    #[arg(long, default_value = "0")]
+    hint_seed: u64,
+
+    /// Optional path to write the hint sets to [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct VerifyArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    file: String,
+
+    /// Size of the Latin square [default: from the header of the file]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    /// Also count the solutions of every puzzle with the slow reference solver and
+    /// report the puzzles where both solvers disagree
+    #[arg(long)]
+    cross_check: bool,
+
+    /// What the tiles that are not placed say, as given to `search` [default: from the header
+    /// of the file, sudodle otherwise]
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Solutions compared to find the fewest cells on which two solutions of a puzzle
+    /// without a unique solution differ
+    #[arg(long, default_value_t = witness::DEFAULT_WITNESS_SOLUTIONS)]
+    witness_solutions: usize,
+
+    /// Solve every puzzle, even those whose record holds a certificate of uniqueness
+    #[arg(long)]
+    ignore_certificates: bool,
+}
+
+#[derive(ClapArgs)]
+struct RepairArgs {
+    /// File holding the puzzle as JSON, e.g. {"size": 5, "tiles": [[0, 0], [0, 1]]}, or in dot notation
+    #[arg(long)]
+    input: String,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Most tiles to add
+    #[arg(long, default_value_t = RepairOptions::default().max_extra)]
+    max_extra: usize,
+
+    /// Most repairs to print, the easiest first
+    #[arg(long, default_value_t = RepairOptions::default().limit)]
+    limit: usize,
+
+    /// Solutions compared to find the cells on which they differ
+    #[arg(long, default_value_t = witness::DEFAULT_WITNESS_SOLUTIONS)]
+    witness_solutions: usize,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct NeighborsArgs {
+    /// File holding the puzzle as JSON, e.g. {"size": 5, "tiles": [[0, 0], [0, 1]]}, or in dot notation
+    #[arg(long)]
+    input: String,
+
+    /// Most tiles added to or removed from the puzzle
+    #[arg(long, default_value = "1")]
+    distance: usize,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Optional path to write the unique neighbors to, in the format of its extension
+    /// (.txt, .jsonl, .parquet, or CSV otherwise)
+    #[arg(long)]
+    out_file: Option<String>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct RobustnessArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    file: String,
+
+    /// Size of the Latin square [default: from the header of the file]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// What the tiles that are not placed say [default: from the header of the file]
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Also list the moves of each tile that keep the puzzle unique
+    #[arg(long)]
+    details: bool,
+
+    /// Sort the puzzles by decreasing wiggle room
+    #[arg(long)]
+    sort: bool,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct StatsArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Placed tiles, in the format of the text output, e.g. "(0,1), (2,3)"
+    #[arg(long)]
+    tiles: String,
+
+    /// Stop counting beyond this number of completions, when they are enumerated
+    #[arg(long, default_value = "100000")]
+    max_solutions: usize,
+
+    /// Enumerate the completions with the solver even for sizes up to 6, which are
+    /// otherwise counted exactly without listing them
+    #[arg(long, conflicts_with = "estimate")]
+    enumerate: bool,
+
+    /// Estimate the number of completions from this many random probes of the search
+    /// tree instead, for clue sets with too many completions to count
+    #[arg(long)]
+    estimate: Option<usize>,
+
+    /// Seed of the probes of --estimate
+    #[arg(long, default_value = "0", requires = "estimate")]
+    seed: u64,
+
+    /// Latin square whose tiles the puzzle places (see `search --help`)
+    #[arg(long, value_enum, default_value_t = BaseGrid::Cyclic)]
+    base_grid: BaseGrid,
+}
+
+#[derive(ClapArgs)]
+struct RateArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    #[arg(long)]
+    input: String,
+
+    /// Size of the Latin square [default: from the header of the file]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// Optional path to write the rated puzzles to [default: standard output]
+    #[arg(long)]
+    output: Option<String>,
+
+    /// Format of the rated puzzles [default: from the extension of --output, CSV otherwise]
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Sort the puzzles by increasing difficulty
+    #[arg(long)]
+    sort: bool,
+
+    /// Write one file per level instead, e.g. rated.hard.jsonl for --output rated.jsonl
+    #[arg(long, requires = "output")]
+    split: bool,
+
+    /// Play this many simulated games of each puzzle, as with `search --expected-guesses`
+    #[arg(long, value_name = "GAMES")]
+    expected_guesses: Option<usize>,
+
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct ProfileArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    #[arg(long)]
+    input: String,
+
+    /// Size of the Latin square [default: from the header of the file]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// What the tiles that are not placed say, as given to `search` [default: from the header
+    /// of the file, sudodle otherwise]
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Optional path to write the profile to as folded stacks, for flame graph tools
+    #[arg(long)]
+    folded: Option<String>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct PackArgs {
+    /// Puzzle files in the text, CSV or JSON lines format (optionally compressed)
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Size of the Latin square, up to 9: puzzle IDs write it as one digit
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    /// Optional path to write the puzzle list to [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+}
+
+/// Format of the web game's puzzle data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum WebFormat {
+    /// A JSON object
+    Json,
+    /// A JavaScript module exporting `puzzleData`, replacing `sudodle/src/lib/puzzleData.js`
+    Js,
+    /// A TypeScript module exporting `puzzleData` with its type
+    Ts,
+}
+
+#[derive(ClapArgs)]
+struct ExportWebArgs {
+    /// Puzzle files of the game's puzzles in the text, CSV or JSON lines format (optionally compressed)
+    #[arg(required = true)]
+    files: Vec<String>,
+
+    /// Size of the Latin square, for files without a header (up to 9: puzzle IDs write it as one digit)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    /// Format of the puzzle data [default: from the extension of --out-file, JSON otherwise]
+    #[arg(long, value_enum)]
+    format: Option<WebFormat>,
+
+    /// Optional path to write the puzzle data to [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct DedupeArgs {
+    /// Puzzle files in the same text, CSV or JSON lines format (optionally compressed)
+    #[arg(long, num_args = 1.., required = true)]
+    inputs: Vec<String>,
+
+    /// Optional path to write the merged puzzles to [default: standard output]
+    #[arg(long)]
+    output: Option<String>,
+
+    /// Size of the Latin square [default: from the headers of the files]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// What the tiles that are not placed say [default: from the headers of the files]
+    #[arg(long, value_enum)]
+    semantics: Option<ClueSemantics>,
+
+    /// Also drop puzzles equivalent to one kept under a symmetry of the cyclic square
+    /// (shifting rows and columns, scaling their indices, transposing)
+    #[arg(long)]
+    symmetry: bool,
+}
+
+/// Difficulty thresholds of the web game's levels.
+#[derive(ClapArgs)]
+struct LevelArgs {
+    /// Smallest difficulty (number of guesses) rated `hard`
+    #[arg(long, default_value = "2")]
+    hard_from: usize,
+
+    /// Smallest difficulty (number of guesses) rated `expert`
+    #[arg(long, default_value = "5")]
+    expert_from: usize,
+}
+
+/// Levels of the web game, from the easiest.
+const LEVELS: [&str; 3] = ["normal", "hard", "expert"];
+
+impl LevelArgs {
+    fn validate(&self) -> Result<(), String> {
+        if self.hard_from > self.expert_from {
+            return Err("--hard-from cannot be above --expert-from".to_string());
+        }
+        Ok(())
+    }
+
+    fn level(&self, difficulty: usize) -> &'static str {
+        if difficulty >= self.expert_from {
+            LEVELS[2]
+        } else if difficulty >= self.hard_from {
+            LEVELS[1]
+        } else {
+            LEVELS[0]
+        }
+    }
+}
+
+#[derive(ClapArgs)]
+struct DailyArgs {
+    /// Puzzle file in the text, CSV or JSON lines format (optionally compressed)
+    file: String,
+
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Day to pick the puzzle of, as YYYY-MM-DD [default: today, in UTC]
+    #[arg(long)]
+    date: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+
+    /// Answer at most this many connections at a time, turning the others away with
+    /// `503 Service Unavailable`
+    #[arg(long, default_value = "64", value_parser = clap::value_parser!(u32).range(1..))]
+    max_connections: u32,
+
+    /// Keep this many verified puzzles ready for each size and level, for `GET /puzzle`
+    /// [default: no pool]
+    #[arg(long)]
+    pool_size: Option<usize>,
+
+    /// Sizes of the puzzles of the pool, separated by commas
+    #[arg(long, value_delimiter = ',', default_values_t = [4, 5, 6], requires = "pool_size",
+          value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    pool_sizes: Vec<u8>,
+
+    /// Seed of the greedy removal runs filling the pool
+    #[arg(long, default_value = "0", requires = "pool_size")]
+    pool_seed: u64,
+
+    /// Also add to the pool the family of each puzzle found: its images under the symmetries
+    /// of the cyclic square, served with the content ID of the puzzle and the symmetry
+    #[arg(long, requires = "pool_size")]
+    pool_families: bool,
+
+    #[command(flatten)]
+    levels: LevelArgs,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+#[derive(ClapArgs)]
+struct CoordinateArgs {
+    /// Address to listen on for workers
+    #[arg(long, default_value = "0.0.0.0:7878")]
+    addr: String,
+
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Number of tiles to place as correct values (with the top-down generator, the
+    /// fewest tiles a run may stop at)
+    #[arg(long)]
+    placed: usize,
+
+    /// Number of constructor runs to spread over the workers (without it, every
+    /// combination is tried)
+    #[arg(long)]
+    random_tries: Option<usize>,
+
+    /// How candidate clue sets are built; bottom-up searches try every combination
+    #[arg(long, value_enum, default_value_t = Generator::BottomUp)]
+    generator: Generator,
+
+    /// Seed of the constructor runs
+    #[arg(long, default_value = "0")]
+    generator_seed: u64,
+
+    /// What the tiles that are not placed say
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+
+    /// Latin square whose tiles the puzzles place (see `search --help`)
+    #[arg(long, value_enum, default_value_t = BaseGrid::Cyclic)]
+    base_grid: BaseGrid,
+
+    /// Only keep puzzles that can be solved with naked and hidden singles alone
+    #[arg(long)]
+    require_singles_solvable: bool,
+
+    /// Combinations (or constructor runs) in each range handed to a worker
+    #[arg(long, default_value = "1000000")]
+    range_size: u64,
+
+    /// Hand a range out again if its worker has not returned it after this many seconds
+    #[arg(long, default_value = "3600")]
+    lease_timeout_secs: u64,
+
+    /// Optional output file path
+    #[arg(long)]
+    out_file: Option<String>,
+
+    /// Format of the records written to the output file
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(ClapArgs)]
+struct PlayArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Placed tiles, in the format of the text output, e.g. "(0,1), (2,3)"
+    #[arg(long)]
+    tiles: String,
+}
+
+#[derive(ClapArgs)]
+struct BenchArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Number of tiles to place as correct values
+    #[arg(long)]
+    placed: usize,
+
+    /// Number of random tile combinations to solve with each strategy
+    #[arg(long, default_value = "1000")]
+    samples: usize,
+
+    /// Seed used to draw the combinations and for the random value ordering
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Propagation techniques used by every strategy
+    #[arg(long, default_value = "singles")]
+    techniques: TechniqueSet,
+
+    /// Compare these propagation thresholds (percentages of empty cells below which singles
+    /// propagate after a decision, or auto, comma-separated) instead of the value orders
+    #[arg(long, value_delimiter = ',')]
+    propagation_thresholds: Vec<PropagationThreshold>,
+
+    /// Compare these solver kernels (comma-separated) instead of the value orders
+    #[arg(long, value_enum, value_delimiter = ',')]
+    kernels: Vec<Kernel>,
+
+    /// What the tiles that are not placed say about the solution
+    #[arg(long, value_enum, default_value_t = ClueSemantics::Sudodle)]
+    semantics: ClueSemantics,
+}
+
+#[derive(ClapArgs)]
+struct BenchQcpArgs {
+    /// Order of the quasigroups, the size of the partial Latin squares (3-31)
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=qcp::MAX_ORDER as i64))]
+    size: u8,
+
+    /// Instances to solve: partial Latin squares filled with forward checking (qcp), or
+    /// random Latin squares with balanced holes (qwh)
+    #[arg(long, value_enum, default_value_t = Family::Qwh)]
+    family: Family,
+
+    /// Shares of the cells given by the instances, comma-separated, one row of the report each
+    #[arg(long, value_delimiter = ',', default_values_t = [0.3, 0.4, 0.5, 0.6, 0.7])]
+    fill: Vec<f64>,
+
+    /// Number of random instances solved for each share
+    #[arg(long, default_value = "100")]
+    instances: usize,
+
+    /// Seed used to draw the instances
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Propagation techniques used by the solver
+    #[arg(long, default_value = "singles")]
+    techniques: TechniqueSet,
+
+    /// Give up an instance after visiting this many search nodes, and count it as timed out
+    #[arg(long)]
+    node_budget: Option<usize>,
+
+    /// Restart each solve with a new random value order after this many search nodes,
+    /// times the Luby sequence
+    #[arg(long)]
+    restarts: Option<usize>,
+
+    /// Solve with conflict-driven clause learning instead of plain backtracking
+    #[arg(long)]
+    learning: bool,
+
+    /// Solve as an exact cover problem with dancing links instead of plain backtracking
+    #[arg(long)]
+    dlx: bool,
+
+    /// Race the backtracker, the backtracker with restarts, dancing links and clause learning
+    /// on each instance
+    #[arg(long)]
+    portfolio: bool,
+
+    /// Optional directory to write the instances to, in the `.pls` format of the
+    /// lsencode generator, for other solvers
+    #[arg(long)]
+    out_dir: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct SolveArgs {
+    /// Size of the Latin square
+    #[arg(long, required_unless_present = "stdin", value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: Option<u8>,
+
+    /// Placed tiles, in the format of the text output, e.g. "(0,1), (2,3)"
+    #[arg(long, required_unless_present = "stdin")]
+    tiles: Option<String>,
+
+    /// Read one puzzle per line from standard input, in dot notation or as JSON,
+    /// and write one JSON result per line to standard output
+    #[arg(long, conflicts_with_all = ["size", "tiles", "trace", "parallel"])]
+    stdin: bool,
+
+    /// Optional path to write the search trace to, as JSON lines
+    #[arg(long)]
+    trace: Option<String>,
+
+    /// Explore the candidates of the first branching cell on separate threads
+    #[arg(long)]
+    parallel: bool,
+
+    /// Number of processor threads to use with --parallel
+    #[arg(long, default_value = "10")]
+    processors: usize,
+
+    /// Give up after this many seconds (on each puzzle with --stdin)
+    #[arg(long)]
+    timeout: Option<f64>,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+}
+
+/// Solver settings shared by the commands that solve puzzles.
+#[derive(ClapArgs)]
+struct SolverArgs {
+    /// Propagation techniques the solver applies before guessing
+    /// (comma-separated techniques or presets: none, singles, subsets, full)
+    #[arg(long, default_value = "singles")]
+    techniques: TechniqueSet,
+
+    /// Propagate singles after a decision only once fewer than this percentage of the cells
+    /// are empty (100 after every decision), or auto for the fastest threshold measured for
+    /// the size and clues; difficulty ratings use the default
+    #[arg(long, default_value_t = PropagationThreshold::default())]
+    propagation_threshold: PropagationThreshold,
+
+    /// Order in which the candidates of a cell are tried
+    #[arg(long, value_enum, default_value_t = ValueOrder::Ascending)]
+    value_order: ValueOrder,
+
+    /// Seed of the random value ordering
+    #[arg(long, default_value = "0")]
+    seed: u64,
+
+    /// Break ties between equally constrained cells by picking the one with the most empty peers
+    #[arg(long)]
+    degree_tie_break: bool,
+
+    /// How the solver stores its state: arrays of a size fixed at compile time for sizes 5 to 9,
+    /// or vectors on the heap for every size; the searches are the same, only their speed differs
+    #[arg(long, value_enum, default_value_t = Kernel::Specialized)]
+    kernel: Kernel,
+
+    /// Give up on a puzzle after visiting this many search nodes, as if it timed out
+    #[arg(long)]
+    node_budget: Option<usize>,
+
+    /// Give up on a puzzle after this many milliseconds, as if it timed out (for `serve`, on
+    /// each request to `/solve`)
+    #[arg(long)]
+    solve_timeout_ms: Option<u64>,
+
+    /// Restart each solve with a new random value order after this many search nodes,
+    /// times the Luby sequence (1, 1, 2, 1, 1, 2, 4, ...), to cut the long tail of solve times
+    #[arg(long)]
+    restarts: Option<usize>,
+
+    /// Search with conflict-driven clause learning, learning a clause from each dead end
+    /// and jumping back past the decisions that played no part in it
+    #[arg(long)]
+    learning: bool,
+
+    /// Search the puzzle as an exact cover problem with dancing links (Knuth's Algorithm X)
+    #[arg(long)]
+    dlx: bool,
+
+    /// Race the backtracker, the backtracker with restarts, dancing links and clause learning
+    /// on separate threads for each solve, taking the first to finish
+    #[arg(long)]
+    portfolio: bool,
+}
+
+impl SolverArgs {
+    fn options(&self) -> SolverOptions {
+        let mut builder = SolverOptions::builder()
+            .techniques(self.techniques)
+            .value_order(self.value_order)
+            .seed(self.seed)
+            .degree_tie_break(self.degree_tie_break)
+            .kernel(self.kernel)
+            .learning(self.learning)
+            .dlx(self.dlx)
+            .portfolio(self.portfolio);
+        if let Some(node_budget) = self.node_budget {
+            builder = builder.node_budget(node_budget);
+        }
+        if let Some(timeout) = self.solve_timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout));
+        }
+        if let Some(unit) = self.restarts {
+            builder = builder.restarts(unit);
+        }
+        builder.propagation_threshold(self.propagation_threshold).build()
+    }
+}
+
+#[derive(ClapArgs)]
+struct TraceDotArgs {
+    /// Trace file written by `solve --trace`
+    trace: String,
+
+    /// Optional path to write the DOT graph to [default: standard output]
+    #[arg(long)]
+    out_file: Option<String>,
+}
+
+#[derive(ClapArgs)]
+struct TableArgs {
+    /// Size of the Latin square
+    #[arg(long, value_parser = clap::value_parser!(u8).range(3..=MAX_SIZE as i64))]
+    size: u8,
+
+    /// Number of random tile combinations to try per placed count
+    /// (placed counts with fewer combinations are searched exhaustively)
+    #[arg(long, default_value = "10000")]
+    probes: usize,
+
+    /// Placed count to start the descent from [default: the size]
+    #[arg(long)]
+    max_placed: Option<usize>,
+
+    /// Number of processor threads to use for parallel processing
+    #[arg(long, default_value = "10")]
+    processors: usize,
+}
+
+#[derive(ClapArgs)]
+struct QueryArgs {
+    /// Path of the puzzle database
+    #[arg(long)]
+    db: String,
+
+    /// Size of the Latin square
+    #[arg(long)]
+    size: usize,
+
+    /// Only return puzzles with this number of placed tiles
+    #[arg(long)]
+    placed: Option<usize>,
+
+    /// Minimum difficulty rating
+    #[arg(long)]
+    min_difficulty: Option<usize>,
+
+    /// Maximum difficulty rating
+    #[arg(long)]
+    max_difficulty: Option<usize>,
+
+    /// Maximum number of puzzles to return
+    #[arg(long, default_value = "10")]
+    limit: usize,
+}
+
+fn validate_args(size: usize, placed: usize) -> Result<(), String> {
+    if placed > size * size {
+        return Err(format!(
+            "Number of placed tiles ({}) cannot exceed total tiles ({})", 
+            placed, 
+            size * size
+        ));
+    }
+    Ok(())
+}
+
+/// Search for unique puzzles of each requested size and write them to the
+/// requested outputs, with a combined summary for several sizes.
+fn run_search(args: &SearchArgs) -> Result<(), String> {
+    if let Some(ref addr) = args.worker {
+        init_thread_pool(args.processors);
+        let (ranges, found) =
+            cluster::work(addr, args.solver.options()).map_err(|e| format!("worker of {} failed: {}", addr, e))?;
+        println!("Searched {} ranges, finding {} puzzles", ranges, found);
+        return Ok(());
+    }
+    if args.find_minimum {
+        init_thread_pool(args.processors);
+        return run_find_minimum(args, args.size.expect("--size is required without --sizes") as usize);
+    }
+    let sizes: Vec<usize> = match args.size {
+        Some(size) => vec![size as usize],
+        None => args.sizes.iter().map(|&size| size as usize).collect(),
+    };
+    let placed = match args.placed.len() {
+        1 => vec![args.placed[0]; sizes.len()],
+        count if count == sizes.len() => args.placed.clone(),
+        count => return Err(format!("--placed has {} values for {} sizes", count, sizes.len())),
+    };
+    for (&size, &placed) in sizes.iter().zip(&placed) {
+        check_search_args(args, size, placed)?;
+    }
+
+    // Configure rayon thread pool
+    init_thread_pool(args.processors);
+
+    if let [size] = sizes[..] {
+        let summary = search_size(args, size, placed[0], args.out_file.as_deref(), args.checkpoint.as_deref())?;
+        if let Some(ref path) = args.summary {
+            summary.write(path).map_err(|e| format!("cannot write {}: {}", path, e))?;
+        }
+        return Ok(());
+    }
+    let mut runs = Vec::new();
+    for (&size, &placed) in sizes.iter().zip(&placed) {
+        let out_file = args.out_file.as_deref().map(|path| level_path(path, &format!("s{}", size)));
+        let checkpoint = args.checkpoint.as_deref().map(|path| level_path(path, &format!("s{}", size)));
+        runs.push(search_size(args, size, placed, out_file.as_deref(), checkpoint.as_deref())?);
+    }
+    let combined = CombinedSummary::new(runs);
+    println!(
+        "\nFound {} puzzles over {} sizes in {:.1}s",
+        combined.unique_puzzles,
+        combined.runs.len(),
+        combined.wall_time_seconds
+    );
+    if let Some(ref path) = args.summary {
+        combined.write(path).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+/// Descend over the placed counts of `size` with `search --find-minimum`.
+fn run_find_minimum(args: &SearchArgs, size: usize) -> Result<(), String> {
+    let grid = base_grid(args.base_grid, size)?;
+    let semantics = args.semantics(size);
+    if args.variant != Variant::Latin && !args.variant.allows(&grid) {
+        let name = args.variant.to_possible_value().expect("no skipped variants");
+        return Err(format!(
+            "--variant {0} needs a base grid following its rules: pass --base-grid {0}",
+            name.get_name()
+        ));
+    }
+    let bound = bounds::lower_bound(&grid, semantics, args.variant, bounds::DEFAULT_NODE_BUDGET);
+    let floor = bound.as_ref().map_or(0, |bound| bound.tiles);
+    let runs = args.random_tries.unwrap_or(DEFAULT_TOP_DOWN_RUNS);
+    let start = Instant::now();
+    let levels = generators::find_minimum(
+        &grid,
+        floor,
+        runs,
+        args.generator_seed,
+        &args.anneal.options(),
+        &args.solver.options(),
+        semantics,
+        args.variant,
+    );
+    let Some(smallest) = levels.iter().rev().find(|level| level.example.is_some()) else {
+        return Err(format!("none of the {} top-down runs found a unique puzzle", runs));
+    };
+    let mut out = args.out_file.as_deref().map(|path| create_output(Some(path))).transpose()?;
+    for level in &levels {
+        match &level.example {
+            Some(tiles) => {
+                println!("{:>4} placed: {}", level.placed, output::format_tiles(tiles));
+                if let Some(out) = out.as_mut() {
+                    writeln!(out, "{}", output::format_tiles(tiles))
+                        .map_err(|e| format!("cannot write the puzzles: {}", e))?;
+                }
+            }
+            None => println!("{:>4} placed: none found by {} annealing runs", level.placed, runs),
+        }
+    }
+    println!(
+        "Fewest placed tiles found: {} in {:.1}s{}",
+        smallest.placed,
+        start.elapsed().as_secs_f64(),
+        if smallest.placed == floor && bound.is_some() { ", the lower bound, so no unique puzzle has fewer" } else { "" }
+    );
+    Ok(())
+}
+
+/// Check the search settings for one size before any search starts.
+fn check_search_args(args: &SearchArgs, size: usize, placed: usize) -> Result<(), String> {
+    validate_args(size, placed)?;
+    let grid = base_grid(args.base_grid, size)?;
+    let generator = args.generator(size);
+    let semantics = args.semantics(size);
+    if let Some(max_per_line) = args.max_per_line {
+        if max_per_line == 0 || placed > size * max_per_line {
+            return Err(format!(
+                "{} placed tiles don't fit in a {}x{} grid with at most {} per row and column",
+                placed, size, size, max_per_line
+            ));
+        }
+        if generator != Generator::BottomUp {
+            return Err("--max-per-line only applies to the bottom-up generator".to_string());
+        }
+    }
+    if generator != Generator::BottomUp && args.random_tries(size).is_none() {
+        let name = generator.to_possible_value().expect("no skipped generators");
+        return Err(format!("--generator {} needs --random-tries (the number of runs)", name.get_name()));
+    }
+    if args.tried_false_positive_rate.is_some_and(|rate| !(rate > 0.0 && rate < 1.0)) {
+        return Err("--tried-false-positive-rate must be between 0 and 1".to_string());
+    }
+    if args.anneal.anneal_start_temperature <= 0.0 || args.anneal.anneal_end_temperature <= 0.0 {
+        return Err("annealing temperatures must be positive".to_string());
+    }
+    if let Some(bound) = bounds::lower_bound(&grid, semantics, args.variant, bounds::DEFAULT_NODE_BUDGET) {
+        tracing::info!(
+            bound = bound.tiles,
+            trades = bound.trades.len(),
+            exact = bound.exact,
+            "lower bound on the placed tiles of unique classic puzzles"
+        );
+        if placed < bound.tiles {
+            let name = args.base_grid.to_possible_value().expect("no skipped base grids");
+            let reason = format!(
+                "no classic puzzle of size {} with {} placed tiles is unique: every one leaves two lines or a cycle \
+                 of the base grid empty (see `analyze --size {} --base-grid {}`)",
+                size,
+                placed,
+                size,
+                name.get_name()
+            );
+            if generator == Generator::BottomUp {
+                return Err(reason);
+            }
+            // The top-down generator stops at the bound anyway
+            if generator == Generator::Anneal {
+                tracing::warn!("{}", reason);
+            }
+        }
+    }
+    if args.variant != Variant::Latin {
+        let name = args.variant.to_possible_value().expect("no skipped variants");
+        if !args.variant.allows(&grid) {
+            return Err(format!(
+                "--variant {0} needs a base grid following its rules: pass --base-grid {0}",
+                name.get_name()
+            ));
+        }
+    }
+    if args.nogoods && semantics != ClueSemantics::GivensOnly {
+        return Err("--nogoods needs --semantics givens-only: in the game, a second solution of a \
+            combination completes no other combination"
+            .to_string());
+    }
+    Ok(())
+}
+
+/// The square of `kind` of `size`, or an error if there is none.
+fn base_grid(kind: BaseGrid, size: usize) -> Result<Grid, String> {
+    kind.grid(size).ok_or_else(|| {
+        let name = kind.to_possible_value().expect("no skipped base grids");
+        format!("there is no {} base grid of size {}", name.get_name(), size)
+    })
+}
+
+/// Search for the unique puzzles of one size, write them to `out_file` and
+/// the other outputs, and return the summary of the run. A random search
+/// continues from `checkpoint` if it exists, and saves its state there.
+fn search_size(
+    args: &SearchArgs,
+    size: usize,
+    placed: usize,
+    out_file: Option<&str>,
+    checkpoint: Option<&str>,
+) -> Result<RunSummary, String> {
+    tracing::info!(
+        size,
+        placed,
+        processors = args.processors,
+        random_tries = ?args.random_tries(size),
+        out_file = ?out_file,
+        "finding single solution puzzles"
+    );
+    
+    let grid = base_grid(args.base_grid, size)?;
+    let generator = args.generator(size);
+    let semantics = args.semantics(size);
+    // When appending, skip the puzzles already present in the output file
+    let mut known_puzzles = HashSet::new();
+    if let (true, Some(path)) = (args.append, out_file) {
+        if std::path::Path::new(path).exists() {
+            if let Ok(Some(header)) = output::read_file_header(path) {
+                if (header.size, header.clue_semantics()) != (size, Some(semantics)) {
+                    return Err(format!(
+                        "cannot append to {}: it holds {}x{} {} puzzles",
+                        path, header.size, header.size, header.semantics
+                    ));
+                }
+                if header.base_grid != output::format_grid(&grid) {
+                    return Err(format!(
+                        "cannot append to {}: it places the tiles of another base grid ({})",
+                        path, header.base_grid
+                    ));
+                }
+                if header.puzzle_variant() != Some(args.variant) {
+                    return Err(format!(
+                        "cannot append to {}: its puzzles are of another variant ({})",
+                        path,
+                        header.variant.as_deref().unwrap_or("latin")
+                    ));
+                }
+                output::check_rules_version(header.rules_version, path)
+                    .map_err(|e| format!("cannot append to it: {}", e))?;
+            }
+            let previous = output::read_puzzle_tiles(path)
+                .map_err(|e| format!("cannot read existing puzzles from {}: {}", path, e))?;
+            known_puzzles.extend(previous.iter().map(|tiles| standardize_tile_tuple(tiles)));
+            tracing::info!(puzzles = known_puzzles.len(), path, "loaded previously found puzzles");
+        }
+    }
+    
+    let header = output::FileHeader {
+        generator: Some(generator.to_possible_value().expect("no skipped generators").get_name().to_string()),
+        seed: (generator != Generator::BottomUp).then_some(args.generator_seed),
+        ..output::FileHeader::new(size, semantics).with_base_grid(&grid).with_variant(args.variant)
+    };
+    let mut sinks: Vec<Box<dyn PuzzleSink>> = Vec::new();
+    if let Some(path) = out_file {
+        let action = if args.append { "append to" } else { "create" };
+        let sink = output::create_file_sink(path, args.format, args.compress, args.append, &header)
+            .map_err(|e| format!("cannot {} {}: {}", action, path, e))?;
+        sinks.push(sink);
+    }
+    if let Some(ref path) = args.out_db {
+        #[cfg(feature = "sqlite")]
+        sinks.push(Box::new(
+            db::PuzzleDb::open(path, &header).map_err(|e| format!("cannot open {}: {}", path, e))?,
+        ));
+        #[cfg(not(feature = "sqlite"))]
+        return Err(format!("cannot write to {}: {}", path, NO_SQLITE));
+    }
+    
+    let mut drop_supersets = None;
+    for path in &args.drop_supersets_of {
+        let filter = drop_supersets.get_or_insert_with(|| SupersetFilter::new(size));
+        for tiles in load_puzzles(path, size)? {
+            filter.insert(&tiles);
+        }
+    }
+    if let Some(filter) = &drop_supersets {
+        tracing::info!(clue_sets = filter.len(), "skipping supersets of known unique clue sets");
+    }
+    
+    let mut resume = None;
+    if let Some(path) = checkpoint {
+        resume = Checkpoint::load(Path::new(path)).map_err(|e| format!("cannot read checkpoint {}: {}", path, e))?;
+        if let Some(saved) = &resume {
+            if (saved.size, saved.placed, saved.generator) != (size, placed, generator) {
+                let generator = saved.generator.to_possible_value().expect("no skipped generators");
+                return Err(format!(
+                    "checkpoint {} is of another search ({}x{}, {} placed tiles, {} generator)",
+                    path,
+                    saved.size,
+                    saved.size,
+                    saved.placed,
+                    generator.get_name()
+                ));
+            }
+            if !args.append && out_file.is_some() {
+                tracing::warn!(path, "resuming without --append: the output file only gets the new puzzles");
+            }
+        }
+    }
+
+    let results_index = match &args.results_index {
+        Some(path) => {
+            // Like output files, the index of each size of --sizes is a file of its own
+            let path = match args.size {
+                Some(_) => path.clone(),
+                None => PathBuf::from(level_path(&path.to_string_lossy(), &format!("s{}", size))),
+            };
+            let index = ResultsIndex::open(&path, &grid, semantics, args.variant)
+                .map_err(|e| format!("cannot open the results index {}: {}", path.display(), e))?;
+            tracing::info!(path = %path.display(), evaluated = index.loaded(), "skipping the indexed combinations");
+            Some(Arc::new(index))
+        }
+        None => None,
+    };
+
+    let options = SearchOptions {
+        random_tries: args.random_tries(size),
+        first_only: args.first_only,
+        solver: args.solver.options(),
+        require_singles_solvable: args.require_singles_solvable,
+        per_puzzle_timeout: args.per_puzzle_timeout_ms.map(Duration::from_millis),
+        incremental: args.incremental,
+        drop_supersets,
+        max_per_line: args.max_per_line,
+        generator,
+        generator_seed: args.generator_seed,
+        anneal: args.anneal.options(),
+        semantics,
+        base_grid: args.base_grid,
+        variant: args.variant,
+        expected_guess_games: args.expected_guesses,
+        nogoods: args.nogoods,
+        duplicate_structure: args.duplicate_structure,
+        results_index,
+        resume,
+        checkpoint: checkpoint.map(PathBuf::from),
+        tried_false_positive_rate: args.tried_false_positive_rate,
+        max_memory_bytes: args.max_memory_mb.map(|megabytes| megabytes << 20),
+        no_solutions: args.no_solutions,
+        metrics_file: args.metrics_file.clone(),
+        deterministic_output: args.deterministic_output,
+        range: None,
+    };
+    let result = find_single_solution_puzzles(size, placed, options, sinks, known_puzzles);
+    let solutions = result.puzzles;
+    
+    println!("\nFound {} puzzles with exactly one solution:", result.summary.unique_puzzles);
+    
+    for (i, puzzle) in solutions.iter().enumerate().take(5) {
+        println!("\nPuzzle {} - Placed tiles: {:?}", i + 1, puzzle.tiles);
+        println!("Unique solution:");
+        for row in puzzle.unique_solution_on(&grid, semantics).rows() {
+            println!("  {:?}", row);
+        }
+    }
+    
+    let shown = solutions.len().min(5);
+    if shown > 0 && result.summary.unique_puzzles > shown {
+        println!("... and {} more puzzles", result.summary.unique_puzzles - shown);
+    }
+    if let Some(path) = &result.spill_file {
+        println!("{} puzzles were moved out of memory to {}", result.spilled, path.display());
+    }
+    
+    println!("\nSummary:\n{}", result.summary.to_json());
+    if let Some(sample) = args.cross_check {
+        cross_check(&grid, &solutions, sample, semantics, args.variant)?;
+    }
+    Ok(result.summary)
+}
+
+/// Check that the reference solver finds exactly the solution of the search
+/// for `sample` of the `puzzles` placing tiles of `grid`, picked at random.
+fn cross_check(
+    grid: &Grid,
+    puzzles: &[FoundPuzzle],
+    sample: usize,
+    semantics: ClueSemantics,
+    variant: Variant,
+) -> Result<(), String> {
+    let tile_coordinates = all_tiles(grid.size());
+    let sampled: Vec<&FoundPuzzle> = puzzles.choose_multiple(&mut thread_rng(), sample).collect();
+    let start = Instant::now();
+    let mismatches: Vec<(&FoundPuzzle, usize)> = sampled
+        .par_iter()
+        .filter_map(|puzzle| {
+            let mut constraints = puzzle_constraints_with(&puzzle.tiles, grid, &tile_coordinates, semantics);
+            constraints.variant = variant;
+            let solutions = reference::solve(&constraints, 2);
+            (solutions != [puzzle.unique_solution_on(grid, semantics)]).then_some((*puzzle, solutions.len()))
+        })
+        .collect();
+    for (puzzle, solutions) in &mismatches {
+        println!(
+            "not confirmed by the reference solver ({} solutions): {}",
+            solutions,
+            output::format_tiles(&puzzle.tiles)
+        );
+    }
+    if !mismatches.is_empty() {
+        return Err(format!("{} of {} cross-checked puzzles are not confirmed", mismatches.len(), sampled.len()));
+    }
+    println!("Cross-checked {} puzzles with the reference solver in {:.3?}", sampled.len(), start.elapsed());
+    Ok(())
+}
+
+/// Parse placed tiles given on the command line and check that they fit in the grid.
+fn parse_puzzle(size: usize, tiles: &str) -> Result<Vec<(usize, usize)>, String> {
+    let tiles = output::parse_tiles(tiles).ok_or_else(|| format!("could not parse tiles '{}'", tiles))?;
+    check_tiles(size, &tiles)?;
+    Ok(tiles)
+}
+
+fn check_tiles(size: usize, tiles: &[(usize, usize)]) -> Result<(), String> {
+    match tiles.iter().find(|&&(r, c)| r >= size || c >= size) {
+        Some(&(r, c)) => Err(format!("tile ({},{}) is outside of a {}x{} grid", r, c, size, size)),
+        None => Ok(()),
+    }
+}
+
+/// Read the puzzles of a text or CSV puzzle file and check that they fit in the grid.
+fn load_puzzles(path: &str, size: usize) -> Result<Vec<Vec<(usize, usize)>>, String> {
+    let puzzles = output::read_puzzle_tiles(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    for tiles in &puzzles {
+        check_tiles(size, tiles).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(puzzles)
+}
+
+/// Size and clue semantics of the puzzles of the file at `path`: those given,
+/// or else those of the header of the file.
+fn file_settings(
+    path: &str,
+    size: Option<u8>,
+    semantics: Option<ClueSemantics>,
+) -> Result<(usize, ClueSemantics), String> {
+    let header = output::read_file_header(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    if let Some(header) = &header {
+        if !(3..=MAX_SIZE).contains(&header.size) {
+            return Err(format!("{}: the header gives an unsupported size {}", path, header.size));
+        }
+        if header.base_grid != output::format_grid(&cyclic_latin_square(header.size)) {
+            return Err(format!("{} places the tiles of another base grid ({})", path, header.base_grid));
+        }
+        output::check_rules_version(header.rules_version, path)?;
+    }
+    let size = match (size.map(usize::from), &header) {
+        (Some(size), Some(header)) if size != header.size => {
+            return Err(format!("--size {} but the puzzles of {} are {}x{}", size, path, header.size, header.size))
+        }
+        (Some(size), _) => size,
+        (None, Some(header)) => header.size,
+        (None, None) => return Err(format!("{} has no header giving the size of its puzzles: pass --size", path)),
+    };
+    let semantics = match (semantics, &header) {
+        (Some(semantics), _) => semantics,
+        (None, Some(header)) => header
+            .clue_semantics()
+            .ok_or_else(|| format!("{}: unknown semantics '{}' in the header", path, header.semantics))?,
+        (None, None) => ClueSemantics::default(),
+    };
+    Ok((size, semantics))
+}
+
+/// Open `path` for writing, or standard output if there is none.
+fn create_output(path: Option<&str>) -> Result<Box<dyn Write>, String> {
+    match path {
+        Some(path) => Ok(Box::new(File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?)),
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+/// Print a random sample of puzzles from a database, one text-format line per puzzle.
+#[cfg(feature = "sqlite")]
+fn run_query(args: &QueryArgs) -> Result<(), String> {
+    let query = db::PuzzleQuery {
+        size: args.size,
+        placed: args.placed,
+        min_difficulty: args.min_difficulty,
+        max_difficulty: args.max_difficulty,
+        limit: args.limit,
+    };
+    let puzzles = db::sample_puzzles(&args.db, &query).map_err(|e| e.to_string())?;
+    for tiles in puzzles {
+        println!("{}", output::format_tiles(&tiles));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn run_query(_args: &QueryArgs) -> Result<(), String> {
+    Err(NO_SQLITE.to_string())
+}
+
+#[cfg(not(feature = "sqlite"))]
+const NO_SQLITE: &str = "this build does not include SQLite support (rebuild with `--features sqlite`)";
+
+/// Probe placed counts from the maximum downwards and print a feasibility table.
+///
+/// The descent stops at the first placed count where no unique puzzle is found.
+fn run_table(args: &TableArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let max_placed = args.max_placed.unwrap_or(size);
+    validate_args(size, max_placed)?;
+    init_thread_pool(args.processors);
+
+    let mut rows = Vec::new();
+    for placed in (1..=max_placed).rev() {
+        let combinations = binomial(size * size, placed);
+        // Small levels are cheaper to search exhaustively, which also gives an exact answer
+        let exhaustive = combinations <= args.probes as u128;
+        let random_tries = if exhaustive { None } else { Some(args.probes) };
+        let result = find_single_solution_puzzles(
+            size,
+            placed,
+            SearchOptions { random_tries, ..SearchOptions::default() },
+            Vec::new(),
+            HashSet::new(),
+        );
+        let found = result.puzzles.len();
+        rows.push((placed, combinations, result.summary.combinations_processed, found, exhaustive));
+        if found == 0 {
+            break;
+        }
+    }
+
+    println!("\nFeasibility table for N={} ({} probes per placed count):", size, args.probes);
+    println!("{:>6} {:>14} {:>10} {:>8} {:>10}  exists", "placed", "combinations", "probed", "unique", "rate");
+    for (placed, combinations, probed, found, exhaustive) in rows {
+        let rate = found as f64 / probed.max(1) as f64;
+        let exists = match (found > 0, exhaustive) {
+            (true, _) => "yes",
+            (false, true) => "no",
+            (false, false) => "none found",
+        };
+        println!("{:>6} {:>14} {:>10} {:>8} {:>10.2e}  {}", placed, combinations, probed, found, rate, exists);
+    }
+    Ok(())
+}
+
+/// Parse a line of `solve --stdin`: a JSON object like
+/// `{"size": 5, "tiles": [[0, 0], [0, 1]]}`, or a puzzle in dot notation.
+fn parse_stdin_puzzle(line: &str) -> Result<PuzzleSpec, String> {
+    if line.starts_with('{') {
+        serde_json::from_str(line).map_err(|e| format!("invalid JSON: {}", e))
+    } else {
+        output::parse_dot_notation(line).map(|(size, tiles)| PuzzleSpec::new(size, &tiles))
+    }
+}
+
+/// Solve the puzzles read from standard input, one JSON object per line out.
+///
+/// Results are flushed after every line so that the program can be driven as
+/// a co-process. Puzzles that cannot be read get an `error` instead.
+fn run_solve_stdin(args: &SolveArgs) -> Result<(), String> {
+    let timeout = args
+        .timeout
+        .map(Duration::try_from_secs_f64)
+        .transpose()
+        .map_err(|e| format!("invalid --timeout: {}", e))?;
+    let options = args.solver.options();
+    let mut stdout = io::stdout().lock();
+    for (line_number, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.map_err(|e| format!("cannot read standard input: {}", e))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let report = parse_stdin_puzzle(line)
+            .and_then(|puzzle| solve_report(&puzzle, &options, deadline))
+            .and_then(|report| serde_json::to_value(report).map_err(|e| e.to_string()));
+        let mut result = report.unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        result["line"] = (line_number + 1).into();
+        writeln!(stdout, "{}", result)
+            .and_then(|()| stdout.flush())
+            .map_err(|e| format!("cannot write results: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Solve the puzzle given on the command line, optionally recording a trace of the search.
+fn run_solve(args: &SolveArgs) -> Result<(), String> {
+    if args.stdin {
+        return run_solve_stdin(args);
+    }
+    // Both are required by clap without --stdin
+    let size = args.size.expect("--size is required") as usize;
+    let tiles = parse_puzzle(size, args.tiles.as_deref().expect("--tiles is required"))?;
+    let constraints = puzzle_constraints(&tiles, &cyclic_latin_square(size), &all_tiles(size));
+    let tracer = args.trace.as_ref().map(|_| Tracer::new());
+
+    if args.parallel && tracer.is_some() {
+        return Err("--trace cannot be combined with --parallel".to_string());
+    }
+
+    let cancel = CancellationToken::new();
+    if let Some(timeout) = args.timeout {
+        let cancel = cancel.clone();
+        let timeout = Duration::try_from_secs_f64(timeout).map_err(|e| format!("invalid --timeout: {}", e))?;
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            cancel.cancel();
+        });
+    }
+
+    let start = Instant::now();
+    let options = args.solver.options();
+    let (solutions, stats) = if args.parallel {
+        init_thread_pool(args.processors);
+        complete_latin_square_parallel(&constraints, Some(2), &options, Some(&cancel))
+    } else {
+        let observer = tracer.as_ref().map(|tracer| tracer as &dyn SearchObserver);
+        let hooks = SolveHooks { observer, cancel: Some(&cancel), ..SolveHooks::default() };
+        solve_constraints(&constraints, Some(2), &options, hooks)
+    };
+    let solve_time = start.elapsed();
+    if cancel.is_cancelled() {
+        return Err(format!(
+            "gave up after {:.3?} and {} search nodes ({} solutions found so far)",
+            solve_time,
+            stats.backtrack_nodes,
+            solutions.len()
+        ));
+    }
+
+    match solutions.len() {
+        0 => println!("No solution"),
+        1 => println!("Unique solution:"),
+        _ => println!("Several solutions, the first two are:"),
+    }
+    for solution in &solutions {
+        for row in solution.rows() {
+            println!("  {:?}", row);
+        }
+        println!();
+    }
+    println!("Difficulty: {}", stats.difficulty());
+    if solutions.len() == 1 {
+        let singles_solvable = techniques::solvable_by_deduction(&constraints, TechniqueSet::SINGLES);
+        println!("Solvable with singles alone: {}", if singles_solvable { "yes" } else { "no" });
+    }
+    println!("Stats: {:?}", stats);
+    println!("Solved in {:.3?}", solve_time);
+
+    if let (Some(path), Some(tracer)) = (&args.trace, tracer) {
+        let events = tracer.into_events();
+        trace::write_jsonl(path, &events).map_err(|e| format!("cannot write {}: {}", path, e))?;
+        tracing::info!(events = events.len(), path, "wrote trace");
+    }
+    Ok(())
+}
+
+/// Check every puzzle of a file, listing those without a unique solution.
+fn run_verify(args: &VerifyArgs) -> Result<(), String> {
+    let (size, semantics) = file_settings(&args.file, args.size, args.semantics)?;
+    let lines = output::read_puzzle_lines(&args.file).map_err(|e| format!("cannot read {}: {}", args.file, e))?;
+    for (tiles, _) in &lines.records {
+        check_tiles(size, tiles).map_err(|e| format!("{}: {}", args.file, e))?;
+    }
+    let puzzles: Vec<_> = lines.records.iter().map(|(tiles, _)| tiles).collect();
+    init_thread_pool(args.processors);
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    // Whether the certificate of the puzzle checks, if it has one, and the number of solutions (up to 2)
+    // found by the solver and, with --cross-check, the reference solver. Puzzles whose certificate checks
+    // have one without a solve.
+    let counts: Vec<_> = lines
+        .records
+        .par_iter()
+        .map(|(tiles, line)| {
+            let constraints = puzzle_constraints_with(tiles, &grid, &tile_coordinates, semantics);
+            let certificate = output::record_certificate(lines.columns.as_deref(), line);
+            let certified = certificate.filter(|_| !args.ignore_certificates).map(|certificate| {
+                certificate.and_then(|certificate| certificate::check(&constraints, &certificate).map(|_| ()))
+            });
+            let solutions = match certified {
+                Some(Ok(())) if !args.cross_check => 1,
+                _ => solve_constraints(&constraints, Some(2), &SolverOptions::default(), SolveHooks::default()).0.len(),
+            };
+            let reference_solutions = args.cross_check.then(|| reference::solve(&constraints, 2).len());
+            (tiles, certified, solutions, reference_solutions)
+        })
+        .collect();
+    let invalid: Vec<_> = counts
+        .iter()
+        .filter_map(|(tiles, certified, _, _)| match certified {
+            Some(Err(e)) => Some((tiles, e)),
+            _ => None,
+        })
+        .collect();
+    for (tiles, e) in &invalid {
+        println!("invalid certificate ({}): {}", e, output::format_tiles(tiles));
+    }
+    let failures: Vec<_> = counts.iter().filter(|(_, _, solutions, _)| *solutions != 1).collect();
+    for (tiles, _, solutions, _) in &failures {
+        let verdict = if *solutions == 0 { "no solution" } else { "several solutions" };
+        println!("{}: {}", verdict, output::format_tiles(tiles));
+        if *solutions > 1 {
+            let constraints = puzzle_constraints_with(tiles, &grid, &tile_coordinates, semantics);
+            if let Some(witness) = witness::find_witness(&constraints, args.witness_solutions) {
+                let cells: Vec<String> =
+                    witness.cells.iter().map(|((r, c), first, second)| format!("({},{}) {}|{}", r, c, first, second)).collect();
+                println!("  two solutions differ on {} cells ({}): {}", witness.len(), witness.kind(), cells.join(", "));
+            }
+        }
+    }
+    let disagreements: Vec<_> = counts
+        .iter()
+        .filter(|(_, _, solutions, reference_solutions)| reference_solutions.is_some_and(|count| count != *solutions))
+        .collect();
+    for (tiles, _, solutions, reference_solutions) in &disagreements {
+        println!(
+            "solvers disagree ({} solutions, {} with the reference solver): {}",
+            solutions,
+            reference_solutions.unwrap_or_default(),
+            output::format_tiles(tiles)
+        );
+    }
+    if !disagreements.is_empty() {
+        return Err(format!("the solvers disagree on {} of {} puzzles", disagreements.len(), puzzles.len()));
+    }
+    if !failures.is_empty() {
+        return Err(format!("{} of {} puzzles do not have a unique solution", failures.len(), puzzles.len()));
+    }
+    if !invalid.is_empty() {
+        return Err(format!("{} of {} certificates do not check", invalid.len(), puzzles.len()));
+    }
+    let certified = counts.iter().filter(|(_, certified, _, _)| matches!(certified, Some(Ok(())))).count();
+    println!("All {} puzzles have a unique solution ({} proved by their certificate)", puzzles.len(), certified);
+    Ok(())
+}
+
+/// Print the smallest sets of tiles to add to the puzzle so that it has a unique solution.
+fn run_repair(args: &RepairArgs) -> Result<(), String> {
+    let text = std::fs::read_to_string(&args.input).map_err(|e| format!("cannot read {}: {}", args.input, e))?;
+    let puzzle = parse_stdin_puzzle(text.trim()).map_err(|e| format!("{}: {}", args.input, e))?;
+    let (size, tiles) = (puzzle.size, puzzle.tile_pairs());
+    if !(3..=MAX_SIZE).contains(&size) {
+        return Err(format!("{}: unsupported size {}", args.input, size));
+    }
+    check_tiles(size, &tiles)?;
+    init_thread_pool(args.processors);
+    let options = RepairOptions { max_extra: args.max_extra, limit: args.limit, witness_solutions: args.witness_solutions };
+    let Some(found) = repair::repair(size, &tiles, args.semantics, &options) else {
+        return Err(format!("no set of up to {} extra tiles makes the puzzle unique", args.max_extra));
+    };
+    if found.extra == 0 {
+        println!("The puzzle already has a unique solution");
+        return Ok(());
+    }
+    let noun = if found.extra == 1 { "tile" } else { "tiles" };
+    println!("Placing {} more {} makes the puzzle unique, for example:", found.extra, noun);
+    for (extra_tiles, difficulty) in &found.repairs {
+        let mut all = tiles.clone();
+        all.extend(extra_tiles);
+        all.sort_unstable();
+        println!("  + {} (difficulty {}): {}", output::format_tiles(extra_tiles), difficulty, output::format_tiles(&all));
+    }
+    Ok(())
+}
+
+/// Classify the clue sets near a puzzle, and write the unique ones.
+fn run_neighbors(args: &NeighborsArgs) -> Result<(), String> {
+    let text = std::fs::read_to_string(&args.input).map_err(|e| format!("cannot read {}: {}", args.input, e))?;
+    let puzzle = parse_stdin_puzzle(text.trim()).map_err(|e| format!("{}: {}", args.input, e))?;
+    let (size, tiles) = (puzzle.size, puzzle.tile_pairs());
+    if !(3..=MAX_SIZE).contains(&size) {
+        return Err(format!("{}: unsupported size {}", args.input, size));
+    }
+    check_tiles(size, &tiles)?;
+    init_thread_pool(args.processors);
+    let start = Instant::now();
+    let grid = cyclic_latin_square(size);
+    let found = neighbors::neighbors(&grid, &tiles, args.distance, args.semantics, &args.solver.options());
+    println!("{:>8} {:>10} {:>8} {:>8} {:>8}", "distance", "neighbors", "unique", "multiple", "invalid");
+    for distance in 1..=args.distance {
+        let at_distance: Vec<&Neighbor> = found.iter().filter(|neighbor| neighbor.distance == distance).collect();
+        let count = |class| at_distance.iter().filter(|neighbor| neighbor.class == class).count();
+        println!(
+            "{:>8} {:>10} {:>8} {:>8} {:>8}",
+            distance,
+            at_distance.len(),
+            count(neighbors::Class::Unique),
+            count(neighbors::Class::Multiple),
+            count(neighbors::Class::Invalid)
+        );
+    }
+    let unique: Vec<FoundPuzzle> = found.into_iter().filter_map(|neighbor| neighbor.puzzle).collect();
+    tracing::info!(unique = unique.len(), elapsed = ?start.elapsed(), "neighbors classified");
+    if let Some(path) = &args.out_file {
+        let header = output::FileHeader::new(size, args.semantics);
+        let mut sink = output::create_file_sink(path, format_from_extension(Some(path)), None, false, &header)
+            .map_err(|e| format!("cannot create {}: {}", path, e))?;
+        sink.write_puzzles(&unique).and_then(|()| sink.finish()).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+/// Print the wiggle room of each unique puzzle of the file: the share of the
+/// moves of one tile along its row or column that keep it unique.
+fn run_robustness(args: &RobustnessArgs) -> Result<(), String> {
+    let (size, semantics) = file_settings(&args.file, args.size, args.semantics)?;
+    let puzzles = load_puzzles(&args.file, size)?;
+    init_thread_pool(args.processors);
+    let options = SolverOptions::default();
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let mut rated: Vec<_> = puzzles
+        .par_iter()
+        .filter_map(|tiles| {
+            let constraints = puzzle_constraints_with(tiles, &grid, &tile_coordinates, semantics);
+            if solve_constraints(&constraints, Some(2), &options, SolveHooks::default()).0.len() != 1 {
+                tracing::warn!(tiles = %output::format_tiles(tiles), "skipped puzzle without a unique solution");
+                return None;
+            }
+            let clues = robustness::clue_robustness(size, tiles, semantics, &options);
+            Some((tiles, robustness::wiggle_room(&clues), clues))
+        })
+        .collect();
+    if args.sort {
+        rated.sort_by(|a, b| b.1.total_cmp(&a.1));
+    }
+    for (tiles, wiggle_room, clues) in &rated {
+        println!("{:.3}: {}", wiggle_room, output::format_tiles(tiles));
+        if args.details {
+            for clue in clues {
+                let (r, c) = clue.tile;
+                print!("  ({},{}) {}/{} moves stay unique", r, c, clue.unique_moves.len(), clue.moves);
+                if clue.unique_moves.is_empty() {
+                    println!();
+                } else {
+                    println!(": {}", output::format_tiles(&clue.unique_moves));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Count the completions of a clue set, up to `--max-solutions`.
+fn run_stats(args: &StatsArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let tiles = parse_puzzle(size, &args.tiles)?;
+    let constraints = puzzle_constraints(&tiles, &base_grid(args.base_grid, size)?, &all_tiles(size));
+    let start = Instant::now();
+    if let Some(probes) = args.estimate {
+        let estimate = completions::estimate_completions(&constraints, probes, args.seed);
+        println!(
+            "Completions: {:.4e} ± {:.2e} (estimated from {} probes, {:.1}% reaching a completion)",
+            estimate.completions,
+            estimate.completions_std_error,
+            estimate.probes,
+            100.0 * estimate.success_rate
+        );
+        println!("Search tree nodes: {:.4e} ± {:.2e}", estimate.tree_nodes, estimate.tree_nodes_std_error);
+        println!("Estimated in {:.3?}", start.elapsed());
+        return Ok(());
+    }
+    if !args.enumerate {
+        if let Some(count) = completions::count_exact(&constraints) {
+            println!("Completions: {} (exact)", count);
+            println!("Counted in {:.3?}", start.elapsed());
+            return Ok(());
+        }
+    }
+    let (solutions, stats) =
+        solve_constraints(&constraints, Some(args.max_solutions), &SolverOptions::default(), SolveHooks::default());
+    if solutions.len() >= args.max_solutions {
+        println!("Completions: at least {} (stopped at --max-solutions)", solutions.len());
+    } else {
+        println!("Completions: {}", solutions.len());
+    }
+    println!("Search nodes: {}", stats.backtrack_nodes);
+    println!("Counted in {:.3?}", start.elapsed());
+    Ok(())
+}
+
+/// Output format matching the extension of `path`, CSV if there is none.
+fn format_from_extension(path: Option<&str>) -> OutputFormat {
+    match path.and_then(|path| Path::new(path).extension()).and_then(|extension| extension.to_str()) {
+        Some("txt") => OutputFormat::Text,
+        Some("jsonl") => OutputFormat::Jsonl,
+        Some("parquet") => OutputFormat::Parquet,
+        _ => OutputFormat::Csv,
+    }
+}
+
+/// Path of the file of one level, e.g. `rated.hard.jsonl` for `rated.jsonl`.
+fn level_path(path: &str, level: &str) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let name = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}.{}.{}", stem, level, extension),
+        None => format!("{}.{}", stem, level),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Write rated puzzles to `path` in the given format, or to standard output.
+fn write_rated(path: Option<&str>, format: OutputFormat, size: usize, puzzles: &[&FoundPuzzle]) -> Result<(), String> {
+    let header = output::FileHeader::new(size, ClueSemantics::Sudodle);
+    let mut sink: Box<dyn PuzzleSink> = match path {
+        Some(path) => output::create_file_sink(path, format, None, false, &header)
+            .map_err(|e| format!("cannot create {}: {}", path, e))?,
+        None => Box::new(
+            output::PuzzleWriter::new(Box::new(io::stdout()), format, &header, true).map_err(|e| e.to_string())?,
+        ),
+    };
+    let puzzles: Vec<FoundPuzzle> = puzzles.iter().map(|&puzzle| puzzle.clone()).collect();
+    sink.write_puzzles(&puzzles).and_then(|()| sink.finish()).map_err(|e| e.to_string())
+}
+
+/// Solve every puzzle of a file again and write the unique ones with their
+/// difficulty and solver metrics, optionally sorted or split by level.
+fn run_rate(args: &RateArgs) -> Result<(), String> {
+    let (size, semantics) = file_settings(&args.input, args.size, None)?;
+    if semantics != ClueSemantics::Sudodle {
+        return Err(format!("{} holds classic puzzles: only the game's puzzles can be rated", args.input));
+    }
+    args.levels.validate()?;
+    let puzzles = load_puzzles(&args.input, size)?;
+    init_thread_pool(args.processors);
+    let options = args.solver.options();
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let rated: Vec<_> = puzzles
+        .par_iter()
+        .map(|tiles| {
+            let mut puzzle = check_puzzle(&PuzzleSpec::new(size, tiles), &options)?;
+            if let Some(games) = args.expected_guesses {
+                let constraints = puzzle_constraints(tiles, &grid, &tile_coordinates);
+                let solution = puzzle.unique_solution(size, ClueSemantics::Sudodle);
+                puzzle.expected_guesses = Some(simulate::expected_guesses(&constraints, &solution, games));
+            }
+            Ok::<_, SolveStats>(puzzle)
+        })
+        .collect();
+
+    let mut found = Vec::new();
+    for (tiles, puzzle) in puzzles.iter().zip(rated) {
+        match puzzle {
+            Ok(puzzle) => found.push(puzzle),
+            Err(_) => tracing::warn!(tiles = %output::format_tiles(tiles), "skipped puzzle without a unique solution"),
+        }
+    }
+    if args.sort {
+        // Stable, so that puzzles of the same difficulty keep the order of the input
+        found.sort_by_key(|puzzle| puzzle.stats.difficulty());
+    }
+    tracing::info!(rated = found.len(), skipped = puzzles.len() - found.len(), "rated puzzles");
+
+    let format = args.format.unwrap_or_else(|| format_from_extension(args.output.as_deref()));
+    match &args.output {
+        Some(path) if args.split => {
+            for level in LEVELS {
+                let bucket: Vec<&FoundPuzzle> =
+                    found.iter().filter(|puzzle| args.levels.level(puzzle.stats.difficulty()) == level).collect();
+                write_rated(Some(&level_path(path, level)), format, size, &bucket)?;
+            }
+            Ok(())
+        }
+        path => write_rated(path.as_deref(), format, size, &found.iter().collect::<Vec<_>>()),
+    }
+}
+
+/// Solve every puzzle of the file with a profiler, and print the nodes and
+/// time of the solver at each depth of its search and for each technique.
+fn run_profile(args: &ProfileArgs) -> Result<(), String> {
+    let (size, semantics) = file_settings(&args.input, args.size, args.semantics)?;
+    let puzzles = load_puzzles(&args.input, size)?;
+    init_thread_pool(args.processors);
+    let options = args.solver.options();
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let start = Instant::now();
+    let (profile, not_unique) = puzzles
+        .par_iter()
+        .map(|tiles| {
+            let constraints = puzzle_constraints_with(tiles, &grid, &tile_coordinates, semantics);
+            let (solutions, _, profile) = profile::profile_solve(&constraints, &options);
+            (profile, usize::from(solutions.len() != 1))
+        })
+        .reduce(
+            || (Profile::default(), 0),
+            |(mut profile, not_unique), (other, other_not_unique)| {
+                profile.merge(&other);
+                (profile, not_unique + other_not_unique)
+            },
+        );
+    let total = profile.time();
+    let share = |time: Duration| 100.0 * time.as_secs_f64() / total.as_secs_f64().max(f64::MIN_POSITIVE);
+    println!(
+        "Profiled {} puzzles ({} without a unique solution) in {:.3?}, {:.3?} of it in the solver",
+        profile.puzzles,
+        not_unique,
+        start.elapsed(),
+        total
+    );
+    println!();
+    println!("{:>5} {:>10} {:>10} {:>10} {:>12} {:>6}", "depth", "nodes", "dead ends", "solutions", "time", "share");
+    for (depth, counters) in profile.depths.iter().enumerate() {
+        println!(
+            "{:>5} {:>10} {:>10} {:>10} {:>12} {:>5.1}%",
+            depth,
+            counters.nodes,
+            counters.dead_ends,
+            counters.solutions,
+            format!("{:.3?}", counters.time),
+            share(counters.time)
+        );
+    }
+    println!();
+    println!("{:<20} {:>10} {:>12} {:>6}", "technique", "steps", "time", "share");
+    for (reason, counters) in profile.reasons() {
+        println!(
+            "{:<20} {:>10} {:>12} {:>5.1}%",
+            reason.name(),
+            counters.steps,
+            format!("{:.3?}", counters.time),
+            share(counters.time)
+        );
+    }
+    if let Some(path) = &args.folded {
+        std::fs::write(path, profile.folded()).map_err(|e| format!("cannot write {}: {}", path, e))?;
+        tracing::info!(path, "wrote folded stacks");
+    }
+    Ok(())
+}
+
+/// Rate the puzzles of the files and write them as `compacted_puzzle,level` rows,
+/// the format of the web game's puzzle list, sorted by puzzle ID.
+fn run_pack(args: &PackArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    check_puzzle_id_size(size)?;
+    args.levels.validate()?;
+    let mut puzzles = Vec::new();
+    for path in &args.files {
+        puzzles.extend(load_puzzles(path, size)?);
+    }
+    let levels = puzzle_levels(size, &puzzles, &args.levels)?;
+
+    let mut out = create_output(args.out_file.as_deref())?;
+    let mut text = String::from("compacted_puzzle,level\n");
+    for (id, level) in &levels {
+        text.push_str(&format!("{},{}\n", id, level));
+    }
+    out.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    tracing::info!(puzzles = levels.len(), "packed puzzles");
+    Ok(())
 }
 
-/// Find all completions of a partial Latin square using advanced optimized backtracking.
-///
-/// This function takes a partially filled Latin square with known correct values
-/// and known incorrect values, then uses multiple optimization techniques including:
-/// - 🔥 Constraint Propagation Cascading - automatically fills forced moves
-/// - 🎯 Naked Singles Detection - cells with only one possible value
-/// - 🔍 Hidden Singles Detection - values with only one possible position
-/// - ⚡ Efficient Bitmask Operations - O(1) constraint checking
-/// - 🧠 Most Constrained Variable (MCV) heuristic - tackle hardest cells first
-/// - 🚀 Initial Preprocessing - solve obvious cells before backtracking
-/// - 🛡️ Advanced Validity Checking - early impossible state detection
-///
-/// # Parameters
-/// - `size`: Size of the Latin square (N×N). Default is 5.
-/// - `known_values`: HashMap mapping (row, col) tuples to known correct values.
-///   Example: {(0, 1): 3, (2, 0): 1} means cell (0,1) must be 3 and cell (2,0) must be 1.
-/// - `known_wrong_values`: HashMap mapping (row, col) tuples to vectors of values
-///   that are known to be wrong for that cell.
-///   Example: {(0, 0): vec![1, 2]} means cell (0,0) cannot be 1 or 2.
-/// - `max_solutions`: Maximum number of solutions to find. If None, finds all solutions.
-///   If set, stops when this many solutions are found.
-///
-/// # Returns
-/// A vector of completed N×N Latin squares with values 1 to N.
-/// Returns empty vector if no valid completion exists.
-///
-/// # Algorithm
-/// 1. Initialize square with known values and bitmasks
-/// 2. 🚀 Initial preprocessing: apply constraint propagation to solve obvious cells
-/// 3. Use MCV heuristic to select the most constrained empty cell
-/// 4. Try each candidate value with full constraint propagation
-/// 5. Recursively solve remaining cells with advanced pruning
-/// 6. When complete solution found, save it and continue searching
-/// 7. Stop when max_solutions is reached or all possibilities exhausted
-pub fn complete_latin_square_backtrack_all_solutions(
+/// Check that the web game's puzzle IDs can write puzzles of `size`.
+fn check_puzzle_id_size(size: usize) -> Result<(), String> {
+    if size > output::MAX_PUZZLE_ID_SIZE {
+        return Err(format!(
+            "the web game's puzzle IDs write the size as one digit, so they only encode grids up to {}x{}, not {}x{}",
+            output::MAX_PUZZLE_ID_SIZE,
+            output::MAX_PUZZLE_ID_SIZE,
+            size,
+            size
+        ));
+    }
+    Ok(())
+}
+
+/// Rate the unique puzzles of `puzzles`, returning the level of each by
+/// puzzle ID, in the order of the IDs.
+fn puzzle_levels(
     size: usize,
-    known_values: &HashMap<(usize, usize), usize>,
-    known_wrong_values: &HashMap<(usize, usize), Vec<usize>>,
-    max_solutions: Option<usize>,
-) -> Vec<Vec<Vec<usize>>> {
-    // Initialize the square with 0 for unknown cells (using 0 instead of -1)
-    let mut square = vec![vec![0; size]; size];
-    let mut solutions = Vec::new();
-
-    // Fill in known values
-    for (&(i, j), &value) in known_values {
-        if i < size && j < size && value >= 1 && value <= size {
-            square[i][j] = value;
-        }
-    }
-
-    // Create bitmasks for tracking used values in rows and columns
-    let mut row_used = vec![0u32; size]; // row_used[i] has bit v-1 set iff value v is in row i
-    let mut col_used = vec![0u32; size]; // col_used[j] has bit v-1 set iff value v is in column j
-    let full_mask = (1u32 << size) - 1; // bits 0..size-1 all set
-
-    // Initialize bitmasks based on known values
-    for i in 0..size {
-        for j in 0..size {
-            if square[i][j] != 0 {
-                let value = square[i][j];
-                let bit = 1u32 << (value - 1); // Convert to 0-based for bitmask
-                row_used[i] |= bit;
-                col_used[j] |= bit;
-            }
+    puzzles: &[Vec<(usize, usize)>],
+    levels: &LevelArgs,
+) -> Result<BTreeMap<String, &'static str>, String> {
+    let options = SolverOptions::default();
+    let rated: Vec<_> =
+        puzzles.par_iter().map(|tiles| check_puzzle(&PuzzleSpec::new(size, tiles), &options)).collect();
+    let mut by_id = BTreeMap::new();
+    for (tiles, puzzle) in puzzles.iter().zip(rated) {
+        let Ok(puzzle) = puzzle else {
+            tracing::warn!(tiles = %output::format_tiles(tiles), "skipped puzzle without a unique solution");
+            continue;
+        };
+        let Some(id) = output::encode_puzzle_id(size, tiles) else {
+            return Err(format!("puzzle IDs cannot encode the tiles of a {}x{} grid", size, size));
+        };
+        by_id.insert(id, levels.level(puzzle.stats.difficulty()));
+    }
+    Ok(by_id)
+}
+
+/// Rate the puzzles of the files and write their IDs in the structure the web
+/// game builds from its puzzle list: `{"5": {"normal": ["5015C", ...], ...}}`,
+/// with every level of every size, IDs sorted.
+fn run_export_web(args: &ExportWebArgs) -> Result<(), String> {
+    args.levels.validate()?;
+    let mut puzzles: BTreeMap<usize, Vec<Vec<(usize, usize)>>> = BTreeMap::new();
+    for path in &args.files {
+        let (size, semantics) = file_settings(path, args.size, None)?;
+        if semantics != ClueSemantics::Sudodle {
+            return Err(format!("{} holds classic puzzles: the game only plays its own", path));
+        }
+        check_puzzle_id_size(size)?;
+        puzzles.entry(size).or_default().extend(load_puzzles(path, size)?);
+    }
+    let mut data = serde_json::Map::new();
+    for (size, size_puzzles) in &puzzles {
+        let levels = puzzle_levels(*size, size_puzzles, &args.levels)?;
+        let mut by_level = serde_json::Map::new();
+        for level in LEVELS {
+            let ids: Vec<&String> = levels.iter().filter(|(_, &l)| l == level).map(|(id, _)| id).collect();
+            tracing::info!(size, level, puzzles = ids.len(), "exported puzzles");
+            by_level.insert(level.to_string(), serde_json::json!(ids));
         }
+        data.insert(size.to_string(), serde_json::Value::Object(by_level));
     }
+    let json = serde_json::to_string_pretty(&data).expect("JSON values serialize");
 
-    // 🔥 CONSTRAINT PROPAGATION CASCADE - automatically fills forced moves
-    // Returns true if progress was made, false if contradiction found
-    let apply_constraint_propagation = |square: &mut Vec<Vec<usize>>, 
-                                           row_used: &mut Vec<u32>, 
-                                           col_used: &mut Vec<u32>| -> Result<bool, ()> {
-        let mut progress = true;
-        let mut total_progress = false;
-        
-        while progress {
-            progress = false;
-            
-            // 🎯 NAKED SINGLES DETECTION - cells with only one possible value
-            for i in 0..size {
-                for j in 0..size {
-                    if square[i][j] == 0 {
-                        let used = row_used[i] | col_used[j];
-                        let avail_mask = full_mask & !used;
-                        
-                        // Apply known wrong values constraint
-                        let mut final_mask = avail_mask;
-                        if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-                            for &wrong_val in wrong_values {
-                                let wrong_bit = 1u32 << (wrong_val - 1);
-                                final_mask &= !wrong_bit;
-                            }
-                        }
-                        
-                        if final_mask == 0 {
-                            return Err(()); // Contradiction found
-                        }
-                        
-                        // Check if exactly one bit is set (naked single)
-                        if final_mask & (final_mask - 1) == 0 {
-                            let value = final_mask.trailing_zeros() as usize + 1;
-                            let bit = 1u32 << (value - 1);
-                            
-                            square[i][j] = value;
-                            row_used[i] |= bit;
-                            col_used[j] |= bit;
-                            progress = true;
-                            total_progress = true;
-                        }
-                    }
-                }
-            }
-            
-            // 🔍 HIDDEN SINGLES DETECTION - values with only one possible position
-            // Check rows for hidden singles
-            for i in 0..size {
-                for val in 1..=size {
-                    let bit = 1u32 << (val - 1);
-                    if (row_used[i] & bit) == 0 { // Value not yet in this row
-                        let mut possible_positions = Vec::new();
-                        
-                        for j in 0..size {
-                            if square[i][j] == 0 {
-                                let cell_used = row_used[i] | col_used[j];
-                                let mut can_place = (cell_used & bit) == 0;
-                                
-                                // Check known wrong values
-                                if can_place {
-                                    if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-                                        can_place = !wrong_values.contains(&val);
-                                    }
-                                }
-                                
-                                if can_place {
-                                    possible_positions.push(j);
-                                }
-                            }
-                        }
-                        
-                        if possible_positions.is_empty() {
-                            return Err(()); // Contradiction: value can't be placed anywhere
-                        } else if possible_positions.len() == 1 {
-                            // Hidden single found
-                            let j = possible_positions[0];
-                            square[i][j] = val;
-                            row_used[i] |= bit;
-                            col_used[j] |= bit;
-                            progress = true;
-                            total_progress = true;
-                        }
-                    }
-                }
-            }
-            
-            // Check columns for hidden singles
-            for j in 0..size {
-                for val in 1..=size {
-                    let bit = 1u32 << (val - 1);
-                    if (col_used[j] & bit) == 0 { // Value not yet in this column
-                        let mut possible_positions = Vec::new();
-                        
-                        for i in 0..size {
-                            if square[i][j] == 0 {
-                                let cell_used = row_used[i] | col_used[j];
-                                let mut can_place = (cell_used & bit) == 0;
-                                
-                                // Check known wrong values
-                                if can_place {
-                                    if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-                                        can_place = !wrong_values.contains(&val);
-                                    }
-                                }
-                                
-                                if can_place {
-                                    possible_positions.push(i);
-                                }
-                            }
-                        }
-                        
-                        if possible_positions.is_empty() {
-                            return Err(()); // Contradiction: value can't be placed anywhere
-                        } else if possible_positions.len() == 1 {
-                            // Hidden single found
-                            let i = possible_positions[0];
-                            square[i][j] = val;
-                            row_used[i] |= bit;
-                            col_used[j] |= bit;
-                            progress = true;
-                            total_progress = true;
-                        }
-                    }
-                }
+    let extension = args.out_file.as_deref().and_then(|path| Path::new(path).extension()).and_then(|e| e.to_str());
+    let format = args.format.unwrap_or(match extension {
+        Some("js") => WebFormat::Js,
+        Some("ts") => WebFormat::Ts,
+        _ => WebFormat::Json,
+    });
+    let text = match format {
+        WebFormat::Json => format!("{}\n", json),
+        WebFormat::Js => format!("// Written by `find_puzzles export-web`\nexport const puzzleData = {};\n", json),
+        WebFormat::Ts => format!(
+            "// Written by `find_puzzles export-web`\n\
+             export type Level = {};\n\n\
+             export const puzzleData: Record<number, Record<Level, string[]>> = {};\n",
+            LEVELS.map(|level| format!("\"{}\"", level)).join(" | "),
+            json
+        ),
+    };
+    create_output(args.out_file.as_deref())?.write_all(text.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Merge the puzzle files, keeping the first record of each puzzle by content ID
+/// (or of each class of equivalent puzzles with `--symmetry`), under one header.
+fn run_dedupe(args: &DedupeArgs) -> Result<(), String> {
+    let mut settings = None;
+    let mut files: Vec<(&String, output::PuzzleLines)> = Vec::new();
+    for path in &args.inputs {
+        let file_settings = file_settings(path, args.size, args.semantics)?;
+        if settings.is_some_and(|settings| settings != file_settings) {
+            return Err(format!("{} holds other puzzles than {} (size or semantics)", path, args.inputs[0]));
+        }
+        settings = Some(file_settings);
+        let lines = output::read_puzzle_lines(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        // Files without records or column names may be in any format
+        if let Some((first, first_lines)) = files.iter().find(|(_, lines)| shows_format(lines)) {
+            let same_format = lines.format() == first_lines.format() && lines.columns == first_lines.columns;
+            if shows_format(&lines) && !same_format {
+                return Err(format!("{} is not in the format of {}", path, first));
             }
         }
-        
-        Ok(total_progress)
-    };
+        files.push((path, lines));
+    }
+    let (size, semantics) = settings.expect("at least one input");
+    let format_lines = files.iter().find(|(_, lines)| shows_format(lines)).map_or(&files[0].1, |(_, lines)| lines);
+    let header = output::FileHeader::new(size, semantics);
+    let symmetries = if args.symmetry { symmetry::symmetries(size) } else { Vec::new() };
 
-    // Helper function to get available values for cell (i, j)
-    let get_available_values = |square: &Vec<Vec<usize>>, 
-                               row_used: &Vec<u32>, 
-                               col_used: &Vec<u32>, 
-                               i: usize, 
-                               j: usize,
-                               temp_candidates: &mut Vec<usize>| -> usize {
-        temp_candidates.clear();
-        
-        if square[i][j] != 0 {
-            return 0; // Cell already filled
+    let (mut read, mut duplicates, mut symmetric) = (0, 0, 0);
+    let mut ids = HashSet::new();
+    let mut classes = HashSet::new();
+    let mut kept = Vec::new();
+    for (path, lines) in &files {
+        for (tiles, line) in &lines.records {
+            if tiles.iter().any(|&(r, c)| r >= size || c >= size) {
+                return Err(format!("{}: tiles out of a {}x{} grid in '{}'", path, size, size, line));
+            }
+            read += 1;
+            if !ids.insert(header.content_id(tiles)) {
+                duplicates += 1;
+            } else if args.symmetry && !classes.insert(symmetry::canonical_id(&header, tiles, &symmetries)) {
+                symmetric += 1;
+            } else {
+                kept.push(line.as_str());
+            }
         }
+    }
 
-        // Values already used in this row or column
-        let used = row_used[i] | col_used[j];
-        let avail_mask = full_mask & !used;
+    let format = format_lines.format();
+    let mut text = header.line(format) + "\n";
+    if let Some(columns) = &format_lines.columns {
+        text.push_str(columns);
+        text.push('\n');
+    }
+    for line in &kept {
+        text.push_str(line);
+        text.push('\n');
+    }
+    create_output(args.output.as_deref())?.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    tracing::info!(read, duplicates, symmetric, kept = kept.len(), "merged puzzle files");
+    Ok(())
+}
 
-        if avail_mask == 0 {
-            return 0; // No candidates available
-        }
+/// Whether the format of `lines` can be told: they have records or column names.
+fn shows_format(lines: &output::PuzzleLines) -> bool {
+    lines.columns.is_some() || !lines.records.is_empty()
+}
 
-        // Build list of available values using bit manipulation
-        let mut m = avail_mask;
-        while m != 0 {
-            let bit = m & m.wrapping_neg(); // Get lowest set bit
-            m ^= bit; // Clear the bit
-            let v = bit.trailing_zeros() as usize + 1; // Convert back to 1-based
-            temp_candidates.push(v);
-        }
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Count years from March so that leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
 
-        // Remove values that are known to be wrong for this cell
-        if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-            temp_candidates.retain(|&v| !wrong_values.contains(&v));
-        }
+/// Date of the day numbered `days` since 1970-01-01, inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
 
-        temp_candidates.len()
-    };
+/// Parse a YYYY-MM-DD date into days since 1970-01-01.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    let days = days_from_civil(year, month, day);
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
 
-    // Helper function to find most constrained cell
-    let find_most_constrained_cell = |square: &Vec<Vec<usize>>, 
-                                     row_used: &Vec<u32>, 
-                                     col_used: &Vec<u32>| -> (Option<(usize, usize)>, usize) {
-        let mut best_cell = None;
-        let mut min_choices = size + 1;
-        let mut temp_candidates = Vec::with_capacity(size); // Reuse allocation
-
-        for i in 0..size {
-            for j in 0..size {
-                if square[i][j] == 0 { // Empty cell
-                    let choices = get_available_values(square, row_used, col_used, i, j, &mut temp_candidates);
-                    if choices == 0 {
-                        return (Some((i, j)), 0); // Dead end - return immediately
-                    }
-                    if choices < min_choices {
-                        min_choices = choices;
-                        best_cell = Some((i, j));
-                        if choices == 1 {
-                            return (best_cell, 1); // Can't get better than 1 choice
-                        }
-                    }
-                }
-            }
+/// Pick the puzzle of a day from a file, the same for everyone using that file.
+fn run_daily(args: &DailyArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let puzzles = load_puzzles(&args.file, size)?;
+    if puzzles.is_empty() {
+        return Err(format!("{} contains no puzzles", args.file));
+    }
+    let days = match &args.date {
+        Some(date) => parse_date(date).ok_or_else(|| format!("invalid date '{}', expected YYYY-MM-DD", date))?,
+        None => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?;
+            (now.as_secs() / 86_400) as i64
         }
-
-        (best_cell, min_choices)
     };
+    // Scatter consecutive days over the file, as neighbouring puzzles are often similar
+    let index = (days as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) % puzzles.len() as u64;
+    let tiles = &puzzles[index as usize];
+    let (year, month, day) = civil_from_days(days);
+    println!("Date: {:04}-{:02}-{:02}", year, month, day);
+    println!("Tiles: {}", output::format_tiles(tiles));
+    if let Some(id) = output::encode_puzzle_id(size, tiles) {
+        println!("Puzzle ID: {}", id);
+    }
+    Ok(())
+}
 
+fn run_serve(args: &ServeArgs) -> Result<(), String> {
+    args.levels.validate()?;
+    let pool = args.pool_size.map(|target| {
+        PuzzlePool::new(PoolOptions {
+            sizes: args.pool_sizes.iter().map(|&size| size as usize).collect(),
+            target,
+            hard_from: args.levels.hard_from,
+            expert_from: args.levels.expert_from,
+            seed: args.pool_seed,
+            families: args.pool_families,
+            solver: args.solver.options(),
+        })
+    });
+    server::serve(&args.addr, args.solver.options(), pool, args.max_connections as usize)
+        .map_err(|e| format!("cannot serve on {}: {}", args.addr, e))
+}
 
-
-    // Enhanced early termination with constraint propagation
-    let has_valid_assignment = |square: &Vec<Vec<usize>>, 
-                               row_used: &Vec<u32>, 
-                               col_used: &Vec<u32>| -> bool {
-        let mut temp_candidates = Vec::with_capacity(size);
-        
-        // Check if any empty cell has no possible values
-        for i in 0..size {
-            for j in 0..size {
-                if square[i][j] == 0 {
-                    let choices = get_available_values(square, row_used, col_used, i, j, &mut temp_candidates);
-                    if choices == 0 {
-                        return false;
-                    }
-                }
-            }
+/// Spread a search over `search --worker` processes and write the puzzles
+/// they find.
+fn run_coordinate(args: &CoordinateArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    validate_args(size, args.placed)?;
+    let grid = base_grid(args.base_grid, size)?;
+    let runs = match (args.generator, args.random_tries) {
+        (Generator::BottomUp, None) => None,
+        (Generator::BottomUp, Some(_)) => {
+            return Err("random bottom-up searches cannot be split into ranges: leave out --random-tries to \
+                split the exhaustive search, or use a constructor generator"
+                .to_string())
         }
-        
-        // Additional constraint: check if any value is impossible in any row/column
-        for val in 1..=size {
-            let bit = 1u32 << (val - 1);
-            
-            // Check each row - ensure value can be placed somewhere
-            for i in 0..size {
-                if (row_used[i] & bit) == 0 {
-                    let mut can_place = false;
-                    for j in 0..size {
-                        if square[i][j] == 0 && (col_used[j] & bit) == 0 {
-                            // Check if this cell specifically excludes this value
-                            if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-                                if !wrong_values.contains(&val) {
-                                    can_place = true;
-                                    break;
-                                }
-                            } else {
-                                can_place = true;
-                                break;
-                            }
-                        }
-                    }
-                    if !can_place {
-                        return false;
-                    }
-                }
-            }
-            
-            // Check each column - ensure value can be placed somewhere
-            for j in 0..size {
-                if (col_used[j] & bit) == 0 {
-                    let mut can_place = false;
-                    for i in 0..size {
-                        if square[i][j] == 0 && (row_used[i] & bit) == 0 {
-                            // Check if this cell specifically excludes this value
-                            if let Some(wrong_values) = known_wrong_values.get(&(i, j)) {
-                                if !wrong_values.contains(&val) {
-                                    can_place = true;
-                                    break;
-                                }
-                            } else {
-                                can_place = true;
-                                break;
-                            }
-                        }
-                    }
-                    if !can_place {
-                        return false;
-                    }
-                }
-            }
+        (_, Some(runs)) => Some(runs as u64),
+        (generator, None) => {
+            let name = generator.to_possible_value().expect("no skipped generators");
+            return Err(format!("--generator {} needs --random-tries (the number of runs)", name.get_name()));
         }
-        
-        true
     };
+    let header = output::FileHeader {
+        generator: Some(args.generator.to_possible_value().expect("no skipped generators").get_name().to_string()),
+        seed: (args.generator != Generator::BottomUp).then_some(args.generator_seed),
+        ..output::FileHeader::new(size, args.semantics).with_base_grid(&grid)
+    };
+    let mut sinks: Vec<Box<dyn PuzzleSink>> = Vec::new();
+    if let Some(ref path) = args.out_file {
+        let sink = output::create_file_sink(path, args.format, None, false, &header)
+            .map_err(|e| format!("cannot create {}: {}", path, e))?;
+        sinks.push(sink);
+    }
+    let options = CoordinatorOptions {
+        job: Job {
+            size,
+            placed: args.placed,
+            semantics: args.semantics,
+            base_grid: args.base_grid,
+            generator: args.generator,
+            generator_seed: args.generator_seed,
+            runs,
+            require_singles_solvable: args.require_singles_solvable,
+            rules_version: output::RULES_VERSION,
+        },
+        range_size: args.range_size,
+        lease_timeout: Duration::from_secs(args.lease_timeout_secs),
+    };
+    let summary = cluster::coordinate(&args.addr, options, &mut sinks)
+        .map_err(|e| format!("cannot coordinate on {}: {}", args.addr, e))?;
+    println!(
+        "Found {} puzzles in {} combinations over {} ranges ({} handed out again) from {} workers in {:.1}s",
+        summary.unique_puzzles,
+        summary.combinations,
+        summary.ranges,
+        summary.reissued,
+        summary.workers,
+        summary.wall_time.as_secs_f64()
+    );
+    Ok(())
+}
 
-    // 🚀 ENHANCED BACKTRACKING with optimized constraint propagation
-    fn backtrack(
-        square: &mut Vec<Vec<usize>>,
-        row_used: &mut Vec<u32>,
-        col_used: &mut Vec<u32>,
-        solutions: &mut Vec<Vec<Vec<usize>>>,
-        size: usize,
-        full_mask: u32,
-        max_solutions: Option<usize>,
-        known_wrong_values: &HashMap<(usize, usize), Vec<usize>>,
-        get_available_values: &dyn Fn(&Vec<Vec<usize>>, &Vec<u32>, &Vec<u32>, usize, usize, &mut Vec<usize>) -> usize,
-        find_most_constrained_cell: &dyn Fn(&Vec<Vec<usize>>, &Vec<u32>, &Vec<u32>) -> (Option<(usize, usize)>, usize),
-        has_valid_assignment: &dyn Fn(&Vec<Vec<usize>>, &Vec<u32>, &Vec<u32>) -> bool,
-        apply_constraint_propagation: &dyn Fn(&mut Vec<Vec<usize>>, &mut Vec<u32>, &mut Vec<u32>) -> Result<bool, ()>,
-    ) {
-        // Check if we've found enough solutions
-        if let Some(max) = max_solutions {
-            if solutions.len() >= max {
-                return;
-            }
-        }
+/// Print the grid being played, marking the placed tiles and the values known
+/// to be wrong where they stand.
+fn print_play_grid(grid: &Grid, start: &Grid, placed: &HashSet<(usize, usize)>) {
+    let size = grid.size();
+    println!();
+    println!("   {}", (1..=size).map(|col| format!("{:^3}", col)).collect::<String>());
+    for row in 0..size {
+        let cells: String = (0..size)
+            .map(|col| {
+                let value = output::value_char(grid[(row, col)]);
+                if placed.contains(&(row, col)) {
+                    format!("[{}]", value)
+                } else if grid[(row, col)] == start[(row, col)] {
+                    format!(" {}*", value)
+                } else {
+                    format!(" {} ", value)
+                }
+            })
+            .collect();
+        println!("{:>2} {}", row + 1, cells);
+    }
+}
 
-        // Find the most constrained empty cell
-        let (cell, num_choices) = find_most_constrained_cell(square, row_used, col_used);
+/// Play a puzzle like the web game's single-turn mode: swap the tiles of the
+/// cyclic square until the grid matches the solution.
+fn run_play(args: &PlayArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let tiles = parse_puzzle(size, &args.tiles)?;
+    let solution = check_puzzle(&PuzzleSpec::new(size, &tiles), &SolverOptions::default())
+        .map_err(|_| "this puzzle does not have a unique solution".to_string())?
+        .unique_solution(size, ClueSemantics::Sudodle);
+    let start = cyclic_latin_square(size);
+    let placed: HashSet<(usize, usize)> = tiles.iter().copied().collect();
+    let mut grid = start.clone();
+    let mut swaps = 0;
 
-        if let Some((i, j)) = cell {
-            if num_choices == 0 {
-                return; // Dead end
+    println!("Swap tiles until each value appears once in every row and column.");
+    println!("Tiles in [brackets] are correct, values marked with * are wrong where they stand.");
+    println!("Enter the two tiles to swap as `row col row col`, or `q` to quit.");
+    loop {
+        print_play_grid(&grid, &start, &placed);
+        if grid == solution {
+            println!("\nSolved in {} swaps!", swaps);
+            return Ok(());
+        }
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).map_err(|e| e.to_string())? == 0 || line.trim() == "q" {
+            return Ok(());
+        }
+        let numbers: Option<Vec<usize>> = line.split_whitespace().map(|number| number.parse().ok()).collect();
+        match numbers.as_deref() {
+            Some(&[r1, c1, r2, c2]) if [r1, c1, r2, c2].iter().all(|n| (1..=size).contains(n)) => {
+                let (a, b) = ((r1 - 1, c1 - 1), (r2 - 1, c2 - 1));
+                if placed.contains(&a) || placed.contains(&b) {
+                    println!("Correct tiles cannot be moved");
+                    continue;
+                }
+                let value = grid[a];
+                grid[a] = grid[b];
+                grid[b] = value;
+                swaps += 1;
             }
+            _ => println!("Expected four numbers between 1 and {}: row col row col", size),
+        }
+    }
+}
 
-            let mut candidates = Vec::new();
-            let _choices = get_available_values(square, row_used, col_used, i, j, &mut candidates);
+/// Solve the same random combinations with each solver strategy and print how they compare.
+///
+/// Solves run on a single thread so that timings are comparable.
+fn run_bench(args: &BenchArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    validate_args(size, args.placed)?;
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let samples: Vec<Vec<(usize, usize)>> = (0..args.samples)
+        .map(|_| tile_coordinates.choose_multiple(&mut rng, args.placed).cloned().collect())
+        .collect();
 
-            // Try each candidate value with proper state management
-            for &value in &candidates {
-                // Early termination check
-                if let Some(max) = max_solutions {
-                    if solutions.len() >= max {
-                        return;
-                    }
-                }
+    let thresholds = args.propagation_thresholds.iter().map(|&threshold| {
+        let options = SolverOptions::builder().techniques(args.techniques).propagation_threshold(threshold).build();
+        (format!("propagation-threshold={}", threshold), options)
+    });
+    let kernels = args.kernels.iter().map(|&kernel| {
+        let options = SolverOptions::builder().techniques(args.techniques).kernel(kernel).build();
+        let name = kernel.to_possible_value().expect("no skipped variants").get_name().to_string();
+        (format!("kernel={}", name), options)
+    });
+    let value_orders = [false, true]
+        .into_iter()
+        .flat_map(|degree_tie_break| {
+            ValueOrder::value_variants().iter().map(move |&value_order| {
+                let name = value_order.to_possible_value().expect("no skipped variants").get_name().to_string();
+                let options = SolverOptions::builder()
+                    .techniques(args.techniques)
+                    .value_order(value_order)
+                    .seed(args.seed)
+                    .degree_tie_break(degree_tie_break)
+                    .build();
+                let suffix = if degree_tie_break { " +degree" } else { "" };
+                (format!("value-order={}{}", name, suffix), options)
+            })
+        });
+    let mut strategies: Vec<(String, SolverOptions)> = thresholds.chain(kernels).collect();
+    if strategies.is_empty() {
+        strategies = value_orders.collect();
+    }
 
-                let bit = 1u32 << (value - 1);
-
-                // Save complete state before making changes
-                let original_square: Vec<Vec<usize>> = square.iter().map(|row| row.clone()).collect();
-                let original_row_used = row_used.clone();
-                let original_col_used = col_used.clone();
-
-                // Place the value
-                square[i][j] = value;
-                row_used[i] |= bit;
-                col_used[j] |= bit;
-
-                // Apply constraint propagation after placing value
-                let mut should_continue = true;
-                let empty_cells = square.iter().flatten().filter(|&&x| x == 0).count();
-                if empty_cells < size * size / 2 {  // Only when puzzle is more than half filled
-                    match apply_constraint_propagation(square, row_used, col_used) {
-                        Err(()) => should_continue = false, // Contradiction found
-                        Ok(_) => {} // Continue with current state
-                    }
-                }
+    println!("Solving {} random combinations of {} tiles for N={}:", args.samples, args.placed, size);
+    println!("{:<32} {:>8} {:>12} {:>10} {:>12}", "strategy", "unique", "nodes", "guesses", "time");
+    for (name, options) in &strategies {
+        let mut unique = 0;
+        let mut nodes = 0;
+        let mut guesses = 0;
+        let start = Instant::now();
+        for tiles in &samples {
+            let constraints = puzzle_constraints_with(tiles, &grid, &tile_coordinates, args.semantics);
+            let (solutions, stats) = solve_constraints(&constraints, Some(2), options, SolveHooks::default());
+            unique += usize::from(solutions.len() == 1);
+            nodes += stats.backtrack_nodes;
+            guesses += stats.guesses;
+        }
+        println!("{:<32} {:>8} {:>12} {:>10} {:>12.3?}", name, unique, nodes, guesses, start.elapsed());
+    }
+    Ok(())
+}
 
-                // 🛡️ Enhanced validity check before deeper recursion
-                if should_continue && has_valid_assignment(square, row_used, col_used) {
-                    backtrack(
-                        square,
-                        row_used,
-                        col_used,
-                        solutions,
-                        size,
-                        full_mask,
-                        max_solutions,
-                        known_wrong_values,
-                        get_available_values,
-                        find_most_constrained_cell,
-                        has_valid_assignment,
-                        apply_constraint_propagation,
-                    );
-                }
+/// Solve random QCP or QWH instances of each fill ratio and print how many
+/// the solver completed, proved impossible or gave up, and its effort.
+///
+/// Solves run on a single thread so that timings are comparable.
+fn run_bench_qcp(args: &BenchQcpArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    if let Some(fill) = args.fill.iter().find(|fill| !(0.0..=1.0).contains(*fill)) {
+        return Err(format!("--fill {} is not a share of the cells between 0 and 1", fill));
+    }
+    if let Some(dir) = &args.out_dir {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir, e))?;
+    }
+    let mut builder = SolverOptions::builder()
+        .techniques(args.techniques)
+        .learning(args.learning)
+        .dlx(args.dlx)
+        .portfolio(args.portfolio);
+    if let Some(node_budget) = args.node_budget {
+        builder = builder.node_budget(node_budget);
+    }
+    if let Some(unit) = args.restarts {
+        builder = builder.restarts(unit);
+    }
+    let options = builder.build();
+    let family = args.family.to_possible_value().expect("no skipped variants").get_name().to_string();
 
-                // Restore complete state
-                *square = original_square;
-                *row_used = original_row_used;
-                *col_used = original_col_used;
+    println!("Solving {} {} instances of order {} for each fill ratio:", args.instances, family, size);
+    println!(
+        "{:>6} {:>6} {:>8} {:>8} {:>8} {:>12} {:>12} {:>12} {:>12}",
+        "fill", "given", "sat", "unsat", "timeout", "median nodes", "mean nodes", "median time", "max time"
+    );
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    for &fill in &args.fill {
+        let (mut sat, mut unsat, mut timed_out) = (0, 0, 0);
+        let mut given = 0;
+        let mut nodes = Vec::with_capacity(args.instances);
+        let mut times = Vec::with_capacity(args.instances);
+        for instance in 0..args.instances {
+            let constraints = qcp::instance(args.family, size, fill, &mut rng);
+            given += size * size - constraints.known.empty_cells();
+            if let Some(dir) = &args.out_dir {
+                let path = Path::new(dir).join(format!("{}-{}-{:.2}-{}.pls", family, size, fill, instance));
+                std::fs::write(&path, qcp::format_pls(&constraints))
+                    .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+            }
+            let start = Instant::now();
+            let (solutions, stats) = solve_constraints(&constraints, Some(1), &options, SolveHooks::default());
+            times.push(start.elapsed());
+            nodes.push(stats.backtrack_nodes);
+            match (solutions.is_empty(), stats.timed_out) {
+                (false, _) => sat += 1,
+                (true, false) => unsat += 1,
+                (true, true) => timed_out += 1,
             }
-        } else {
-            // All cells filled successfully - save this solution
-            let solution: Vec<Vec<usize>> = square.iter().map(|row| row.clone()).collect();
-            solutions.push(solution);
         }
+        nodes.sort_unstable();
+        times.sort_unstable();
+        let median = |count: usize| count / 2;
+        println!(
+            "{:>6.2} {:>6.1} {:>8} {:>8} {:>8} {:>12} {:>12.1} {:>12.3?} {:>12.3?}",
+            fill,
+            given as f64 / args.instances.max(1) as f64,
+            sat,
+            unsat,
+            timed_out,
+            nodes.get(median(nodes.len())).copied().unwrap_or(0),
+            nodes.iter().sum::<usize>() as f64 / args.instances.max(1) as f64,
+            times.get(median(times.len())).copied().unwrap_or_default(),
+            times.last().copied().unwrap_or_default(),
+        );
     }
+    Ok(())
+}
 
-    // Validate that known values don't violate Latin square constraints
-    for i in 0..size {
-        let mut row_values = Vec::new();
-        for j in 0..size {
-            if square[i][j] != 0 {
-                row_values.push(square[i][j]);
-            }
-        }
-        let mut sorted_values = row_values.clone();
-        sorted_values.sort();
-        sorted_values.dedup();
-        if row_values.len() != sorted_values.len() {
-            return Vec::new(); // Duplicate values in row
+/// Convert a trace file to a DOT graph.
+fn run_trace_dot(args: &TraceDotArgs) -> Result<(), String> {
+    let events = trace::read_jsonl(&args.trace).map_err(|e| format!("cannot read {}: {}", args.trace, e))?;
+    let dot = trace::to_dot(&events);
+    match &args.out_file {
+        Some(path) => std::fs::write(path, dot).map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => {
+            print!("{}", dot);
+            Ok(())
         }
     }
+}
 
-    for j in 0..size {
-        let mut col_values = Vec::new();
-        for i in 0..size {
-            if square[i][j] != 0 {
-                col_values.push(square[i][j]);
+/// Build minimal mixed hint sets for the solutions of the puzzles of a file and
+/// write the distinct ones, one text-format line per set.
+fn run_hints(args: &HintsArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let puzzles = load_puzzles(&args.file, size)?;
+    init_thread_pool(args.processors);
+    let options = args.solver.options();
+    let grid = cyclic_latin_square(size);
+    let solutions: Vec<Grid> = puzzles
+        .par_iter()
+        .filter_map(|tiles| match check_puzzle(&PuzzleSpec::new(size, tiles), &options) {
+            Ok(puzzle) => Some(puzzle.unique_solution(size, ClueSemantics::Sudodle)),
+            Err(_) => {
+                tracing::warn!(tiles = %output::format_tiles(tiles), "skipped puzzle without a unique solution");
+                None
             }
-        }
-        let mut sorted_values = col_values.clone();
-        sorted_values.sort();
-        sorted_values.dedup();
-        if col_values.len() != sorted_values.len() {
-            return Vec::new(); // Duplicate values in column
-        }
+        })
+        .collect();
+    let runs: Vec<(usize, u64)> = (0..solutions.len())
+        .flat_map(|puzzle| (0..args.runs as u64).map(move |run| (puzzle, run)))
+        .collect();
+    let hint_sets: Vec<Vec<TileHint>> = runs
+        .par_iter()
+        .filter_map(|&(puzzle, run)| {
+            let run = (puzzle * args.runs) as u64 + run;
+            hints::minimal_hints(&grid, &solutions[puzzle], run, args.hint_seed, &options)
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let distinct: Vec<&Vec<TileHint>> = hint_sets.iter().filter(|hints| seen.insert(*hints)).collect();
+    let mut out = create_output(args.out_file.as_deref())?;
+    for hints in &distinct {
+        writeln!(out, "{}", hints::format_hints(hints)).map_err(|e| e.to_string())?;
     }
+    let correct = distinct.iter().flat_map(|hints| hints.iter()).filter(|(_, hint)| *hint == Hint::Correct).count();
+    let total: usize = distinct.iter().map(|hints| hints.len()).sum();
+    tracing::info!(
+        puzzles = solutions.len(),
+        hint_sets = distinct.len(),
+        fewest_hints = distinct.iter().map(|hints| hints.len()).min(),
+        mean_hints = total as f64 / distinct.len().max(1) as f64,
+        mean_correct = correct as f64 / distinct.len().max(1) as f64,
+        "built minimal hint sets"
+    );
+    Ok(())
+}
 
-    // 🚀 INITIAL PREPROCESSING - solve obvious cells only if puzzle is sufficiently constrained
-    let initial_filled = square.iter().flatten().filter(|&&x| x != 0).count();
-    if initial_filled > size {  // Only preprocess if we have enough initial constraints
-        match apply_constraint_propagation(&mut square, &mut row_used, &mut col_used) {
-            Err(()) => return Vec::new(), // Contradiction in initial state
-            Ok(_) => {} // Continue with preprocessed state
+/// Play games against a given or random secret and print the distribution of
+/// the number of guesses they took.
+fn run_simulate(args: &SimulateArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let secret = args.secret.as_deref().map(output::parse_grid).transpose()?;
+    if let Some(secret) = &secret {
+        if secret.size() != size {
+            return Err(format!("the secret is a {}x{} grid, not {}x{}", secret.size(), secret.size(), size, size));
         }
     }
+    init_thread_pool(args.processors);
+    let options = SimulationOptions { strategy: args.strategy, samples: args.samples, relaxed: args.relaxed };
+    if let Some(top) = args.hardest {
+        return run_hardest_secrets(args, top, &options);
+    }
+    let start = Instant::now();
+    let uniform_secrets = match args.uniform_secrets {
+        true => completions::sample_completions(&Constraints::new(size), args.trials, args.seed),
+        false => Vec::new(),
+    };
+    let games: Vec<(Grid, simulate::Transcript)> = (0..args.trials as u64)
+        .into_par_iter()
+        .map(|trial| {
+            let mut rng = simulate::trial_rng(args.seed, trial);
+            let secret = match &secret {
+                Some(secret) => secret.clone(),
+                None if args.uniform_secrets => uniform_secrets[trial as usize].clone(),
+                None => simulate::random_consistent(&Constraints::new(size), &mut rng).expect("Latin squares exist"),
+            };
+            let transcript = simulate::play(&secret, Constraints::new(size), &options, &mut rng);
+            (secret, transcript)
+        })
+        .collect();
 
-    // Final validity check after preprocessing
-    if !has_valid_assignment(&square, &row_used, &col_used) {
-        return Vec::new();
+    if let Some(path) = &args.transcripts {
+        let mut out = create_output(Some(path))?;
+        for (trial, (secret, transcript)) in games.iter().enumerate() {
+            let record = serde_json::json!({
+                "trial": trial,
+                "secret": output::format_grid(secret),
+                "guesses": transcript.guesses.iter().map(output::format_grid).collect::<Vec<_>>(),
+                "correct": transcript.correct,
+            });
+            writeln!(out, "{}", record).map_err(|e| format!("cannot write {}: {}", path, e))?;
+        }
     }
 
-    // Try to find all completions with enhanced backtracking
-    backtrack(
-        &mut square,
-        &mut row_used,
-        &mut col_used,
-        &mut solutions,
-        size,
-        full_mask,
-        max_solutions,
-        known_wrong_values,
-        &get_available_values,
-        &find_most_constrained_cell,
-        &has_valid_assignment,
-        &apply_constraint_propagation,
+    let mut counts = GuessCounts::default();
+    for (_, transcript) in &games {
+        counts.add(transcript.guesses.len());
+    }
+    println!("{} games of N={} in {:.3?}:", counts.games(), size, start.elapsed());
+    println!("{:>8} {:>8} {:>8}", "guesses", "games", "share");
+    for (guesses, games) in &counts.counts {
+        println!("{:>8} {:>8} {:>7.1}%", guesses, games, 100.0 * *games as f64 / counts.games().max(1) as f64);
+    }
+    println!(
+        "Mean: {:.2}, median: {}, 90th percentile: {}, 99th percentile: {}, max: {}",
+        counts.mean(),
+        counts.percentile(0.5),
+        counts.percentile(0.9),
+        counts.percentile(0.99),
+        counts.percentile(1.0)
     );
-
-    solutions
+    Ok(())
 }
 
-/// Generate the basic cyclic Latin square of order N.
-///
-/// A cyclic Latin square is constructed using the formula: L[i][j] = (i + j) mod N + 1
-/// This is guaranteed to be a valid Latin square for any positive integer N.
-///
-/// # Parameters
-/// - `n`: Order of the Latin square (number of rows/columns).
-///
-/// # Returns
-/// An N×N cyclic Latin square with values 1..N.
-///
-/// # Example
-/// ```
-/// let square = cyclic_latin_square(3);
-/// // Returns [[1, 2, 3], [2, 3, 1], [3, 1, 2]]
-/// ```
-///
-/// Note: This is often used as a starting point for generating more random
-/// Latin squares through transformations.
-pub fn cyclic_latin_square(n: usize) -> Vec<Vec<usize>> {
-    (0..n)
-        .map(|i| (0..n).map(|j| (i + j) % n + 1).collect())
-        .collect()
-}
-
-/// Standardize a tuple of tile coordinates to avoid counting equivalent puzzles multiple times.
-/// This function sorts the coordinates to create a canonical representation.
-fn standardize_tile_tuple(tiles: &[(usize, usize)]) -> Vec<(usize, usize)> {
-    let mut standardized = tiles.to_vec();
-    standardized.sort();
-    standardized
-}
-
-/// Process a batch of tile combinations to find single-solution puzzles.
-fn process_batch(
-    batch: &[Vec<(usize, usize)>],
-    grid: &Arc<Vec<Vec<usize>>>,
-    n: usize,
-    tile_coordinates: &[(usize, usize)],
-    all_solutions: &mut Vec<(Vec<(usize, usize)>, Vec<Vec<usize>>)>,
-    seen_standardized_puzzles: &mut HashSet<Vec<(usize, usize)>>,
-    writer: &mut Option<BufWriter<std::fs::File>>,
-    processed_count: &mut usize,
-) {
-    
-    // Process this batch in parallel - first check for single solutions
-    let batch_solutions: Vec<_> = batch
-        .par_iter()
-        .filter_map(|selected_tiles| {
-            // Set up known values and wrong values
-            let mut known_values = HashMap::new();
-            let mut known_wrong_values = HashMap::new();
-            
-            for &(i, j) in tile_coordinates {
-                if selected_tiles.contains(&(i, j)) {
-                    known_values.insert((i, j), grid[i][j]);
-                } else {
-                    known_wrong_values.insert((i, j), vec![grid[i][j]]);
-                }
-            }
-            
-            // Find solutions with max of 2 to check if exactly 1 exists
-            let solutions = complete_latin_square_backtrack_all_solutions(
-                n,
-                &known_values,
-                &known_wrong_values,
-                Some(2),
-            );
-            
-            // Only return if this is a single-solution puzzle
-            if solutions.len() == 1 {
-                Some((selected_tiles.clone(), solutions[0].clone()))
-            } else {
-                None
-            }
-        })
-        .collect();
-    
-    // Process batch results in main thread: standardize, deduplicate, and collect
-    for (selected_tiles, solution) in batch_solutions {
-        // Standardize the tile tuple only after we know it's a valid puzzle
-        let standardized_tiles = standardize_tile_tuple(&selected_tiles);
-        
-        // Check if we've seen this standardized form before
-        if seen_standardized_puzzles.contains(&standardized_tiles) {
-            continue; // Skip this puzzle as we've seen this standardized form before
-        }
-        
-        // Mark this standardized form as seen
-        seen_standardized_puzzles.insert(standardized_tiles);
-        
-        // Add to results
-        all_solutions.push((selected_tiles.clone(), solution.clone()));
-        
-        // Write to output file if specified
-        if let Some(ref mut w) = writer {
-            let tiles_str = selected_tiles.iter()
-                .map(|(r, c)| format!("({},{})", r, c))
-                .collect::<Vec<_>>()
-                .join(", ");
-            writeln!(w, "{}", tiles_str).expect("Failed to write to output file");
-            w.flush().expect("Failed to flush output file");
+/// Play every candidate secret and print the `top` that take the most guesses on average.
+fn run_hardest_secrets(args: &SimulateArgs, top: usize, options: &SimulationOptions) -> Result<(), String> {
+    let size = args.size as usize;
+    let start = Instant::now();
+    let candidates = simulate::candidate_secrets(size, args.candidates, args.secrets, args.seed);
+    let tried = candidates.len();
+    let hardest = simulate::hardest_secrets(candidates, top, args.trials, options, args.seed);
+    println!("Hardest of {} secrets of N={}, {} games each, in {:.3?}:", tried, size, args.trials, start.elapsed());
+    println!("{:>6} {:>8} {:>6} {:>6}  secret", "rank", "mean", "p90", "max");
+    for (rank, (secret, counts)) in hardest.iter().enumerate() {
+        println!(
+            "{:>6} {:>8.2} {:>6} {:>6}  {}",
+            rank + 1,
+            counts.mean(),
+            counts.percentile(0.9),
+            counts.percentile(1.0),
+            output::format_grid(secret)
+        );
+    }
+    if let Some(path) = &args.out_file {
+        let mut out = create_output(Some(path))?;
+        for (secret, counts) in &hardest {
+            let record = serde_json::json!({
+                "secret": output::format_grid(secret),
+                "mean_guesses": counts.mean(),
+                "p90_guesses": counts.percentile(0.9),
+                "max_guesses": counts.percentile(1.0),
+            });
+            writeln!(out, "{}", record).map_err(|e| format!("cannot write {}: {}", path, e))?;
         }
     }
-    
-    *processed_count += batch.len();
+    Ok(())
 }
 
-/// Find puzzles that have exactly one solution.
-///
-/// This function generates a cyclic Latin square and then tests combinations
-/// of n_well_placed tiles to find configurations that result in puzzles with
-/// exactly one valid solution.
-///
-/// # Parameters
-/// - `n`: Size of the Latin square (N×N)
-/// - `n_well_placed`: Number of tiles to place as "correct" values
-/// - `output_file`: Optional path to write puzzles as they are discovered
-/// - `random_tries`: If Some(count), randomly sample this many combinations instead of exhaustive search
-///
-/// # Returns
-/// A vector of tuples containing (selected_tiles, unique_solution) for each
-/// puzzle that has exactly one solution.
-pub fn find_single_solution_puzzles(
-    n: usize,
-    n_well_placed: usize,
-    output_file: Option<&str>,
-    random_tries: Option<usize>,
-) -> Vec<(Vec<(usize, usize)>, Vec<Vec<usize>>)> {
-    let grid = Arc::new(cyclic_latin_square(n));
-    
-    // Generate all tile coordinates
-    let tile_coordinates: Vec<(usize, usize)> = (0..n)
-        .flat_map(|i| (0..n).map(move |j| (i, j)))
-        .collect();
-    
-    // Collection for final results and set to track seen standardized puzzles
-    let mut all_solutions = Vec::new();
-    let mut seen_standardized_puzzles = HashSet::new();
-    
-    // Set up output file writer if specified
-    let mut writer = if let Some(path) = output_file {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)
-            .expect("Failed to create output file");
-        Some(BufWriter::new(file))
+/// Print the best opening guess of each size, searching those missing from the cache.
+fn run_first_guess(args: &FirstGuessArgs) -> Result<(), String> {
+    init_thread_pool(args.processors);
+    let settings = FirstGuessSettings { candidates: args.candidates, secrets: args.secrets, seed: args.seed };
+    let sizes: Vec<usize> = args.sizes.iter().map(|&size| size as usize).collect();
+    let cache = Path::new(&args.cache);
+    if args.refresh {
+        let mut table = FirstGuessTable::load(cache)?;
+        table.entries.retain(|entry| !sizes.contains(&entry.size));
+        table.save(cache)?;
+    }
+    let start = Instant::now();
+    let guesses = first_guess::cached_first_guesses(cache, &sizes, &settings)?;
+    println!("Best first guesses in {:.3?}:", start.elapsed());
+    println!(
+        "{:>4} {:>9} {:>8} {:>8} {:>8} {:>8}  guess",
+        "size", "secrets", "bits", "left", "worst", "cyclic"
+    );
+    for guess in &guesses {
+        println!(
+            "{:>4} {:>8}{} {:>8.3} {:>7.2}% {:>7.2}% {:>8.3}  {}",
+            guess.size,
+            guess.secrets,
+            if guess.exhaustive { " " } else { "~" },
+            guess.rating.entropy,
+            100.0 * guess.rating.expected_share,
+            100.0 * guess.rating.worst_share,
+            guess.cyclic.entropy,
+            guess.guess
+        );
+    }
+    Ok(())
+}
+
+/// Write the model of a puzzle for other constraint solvers.
+fn run_export(args: &ExportArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let tiles = parse_puzzle(size, &args.tiles)?;
+    let constraints = puzzle_constraints_with(&tiles, &cyclic_latin_square(size), &all_tiles(size), args.semantics);
+    let model = export::export_model(&constraints, args.semantics, args.format);
+    let mut out = create_output(args.out_file.as_deref())?;
+    out.write_all(model.as_bytes()).map_err(|e| format!("cannot write the model: {}", e))
+}
+
+/// Draw the puzzle as an SVG image, or a PNG one for `.png` paths.
+fn run_render(args: &RenderArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let tiles = parse_puzzle(size, &args.tiles)?;
+    let solution = if args.solution {
+        let constraints = puzzle_constraints_with(&tiles, &cyclic_latin_square(size), &all_tiles(size), args.semantics);
+        let (mut solutions, _) = solve_constraints(&constraints, Some(2), &SolverOptions::default(), SolveHooks::default());
+        if solutions.len() != 1 {
+            return Err(format!("the puzzle has {} solutions: there is no solution to draw", solutions.len()));
+        }
+        solutions.pop()
     } else {
         None
     };
-    
-    let mut processed_count = 0;
-    let mut batch_count = 0;
-    let chunk_size = if random_tries.is_some() { 10000 } else { 100000 }; // Smaller batches for random mode
-    let progress_interval = 5; // Report progress every 5 batches
-    
-    // Choose iteration strategy based on random_tries parameter
-    if let Some(num_random) = random_tries {
-        println!("Processing {} random combinations in batches of {} to conserve memory...", num_random, chunk_size);
-        
-        let mut rng = thread_rng();
-        let mut tried_combinations = HashSet::new();
-        let mut remaining_tries = num_random;
-        
-        while remaining_tries > 0 {
-            // Collect a batch of random combinations
-            let mut batch = Vec::with_capacity(chunk_size.min(remaining_tries));
-            for _ in 0..chunk_size.min(remaining_tries) {
-                // Generate random combination
-                let mut selected_tiles: Vec<(usize, usize)> = tile_coordinates
-                    .choose_multiple(&mut rng, n_well_placed)
-                    .cloned()
-                    .collect();
-                selected_tiles.sort(); // Normalize for deduplication
-                
-                if tried_combinations.insert(selected_tiles.clone()) {
-                    batch.push(selected_tiles);
-                }
-            }
-            
-            if batch.is_empty() {
-                break; // No more unique combinations possible
-            }
-            
-            remaining_tries = remaining_tries.saturating_sub(batch.len());
-            process_batch(&batch, &grid, n, &tile_coordinates, &mut all_solutions, &mut seen_standardized_puzzles, &mut writer, &mut processed_count);
-            
-            batch_count += 1;
-            
-            // Progress reporting every X batches
-            if batch_count % progress_interval == 0 {
-                println!("Processed {} batches ({} combinations), found {} solutions so far", batch_count, processed_count, all_solutions.len());
-            }
+    let options = RenderOptions { cell_size: args.cell_size, semantics: args.semantics };
+    let svg = render::puzzle_svg(size, &tiles, solution.as_ref(), &options);
+    let image = match &args.out_file {
+        Some(path) if Path::new(path).extension().is_some_and(|extension| extension == "png") => {
+            render::svg_to_png(&svg)?
         }
-    } else {
-        println!("Processing all combinations in batches of {} to conserve memory...", chunk_size);
-        
-        // Process combinations in batches without collecting all into memory
-        let mut combinations = CombinationIterator::new(tile_coordinates.clone(), n_well_placed);
-        
-        // Process combinations in batches
-        loop {
-            // Collect a batch of combinations
-            let mut batch = Vec::with_capacity(chunk_size);
-            for _ in 0..chunk_size {
-                if let Some(combo) = combinations.next() {
-                    batch.push(combo);
-                } else {
-                    break;
-                }
-            }
-            
-            if batch.is_empty() {
-                break; // No more combinations
-            }
-            
-            process_batch(&batch, &grid, n, &tile_coordinates, &mut all_solutions, &mut seen_standardized_puzzles, &mut writer, &mut processed_count);
-            
-            batch_count += 1;
-            
-            // Progress reporting every X batches
-            if batch_count % progress_interval == 0 {
-                println!("Processed {} batches ({} combinations), found {} solutions so far", batch_count, processed_count, all_solutions.len());
-            }
+        _ => svg.into_bytes(),
+    };
+    let mut out = create_output(args.out_file.as_deref())?;
+    out.write_all(&image).map_err(|e| format!("cannot write the image: {}", e))
+}
+
+/// Write the puzzles of the file to a PDF document, with pages of answers.
+fn run_print(args: &PrintArgs) -> Result<(), String> {
+    let (size, semantics) = file_settings(&args.input, args.size, args.semantics)?;
+    let mut puzzles = load_puzzles(&args.input, size)?;
+    puzzles.truncate(args.limit.unwrap_or(usize::MAX));
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    let solved: Vec<_> = puzzles
+        .into_par_iter()
+        .map(|tiles| {
+            let constraints = puzzle_constraints_with(&tiles, &grid, &tile_coordinates, semantics);
+            let (solutions, _) = solve_constraints(&constraints, Some(2), &SolverOptions::default(), SolveHooks::default());
+            (tiles, solutions)
+        })
+        .collect();
+    let mut sheet_puzzles = Vec::new();
+    for (tiles, mut solutions) in solved {
+        if solutions.len() != 1 {
+            tracing::warn!(tiles = %output::format_tiles(&tiles), "skipped puzzle without a unique solution");
+            continue;
         }
+        sheet_puzzles.push(SheetPuzzle { size, tiles, solution: solutions.pop().expect("one solution") });
     }
-    
-    println!("Finished processing {} total combinations", processed_count);
-    
-    all_solutions
-}
-
-/// Generate combinations iteratively to avoid storing all in memory
-struct CombinationIterator<T: Clone> {
-    items: Vec<T>,
-    indices: Vec<usize>,
-    k: usize,
-    first: bool,
-    exhausted: bool,
-}
-
-impl<T: Clone> CombinationIterator<T> {
-    fn new(items: Vec<T>, k: usize) -> Self {
-        if k == 0 || k > items.len() {
-            return Self {
-                items,
-                indices: Vec::new(),
-                k,
-                first: true,
-                exhausted: true,
-            };
-        }
-        
-        Self {
-            items,
-            indices: (0..k).collect(),
-            k,
-            first: true,
-            exhausted: false,
+    let pdf = sheet::puzzle_sheet(&sheet_puzzles, args.per_page as usize, semantics, !args.no_answers);
+    std::fs::write(&args.output, pdf).map_err(|e| format!("cannot write {}: {}", args.output, e))?;
+    tracing::info!(puzzles = sheet_puzzles.len(), path = %args.output, "wrote puzzle sheet");
+    Ok(())
+}
+
+/// Print the grid of the solution of an XCSP3 instance, checking it against
+/// the puzzle if its tiles are given.
+fn run_import(args: &ImportArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let text = std::fs::read_to_string(&args.file).map_err(|e| format!("cannot read {}: {}", args.file, e))?;
+    let grid = export::parse_xcsp3_solution(&text)?;
+    if grid.size() != size {
+        return Err(format!("the solution is a {}x{} grid, not {}x{}", grid.size(), grid.size(), size, size));
+    }
+    if let Some(tiles) = &args.tiles {
+        let tiles = parse_puzzle(size, tiles)?;
+        let constraints = puzzle_constraints_with(&tiles, &cyclic_latin_square(size), &all_tiles(size), args.semantics);
+        let broken = all_tiles(size).into_iter().find(|&(i, j)| {
+            let known = constraints.known[(i, j)];
+            (known != 0 && known != grid[(i, j)]) || constraints.wrong[i * size + j] & 1 << (grid[(i, j)] - 1) != 0
+        });
+        if let Some((i, j)) = broken {
+            return Err(format!("the solution breaks the clue of tile ({},{})", i, j));
         }
     }
+    println!("{}", output::format_grid(&grid));
+    Ok(())
 }
 
-impl<T: Clone> Iterator for CombinationIterator<T> {
-    type Item = Vec<T>;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.exhausted {
-            return None;
-        }
-        
-        if self.first {
-            self.first = false;
-            return Some(self.indices.iter().map(|&i| self.items[i].clone()).collect());
-        }
-        
-        let n = self.items.len();
-        let k = self.k;
-        
-        // Find the rightmost index that can be incremented
-        let mut i = k;
-        while i > 0 {
-            i -= 1;
-            if self.indices[i] < n - k + i {
-                self.indices[i] += 1;
-                // Reset all indices to the right
-                for j in (i + 1)..k {
-                    self.indices[j] = self.indices[j - 1] + 1;
-                }
-                return Some(self.indices.iter().map(|&idx| self.items[idx].clone()).collect());
-            }
+/// Print the cycle types of the pairs of lines of the cyclic square and the
+/// lower bound they give on the tiles of classic puzzles.
+fn run_analyze(args: &AnalyzeArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    let grid = base_grid(args.base_grid, size)?;
+    let name = args.base_grid.to_possible_value().expect("no skipped base grids");
+    println!("Base grid {} of size {}:", name.get_name(), size);
+    for (kind, name) in [(LineKind::Row, "rows"), (LineKind::Column, "columns"), (LineKind::Value, "values")] {
+        println!("Pairs of {} by cycle lengths:", name);
+        for (lengths, pairs) in cycles::cycle_types(&grid, kind) {
+            let lengths: Vec<String> = lengths.iter().map(usize::to_string).collect();
+            println!("{:>8}  {}", pairs, lengths.join("+"));
         }
-        
-        self.exhausted = true;
-        None
     }
+    println!("Intercalates: {}", cycles::intercalates(&grid));
+    let bound = bounds::lower_bound(&grid, ClueSemantics::GivensOnly, Variant::Latin, args.node_budget)
+        .expect("classic Latin puzzles have a bound");
+    println!(
+        "Classic puzzles (--semantics givens-only) need at least {} placed tiles to be unique: {} pairwise disjoint \
+         cycles{}, and no two empty rows, columns or values",
+        bound.tiles,
+        bound.trades.len(),
+        if bound.exact { "" } else { " (the most found within --node-budget)" }
+    );
+    println!("No bound is known for the game's semantics, nor for variants");
+    Ok(())
 }
 
-fn validate_args(args: &Args) -> Result<(), String> {
+/// Write the smallest critical sets of the cyclic square found, exactly or by greedy runs.
+fn run_critical_sets(args: &CriticalSetsArgs) -> Result<(), String> {
     let size = args.size as usize;
-    if args.placed > size * size {
-        return Err(format!(
-            "Number of placed tiles ({}) cannot exceed total tiles ({})", 
-            args.placed, 
-            size * size
-        ));
+    init_thread_pool(args.processors);
+    let start = Instant::now();
+    let solver = SolverOptions::default();
+    let found = if args.exact || (size <= critical::MAX_EXACT_SIZE && !args.greedy) {
+        critical::smallest_critical_sets(size, args.limit, &solver)
+    } else {
+        critical::greedy_critical_sets(size, args.runs, args.seed, args.limit, &solver)
+    };
+    tracing::info!(
+        tiles = found.tiles,
+        sets = found.sets.len(),
+        exact = found.exact,
+        elapsed = ?start.elapsed(),
+        "{}",
+        if found.exact { "smallest critical sets" } else { "smallest critical sets found by greedy runs" }
+    );
+    let mut out = create_output(args.out_file.as_deref())?;
+    for set in &found.sets {
+        writeln!(out, "{}", output::format_tiles(set)).map_err(|e| format!("cannot write the critical sets: {}", e))?;
     }
     Ok(())
 }
 
-fn main() {
-    let args = Args::parse();
-    
-    if let Err(e) = validate_args(&args) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+/// Print the fewest placed tiles of a unique puzzle of each main class of
+/// Latin squares, marking the class of the cyclic square.
+fn run_classes(args: &ClassesArgs) -> Result<(), String> {
+    let size = args.size as usize;
+    init_thread_pool(args.processors);
+    let solver = args.solver.options();
+    let representatives = classes::main_classes(size, &solver);
+    let cyclic = classes::main_class_representative(&cyclic_latin_square(size));
+    println!("{} main classes of {}x{} Latin squares", representatives.len(), size, size);
+    println!("{:>5} {:>6} {:>12}  {:<w$}  example", "class", "placed", "tested", "square", w = size * (size + 1) - 1);
+    for (index, representative) in representatives.iter().enumerate() {
+        let start = Instant::now();
+        let max_placed = args.max_placed.unwrap_or(size * size);
+        let minimum = classes::min_unique_placed(representative, args.semantics, &solver, max_placed);
+        tracing::info!(class = index + 1, elapsed = ?start.elapsed(), "class searched");
+        let (placed, example) = match &minimum.tiles {
+            Some(tiles) => (tiles.len().to_string(), output::format_tiles(tiles)),
+            None => (format!(">{}", max_placed), "none".to_string()),
+        };
+        let marker = if *representative == cyclic { "  (cyclic)" } else { "" };
+        println!(
+            "{:>5} {:>6} {:>12}  {}  {}{}",
+            index + 1,
+            placed,
+            minimum.tested,
+            output::format_grid(representative),
+            example,
+            marker
+        );
     }
-    
-    // Configure rayon thread pool
+    Ok(())
+}
+
+/// Send log messages to standard error, so that they don't mix with results.
+fn init_logging(level: LevelFilter, format: LogFormat) {
+    let builder = tracing_subscriber::fmt().with_max_level(level).with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+fn init_thread_pool(processors: usize) {
     rayon::ThreadPoolBuilder::new()
-        .num_threads(args.processors)
+        .num_threads(processors)
         .build_global()
         .expect("Failed to initialize thread pool");
-    
-    let size = args.size as usize;
-    let placed = args.placed;
-    let out_file = args.out_file;
-    
-    if let Some(ref file_path) = out_file {
-        if let Some(tries) = args.random_tries {
-            println!("Finding single solution puzzles for N={}, n_well_placed={}, processors={}, random_tries={}, output file: {}...", size, placed, args.processors, tries, file_path);
-        } else {
-            println!("Finding single solution puzzles for N={}, n_well_placed={}, processors={}, output file: {}...", size, placed, args.processors, file_path);
-        }
-    } else {
-        if let Some(tries) = args.random_tries {
-            println!("Finding single solution puzzles for N={}, n_well_placed={}, processors={}, random_tries={}...", size, placed, args.processors, tries);
-        } else {
-            println!("Finding single solution puzzles for N={}, n_well_placed={}, processors={}...", size, placed, args.processors);
+}
+
+/// Turn the settings of a TOML config file into `search` flags, leaving out
+/// those already given on the command line so that these take precedence.
+fn config_args(path: &str, matches: &ArgMatches) -> Result<Vec<OsString>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let table: toml::Table = text.parse().map_err(|e| format!("cannot parse {}: {}", path, e))?;
+    let mut command = Args::command();
+    // Building the command propagates the global flags to the subcommands
+    command.build();
+    let search = command.find_subcommand("search").expect("search is a subcommand");
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let id = key.replace('-', "_");
+        let long = search
+            .get_arguments()
+            .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "help"))
+            .find(|arg| arg.get_id() == id.as_str())
+            .and_then(|arg| arg.get_long())
+            .ok_or_else(|| format!("{}: unknown setting `{}`", path, key))?;
+        if matches.value_source(&id) == Some(ValueSource::CommandLine) {
+            continue;
         }
-    }
-    
-    let solutions = find_single_solution_puzzles(size, placed, out_file.as_deref(), args.random_tries);
-    
-    println!("\nFound {} puzzles with exactly one solution:", solutions.len());
-    
-    for (i, (tiles, solution)) in solutions.iter().enumerate().take(5) {
-        println!("\nPuzzle {} - Placed tiles: {:?}", i + 1, tiles);
-        println!("Unique solution:");
-        for row in solution {
-            println!("  {:?}", row);
+        // Arrays repeat the flag once per value
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::Boolean(true) => None,
+                toml::Value::Boolean(false) => continue,
+                toml::Value::String(value) => Some(value),
+                toml::Value::Integer(value) => Some(value.to_string()),
+                toml::Value::Float(value) => Some(value.to_string()),
+                _ => return Err(format!("{}: `{}` must be a string, a number, a boolean or an array of them", path, key)),
+            };
+            args.push(match value {
+                Some(value) => format!("--{}={}", long, value).into(),
+                None => format!("--{}", long).into(),
+            });
         }
     }
-    
-    if solutions.len() > 5 {
-        println!("... and {} more puzzles", solutions.len() - 5);
-    }
+    Ok(args)
+}
+
+/// Parse the command line, with the settings of `search --config` as defaults.
+fn parse_args() -> Args {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    // Only look for the config file and the flags that were given on this first pass, as
+    // required settings may come from the file
+    let matches = Args::command().ignore_errors(true).get_matches_from(&argv);
+    let Some((path, matches)) = matches
+        .subcommand_matches("search")
+        .and_then(|matches| Some((matches.get_one::<String>("config")?, matches)))
+    else {
+        return Args::parse_from(argv);
+    };
+    let config = config_args(path, matches).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+    // Search takes no positional arguments, so its flags can go at the end
+    Args::parse_from(argv.into_iter().chain(config))
 }
 
+fn main() {
+    let args = parse_args();
+    init_logging(args.log_level, args.log_format);
+
+    let result = match &args.command {
+        Command::Search(search_args) => run_search(search_args),
+        Command::Solve(solve_args) => run_solve(solve_args),
+        Command::Verify(verify_args) => run_verify(verify_args),
+        Command::Repair(repair_args) => run_repair(repair_args),
+        Command::Neighbors(neighbors_args) => run_neighbors(neighbors_args),
+        Command::Robustness(robustness_args) => run_robustness(robustness_args),
+        Command::Stats(stats_args) => run_stats(stats_args),
+        Command::Rate(rate_args) => run_rate(rate_args),
+        Command::Profile(profile_args) => run_profile(profile_args),
+        Command::Pack(pack_args) => run_pack(pack_args),
+        Command::ExportWeb(export_web_args) => run_export_web(export_web_args),
+        Command::Dedupe(dedupe_args) => run_dedupe(dedupe_args),
+        Command::Daily(daily_args) => run_daily(daily_args),
+        Command::Serve(serve_args) => run_serve(serve_args),
+        Command::Coordinate(coordinate_args) => run_coordinate(coordinate_args),
+        Command::Play(play_args) => run_play(play_args),
+        Command::Bench(bench_args) => run_bench(bench_args),
+        Command::BenchQcp(bench_qcp_args) => run_bench_qcp(bench_qcp_args),
+        Command::Query(query_args) => run_query(query_args),
+        Command::Table(table_args) => run_table(table_args),
+        Command::TraceDot(trace_dot_args) => run_trace_dot(trace_dot_args),
+        Command::Hints(hints_args) => run_hints(hints_args),
+        Command::Simulate(simulate_args) => run_simulate(simulate_args),
+        Command::FirstGuess(first_guess_args) => run_first_guess(first_guess_args),
+        Command::Render(render_args) => run_render(render_args),
+        Command::Print(print_args) => run_print(print_args),
+        Command::Export(export_args) => run_export(export_args),
+        Command::Import(import_args) => run_import(import_args),
+        Command::Analyze(analyze_args) => run_analyze(analyze_args),
+        Command::CriticalSets(critical_sets_args) => run_critical_sets(critical_sets_args),
+        Command::Classes(classes_args) => run_classes(classes_args),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,195 @@
+//! Best opening guesses of the guessing mode, in the spirit of Wordle
+//! starting-word analyses.
+//!
+//! An opening guess is rated by the entropy of its feedback (the set of its
+//! tiles matching the secret) over the possible secrets: the higher it is, the
+//! more the first feedback tells on average. Relabelling the rows, columns or
+//! values of both the guess and the secret doesn't change the feedback, so
+//! every guess rates the same as some reduced Latin square (first row and
+//! column in order), and only those are tried. The search over every secret
+//! is slow for larger sizes, so its results are cached in a JSON data file.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::mask::TileMask;
+use crate::simulate::{self, SecretSource};
+use crate::{cyclic_latin_square, output, solve_constraints, Constraints, Grid, SolveHooks, SolverOptions};
+
+/// Settings of the search for the best opening guess of a size. Results are
+/// only reused from the cache for the same settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirstGuessSettings {
+    /// Reduced Latin squares tried as opening guesses.
+    pub candidates: usize,
+    /// Secrets the guesses are rated against: every Latin square of the size
+    /// if there are at most this many, otherwise this many random ones.
+    pub secrets: usize,
+    /// Seed of the random secrets.
+    pub seed: u64,
+}
+
+impl Default for FirstGuessSettings {
+    fn default() -> Self {
+        Self { candidates: 100, secrets: 200_000, seed: 0 }
+    }
+}
+
+/// Rating of an opening guess.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GuessRating {
+    /// Entropy of the feedback, in bits.
+    pub entropy: f64,
+    /// Expected share of the secrets still consistent after the feedback.
+    pub expected_share: f64,
+    /// Share of the secrets left by the most common feedback.
+    pub worst_share: f64,
+}
+
+/// Best opening guess of a size, as stored in the cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FirstGuess {
+    pub size: usize,
+    pub settings: FirstGuessSettings,
+    /// The guess, row by row with `/` between rows.
+    pub guess: String,
+    pub rating: GuessRating,
+    /// Rating of the cyclic square, which the game checks first.
+    pub cyclic: GuessRating,
+    /// Number of secrets the guesses were rated against.
+    pub secrets: usize,
+    /// Whether these were all the Latin squares of the size.
+    pub exhaustive: bool,
+}
+
+/// Rate `guess` against `secrets`.
+pub fn rate_guess(guess: &Grid, secrets: &[Grid]) -> GuessRating {
+    let mut groups: HashMap<TileMask, usize> = HashMap::new();
+    for secret in secrets {
+        *groups.entry(simulate::feedback(guess, secret)).or_insert(0) += 1;
+    }
+    // Summed in a fixed order, so that the ratings are the same in every run
+    let mut counts: Vec<usize> = groups.into_values().collect();
+    counts.sort_unstable();
+    let total = secrets.len().max(1) as f64;
+    let shares = counts.iter().map(|&count| count as f64 / total);
+    GuessRating {
+        entropy: shares.clone().map(|p| -p * p.log2()).sum(),
+        expected_share: shares.clone().map(|p| p * p).sum(),
+        worst_share: shares.fold(0.0, f64::max),
+    }
+}
+
+/// The secrets to rate guesses of size `size` against, and whether they are
+/// all the Latin squares of that size.
+pub fn rating_secrets(size: usize, settings: &FirstGuessSettings) -> (Vec<Grid>, bool) {
+    let all = solve_constraints(
+        &Constraints::new(size),
+        Some(settings.secrets + 1),
+        &SolverOptions::default(),
+        SolveHooks::default(),
+    )
+    .0;
+    if all.len() <= settings.secrets {
+        (all, true)
+    } else {
+        (simulate::candidate_secrets(size, settings.secrets, SecretSource::Random, settings.seed), false)
+    }
+}
+
+/// Rate the candidate opening guesses of size `size` against `secrets`, best
+/// (highest entropy) first.
+pub fn rank_first_guesses(size: usize, settings: &FirstGuessSettings, secrets: &[Grid]) -> Vec<(Grid, GuessRating)> {
+    let candidates = simulate::candidate_secrets(size, settings.candidates, SecretSource::Enumerated, settings.seed);
+    let mut ranked: Vec<(Grid, GuessRating)> = candidates
+        .into_par_iter()
+        .map(|guess| {
+            let rating = rate_guess(&guess, secrets);
+            (guess, rating)
+        })
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| b.entropy.total_cmp(&a.entropy));
+    ranked
+}
+
+/// Search the best opening guess of size `size`.
+pub fn best_first_guess(size: usize, settings: &FirstGuessSettings) -> FirstGuess {
+    let (secrets, exhaustive) = rating_secrets(size, settings);
+    let (guess, rating) = rank_first_guesses(size, settings, &secrets).swap_remove(0);
+    FirstGuess {
+        size,
+        settings: *settings,
+        guess: output::format_grid(&guess),
+        rating,
+        cyclic: rate_guess(&cyclic_latin_square(size), &secrets),
+        secrets: secrets.len(),
+        exhaustive,
+    }
+}
+
+/// Best opening guesses cached in a JSON data file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FirstGuessTable {
+    pub entries: Vec<FirstGuess>,
+}
+
+impl FirstGuessTable {
+    /// Read the table at `path`, or an empty table if there is no such file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("invalid table {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).expect("the table is always serializable");
+        fs::write(path, text + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    /// Cached best opening guess of size `size` for `settings`.
+    pub fn get(&self, size: usize, settings: &FirstGuessSettings) -> Option<&FirstGuess> {
+        self.entries.iter().find(|entry| entry.size == size && entry.settings == *settings)
+    }
+
+    /// Add `entry`, replacing the entry of its size if there is one.
+    pub fn insert(&mut self, entry: FirstGuess) {
+        self.entries.retain(|other| other.size != entry.size);
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| entry.size);
+    }
+}
+
+/// Best opening guess of each of `sizes`, read from the table at `path` when
+/// it holds them for `settings`, otherwise searched and added to the table.
+pub fn cached_first_guesses(
+    path: &Path,
+    sizes: &[usize],
+    settings: &FirstGuessSettings,
+) -> Result<Vec<FirstGuess>, String> {
+    let mut table = FirstGuessTable::load(path)?;
+    let mut changed = false;
+    let mut guesses = Vec::with_capacity(sizes.len());
+    for &size in sizes {
+        let guess = match table.get(size, settings) {
+            Some(guess) => guess.clone(),
+            None => {
+                tracing::info!(size, "searching the best first guess");
+                let guess = best_first_guess(size, settings);
+                table.insert(guess.clone());
+                changed = true;
+                guess
+            }
+        };
+        guesses.push(guess);
+    }
+    if changed {
+        table.save(path)?;
+    }
+    Ok(guesses)
+}
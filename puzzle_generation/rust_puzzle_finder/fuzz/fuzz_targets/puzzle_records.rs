@@ -0,0 +1,10 @@
+//! Puzzle files (text or CSV records, as loaded by `--append`) made of arbitrary bytes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use puzzle_finder::output::parse_puzzle_records;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_puzzle_records(data, "input");
+});
@@ -0,0 +1,33 @@
+//! Tile lists of the text format, clue codes of the CSV format, puzzle IDs of
+//! the web game, dot notation and mixed hint sets, parsed from arbitrary strings. Whatever
+//! parses must format back to the same tiles.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use puzzle_finder::output::{
+    decode_clues, decode_puzzle_id, encode_clues, encode_puzzle_id, format_tiles, parse_dot_notation, parse_tiles,
+};
+use puzzle_finder::hints::{format_hints, parse_hints};
+use puzzle_finder::techniques::TechniqueSet;
+
+fuzz_target!(|input: &str| {
+    if let Some(tiles) = parse_tiles(input) {
+        assert_eq!(parse_tiles(&format_tiles(&tiles)), Some(tiles));
+    }
+    let clues = decode_clues(input);
+    assert_eq!(decode_clues(&encode_clues(&clues)), clues);
+    if let Some((size, mut tiles)) = decode_puzzle_id(input) {
+        // IDs list tiles in row order
+        tiles.sort_unstable();
+        let id = encode_puzzle_id(size, &tiles).expect("decoded IDs can be encoded");
+        assert_eq!(decode_puzzle_id(&id), Some((size, tiles)));
+    }
+    if let Ok((size, tiles)) = parse_dot_notation(input) {
+        assert!(tiles.iter().all(|&(r, c)| r < size && c < size));
+    }
+    if let Some(hints) = parse_hints(input) {
+        assert_eq!(parse_hints(&format_hints(&hints)), Some(hints));
+    }
+    let _ = input.parse::<TechniqueSet>();
+});
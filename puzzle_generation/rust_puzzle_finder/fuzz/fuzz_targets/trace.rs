@@ -0,0 +1,13 @@
+//! JSON lines trace files made of arbitrary bytes, parsed and rendered as DOT
+//! like `trace-dot` does.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use puzzle_finder::trace::{parse_jsonl, to_dot};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(events) = parse_jsonl(data, "input") {
+        to_dot(&events);
+    }
+});
@@ -0,0 +1,244 @@
+//! Constructors of unique clue sets, as alternatives to testing random or
+//! enumerated combinations one by one.
+//!
+//! A constructor returns the clue set of one run; the search then checks it
+//! and writes it like any other combination.
+
+use clap::ValueEnum;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::{
+    all_tiles, puzzle_constraints_with, solve_constraints, ClueSemantics, Grid, SolveHooks, SolverOptions, Variant,
+};
+
+/// How the candidate clue sets of a search are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Generator {
+    /// Test every combination, or random combinations with `random_tries`.
+    #[default]
+    BottomUp,
+    /// Greedy removal of tiles from the full grid, one run per random try.
+    TopDown,
+    /// Simulated annealing on the number of solutions, one run per random try.
+    Anneal,
+}
+
+/// Settings of [`anneal`].
+#[derive(Debug, Clone, Copy)]
+pub struct AnnealOptions {
+    /// Clue swaps tried per run.
+    pub steps: usize,
+    /// Temperature of the first step, in units of log solution count.
+    pub start_temperature: f64,
+    /// Temperature of the last step; the schedule is geometric in between.
+    pub end_temperature: f64,
+    /// Solutions are counted up to this many, so that the counts of clue sets
+    /// far from unique stay cheap.
+    pub max_solutions: usize,
+}
+
+impl Default for AnnealOptions {
+    fn default() -> Self {
+        Self {
+            steps: 3000,
+            start_temperature: 0.2,
+            end_temperature: 0.02,
+            max_solutions: 32,
+        }
+    }
+}
+
+/// Random number generator of run `run` of `seed`.
+fn run_rng(seed: u64, run: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ run.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Number of completions of the puzzle revealing `tiles` of `grid`, up to
+/// `max_solutions`, following the rules of `variant`.
+#[allow(clippy::too_many_arguments)]
+fn count_solutions(
+    grid: &Grid,
+    tiles: &[(usize, usize)],
+    tile_coordinates: &[(usize, usize)],
+    max_solutions: usize,
+    solver: &SolverOptions,
+    semantics: ClueSemantics,
+    variant: Variant,
+) -> usize {
+    let mut constraints = puzzle_constraints_with(tiles, grid, tile_coordinates, semantics);
+    constraints.variant = variant;
+    solve_constraints(&constraints, Some(max_solutions), solver, SolveHooks::default()).0.len()
+}
+
+/// Energy of a clue set with `solutions` completions (counted up to
+/// `max_solutions`), 0 when it is unique.
+///
+/// Contradictory clue sets cost more than any other: runs that wander among
+/// them rarely come back to a puzzle.
+fn energy(solutions: usize, max_solutions: usize) -> f64 {
+    match solutions {
+        0 => 2.0 * (max_solutions.max(2) as f64).ln(),
+        solutions => (solutions as f64).ln(),
+    }
+}
+
+/// Look for `placed` tiles of `grid` giving a unique puzzle by simulated annealing.
+///
+/// The run starts from random tiles and repeatedly moves one of them to a
+/// random free tile, keeping moves that lower the energy (the logarithm of the
+/// number of solutions) and moves that raise it with the Metropolis probability. Returns
+/// the sorted tiles as soon as they have a unique solution, or `None` if the
+/// run ends without reaching one.
+#[allow(clippy::too_many_arguments)]
+pub fn anneal(
+    grid: &Grid,
+    placed: usize,
+    run: u64,
+    seed: u64,
+    options: &AnnealOptions,
+    solver: &SolverOptions,
+    semantics: ClueSemantics,
+    variant: Variant,
+) -> Option<Vec<(usize, usize)>> {
+    let n = grid.size();
+    let tile_coordinates = all_tiles(n);
+    let mut rng = run_rng(seed, run);
+    let count = |tiles: &[(usize, usize)]| {
+        count_solutions(grid, tiles, &tile_coordinates, options.max_solutions, solver, semantics, variant)
+    };
+
+    let mut tiles: Vec<(usize, usize)> = tile_coordinates.choose_multiple(&mut rng, placed).cloned().collect();
+    let mut solutions = count(&tiles);
+    if placed == 0 || placed == tile_coordinates.len() {
+        // No tile can be moved
+        tiles.sort();
+        return (solutions == 1).then_some(tiles);
+    }
+    let cooling = (options.end_temperature / options.start_temperature).powf(1.0 / options.steps.max(1) as f64);
+    let mut temperature = options.start_temperature;
+    for _ in 0..options.steps {
+        if solutions == 1 {
+            break;
+        }
+        let removed = rng.gen_range(0..placed);
+        let added = loop {
+            let tile = tile_coordinates[rng.gen_range(0..tile_coordinates.len())];
+            if !tiles.contains(&tile) {
+                break tile;
+            }
+        };
+        let previous = std::mem::replace(&mut tiles[removed], added);
+        let candidate_solutions = count(&tiles);
+        let delta = energy(candidate_solutions, options.max_solutions) - energy(solutions, options.max_solutions);
+        if delta <= 0.0 || rng.gen::<f64>() < (-delta / temperature).exp() {
+            solutions = candidate_solutions;
+        } else {
+            tiles[removed] = previous;
+        }
+        temperature *= cooling;
+    }
+    tiles.sort();
+    (solutions == 1).then_some(tiles)
+}
+
+/// Remove tiles from the full grid in a random order, down to `min_placed`,
+/// as long as no second solution appears.
+///
+/// Passes over the tiles are repeated until none can be removed, so the
+/// remaining tiles are minimal: removing any of them gives a puzzle with
+/// several solutions. With [`ClueSemantics::Sudodle`], a
+/// removed tile is known to be wrong, so the clue sets on the way can have no
+/// solution at all; returns the sorted tiles if the run ends with exactly one,
+/// and `None` otherwise. Solutions follow the rules of `variant`, which
+/// `grid` must follow too.
+pub fn top_down(
+    grid: &Grid,
+    min_placed: usize,
+    run: u64,
+    seed: u64,
+    solver: &SolverOptions,
+    semantics: ClueSemantics,
+    variant: Variant,
+) -> Option<Vec<(usize, usize)>> {
+    let n = grid.size();
+    let tile_coordinates = all_tiles(n);
+    let mut rng = run_rng(seed, run);
+    let mut order = tile_coordinates.clone();
+    order.shuffle(&mut rng);
+
+    let mut tiles = tile_coordinates.clone();
+    let mut removed_any = true;
+    // A removal refused earlier can become possible once other tiles are gone
+    while removed_any && tiles.len() > min_placed {
+        removed_any = false;
+        for &tile in &order {
+            if tiles.len() <= min_placed {
+                break;
+            }
+            if !tiles.contains(&tile) {
+                continue;
+            }
+            let candidate: Vec<(usize, usize)> = tiles.iter().copied().filter(|&other| other != tile).collect();
+            if count_solutions(grid, &candidate, &tile_coordinates, 2, solver, semantics, variant) <= 1 {
+                tiles = candidate;
+                removed_any = true;
+            }
+        }
+    }
+    (count_solutions(grid, &tiles, &tile_coordinates, 2, solver, semantics, variant) == 1).then_some(tiles)
+}
+
+/// A placed count tried by [`find_minimum`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimumLevel {
+    pub placed: usize,
+    /// The sorted tiles of a unique puzzle with `placed` tiles, or `None` if
+    /// no run found one.
+    pub example: Option<Vec<(usize, usize)>>,
+}
+
+/// Look for the fewest placed tiles of a unique puzzle of `grid`, down to
+/// `floor` (e.g. the bound of [`crate::bounds`]).
+///
+/// The smallest of `runs` [`top_down`] runs gives the first level; each level
+/// below then gets `runs` [`anneal`] runs, which sample random tiles and climb
+/// towards a unique puzzle, until a level has none or `floor` is reached.
+/// Returns the levels in the order tried, the last one without an example
+/// unless the descent reached `floor`, or no level if no top-down run found
+/// a unique puzzle. A level without an example only shows that these runs
+/// found none, not that there is none.
+#[allow(clippy::too_many_arguments)]
+pub fn find_minimum(
+    grid: &Grid,
+    floor: usize,
+    runs: usize,
+    seed: u64,
+    options: &AnnealOptions,
+    solver: &SolverOptions,
+    semantics: ClueSemantics,
+    variant: Variant,
+) -> Vec<MinimumLevel> {
+    let smallest = (0..runs as u64)
+        .into_par_iter()
+        .filter_map(|run| top_down(grid, floor, run, seed, solver, semantics, variant))
+        .min_by_key(|tiles| tiles.len());
+    let Some(tiles) = smallest else {
+        return Vec::new();
+    };
+    tracing::info!(placed = tiles.len(), "smallest top-down puzzle");
+    let mut levels = vec![MinimumLevel { placed: tiles.len(), example: Some(tiles) }];
+    for placed in (floor..levels[0].placed).rev() {
+        let example = (0..runs as u64)
+            .into_par_iter()
+            .find_map_first(|run| anneal(grid, placed, run, seed, options, solver, semantics, variant));
+        tracing::info!(placed, found = example.is_some(), "annealing runs done");
+        let found = example.is_some();
+        levels.push(MinimumLevel { placed, example });
+        if !found {
+            break;
+        }
+    }
+    levels
+}
@@ -0,0 +1,163 @@
+//! Flat storage for Latin squares and partially filled grids, with checks
+//! that they follow the rules of Latin squares.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An N×N grid holding values 1..=N, with 0 in the empty cells.
+///
+/// Cells are stored row by row in a single `Vec<u8>` and indexed as
+/// `grid[(row, col)]`, so sizes are limited to 255. Grids are serialized as
+/// the list of their rows, e.g. `[[1, 2], [2, 1]]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    size: usize,
+    cells: Vec<u8>,
+}
+
+impl Grid {
+    /// A grid with every cell empty.
+    pub fn new(size: usize) -> Self {
+        Self { size, cells: vec![0; size * size] }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.cells.chunks_exact(self.size)
+    }
+
+    /// Number of cells without a value.
+    pub fn empty_cells(&self) -> usize {
+        self.cells.iter().filter(|&&value| value == 0).count()
+    }
+}
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<Vec<u8>>::deserialize(deserializer)?;
+        let size = rows.len();
+        if let Some(row) = rows.iter().find(|row| row.len() != size) {
+            return Err(D::Error::custom(format!("a grid of {} rows has a row of {} values", size, row.len())));
+        }
+        if let Some(&value) = rows.iter().flatten().find(|&&value| value as usize > size) {
+            return Err(D::Error::custom(format!("value {} is out of range in a {}x{} grid", value, size, size)));
+        }
+        Ok(Self { size, cells: rows.concat() })
+    }
+}
+
+impl Index<(usize, usize)> for Grid {
+    type Output = u8;
+
+    fn index(&self, (row, col): (usize, usize)) -> &u8 {
+        &self.cells[row * self.size + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Grid {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut u8 {
+        &mut self.cells[row * self.size + col]
+    }
+}
+
+/// Why a grid is not a Latin square (or a partial one).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// A cell holds a value outside of 1..=N, or is empty in a square that
+    /// should be complete.
+    ValueOutOfRange { cell: (usize, usize), value: u8 },
+    /// A row holds a value in two columns.
+    RepeatedInRow { row: usize, columns: (usize, usize), value: u8 },
+    /// A column holds a value in two rows.
+    RepeatedInColumn { column: usize, rows: (usize, usize), value: u8 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::ValueOutOfRange { cell: (r, c), value: 0 } => write!(f, "tile ({},{}) is empty", r, c),
+            Violation::ValueOutOfRange { cell: (r, c), value } => {
+                write!(f, "tile ({},{}) holds {}, out of range", r, c, value)
+            }
+            Violation::RepeatedInRow { row, columns: (first, second), value } => {
+                write!(f, "row {} holds {} in columns {} and {}", row, value, first, second)
+            }
+            Violation::RepeatedInColumn { column, rows: (first, second), value } => {
+                write!(f, "column {} holds {} in rows {} and {}", column, value, first, second)
+            }
+        }
+    }
+}
+
+/// Check that `grid` is a Latin square: every cell holds a value of 1..=N,
+/// and no row or column holds a value twice. The first violation found,
+/// row by row, is returned.
+pub fn validate_latin_square(grid: &Grid) -> Result<(), Violation> {
+    validate(grid, false)
+}
+
+/// Check that `grid` is a partial Latin square: like
+/// [`validate_latin_square`], with empty cells allowed.
+pub fn validate_partial_latin_square(grid: &Grid) -> Result<(), Violation> {
+    validate(grid, true)
+}
+
+fn validate(grid: &Grid, allow_empty: bool) -> Result<(), Violation> {
+    let size = grid.size();
+    for (cell, &value) in grid.cells.iter().enumerate() {
+        if value as usize > size || (value == 0 && !allow_empty) {
+            return Err(Violation::ValueOutOfRange { cell: (cell / size, cell % size), value });
+        }
+    }
+    // First column (or row) of each value in each line, size for none
+    let mut in_row = vec![size; size * (size + 1)];
+    let mut in_column = vec![size; size * (size + 1)];
+    for (row, column) in (0..size).flat_map(|r| (0..size).map(move |c| (r, c))) {
+        let value = grid[(row, column)];
+        if value == 0 {
+            continue;
+        }
+        let seen_in_row = &mut in_row[row * (size + 1) + value as usize];
+        if *seen_in_row < size {
+            return Err(Violation::RepeatedInRow { row, columns: (*seen_in_row, column), value });
+        }
+        *seen_in_row = column;
+        let seen_in_column = &mut in_column[column * (size + 1) + value as usize];
+        if *seen_in_column < size {
+            return Err(Violation::RepeatedInColumn { column, rows: (*seen_in_column, row), value });
+        }
+        *seen_in_column = row;
+    }
+    Ok(())
+}
+
+/// Relabel the values of `grid` so that its first row reads 1, 2, ..., N,
+/// keeping empty cells empty. Values missing from the first row take the
+/// labels left, in increasing order. Relabelling values keeps a Latin square
+/// a Latin square, so squares differing only by their labels normalize to
+/// the same grid.
+pub fn normalize(grid: &Grid) -> Grid {
+    let size = grid.size();
+    let mut labels = vec![0u8; size + 1];
+    let mut next = 1;
+    let first_row = (0..size).map(|c| grid[(0, c)]);
+    for value in first_row.chain(1..=size as u8) {
+        if value != 0 && labels[value as usize] == 0 {
+            labels[value as usize] = next;
+            next += 1;
+        }
+    }
+    Grid { size, cells: grid.cells.iter().map(|&value| labels[value as usize]).collect() }
+}
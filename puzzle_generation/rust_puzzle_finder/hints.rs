@@ -0,0 +1,140 @@
+//! Puzzles mixing the kinds of feedback the game shows about a tile, and a
+//! constructor of minimal mixed hint sets.
+//!
+//! Unlike the tiles of [`crate::FoundPuzzle`], cells without a hint carry no
+//! information, as with [`crate::ClueSemantics::GivensOnly`].
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::incremental::{Clue, IncrementalSolver};
+use crate::{Constraints, Grid, SolverOptions};
+
+/// What the game reveals about a tile of the grid being guessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hint {
+    /// The tile holds its value in the solution.
+    Correct,
+    /// The value of the tile is wrong where it stands. There is no hint that
+    /// a value belongs elsewhere in the row: every value belongs in every row
+    /// of a Latin square, so it would say no more than this one.
+    WrongHere,
+}
+
+impl Hint {
+    /// Letter of the hint in the text format.
+    pub fn letter(self) -> char {
+        match self {
+            Hint::Correct => 'c',
+            Hint::WrongHere => 'w',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'c' => Some(Hint::Correct),
+            'w' => Some(Hint::WrongHere),
+            _ => None,
+        }
+    }
+}
+
+/// A hint about one tile of the grid.
+pub type TileHint = ((usize, usize), Hint);
+
+/// Constraints of the puzzle giving `hints` about the tiles of `grid`.
+pub fn hint_constraints(hints: &[TileHint], grid: &Grid) -> Constraints {
+    let mut constraints = Constraints::new(grid.size());
+    for &(tile, hint) in hints {
+        match hint {
+            Hint::Correct => constraints.set_known(tile, grid[tile].into()),
+            Hint::WrongHere => constraints.set_wrong(tile, grid[tile].into()),
+        }
+    }
+    constraints
+}
+
+/// Format hints as a line of the text format, e.g. `(0,0)c, (1,2)w, (3,1)w`.
+pub fn format_hints(hints: &[TileHint]) -> String {
+    hints.iter()
+        .map(|&((r, c), hint)| format!("({},{}){}", r, c, hint.letter()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a line of the text format back into hints.
+pub fn parse_hints(line: &str) -> Option<Vec<TileHint>> {
+    line.split(',')
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|coord| {
+            let [r, c] = coord else { return None };
+            let c = c.trim();
+            let letter = c.chars().last()?;
+            let hint = Hint::from_letter(letter)?;
+            let c = c[..c.len() - letter.len_utf8()].strip_suffix(')')?;
+            let r = r.trim().strip_prefix('(')?;
+            Some(((r.trim().parse().ok()?, c.trim().parse().ok()?), hint))
+        })
+        .collect()
+}
+
+/// Feedback the game gives about `tile` of `grid` when the secret grid is `solution`.
+pub fn feedback(grid: &Grid, solution: &Grid, tile: (usize, usize)) -> Hint {
+    if grid[tile] == solution[tile] {
+        Hint::Correct
+    } else {
+        Hint::WrongHere
+    }
+}
+
+/// Clue that `hint` about `tile` of `grid` gives.
+fn hint_clue(grid: &Grid, (tile, hint): TileHint) -> Clue {
+    match hint {
+        Hint::Correct => Clue::Given(tile, grid[tile].into()),
+        Hint::WrongHere => Clue::Forbidden(tile, grid[tile].into()),
+    }
+}
+
+/// Build a minimal mixed hint set about `grid` for the secret `solution`,
+/// starting from the feedback of every tile and dropping hints in a random order.
+///
+/// Each hint is dropped if the puzzle stays unique without it. Dropping hints
+/// only removes constraints, so a hint that cannot go cannot go later either,
+/// and one pass leaves a set where dropping any hint gives several solutions.
+/// Returns the hints sorted by tile, or `None` if the feedback of every tile
+/// doesn't single out `solution` to begin with.
+pub fn minimal_hints(
+    grid: &Grid,
+    solution: &Grid,
+    run: u64,
+    seed: u64,
+    solver: &SolverOptions,
+) -> Option<Vec<TileHint>> {
+    let n = grid.size();
+    let mut rng = StdRng::seed_from_u64(seed ^ run.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let mut incremental = IncrementalSolver::new(Constraints::new(n), *solver);
+    let mut hints: Vec<_> = (0..n)
+        .flat_map(|i| (0..n).map(move |j| (i, j)))
+        .map(|tile| {
+            let hint = (tile, feedback(grid, solution, tile));
+            (hint, incremental.add(hint_clue(grid, hint)))
+        })
+        .collect();
+    if incremental.solution_count(2) != 1 {
+        return None;
+    }
+    hints.shuffle(&mut rng);
+    let mut index = 0;
+    while index < hints.len() {
+        let (hint, id) = hints.remove(index);
+        incremental.remove(id);
+        if incremental.solution_count(2) != 1 {
+            hints.insert(index, (hint, incremental.add(hint_clue(grid, hint))));
+            index += 1;
+        }
+    }
+    let mut hints: Vec<TileHint> = hints.into_iter().map(|(hint, _)| hint).collect();
+    hints.sort();
+    Some(hints)
+}
@@ -0,0 +1,160 @@
+//! Solver of a puzzle whose clues change one at a time, for the searches
+//! solving many nearly identical puzzles: minimal hint sets, repairs.
+//!
+//! The solutions found for one set of clues are kept for the next: adding a
+//! clue keeps those satisfying it, and removing one keeps them all. A count
+//! is answered without solving when enough solutions are known, or when all
+//! of them are known and clues were only added since. The last set of clues
+//! whose solutions were all found is remembered as well, so that removing a
+//! clue and adding it back takes a single solve.
+
+use crate::{solve_constraints, Constraints, Grid, SolveHooks, SolverOptions};
+
+/// A clue about one cell of a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clue {
+    /// The cell holds the value.
+    Given((usize, usize), usize),
+    /// The cell does not hold the value.
+    Forbidden((usize, usize), usize),
+}
+
+impl Clue {
+    pub fn satisfied_by(&self, grid: &Grid) -> bool {
+        match *self {
+            Clue::Given(cell, value) => usize::from(grid[cell]) == value,
+            Clue::Forbidden(cell, value) => usize::from(grid[cell]) != value,
+        }
+    }
+}
+
+/// Handle of a clue added to an [`IncrementalSolver`], to remove it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClueId(usize);
+
+/// A puzzle whose clues can be added and removed between solution counts.
+pub struct IncrementalSolver {
+    /// Clues that cannot be removed.
+    base: Constraints,
+    /// Clues added, `None` once removed.
+    clues: Vec<Option<Clue>>,
+    /// The base clues and those added.
+    constraints: Constraints,
+    /// Whether two clues contradict each other, leaving no solution.
+    contradiction: bool,
+    options: SolverOptions,
+    /// Solutions of the current clues found so far.
+    solutions: Vec<Grid>,
+    /// Whether `solutions` holds every solution of the current clues.
+    complete: bool,
+    /// The last clues whose solutions were all found, with them.
+    memo: Option<(Constraints, Vec<Grid>)>,
+    solves: usize,
+}
+
+impl IncrementalSolver {
+    /// A solver of the puzzle of `base`, whose clues are never removed.
+    pub fn new(base: Constraints, options: SolverOptions) -> Self {
+        Self {
+            constraints: base.clone(),
+            base,
+            clues: Vec::new(),
+            contradiction: false,
+            options,
+            solutions: Vec::new(),
+            complete: false,
+            memo: None,
+            solves: 0,
+        }
+    }
+
+    /// Add the clue that `cell` holds `value`.
+    pub fn add_given(&mut self, cell: (usize, usize), value: usize) -> ClueId {
+        self.add(Clue::Given(cell, value))
+    }
+
+    /// Add the clue that `cell` does not hold `value`.
+    pub fn add_forbidden(&mut self, cell: (usize, usize), value: usize) -> ClueId {
+        self.add(Clue::Forbidden(cell, value))
+    }
+
+    pub fn add(&mut self, clue: Clue) -> ClueId {
+        apply(&mut self.constraints, &mut self.contradiction, clue);
+        self.solutions.retain(|solution| clue.satisfied_by(solution));
+        self.clues.push(Some(clue));
+        ClueId(self.clues.len() - 1)
+    }
+
+    /// Remove a clue added before; removing it again does nothing.
+    pub fn remove(&mut self, id: ClueId) {
+        if self.clues[id.0].take().is_none() {
+            return;
+        }
+        self.constraints = self.base.clone();
+        self.contradiction = false;
+        for &clue in self.clues.iter().flatten() {
+            apply(&mut self.constraints, &mut self.contradiction, clue);
+        }
+        // The solutions found are still solutions, but there may be more
+        self.complete = false;
+    }
+
+    /// Number of solutions of the current clues, up to `limit`.
+    pub fn solution_count(&mut self, limit: usize) -> usize {
+        if self.contradiction {
+            return 0;
+        }
+        if !self.complete && self.solutions.len() < limit {
+            match &self.memo {
+                Some((constraints, solutions)) if *constraints == self.constraints => {
+                    self.solutions = solutions.clone();
+                    self.complete = true;
+                }
+                _ => {
+                    let (solutions, _) =
+                        solve_constraints(&self.constraints, Some(limit), &self.options, SolveHooks::default());
+                    self.solves += 1;
+                    self.complete = solutions.len() < limit;
+                    self.solutions = solutions;
+                    if self.complete {
+                        self.memo = Some((self.constraints.clone(), self.solutions.clone()));
+                    }
+                }
+            }
+        }
+        self.solutions.len().min(limit)
+    }
+
+    /// Solutions of the current clues found so far: after
+    /// [`IncrementalSolver::solution_count`], at least as many as it returned.
+    pub fn solutions(&self) -> &[Grid] {
+        &self.solutions
+    }
+
+    /// The base clues and those added, in the solver's representation.
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+
+    /// Calls to the solver so far, the other counts being answered from the
+    /// solutions kept.
+    pub fn solves(&self) -> usize {
+        self.solves
+    }
+}
+
+/// Add `clue` to `constraints`, setting `contradiction` if it contradicts them.
+fn apply(constraints: &mut Constraints, contradiction: &mut bool, clue: Clue) {
+    let size = constraints.size;
+    match clue {
+        Clue::Given((i, j), value) => {
+            let known = usize::from(constraints.known[(i, j)]);
+            *contradiction |= (known != 0 && known != value) || constraints.wrong[i * size + j] & 1 << (value - 1) != 0;
+            constraints.known[(i, j)] = value as u8;
+        }
+        Clue::Forbidden((i, j), value) => {
+            *contradiction |= usize::from(constraints.known[(i, j)]) == value;
+            constraints.forbid((i, j), value);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Persistent index of the clue sets evaluated by searches, so that long
+//! random runs (and the runs after them) skip the clue sets any previous run
+//! already solved, whether they were unique or not.
+//!
+//! The index is a binary file: a header naming the search, then one record
+//! per clue set evaluated, appended as the search goes:
+//!
+//! ```text
+//! magic "SDRI", version (u8), size (u8), semantics (u8), variant (u8),
+//! the values of the base grid (u8, row by row),
+//! records: tile mask (two u128), outcome (u8)
+//! ```
+//!
+//! Integers are little-endian, and clue sets are keyed by their tile masks,
+//! see [`TileMask`], which are the same for every order of the tiles. Opening
+//! the index reads the records into memory, where lookups take a hash and a
+//! lock, and drops a record cut short by an interrupted run.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::mask::TileMask;
+use crate::{ClueSemantics, Grid, Variant};
+
+const MAGIC: &[u8; 4] = b"SDRI";
+const VERSION: u8 = 1;
+/// Bytes of a record: the mask, then the outcome.
+const RECORD_BYTES: usize = 33;
+/// Number of independently locked parts of the index, as in [`crate::dedup`].
+const SHARDS: usize = 64;
+
+/// What the evaluation of a clue set found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    NoSolution,
+    Unique,
+    Several,
+}
+
+impl Outcome {
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Outcome::NoSolution),
+            1 => Some(Outcome::Unique),
+            2 => Some(Outcome::Several),
+            _ => None,
+        }
+    }
+
+    /// The outcome of a solve finding `solutions` (up to 2).
+    pub fn of_solutions(solutions: usize) -> Self {
+        match solutions {
+            0 => Outcome::NoSolution,
+            1 => Outcome::Unique,
+            _ => Outcome::Several,
+        }
+    }
+}
+
+/// Clue sets of one search evaluated so far, by this run and the previous ones.
+#[derive(Debug)]
+pub struct ResultsIndex {
+    size: usize,
+    shards: Vec<Mutex<HashMap<TileMask, Outcome>>>,
+    writer: Mutex<BufWriter<File>>,
+    /// Records read when the index was opened.
+    loaded: usize,
+}
+
+impl ResultsIndex {
+    /// Open the index at `path` of the search placing tiles of `grid`, creating
+    /// it if there is no such file. Fails if the file is the index of another
+    /// search.
+    pub fn open(path: &Path, grid: &Grid, semantics: ClueSemantics, variant: Variant) -> io::Result<Self> {
+        let size = grid.size();
+        let mut header = MAGIC.to_vec();
+        header.extend([VERSION, size as u8, semantics as u8, variant as u8]);
+        header.extend(grid.rows().flatten());
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let length = file.metadata()?.len();
+        let index = Self {
+            size,
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            writer: Mutex::new(BufWriter::new(file.try_clone()?)),
+            loaded: 0,
+        };
+        if length == 0 {
+            file.write_all(&header)?;
+            return Ok(index);
+        }
+        file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(&file);
+        let mut found = vec![0; header.len()];
+        if reader.read_exact(&mut found).is_err() || found != header {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a results index of this size, semantics, variant and base grid",
+            ));
+        }
+        let records = (length as usize - header.len()) / RECORD_BYTES;
+        for _ in 0..records {
+            let mut record = [0; RECORD_BYTES];
+            reader.read_exact(&mut record)?;
+            let outcome = Outcome::from_byte(record[32])
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unknown outcome in the results index"))?;
+            let mask = TileMask::from_halves(
+                [0, 16].map(|start| u128::from_le_bytes(record[start..start + 16].try_into().unwrap())),
+            );
+            index.shard(mask).lock().unwrap().insert(mask, outcome);
+        }
+        drop(reader);
+        // Drop the end of a record cut short, so that the next ones line up
+        file.set_len((header.len() + records * RECORD_BYTES) as u64)?;
+        Ok(Self { loaded: records, ..index })
+    }
+
+    fn shard(&self, mask: TileMask) -> &Mutex<HashMap<TileMask, Outcome>> {
+        &self.shards[mask.shard(SHARDS)]
+    }
+
+    /// The outcome recorded for the clue set `tiles`, if it was evaluated.
+    pub fn get(&self, tiles: &[(usize, usize)]) -> Option<Outcome> {
+        let mask = TileMask::of_tiles(self.size, tiles);
+        self.shard(mask).lock().unwrap().get(&mask).copied()
+    }
+
+    /// Record the outcome of the clue set `tiles`, unless it is recorded already.
+    pub fn record(&self, tiles: &[(usize, usize)], outcome: Outcome) -> io::Result<()> {
+        let mask = TileMask::of_tiles(self.size, tiles);
+        if self.shard(mask).lock().unwrap().insert(mask, outcome).is_some() {
+            return Ok(());
+        }
+        let mut record = [0; RECORD_BYTES];
+        for (start, half) in [0, 16].into_iter().zip(mask.halves()) {
+            record[start..start + 16].copy_from_slice(&half.to_le_bytes());
+        }
+        record[32] = outcome as u8;
+        self.writer.lock().unwrap().write_all(&record)
+    }
+
+    /// Write the records buffered so far to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+
+    /// Records read when the index was opened.
+    pub fn loaded(&self) -> usize {
+        self.loaded
+    }
+
+    /// Clue sets recorded, by previous runs and this one.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
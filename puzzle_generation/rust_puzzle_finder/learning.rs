@@ -0,0 +1,544 @@
+//! Conflict-driven clause learning, an alternative search engine for the
+//! solver, enabled with [`crate::SolverOptions::learning`].
+//!
+//! The puzzle is encoded over one variable per cell and value, true when the
+//! cell holds the value. Making one true rules out those it excludes: the
+//! other values of its cell, its value in the rest of its row, column and
+//! box, and the values the variant forbids next to it. Clauses require each
+//! cell to hold a value and each row, column and box to hold each value, and
+//! the clues assign variables from the start. Propagation over both makes the
+//! same deductions as naked and hidden singles.
+//!
+//! When propagation fails, the conflict is traced back through the clauses
+//! and exclusions that forced each assignment to the first decision-level cut
+//! (the first unique implication point). The assignments on that cut can
+//! never hold together, which is learned as a clause, and the search jumps
+//! back to the deepest level where the clause forces a value, rather than to
+//! the previous decision. Solutions are counted by learning a clause that
+//! rules each one out before searching on.
+
+use std::ops::ControlFlow;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use crate::trace::{Reason, SearchObserver};
+use crate::{
+    all_tiles, box_cells, box_shape, consecutive, luby, neighbours, CancellationToken, Constraints, Grid, SolveHooks,
+    SolveStats, SolverOptions, Variant,
+};
+
+/// Conflicts between restarts, times the Luby sequence. Learned clauses are
+/// kept across restarts, so they only change the order of the decisions.
+const RESTART_UNIT: usize = 100;
+
+/// Decay of the activity of the variables at each conflict, which makes the
+/// decisions favour those involved in recent conflicts.
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// A variable and its sign: `var << 1`, plus 1 if negated.
+type Lit = u32;
+
+fn negate(lit: Lit) -> Lit {
+    lit ^ 1
+}
+
+fn var(lit: Lit) -> usize {
+    (lit >> 1) as usize
+}
+
+fn positive(var: usize) -> Lit {
+    (var << 1) as Lit
+}
+
+/// Value of a variable or literal: unassigned, true or false.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Unassigned,
+    True,
+    False,
+}
+
+/// Why a variable was assigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cause {
+    /// A decision, or a clue or learned unit clause at level 0.
+    Decision,
+    /// The clause of this index had no other literal left.
+    Clause(usize),
+    /// The variable was excluded by this true literal.
+    Excluded(Lit),
+}
+
+/// An assignment that propagation could not make.
+#[derive(Debug, Clone, Copy)]
+enum Conflict {
+    /// Every literal of the clause of this index is false.
+    Clause(usize),
+    /// Two true literals exclude each other.
+    Excluded(Lit, Lit),
+}
+
+/// Clauses and assignment of a search.
+struct Engine {
+    size: usize,
+    /// Variables excluded by each variable, those of variable `v` at
+    /// `exclusions[exclusion_starts[v]..exclusion_starts[v + 1]]`.
+    exclusions: Vec<u32>,
+    exclusion_starts: Vec<usize>,
+    clauses: Vec<Vec<Lit>>,
+    /// The deduction that a clause forcing a value stands for.
+    origins: Vec<Reason>,
+    /// Clauses watching each literal among their first two, visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Value>,
+    levels: Vec<usize>,
+    causes: Vec<Cause>,
+    trail: Vec<Lit>,
+    /// Start of each decision level on the trail.
+    level_starts: Vec<usize>,
+    /// Next trail literal to propagate.
+    queue_head: usize,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    seen: Vec<bool>,
+    /// Values placed by propagation, over the whole search.
+    forced: usize,
+}
+
+impl Engine {
+    fn new(size: usize) -> Self {
+        let variables = size * size * size;
+        Self {
+            size,
+            exclusions: Vec::new(),
+            exclusion_starts: Vec::with_capacity(variables + 1),
+            clauses: Vec::new(),
+            origins: Vec::new(),
+            watches: vec![Vec::new(); 2 * variables],
+            values: vec![Value::Unassigned; variables],
+            levels: vec![0; variables],
+            causes: vec![Cause::Decision; variables],
+            trail: Vec::new(),
+            level_starts: Vec::new(),
+            queue_head: 0,
+            activity: vec![0.0; variables],
+            activity_increment: 1.0,
+            seen: vec![false; variables],
+            forced: 0,
+        }
+    }
+
+    /// The variable of cell (i, j) holding `value`, counted from 0.
+    fn var(&self, (i, j): (usize, usize), value: usize) -> usize {
+        (i * self.size + j) * self.size + value
+    }
+
+    /// The cell and value, counted from 1, of a variable.
+    fn cell_value(&self, var: usize) -> ((usize, usize), usize) {
+        let (cell, value) = (var / self.size, var % self.size);
+        ((cell / self.size, cell % self.size), value + 1)
+    }
+
+    fn value(&self, lit: Lit) -> Value {
+        match (self.values[var(lit)], lit & 1) {
+            (Value::Unassigned, _) => Value::Unassigned,
+            (Value::True, 0) | (Value::False, 1) => Value::True,
+            _ => Value::False,
+        }
+    }
+
+    fn level(&self) -> usize {
+        self.level_starts.len()
+    }
+
+    /// Add a clause of at least two literals, watching its first two.
+    fn add_clause(&mut self, clause: Vec<Lit>, origin: Reason) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0] as usize].push(index);
+        self.watches[clause[1] as usize].push(index);
+        self.clauses.push(clause);
+        self.origins.push(origin);
+        index
+    }
+
+    /// Make `lit` true. Returns false if it already is false.
+    fn assign(&mut self, lit: Lit, cause: Cause, observer: &(impl SearchObserver + ?Sized)) -> bool {
+        match self.value(lit) {
+            Value::True => true,
+            Value::False => false,
+            Value::Unassigned => {
+                let v = var(lit);
+                self.values[v] = if lit & 1 == 0 { Value::True } else { Value::False };
+                self.levels[v] = self.level();
+                self.causes[v] = cause;
+                self.trail.push(lit);
+                if let (Cause::Clause(index), 0) = (cause, lit & 1) {
+                    self.forced += 1;
+                    let (cell, value) = self.cell_value(v);
+                    observer.propagate(cell, value, self.origins[index]);
+                }
+                true
+            }
+        }
+    }
+
+    /// Propagate the assignments of the trail, stopping at the first conflict.
+    fn propagate(&mut self, observer: &(impl SearchObserver + ?Sized)) -> Option<Conflict> {
+        while self.queue_head < self.trail.len() {
+            let lit = self.trail[self.queue_head];
+            self.queue_head += 1;
+            if lit & 1 == 0 {
+                let v = var(lit);
+                for k in self.exclusion_starts[v]..self.exclusion_starts[v + 1] {
+                    let excluded = positive(self.exclusions[k] as usize);
+                    if !self.assign(negate(excluded), Cause::Excluded(lit), observer) {
+                        return Some(Conflict::Excluded(lit, excluded));
+                    }
+                }
+            }
+            if let Some(index) = self.propagate_clauses(negate(lit), observer) {
+                return Some(Conflict::Clause(index));
+            }
+        }
+        None
+    }
+
+    /// Visit the clauses watching `false_lit`, which just became false.
+    /// Returns the one left with every literal false, if any.
+    fn propagate_clauses(&mut self, false_lit: Lit, observer: &(impl SearchObserver + ?Sized)) -> Option<usize> {
+        let mut watching = std::mem::take(&mut self.watches[false_lit as usize]);
+        let mut kept = 0;
+        let mut conflict = None;
+        let mut next = 0;
+        while next < watching.len() {
+            let index = watching[next];
+            next += 1;
+            let clause = &mut self.clauses[index];
+            if clause[0] == false_lit {
+                clause.swap(0, 1);
+            }
+            let first = clause[0];
+            if self.value(first) == Value::True {
+                watching[kept] = index;
+                kept += 1;
+                continue;
+            }
+            let clause = &self.clauses[index];
+            let replacement = (2..clause.len()).find(|&k| self.value(clause[k]) != Value::False);
+            if let Some(k) = replacement {
+                let clause = &mut self.clauses[index];
+                clause.swap(1, k);
+                self.watches[clause[1] as usize].push(index);
+                continue;
+            }
+            watching[kept] = index;
+            kept += 1;
+            if !self.assign(first, Cause::Clause(index), observer) {
+                conflict = Some(index);
+                watching.copy_within(next.., kept);
+                kept += watching.len() - next;
+                break;
+            }
+        }
+        watching.truncate(kept);
+        self.watches[false_lit as usize] = watching;
+        conflict
+    }
+
+    /// Collect into `into` the false literals that `conflict` is made of.
+    fn conflict_literals(&self, conflict: Conflict, into: &mut Vec<Lit>) {
+        into.clear();
+        match conflict {
+            Conflict::Clause(index) => into.extend_from_slice(&self.clauses[index]),
+            Conflict::Excluded(a, b) => into.extend([negate(a), negate(b)]),
+        }
+    }
+
+    /// Collect into `into` the false literals that forced `lit`.
+    fn antecedents(&self, lit: Lit, into: &mut Vec<Lit>) {
+        into.clear();
+        match self.causes[var(lit)] {
+            Cause::Decision => unreachable!("the first decision of the level ends the cut"),
+            Cause::Clause(index) => into.extend_from_slice(&self.clauses[index][1..]),
+            Cause::Excluded(by) => into.push(negate(by)),
+        }
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.activity_increment;
+        if self.activity[v] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+    }
+
+    /// The clause learned from `conflict`, its asserting literal first, and
+    /// the level to jump back to.
+    fn analyze(&mut self, conflict: Conflict) -> (Vec<Lit>, usize) {
+        let mut learned = vec![0];
+        let mut literals = Vec::new();
+        self.conflict_literals(conflict, &mut literals);
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let implied = loop {
+            for &lit in &literals {
+                let v = var(lit);
+                if !self.seen[v] && self.levels[v] > 0 {
+                    self.seen[v] = true;
+                    self.bump(v);
+                    if self.levels[v] >= self.level() {
+                        pending += 1;
+                    } else {
+                        learned.push(lit);
+                    }
+                }
+            }
+            // The latest assignment of the current level involved in the conflict
+            loop {
+                index -= 1;
+                if self.seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            self.seen[var(lit)] = false;
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            self.antecedents(lit, &mut literals);
+        };
+        learned[0] = negate(implied);
+        for &lit in &learned[1..] {
+            self.seen[var(lit)] = false;
+        }
+        // Watch the literal of the deepest level to jump back to next to the asserting one
+        let mut jump = 0;
+        if learned.len() > 1 {
+            let deepest = (1..learned.len()).max_by_key(|&k| self.levels[var(learned[k])]).expect("not empty");
+            learned.swap(1, deepest);
+            jump = self.levels[var(learned[1])];
+        }
+        self.activity_increment /= ACTIVITY_DECAY;
+        (learned, jump)
+    }
+
+    /// Undo the assignments of the levels above `level`.
+    fn backjump(&mut self, level: usize, observer: &(impl SearchObserver + ?Sized)) {
+        while self.level() > level {
+            let start = self.level_starts.pop().expect("above level 0");
+            for lit in self.trail.drain(start..) {
+                self.values[var(lit)] = Value::Unassigned;
+            }
+            observer.exit_node(self.level() + 1);
+        }
+        self.queue_head = self.queue_head.min(self.trail.len());
+    }
+
+    /// The unassigned variable of highest activity, to make true.
+    fn decision(&self) -> Option<usize> {
+        (0..self.values.len())
+            .filter(|&v| self.values[v] == Value::Unassigned)
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]).then(b.cmp(&a)))
+    }
+
+    fn grid(&self) -> Grid {
+        let mut grid = Grid::new(self.size);
+        for (v, value) in self.values.iter().enumerate() {
+            if *value == Value::True {
+                let (cell, value) = self.cell_value(v);
+                grid[cell] = value as u8;
+            }
+        }
+        grid
+    }
+}
+
+/// Cells holding each value once, and the deduction that the clause placing
+/// a value in one of them stands for, if it is encoded.
+type Line = (Vec<(usize, usize)>, Option<Reason>);
+
+/// Add the exclusions and clauses of `constraints` to `engine` and assign its
+/// clues at level 0. Returns `false` if the clues contradict each other at once.
+fn encode(engine: &mut Engine, constraints: &Constraints, observer: &(impl SearchObserver + ?Sized)) -> bool {
+    let size = constraints.size;
+    let cells = all_tiles(size);
+    let mut lines: Vec<Line> = Vec::new();
+    for k in 0..size {
+        lines.push(((0..size).map(|j| (k, j)).collect(), Some(Reason::HiddenSingleRow)));
+        lines.push(((0..size).map(|i| (i, k)).collect(), Some(Reason::HiddenSingleColumn)));
+    }
+    match constraints.variant {
+        Variant::Latin | Variant::NonConsecutive => {}
+        Variant::Pandiagonal => {
+            // Values are distinct on the N cells of a diagonal, so each one appears
+            for line in 0..size {
+                lines.push(((0..size).map(|k| (k, (k + line) % size)).collect(), None));
+                lines.push(((0..size).map(|k| (k, (line + size - k) % size)).collect(), None));
+            }
+        }
+        Variant::Sudoku => {
+            let boxes = box_shape(size);
+            for index in 0..size {
+                lines.push((box_cells(size, boxes, index).collect(), Some(Reason::HiddenSingleBox)));
+            }
+        }
+    }
+
+    let mut lines_of = vec![Vec::new(); size * size];
+    for (index, (line, _)) in lines.iter().enumerate() {
+        for &(i, j) in line {
+            lines_of[i * size + j].push(index);
+        }
+    }
+    engine.exclusion_starts.push(0);
+    let mut excluded = Vec::new();
+    for &(i, j) in &cells {
+        for value in 0..size {
+            excluded.clear();
+            excluded.extend((0..size).filter(|&other| other != value).map(|other| engine.var((i, j), other)));
+            for &line in &lines_of[i * size + j] {
+                let others = lines[line].0.iter().filter(|&&cell| cell != (i, j));
+                excluded.extend(others.map(|&cell| engine.var(cell, value)));
+            }
+            if constraints.variant == Variant::NonConsecutive {
+                let mut others = consecutive(1 << value, size);
+                while others != 0 {
+                    let other = others.trailing_zeros() as usize;
+                    others &= others - 1;
+                    excluded.extend(neighbours(size, i, j).map(|cell| engine.var(cell, other)));
+                }
+            }
+            excluded.sort_unstable();
+            excluded.dedup();
+            engine.exclusions.extend(excluded.iter().map(|&v| v as u32));
+            engine.exclusion_starts.push(engine.exclusions.len());
+        }
+    }
+
+    for &cell in &cells {
+        let clause = (0..size).map(|value| positive(engine.var(cell, value))).collect();
+        engine.add_clause(clause, Reason::NakedSingle);
+    }
+    for (line, origin) in &lines {
+        if let Some(origin) = *origin {
+            for value in 0..size {
+                let clause = line.iter().map(|&cell| positive(engine.var(cell, value))).collect();
+                engine.add_clause(clause, origin);
+            }
+        }
+    }
+
+    // The clues, as assignments of level 0
+    for &cell in &cells {
+        let (i, j) = cell;
+        let known = constraints.known[cell] as usize;
+        for value in 0..size {
+            let wrong = constraints.wrong[i * size + j] & (1 << value) != 0;
+            let lit = positive(engine.var(cell, value));
+            let holds = if known != 0 { known == value + 1 } else if wrong { false } else { continue };
+            if !engine.assign(if holds { lit } else { negate(lit) }, Cause::Decision, observer) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Search the solutions of `constraints` with clause learning, passing each
+/// one to `on_solution`, in the way of [`crate::for_each_solution`].
+pub fn search<O: SearchObserver + ?Sized>(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+    observer: &O,
+    on_solution: &mut dyn FnMut(&Grid) -> ControlFlow<()>,
+) -> SolveStats {
+    let mut stats = SolveStats::default();
+    let mut engine = Engine::new(constraints.size);
+    let mut found = 0;
+    let consistent = encode(&mut engine, constraints, observer);
+    stats.propagation_passes += 1;
+    if !consistent || engine.propagate(observer).is_some() {
+        observer.contradiction(Reason::Contradiction);
+        return stats;
+    }
+    // Solutions found so far, by this search or all those sharing the count
+    let limit_reached = |found: usize| {
+        let found = hooks.shared_solutions.map_or(found, |shared| shared.load(Ordering::Relaxed));
+        options.max_solutions.is_some_and(|max| found >= max)
+    };
+    let (mut conflicts, mut restarts) = (0, 1);
+    let mut decisions = 0;
+    loop {
+        stats.propagation_passes += 1;
+        if let Some(conflict) = engine.propagate(observer) {
+            observer.contradiction(Reason::Contradiction);
+            if engine.level() == 0 {
+                break;
+            }
+            conflicts += 1;
+            let (learned, jump) = engine.analyze(conflict);
+            engine.backjump(jump, observer);
+            let asserting = learned[0];
+            let cause = match learned.len() {
+                1 => Cause::Decision,
+                _ => Cause::Clause(engine.add_clause(learned, Reason::Learned)),
+            };
+            engine.assign(asserting, cause, observer);
+            if conflicts >= luby(restarts) * RESTART_UNIT {
+                conflicts = 0;
+                restarts += 1;
+                engine.backjump(0, observer);
+            }
+            continue;
+        }
+        let Some(v) = engine.decision() else {
+            // Every variable is assigned: a solution
+            let solution = engine.grid();
+            observer.solution(&solution);
+            if let Some(shared) = hooks.shared_solutions {
+                shared.fetch_add(1, Ordering::Relaxed);
+            }
+            found += 1;
+            if on_solution(&solution).is_break() || limit_reached(found) {
+                break;
+            }
+            // Rule it out: the decisions that led to it imply the rest
+            let blocking: Vec<Lit> = engine.level_starts.iter().map(|&start| negate(engine.trail[start])).collect();
+            engine.backjump(0, observer);
+            match blocking.len() {
+                0 => break,
+                1 => {
+                    engine.assign(blocking[0], Cause::Decision, observer);
+                }
+                _ => {
+                    engine.add_clause(blocking, Reason::Learned);
+                }
+            }
+            continue;
+        };
+        if limit_reached(found) || hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+            break;
+        }
+        if options.node_budget.is_some_and(|budget| decisions >= budget)
+            || (decisions.is_multiple_of(64) && hooks.deadline.is_some_and(|deadline| Instant::now() >= deadline))
+        {
+            stats.timed_out = true;
+            break;
+        }
+        decisions += 1;
+        engine.level_starts.push(engine.trail.len());
+        stats.max_depth = stats.max_depth.max(engine.level());
+        let (cell, value) = engine.cell_value(v);
+        observer.enter_node(cell, value, engine.level(), Reason::Guess);
+        engine.assign(positive(v), Cause::Decision, observer);
+    }
+    engine.backjump(0, observer);
+    stats.backtrack_nodes = decisions + 1;
+    stats.guesses = decisions;
+    stats.forced_cells = engine.forced;
+    stats
+}
@@ -0,0 +1,3269 @@
+//! Latin square solver and search for Sudodle puzzles with a unique solution,
+//! shared by the `find_puzzles` binary and the benchmarks.
+
+pub mod bloom;
+pub mod bounds;
+pub mod certificate;
+pub mod checkpoint;
+pub mod classes;
+pub mod cluster;
+pub mod completions;
+pub mod critical;
+pub mod cycles;
+#[cfg(feature = "sqlite")]
+pub mod db;
+pub mod dedup;
+pub mod dlx;
+pub mod export;
+pub mod first_guess;
+pub mod generators;
+pub mod grid;
+pub mod hints;
+pub mod incremental;
+pub mod index;
+pub mod learning;
+pub mod mask;
+pub mod metrics;
+pub mod neighbors;
+pub mod nogoods;
+pub mod output;
+pub mod pool;
+pub mod profile;
+pub mod puzzle;
+pub mod qcp;
+pub mod reference;
+pub mod render;
+pub mod repair;
+pub mod robustness;
+pub mod server;
+pub mod sheet;
+pub mod simulate;
+pub mod summary;
+pub mod superset;
+pub mod symmetry;
+pub mod techniques;
+pub mod trace;
+pub mod witness;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{ControlFlow, Deref, DerefMut};
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use clap::ValueEnum;
+use rand::prelude::*;
+
+pub use grid::{normalize, validate_latin_square, validate_partial_latin_square, Grid, Violation};
+pub use puzzle::{CluePlacement, PuzzleSpec, SolveReport};
+use bloom::BloomFilter;
+use certificate::Certificate;
+use checkpoint::Checkpoint;
+use dedup::ConcurrentTileSet;
+use generators::{AnnealOptions, Generator};
+use nogoods::NogoodSet;
+use output::PuzzleSink;
+use summary::{DifferenceCounts, RunSummary, StratumCounts};
+use techniques::TechniqueSet;
+use trace::{Reason, SearchObserver};
+
+/// Clues of a puzzle in the solver's representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraints {
+    pub size: usize,
+    /// Known correct values, 0 where unknown.
+    pub known: Grid,
+    /// Values known to be wrong for each cell, as bitmasks indexed
+    /// `row * size + col` with bit `v - 1` set iff value `v` is excluded.
+    pub wrong: Vec<u32>,
+    /// Rules the solutions follow on top of those of Latin squares.
+    pub variant: Variant,
+}
+
+impl Constraints {
+    /// Constraints of a grid with no clues.
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            known: Grid::new(size),
+            wrong: vec![0; size * size],
+            variant: Variant::Latin,
+        }
+    }
+
+    /// Convert clues given as maps from cells to known and known wrong values.
+    /// Out-of-range cells and values are ignored.
+    pub fn from_maps(
+        size: usize,
+        known_values: &HashMap<CluePlacement, usize>,
+        known_wrong_values: &HashMap<CluePlacement, Vec<usize>>,
+    ) -> Self {
+        let mut constraints = Self::new(size);
+        for (&CluePlacement { row: i, col: j }, &value) in known_values {
+            if i < size && j < size && value >= 1 && value <= size {
+                constraints.known[(i, j)] = value as u8;
+            }
+        }
+        for (&CluePlacement { row: i, col: j }, values) in known_wrong_values {
+            for &value in values {
+                if i < size && j < size && value >= 1 && value <= size {
+                    constraints.wrong[i * size + j] |= 1 << (value - 1);
+                }
+            }
+        }
+        constraints
+    }
+
+    /// Mark `value` as the correct value of `cell`.
+    pub fn set_known(&mut self, (i, j): (usize, usize), value: usize) {
+        self.known[(i, j)] = value as u8;
+        self.wrong[i * self.size + j] = 0;
+    }
+
+    /// Mark `cell` as unknown, with `value` known to be wrong for it.
+    pub fn set_wrong(&mut self, (i, j): (usize, usize), value: usize) {
+        self.known[(i, j)] = 0;
+        self.wrong[i * self.size + j] = 1 << (value - 1);
+    }
+
+    /// Add `value` to the values known to be wrong for `cell`.
+    pub fn forbid(&mut self, (i, j): (usize, usize), value: usize) {
+        self.wrong[i * self.size + j] |= 1 << (value - 1);
+    }
+
+    /// Mark `value` as wrong for every cell of `row` but those of the columns
+    /// `except_cols`, as when the game tells that the value of a row is in
+    /// one of a few cells. Cells whose value is known are left as they are.
+    pub fn forbid_value_in_row(&mut self, value: usize, row: usize, except_cols: &[usize]) {
+        for col in (0..self.size).filter(|col| !except_cols.contains(col)) {
+            if self.known[(row, col)] == 0 {
+                self.forbid((row, col), value);
+            }
+        }
+    }
+
+    /// Mark `value` as wrong for every cell of `col` but those of the rows
+    /// `except_rows`, like [`Constraints::forbid_value_in_row`].
+    pub fn forbid_value_in_column(&mut self, value: usize, col: usize, except_rows: &[usize]) {
+        for row in (0..self.size).filter(|row| !except_rows.contains(row)) {
+            if self.known[(row, col)] == 0 {
+                self.forbid((row, col), value);
+            }
+        }
+    }
+
+    /// Mark `cell` as unknown, with no value known to be wrong for it.
+    pub fn set_unknown(&mut self, (i, j): (usize, usize)) {
+        self.known[(i, j)] = 0;
+        self.wrong[i * self.size + j] = 0;
+    }
+
+    /// Update the constraints of a puzzle revealing tiles of `grid` to those of
+    /// the puzzle where `removed` is no longer revealed and `added` is.
+    pub fn swap_tiles(
+        &mut self,
+        grid: &Grid,
+        removed: (usize, usize),
+        added: (usize, usize),
+        semantics: ClueSemantics,
+    ) {
+        match semantics {
+            ClueSemantics::Sudodle => self.set_wrong(removed, grid[removed].into()),
+            ClueSemantics::GivensOnly => self.set_unknown(removed),
+        }
+        self.set_known(added, grid[added].into());
+    }
+}
+
+/// What the tiles that are not placed say about the solution of a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ClueSemantics {
+    /// The game's feedback: every tile that is not placed is wrong where it stands.
+    #[default]
+    Sudodle,
+    /// Classic puzzles: only the placed tiles are given, the other cells are blank.
+    GivensOnly,
+}
+
+/// Rules of a puzzle's solutions, on top of those of Latin squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Variant {
+    /// Latin squares, as in the game.
+    #[default]
+    Latin,
+    /// Each broken diagonal and anti-diagonal (the cells with the same
+    /// `col - row`, or `row + col`, modulo the size) holds every value once too.
+    Pandiagonal,
+    /// Orthogonally adjacent cells never hold consecutive values.
+    NonConsecutive,
+    /// Each box of the grid holds every value once too, see [`box_shape`].
+    Sudoku,
+}
+
+impl Variant {
+    /// Whether the Latin square `square` follows the rules of the variant.
+    pub fn allows(self, square: &Grid) -> bool {
+        square.empty_cells() == 0 && !breaks_variant(square, self)
+    }
+}
+
+/// Latin square whose tiles the puzzles of a search place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BaseGrid {
+    /// The cyclic square of the web game, see [`cyclic_latin_square`].
+    #[default]
+    Cyclic,
+    /// A row-complete square, see [`row_complete_latin_square`]: even sizes only.
+    RowComplete,
+    /// A square equal to its transpose, see [`symmetric_latin_square`].
+    Symmetric,
+    /// A square with 1 to N down its diagonal, see [`idempotent_latin_square`]:
+    /// every size but 2.
+    Idempotent,
+    /// A pandiagonal square, see [`pandiagonal_latin_square`]: sizes with no
+    /// factor 2 or 3 only.
+    Pandiagonal,
+    /// A non-consecutive square, see [`non_consecutive_latin_square`]: sizes 1
+    /// and 5 up only.
+    NonConsecutive,
+    /// A Sudoku solution, see [`sudoku_latin_square`].
+    Sudoku,
+}
+
+impl BaseGrid {
+    /// The N×N square of this kind, or `None` if there is none of this size.
+    pub fn grid(self, size: usize) -> Option<Grid> {
+        match self {
+            BaseGrid::Cyclic => Some(cyclic_latin_square(size)),
+            BaseGrid::RowComplete => row_complete_latin_square(size),
+            BaseGrid::Symmetric => Some(symmetric_latin_square(size)),
+            BaseGrid::Idempotent => idempotent_latin_square(size),
+            BaseGrid::Pandiagonal => pandiagonal_latin_square(size),
+            BaseGrid::NonConsecutive => non_consecutive_latin_square(size),
+            BaseGrid::Sudoku => Some(sudoku_latin_square(size)),
+        }
+    }
+}
+
+/// A buffer of the solver's state, indexed as a flat slice.
+trait Buffer<T>: Clone + Deref<Target = [T]> + DerefMut {
+    /// A buffer of `len` zeros.
+    fn zeroed(len: usize) -> Self;
+}
+
+impl<T: Copy + Default> Buffer<T> for Vec<T> {
+    fn zeroed(len: usize) -> Self {
+        vec![T::default(); len]
+    }
+}
+
+/// A buffer of `A * B * C` values stored inline, so that copying it is a
+/// `memcpy` and indexing it needs no pointer chasing.
+#[derive(Clone, Copy)]
+struct Array<T, const A: usize, const B: usize, const C: usize>([[[T; A]; B]; C]);
+
+impl<T, const A: usize, const B: usize, const C: usize> Deref for Array<T, A, B, C> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.0.as_flattened().as_flattened()
+    }
+}
+
+impl<T, const A: usize, const B: usize, const C: usize> DerefMut for Array<T, A, B, C> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.0.as_flattened_mut().as_flattened_mut()
+    }
+}
+
+impl<T: Copy + Default, const A: usize, const B: usize, const C: usize> Buffer<T> for Array<T, A, B, C> {
+    fn zeroed(len: usize) -> Self {
+        debug_assert_eq!(len, A * B * C, "fixed-size buffer of another size");
+        Array([[[T::default(); A]; B]; C])
+    }
+}
+
+/// Buffers holding the state of the backtracker, copied at each decision.
+trait Storage: Clone {
+    /// One entry per cell.
+    type Cells<T: Copy + Default>: Buffer<T>;
+    /// One mask per line, of up to four kinds: see [`Domains::lines`].
+    type Lines: Buffer<u32>;
+    /// One count per line and value.
+    type Places: Buffer<u8>;
+}
+
+/// Storage of the general kernel: vectors sized at runtime, for every size.
+#[derive(Clone)]
+struct Heap;
+
+impl Storage for Heap {
+    type Cells<T: Copy + Default> = Vec<T>;
+    type Lines = Vec<u32>;
+    type Places = Vec<u8>;
+}
+
+/// Storage of the kernel specialized for size `N`: arrays whose size is known
+/// at compile time, on the stack.
+#[derive(Clone)]
+struct Fixed<const N: usize>;
+
+impl<const N: usize> Storage for Fixed<N> {
+    type Cells<T: Copy + Default> = Array<T, N, N, 1>;
+    type Lines = Array<u32, N, 4, 1>;
+    type Places = Array<u8, N, N, 4>;
+}
+
+/// A partial square, the values used in each of its lines and the cached
+/// candidates of each cell, kept in sync as the solver fills cells.
+struct Domains<S: Storage> {
+    size: usize,
+    /// Rules followed on top of those of Latin squares.
+    variant: Variant,
+    /// Height and width of the boxes of the Sudoku variant, see [`box_shape`].
+    boxes: (usize, usize),
+    /// Values of the cells indexed `row * size + col`, 0 for empty cells.
+    values: S::Cells<u8>,
+    /// Number of empty cells.
+    empty: usize,
+    /// `used[line]` has bit v-1 set iff value v is in the line, numbered as
+    /// in [`Domains::lines`].
+    used: S::Lines,
+    /// Candidates of each cell indexed `row * size + col`, excluding the
+    /// values of its row and column, those the variant rules out and its
+    /// known wrong values; 0 for filled cells.
+    candidates: S::Cells<u32>,
+    /// Cells of each line having each value as a candidate, indexed
+    /// `line * size + value - 1`.
+    places: S::Places,
+    /// Whether an empty cell has no candidate left, or a line no cell left
+    /// for a value it lacks, so that the grid cannot be completed. Kept up to
+    /// date as candidates are removed, rather than by scanning every node.
+    contradiction: bool,
+}
+
+impl<S: Storage> Clone for Domains<S> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            used: self.used.clone(),
+            candidates: self.candidates.clone(),
+            places: self.places.clone(),
+            ..*self
+        }
+    }
+
+    /// Copy `source` into the buffers of `self`, so that saving and restoring
+    /// the state of a node allocates nothing.
+    fn clone_from(&mut self, source: &Self) {
+        self.values.clone_from(&source.values);
+        self.empty = source.empty;
+        self.used.clone_from(&source.used);
+        self.candidates.clone_from(&source.candidates);
+        self.places.clone_from(&source.places);
+        self.contradiction = source.contradiction;
+    }
+}
+
+impl<S: Storage> Domains<S> {
+    fn new(square: &Grid, wrong: &[u32], variant: Variant) -> Self {
+        let size = square.size();
+        let full_mask = (1u32 << size) - 1; // bits 0..size-1 all set
+        let mut domains = Self {
+            size,
+            variant,
+            boxes: box_shape(size),
+            values: Buffer::zeroed(size * size),
+            empty: 0,
+            used: Buffer::zeroed(4 * size),
+            candidates: Buffer::zeroed(size * size),
+            places: Buffer::zeroed(4 * size * size),
+            contradiction: false,
+        };
+        let cells = || (0..size).flat_map(|i| (0..size).map(move |j| (i, j)));
+        for (i, j) in cells() {
+            domains.values[i * size + j] = square[(i, j)];
+            if square[(i, j)] == 0 {
+                domains.empty += 1;
+                continue;
+            }
+            let bit = 1u32 << (square[(i, j)] - 1); // Convert to 0-based for bitmask
+            let (lines, count) = domains.lines(i, j);
+            for &line in &lines[..count] {
+                domains.used[line] |= bit;
+            }
+        }
+        for (i, j) in cells().filter(|&cell| square[cell] == 0) {
+            let (lines, count) = domains.lines(i, j);
+            let used = lines[..count].iter().fold(0, |used, &line| used | domains.used[line]);
+            domains.candidates[i * size + j] = full_mask & !(used | wrong[i * size + j]);
+        }
+        if variant == Variant::NonConsecutive {
+            for (i, j) in cells().filter(|&cell| square[cell] != 0) {
+                let bit = 1u32 << (square[(i, j)] - 1);
+                for (r, c) in neighbours(size, i, j) {
+                    domains.candidates[r * size + c] &= !consecutive(bit, size);
+                }
+            }
+        }
+        for (i, j) in cells() {
+            let mask = domains.of(i, j);
+            domains.contradiction |= square[(i, j)] == 0 && mask == 0;
+            let (lines, count) = domains.lines(i, j);
+            for &line in &lines[..count] {
+                for value in (0..size).filter(|value| mask & 1 << value != 0) {
+                    domains.places[line * size + value] += 1;
+                }
+            }
+        }
+        let lines = match variant {
+            Variant::Latin | Variant::NonConsecutive => 2 * size,
+            Variant::Pandiagonal => 4 * size,
+            Variant::Sudoku => 3 * size,
+        };
+        for line in 0..lines {
+            let used = domains.used[line];
+            domains.contradiction |=
+                (0..size).any(|value| used & 1 << value == 0 && domains.places[line * size + value] == 0);
+        }
+        domains
+    }
+
+    /// Value of cell (i, j), 0 if it is empty.
+    fn value(&self, i: usize, j: usize) -> u8 {
+        self.values[i * self.size + j]
+    }
+
+    /// Candidates of cell (i, j).
+    fn of(&self, i: usize, j: usize) -> u32 {
+        self.candidates[i * self.size + j]
+    }
+
+    /// Whether the grid may still be completed: every empty cell has a
+    /// candidate, and every line a cell for each value it lacks.
+    fn is_consistent(&self) -> bool {
+        !self.contradiction
+    }
+
+    /// Copy the values of the cells into `grid`, of the same size.
+    fn fill(&self, grid: &mut Grid) {
+        for (cell, &value) in self.values.iter().enumerate() {
+            grid[(cell / self.size, cell % self.size)] = value;
+        }
+    }
+
+    /// The lines of cell (i, j) and their number. Lines are the rows, the
+    /// columns, then the broken diagonals and anti-diagonals, or the boxes,
+    /// of the variant.
+    fn lines(&self, i: usize, j: usize) -> ([usize; 4], usize) {
+        let size = self.size;
+        match self.variant {
+            Variant::Latin | Variant::NonConsecutive => ([i, size + j, 0, 0], 2),
+            Variant::Pandiagonal => ([i, size + j, 2 * size + (j + size - i) % size, 3 * size + (i + j) % size], 4),
+            Variant::Sudoku => ([i, size + j, 2 * size + box_of(size, self.boxes, i, j), 0], 3),
+        }
+    }
+
+    /// Count the values of `removed` out of the lines of cell (i, j), noting
+    /// a contradiction when a line has no cell left for a value it lacks.
+    fn discount(&mut self, i: usize, j: usize, removed: u32) {
+        let size = self.size;
+        let (lines, count) = self.lines(i, j);
+        for &line in &lines[..count] {
+            let used = self.used[line];
+            let mut values = removed;
+            while values != 0 {
+                let bit = values & values.wrapping_neg();
+                values ^= bit;
+                let places = &mut self.places[line * size + bit.trailing_zeros() as usize];
+                *places -= 1;
+                self.contradiction |= *places == 0 && used & bit == 0;
+            }
+        }
+    }
+
+    /// Remove the values of `mask` from the candidates of the empty cell
+    /// (i, j), noting a contradiction if none is left.
+    fn remove(&mut self, i: usize, j: usize, mask: u32) {
+        let cell = i * self.size + j;
+        let removed = self.candidates[cell] & mask;
+        if removed != 0 {
+            self.candidates[cell] &= !mask;
+            self.contradiction |= self.candidates[cell] == 0;
+            self.discount(i, j, removed);
+        }
+    }
+
+    /// Place `bit` (a single value) in cell (i, j), removing it from the
+    /// candidates of the cells of its row and column, and of those where the
+    /// variant rules it out.
+    fn assign(&mut self, i: usize, j: usize, bit: u32) {
+        let size = self.size;
+        self.values[i * size + j] = bit.trailing_zeros() as u8 + 1;
+        self.empty -= 1;
+        let (lines, count) = self.lines(i, j);
+        for &line in &lines[..count] {
+            self.used[line] |= bit;
+        }
+        // The cell itself is filled, so losing its candidates only matters to its lines
+        let own = std::mem::take(&mut self.candidates[i * size + j]);
+        self.discount(i, j, own);
+        for k in 0..size {
+            self.remove(i, k, bit);
+            self.remove(k, j, bit);
+        }
+        match self.variant {
+            Variant::Latin => {}
+            Variant::Pandiagonal => {
+                let (diagonal, anti_diagonal) = ((j + size - i) % size, (i + j) % size);
+                for k in 0..size {
+                    self.remove(k, (k + diagonal) % size, bit);
+                    self.remove(k, (anti_diagonal + size - k) % size, bit);
+                }
+            }
+            Variant::NonConsecutive => {
+                for (r, c) in neighbours(size, i, j) {
+                    self.remove(r, c, consecutive(bit, size));
+                }
+            }
+            Variant::Sudoku => {
+                for (r, c) in box_cells(size, self.boxes, box_of(size, self.boxes, i, j)) {
+                    self.remove(r, c, bit);
+                }
+            }
+        }
+    }
+}
+
+/// Height and width of the boxes of the Sudoku variant of `size`: the largest
+/// divisor of the size up to its square root, and the size divided by it, e.g.
+/// 3×3 boxes for 9, 2×3 for 6 and 4×4 for 16. The boxes of prime sizes are
+/// the rows, which adds no rule.
+pub fn box_shape(size: usize) -> (usize, usize) {
+    let height = (1..=size).filter(|&height| height * height <= size && size.is_multiple_of(height)).max();
+    height.map_or((1, size), |height| (height, size / height))
+}
+
+/// Index of the box of cell (i, j), numbering boxes row by row.
+fn box_of(size: usize, (height, width): (usize, usize), i: usize, j: usize) -> usize {
+    (i / height) * (size / width) + j / width
+}
+
+/// The cells of box `index`, row by row.
+fn box_cells(size: usize, (height, width): (usize, usize), index: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (top, left) = (index / (size / width) * height, index % (size / width) * width);
+    (top..top + height).flat_map(move |i| (left..left + width).map(move |j| (i, j)))
+}
+
+/// The cells orthogonally adjacent to (i, j) in a grid of `size`.
+fn neighbours(size: usize, i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
+    [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)]
+        .into_iter()
+        .filter(move |&(r, c)| r < size && c < size)
+}
+
+/// The values consecutive to the single value `bit`, as a bitmask.
+fn consecutive(bit: u32, size: usize) -> u32 {
+    (bit << 1 | bit >> 1) & ((1u32 << size) - 1)
+}
+
+/// Values set in at least one of `masks`, and in at least two. The values of
+/// a line with a single place, or none, then come out of two masks for every
+/// value at once, rather than by counting the places of each value.
+fn once_and_twice(masks: impl Iterator<Item = u32>) -> (u32, u32) {
+    masks.fold((0, 0), |(once, twice), mask| (once | mask, twice | once & mask))
+}
+
+/// Number of values in each of up to four `masks`, counted at once in the
+/// 16-bit lanes of a u64 (masks have at most [`MAX_SIZE`] bits): lane k, the
+/// bits from 16k, holds the count of `masks[k]`.
+fn popcounts(masks: &[u32]) -> u64 {
+    let lanes = masks.iter().rev().fold(0u64, |lanes, &mask| lanes << 16 | u64::from(mask));
+    let lanes = lanes - (lanes >> 1 & 0x5555_5555_5555_5555);
+    let lanes = (lanes & 0x3333_3333_3333_3333) + (lanes >> 2 & 0x3333_3333_3333_3333);
+    let lanes = (lanes + (lanes >> 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    (lanes + (lanes >> 8)) & 0x00FF_00FF_00FF_00FF
+}
+
+/// Whether the filled cells of `square` break the rules that `variant` adds
+/// to those of Latin squares.
+fn breaks_variant(square: &Grid, variant: Variant) -> bool {
+    let size = square.size();
+    let filled = (0..size).flat_map(|i| (0..size).map(move |j| (i, j))).filter(|&cell| square[cell] != 0);
+    match variant {
+        Variant::Latin => false,
+        Variant::Pandiagonal => {
+            let mut diag_used = vec![0u32; size];
+            let mut anti_used = vec![0u32; size];
+            for (i, j) in filled {
+                let bit = 1u32 << (square[(i, j)] - 1);
+                let (diagonal, anti_diagonal) = ((j + size - i) % size, (i + j) % size);
+                if (diag_used[diagonal] | anti_used[anti_diagonal]) & bit != 0 {
+                    return true;
+                }
+                diag_used[diagonal] |= bit;
+                anti_used[anti_diagonal] |= bit;
+            }
+            false
+        }
+        Variant::NonConsecutive => filled.into_iter().any(|(i, j)| {
+            neighbours(size, i, j).any(|cell| square[cell] != 0 && square[cell].abs_diff(square[(i, j)]) == 1)
+        }),
+        Variant::Sudoku => {
+            let boxes = box_shape(size);
+            let mut box_used = vec![0u32; size];
+            for (i, j) in filled {
+                let bit = 1u32 << (square[(i, j)] - 1);
+                let box_index = box_of(size, boxes, i, j);
+                if box_used[box_index] & bit != 0 {
+                    return true;
+                }
+                box_used[box_index] |= bit;
+            }
+            false
+        }
+    }
+}
+
+/// Counters collected by the solver while completing a single partial square.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolveStats {
+    /// Number of search nodes visited by the backtracker.
+    pub backtrack_nodes: usize,
+    /// Number of nodes where more than one candidate had to be tried.
+    pub guesses: usize,
+    /// Deepest level of the search tree reached (0 if no backtracking was needed).
+    pub max_depth: usize,
+    /// Number of cell assignments made by forced deductions (propagation or
+    /// single-candidate cells) rather than by guesses, over the whole search.
+    pub forced_cells: usize,
+    /// Number of constraint propagation sweeps over the grid.
+    pub propagation_passes: usize,
+    /// Whether the search was abandoned because its deadline passed or its
+    /// node budget ran out.
+    pub timed_out: bool,
+}
+
+impl SolveStats {
+    /// Difficulty rating of the puzzle: the number of guesses needed by the solver.
+    ///
+    /// A rating of 0 means the puzzle can be solved by logical deductions alone.
+    pub fn difficulty(&self) -> usize {
+        self.guesses
+    }
+}
+
+/// Order in which the backtracker tries the candidates of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ValueOrder {
+    /// Smallest value first.
+    #[default]
+    Ascending,
+    /// Values ruling out the fewest candidates of the cell's row and column peers first.
+    #[value(name = "lcv")]
+    LeastConstraining,
+    /// A random order, reproducible from the seed.
+    Random,
+}
+
+/// How the backtracker stores its state, which it copies at every decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Kernel {
+    /// Arrays of a size fixed at compile time, on the stack, for sizes 5 to 9
+    /// (copying them allocates nothing); the general kernel for other sizes.
+    #[default]
+    Specialized,
+    /// Vectors on the heap, for every size.
+    General,
+}
+
+/// Settings of the solver's search strategy and limits, built with
+/// [`SolverOptions::builder`] or by updating [`SolverOptions::default`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolverOptions {
+    /// Propagation techniques applied before guessing.
+    pub techniques: TechniqueSet,
+    /// When singles propagate after a decision. Eliminations always
+    /// propagate after every decision.
+    pub propagation_threshold: PropagationThreshold,
+    /// Order in which candidate values are tried.
+    pub value_order: ValueOrder,
+    /// How the state of the search is stored. Kernels only differ in speed:
+    /// their searches are the same.
+    pub kernel: Kernel,
+    /// Seed of [`ValueOrder::Random`].
+    pub seed: u64,
+    /// Among the cells with the fewest candidates, pick the one with the most
+    /// empty cells in its row and column.
+    pub degree_tie_break: bool,
+    /// Stop once this many solutions are found. The limit passed to
+    /// [`solve_constraints`] also applies, the smaller one winning.
+    pub max_solutions: Option<usize>,
+    /// Abandon the search after visiting this many nodes, setting
+    /// [`SolveStats::timed_out`]. Each branch of
+    /// [`complete_latin_square_parallel`] has a budget of its own.
+    pub node_budget: Option<usize>,
+    /// Abandon the search after this long, setting [`SolveStats::timed_out`].
+    /// A deadline in [`SolveHooks`] also applies, the earlier one winning.
+    pub timeout: Option<Duration>,
+    /// Restart the search with a new random value order after node budgets
+    /// of this many nodes times the Luby sequence (1, 1, 2, 1, 1, 2, 4, ...),
+    /// keeping the solutions found, until a run completes. Puzzles where one
+    /// order is unlucky then take no longer than the others; the
+    /// [`SolverOptions::node_budget`] counts the nodes of every run. Only
+    /// [`solve_constraints`] restarts.
+    pub restarts: Option<usize>,
+    /// Search with conflict-driven clause learning, see [`learning`]: each
+    /// dead end teaches a clause ruling out its cause, and the search jumps
+    /// back past the decisions that played no part in it. Nodes cost more,
+    /// but hard uniqueness proofs take far fewer. The propagation techniques
+    /// and value order do not apply.
+    pub learning: bool,
+    /// Search the puzzle as an exact cover problem with dancing links, see
+    /// [`dlx`], rather than with the backtracker. The propagation techniques
+    /// and value order do not apply.
+    pub dlx: bool,
+    /// Race the backtracker, the backtracker with restarts, dancing links and
+    /// clause learning on threads of their own, taking the first to finish
+    /// and cancelling the others, see [`solve_constraints`]. Each one has the
+    /// whole [`SolverOptions::node_budget`], and the restarts use
+    /// [`SolverOptions::restarts`] as their unit, or 1000 nodes. Only
+    /// [`solve_constraints`] races, and only without an observer, which
+    /// cannot be shared between threads: [`for_each_solution`], and solves
+    /// with an observer, search with the engine the other options select.
+    pub portfolio: bool,
+}
+
+impl SolverOptions {
+    /// A builder of options, starting from the defaults, e.g.
+    /// `SolverOptions::builder().max_solutions(2).node_budget(10_000).build()`.
+    pub fn builder() -> SolverOptionsBuilder {
+        SolverOptionsBuilder::default()
+    }
+
+    /// The smaller of `max_solutions` and [`SolverOptions::max_solutions`].
+    fn solution_limit(&self, max_solutions: Option<usize>) -> Option<usize> {
+        match (max_solutions, self.max_solutions) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Builder of [`SolverOptions`]; options that are not set keep their default.
+#[derive(Debug, Clone, Default)]
+pub struct SolverOptionsBuilder {
+    options: SolverOptions,
+}
+
+impl SolverOptionsBuilder {
+    pub fn techniques(mut self, techniques: TechniqueSet) -> Self {
+        self.options.techniques = techniques;
+        self
+    }
+
+    pub fn propagation_threshold(mut self, threshold: PropagationThreshold) -> Self {
+        self.options.propagation_threshold = threshold;
+        self
+    }
+
+    pub fn value_order(mut self, value_order: ValueOrder) -> Self {
+        self.options.value_order = value_order;
+        self
+    }
+
+    pub fn kernel(mut self, kernel: Kernel) -> Self {
+        self.options.kernel = kernel;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = seed;
+        self
+    }
+
+    pub fn degree_tie_break(mut self, degree_tie_break: bool) -> Self {
+        self.options.degree_tie_break = degree_tie_break;
+        self
+    }
+
+    pub fn max_solutions(mut self, max_solutions: usize) -> Self {
+        self.options.max_solutions = Some(max_solutions);
+        self
+    }
+
+    pub fn node_budget(mut self, node_budget: usize) -> Self {
+        self.options.node_budget = Some(node_budget);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    pub fn restarts(mut self, unit: usize) -> Self {
+        self.options.restarts = Some(unit);
+        self
+    }
+
+    pub fn learning(mut self, learning: bool) -> Self {
+        self.options.learning = learning;
+        self
+    }
+
+    pub fn dlx(mut self, dlx: bool) -> Self {
+        self.options.dlx = dlx;
+        self
+    }
+
+    pub fn portfolio(mut self, portfolio: bool) -> Self {
+        self.options.portfolio = portfolio;
+        self
+    }
+
+    pub fn build(self) -> SolverOptions {
+        self.options
+    }
+}
+
+/// When the backtracker propagates singles after a decision.
+///
+/// The guesses of the search, and so the difficulty ratings, depend on it:
+/// ratings are those of the default threshold, half the cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropagationThreshold {
+    /// Once fewer than this percentage of the cells are empty; 100
+    /// propagates after every decision.
+    Percent(u8),
+    /// The threshold measured fastest for the size and clues, see
+    /// [`auto_propagation_threshold`].
+    Auto,
+}
+
+impl Default for PropagationThreshold {
+    fn default() -> Self {
+        PropagationThreshold::Percent(50)
+    }
+}
+
+impl std::str::FromStr for PropagationThreshold {
+    type Err = String;
+
+    /// Parse `auto` or a percentage from 0 to 100.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim() {
+            "auto" => Ok(PropagationThreshold::Auto),
+            percent => match percent.parse::<u8>() {
+                Ok(percent) if percent <= 100 => Ok(PropagationThreshold::Percent(percent)),
+                _ => Err(format!("invalid propagation threshold '{}' (expected auto or 0 to 100)", text)),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for PropagationThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PropagationThreshold::Percent(percent) => write!(f, "{}", percent),
+            PropagationThreshold::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// Largest size where propagating singles after every decision pays off for
+/// constraints ruling out a value in most cells, as the game's clues do.
+const EAGER_PROPAGATION_MAX_SIZE: usize = 6;
+
+/// Percentage of empty cells below which singles propagate fastest for
+/// `constraints`, as measured with `bench --propagation-thresholds`.
+///
+/// When most cells have a wrong value, propagating after every decision
+/// checks random 5x5 clue sets a third faster and 6x6 ones slightly faster,
+/// but larger ones slower, as most of the passes high up the search tree find
+/// nothing. With givens alone, the threshold hardly matters, so other
+/// constraints keep the default of half the cells.
+pub fn auto_propagation_threshold(constraints: &Constraints) -> u8 {
+    let ruled_out = constraints.wrong.iter().filter(|&&wrong| wrong != 0).count();
+    if constraints.size <= EAGER_PROPAGATION_MAX_SIZE && 2 * ruled_out > constraints.wrong.len() {
+        100
+    } else {
+        50
+    }
+}
+
+/// Shuffle `values` deterministically from `seed` and the search node number.
+fn shuffle_values(values: &mut [usize], seed: u64, node: usize) {
+    // splitmix64, cheap enough to reseed at every node
+    let mut state = seed ^ (node as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..values.len()).rev() {
+        values.swap(i, (next() % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Find all completions of a partial Latin square using advanced optimized backtracking.
+///
+/// This function takes a partially filled Latin square with known correct values
+/// and known incorrect values, then uses multiple optimization techniques including:
+/// - 🔥 Constraint Propagation Cascading - automatically fills forced moves
+/// - 🎯 Naked Singles Detection - cells with only one possible value
+/// - 🔍 Hidden Singles Detection - values with only one possible position
+/// - ⚡ Efficient Bitmask Operations - O(1) constraint checking
+/// - 🧠 Most Constrained Variable (MCV) heuristic - tackle hardest cells first,
+///   optionally breaking ties with the degree heuristic
+/// - 🚀 Initial Preprocessing - solve obvious cells before backtracking
+/// - 🛡️ Advanced Validity Checking - early impossible state detection
+///
+/// # Parameters
+/// - `size`: Size of the Latin square (N×N). Default is 5.
+/// - `known_values`: HashMap mapping cells (as [`CluePlacement`]s) to known correct values.
+///   Example: {(0, 1): 3, (2, 0): 1} means cell (0,1) must be 3 and cell (2,0) must be 1.
+/// - `known_wrong_values`: HashMap mapping cells to vectors of values
+///   that are known to be wrong for that cell.
+///   Example: {(0, 0): vec![1, 2]} means cell (0,0) cannot be 1 or 2.
+/// - `max_solutions`: Maximum number of solutions to find. If None, finds all solutions.
+///   If set, stops when this many solutions are found.
+///
+/// # Returns
+/// A vector of completed N×N Latin squares with values 1 to N, along with the
+/// solver statistics collected during the search.
+/// Returns empty vector if no valid completion exists.
+///
+/// # Algorithm
+/// 1. Initialize square with known values and bitmasks
+/// 2. 🚀 Initial preprocessing: apply constraint propagation to solve obvious cells
+/// 3. Use MCV heuristic to select the most constrained empty cell
+/// 4. Try each candidate value with full constraint propagation
+/// 5. Recursively solve remaining cells with advanced pruning
+/// 6. When complete solution found, save it and continue searching
+/// 7. Stop when max_solutions is reached or all possibilities exhausted
+pub fn complete_latin_square_backtrack_all_solutions(
+    size: usize,
+    known_values: &HashMap<CluePlacement, usize>,
+    known_wrong_values: &HashMap<CluePlacement, Vec<usize>>,
+    max_solutions: Option<usize>,
+) -> (Vec<Grid>, SolveStats) {
+    complete_latin_square_with(
+        size,
+        known_values,
+        known_wrong_values,
+        max_solutions,
+        &SolverOptions::default(),
+        SolveHooks::default(),
+    )
+}
+
+/// Optional observers and shared state of a running solve.
+#[derive(Clone, Copy, Default)]
+pub struct SolveHooks<'a> {
+    /// Told of every step of the search, see [`SearchObserver`]. Solves
+    /// without one run a copy of the solver where the calls compile away.
+    pub observer: Option<&'a dyn SearchObserver>,
+    /// Number of solutions found by all the solvers working on the same puzzle.
+    /// When set, solutions are counted there and the search stops once the
+    /// shared count reaches `max_solutions`.
+    pub shared_solutions: Option<&'a AtomicUsize>,
+    /// Aborts the search when cancelled; the search tree is unwound at the
+    /// next node and the solutions found so far are returned.
+    pub cancel: Option<&'a CancellationToken>,
+    /// Abandons the search once this instant has passed, setting
+    /// [`SolveStats::timed_out`].
+    pub deadline: Option<Instant>,
+}
+
+/// Handle used to abort a running solve from another thread.
+///
+/// Clones share the same flag. After cancelling, the solver's results are
+/// incomplete: check [`CancellationToken::is_cancelled`] before trusting them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every solve using this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Same as [`complete_latin_square_backtrack_all_solutions`], searching with
+/// the given `options` and reporting to `hooks`.
+///
+/// With the default [`TechniqueSet::SINGLES`], propagation only starts once
+/// the grid is more than half filled; any elimination technique makes the
+/// solver propagate at every node.
+pub fn complete_latin_square_with(
+    size: usize,
+    known_values: &HashMap<CluePlacement, usize>,
+    known_wrong_values: &HashMap<CluePlacement, Vec<usize>>,
+    max_solutions: Option<usize>,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+) -> (Vec<Grid>, SolveStats) {
+    let constraints = Constraints::from_maps(size, known_values, known_wrong_values);
+    solve_constraints(&constraints, max_solutions, options, hooks)
+}
+
+/// Same as [`complete_latin_square_with`], for clues already in the solver's representation.
+///
+/// With [`SolverOptions::portfolio`], the statistics are those of the engine
+/// that finished first. Observers cannot be shared between threads, so solves
+/// with one do not race.
+pub fn solve_constraints(
+    constraints: &Constraints,
+    max_solutions: Option<usize>,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+) -> (Vec<Grid>, SolveStats) {
+    let options = SolverOptions { max_solutions: options.solution_limit(max_solutions), ..*options };
+    if options.portfolio && hooks.observer.is_none() {
+        return solve_portfolio(constraints, &options, hooks);
+    }
+    if let Some(unit) = options.restarts {
+        return solve_with_restarts(constraints, &options, hooks, unit);
+    }
+    let mut solutions = Vec::new();
+    let stats = for_each_solution(constraints, &options, hooks, |solution| {
+        solutions.push(solution.clone());
+        ControlFlow::Continue(())
+    });
+    (solutions, stats)
+}
+
+/// Term `i` (counted from 1) of the Luby sequence: 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...
+fn luby(i: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) - 1 < i {
+        k += 1;
+    }
+    if i == (1 << k) - 1 {
+        1 << (k - 1)
+    } else {
+        luby(i - (1 << (k - 1)) + 1)
+    }
+}
+
+/// [`solve_constraints`] restarting after node budgets of `unit` times the
+/// Luby sequence, see [`SolverOptions::restarts`].
+///
+/// A run that completes has found every solution, up to the limit, and ends
+/// the search, as does reaching the limit with the distinct solutions of all
+/// the runs. The statistics add up those of the runs.
+fn solve_with_restarts(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    mut hooks: SolveHooks,
+    unit: usize,
+) -> (Vec<Grid>, SolveStats) {
+    if let Some(timeout) = options.timeout {
+        let deadline = Instant::now() + timeout;
+        hooks.deadline = Some(hooks.deadline.map_or(deadline, |other| other.min(deadline)));
+    }
+    let mut solutions: Vec<Grid> = Vec::new();
+    let mut stats = SolveStats::default();
+    for run in 1.. {
+        let mut budget = luby(run).saturating_mul(unit.max(1));
+        if let Some(node_budget) = options.node_budget {
+            if stats.backtrack_nodes >= node_budget {
+                stats.timed_out = true;
+                break;
+            }
+            budget = budget.min(node_budget - stats.backtrack_nodes);
+        }
+        let run_options = SolverOptions {
+            value_order: ValueOrder::Random,
+            seed: options.seed.wrapping_add(run as u64),
+            node_budget: Some(budget),
+            timeout: None,
+            restarts: None,
+            ..*options
+        };
+        let (run_solutions, run_stats) = solve_constraints(constraints, None, &run_options, hooks);
+        stats.backtrack_nodes += run_stats.backtrack_nodes;
+        stats.guesses += run_stats.guesses;
+        stats.max_depth = stats.max_depth.max(run_stats.max_depth);
+        stats.forced_cells += run_stats.forced_cells;
+        stats.propagation_passes += run_stats.propagation_passes;
+        for solution in run_solutions {
+            if !solutions.contains(&solution) {
+                solutions.push(solution);
+            }
+        }
+        let limit_reached = options.max_solutions.is_some_and(|max| solutions.len() >= max);
+        if !run_stats.timed_out || limit_reached || hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+            break;
+        }
+        if hooks.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            stats.timed_out = true;
+            break;
+        }
+    }
+    if let Some(max) = options.max_solutions {
+        solutions.truncate(max);
+    }
+    (solutions, stats)
+}
+
+/// Node budget unit of the restarting engine of [`SolverOptions::portfolio`]
+/// when [`SolverOptions::restarts`] is not set.
+const PORTFOLIO_RESTART_UNIT: usize = 1000;
+
+/// [`solve_constraints`] racing several engines, see [`SolverOptions::portfolio`].
+///
+/// The first engine to complete, or to reach the solution limit, gives the
+/// result. If each one gives up, on its node budget or the deadline, the one
+/// with the most solutions does, and the result is timed out.
+fn solve_portfolio(constraints: &Constraints, options: &SolverOptions, hooks: SolveHooks) -> (Vec<Grid>, SolveStats) {
+    let base = SolverOptions { portfolio: false, learning: false, dlx: false, restarts: None, ..*options };
+    let engines = [
+        base,
+        SolverOptions { restarts: Some(options.restarts.unwrap_or(PORTFOLIO_RESTART_UNIT)), ..base },
+        SolverOptions { dlx: true, ..base },
+        SolverOptions { learning: true, ..base },
+    ];
+    let race = &CancellationToken::new();
+    let deadline = hooks.deadline;
+    let (sender, receiver) = mpsc::channel();
+    let (solutions, stats) = std::thread::scope(|scope| {
+        for engine in &engines {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                let hooks = SolveHooks { cancel: Some(race), deadline, ..SolveHooks::default() };
+                sender.send(solve_constraints(constraints, None, engine, hooks))
+            });
+        }
+        drop(sender);
+        let mut best: Option<(Vec<Grid>, SolveStats)> = None;
+        loop {
+            match receiver.recv_timeout(Duration::from_millis(10)) {
+                Ok((solutions, stats)) => {
+                    if !stats.timed_out && !race.is_cancelled() {
+                        race.cancel();
+                        return (solutions, stats);
+                    }
+                    if best.as_ref().is_none_or(|(best, _)| solutions.len() > best.len()) {
+                        best = Some((solutions, SolveStats { timed_out: true, ..stats }));
+                    }
+                }
+                // Pass a cancellation of the whole solve on to the engines
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+                        race.cancel();
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return best.expect("every engine sends its result");
+                }
+            }
+        }
+    });
+    if let Some(shared) = hooks.shared_solutions {
+        shared.fetch_add(solutions.len(), Ordering::Relaxed);
+    }
+    (solutions, stats)
+}
+
+/// Solutions reported by a running solve, and whether the caller asked to stop.
+struct SolutionSink<'s> {
+    on_solution: &'s mut dyn FnMut(&Grid) -> ControlFlow<()>,
+    /// Grid the solutions are copied into to be passed on.
+    grid: Grid,
+    found: usize,
+    stopped: bool,
+}
+
+/// Solve `constraints`, passing each solution to `on_solution` as soon as it
+/// is found instead of collecting them, and return the solver statistics.
+///
+/// The search stops when `on_solution` returns [`ControlFlow::Break`], which
+/// allows stopping rules of any kind, or at [`SolverOptions::max_solutions`].
+/// The grid passed is only borrowed: counting solutions allocates nothing.
+pub fn for_each_solution(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+    mut on_solution: impl FnMut(&Grid) -> ControlFlow<()>,
+) -> SolveStats {
+    match hooks.observer {
+        Some(observer) => search(constraints, options, hooks, observer, &mut on_solution),
+        None => search(constraints, options, hooks, &NoObserver, &mut on_solution),
+    }
+}
+
+/// Observer of the solves without one, whose calls do nothing.
+struct NoObserver;
+
+impl SearchObserver for NoObserver {}
+
+/// The solver behind [`for_each_solution`], reporting its steps to `observer`.
+fn search<O: SearchObserver + ?Sized>(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    mut hooks: SolveHooks,
+    observer: &O,
+    on_solution: &mut dyn FnMut(&Grid) -> ControlFlow<()>,
+) -> SolveStats {
+    if let Some(timeout) = options.timeout {
+        let deadline = Instant::now() + timeout;
+        hooks.deadline = Some(hooks.deadline.map_or(deadline, |other| other.min(deadline)));
+    }
+    if options.learning {
+        return learning::search(constraints, options, hooks, observer, on_solution);
+    }
+    if options.dlx {
+        return dlx::search(constraints, options, hooks, observer, on_solution);
+    }
+    let backtrack_search = match (options.kernel, constraints.size) {
+        (Kernel::Specialized, 5) => backtrack_search::<Fixed<5>, O>,
+        (Kernel::Specialized, 6) => backtrack_search::<Fixed<6>, O>,
+        (Kernel::Specialized, 7) => backtrack_search::<Fixed<7>, O>,
+        (Kernel::Specialized, 8) => backtrack_search::<Fixed<8>, O>,
+        (Kernel::Specialized, 9) => backtrack_search::<Fixed<9>, O>,
+        _ => backtrack_search::<Heap, O>,
+    };
+    backtrack_search(constraints, options, hooks, observer, on_solution)
+}
+
+/// The backtracker of [`search`], keeping its state in the buffers of `S`.
+fn backtrack_search<S: Storage, O: SearchObserver + ?Sized>(
+    constraints: &Constraints,
+    options: &SolverOptions,
+    hooks: SolveHooks,
+    observer: &O,
+    on_solution: &mut dyn FnMut(&Grid) -> ControlFlow<()>,
+) -> SolveStats {
+    let max_solutions = options.max_solutions;
+    let node_budget = options.node_budget;
+    let size = constraints.size;
+    let wrong = &constraints.wrong;
+    let techniques = options.techniques;
+    let threshold = match options.propagation_threshold {
+        PropagationThreshold::Percent(percent) => percent.min(100),
+        PropagationThreshold::Auto => auto_propagation_threshold(constraints),
+    };
+    let propagate_below = size * size * usize::from(threshold) / 100;
+    let mut solutions = SolutionSink { on_solution, grid: constraints.known.clone(), found: 0, stopped: false };
+    let mut stats = SolveStats::default();
+    let propagation_passes = Cell::new(0);
+    let propagated_cells = Cell::new(0);
+    // Copy the counters maintained by the propagation closure into the stats
+    let collect_propagation_counters = |stats: &mut SolveStats| {
+        stats.propagation_passes = propagation_passes.get();
+        stats.forced_cells += propagated_cells.get();
+    };
+
+    // The square with 0 for unknown cells, the values used in its lines and the candidates of each cell
+    let mut domains = Domains::<S>::new(&constraints.known, wrong, constraints.variant);
+    // Candidates left by the elimination techniques, reused by every propagation pass
+    let eliminated = RefCell::new(domains.candidates.clone());
+
+    // 🔥 CONSTRAINT PROPAGATION CASCADE - automatically fills forced moves
+    // Returns true if progress was made, false if contradiction found
+    let apply_constraint_propagation = |domains: &mut Domains<S>| -> Result<bool, ()> {
+        let mut progress = true;
+        let mut total_progress = false;
+        let naked_singles = techniques.contains(TechniqueSet::NAKED_SINGLES);
+        let hidden_singles = techniques.contains(TechniqueSet::HIDDEN_SINGLES);
+        
+        while progress {
+            progress = false;
+            propagation_passes.set(propagation_passes.get() + 1);
+            
+            // 🎯 NAKED SINGLES DETECTION - cells with only one possible value
+            for i in 0..size {
+                for j in 0..size {
+                    if naked_singles && domains.value(i, j) == 0 {
+                        // Candidates already exclude the row, column and known wrong values
+                        let final_mask = domains.of(i, j);
+                        
+                        if final_mask == 0 {
+                            return Err(()); // Contradiction found
+                        }
+                        
+                        // Check if exactly one bit is set (naked single)
+                        if final_mask & (final_mask - 1) == 0 {
+                            let value = final_mask.trailing_zeros() as usize + 1;
+                            let bit = 1u32 << (value - 1);
+                            
+                            domains.assign(i, j, bit);
+                            propagated_cells.set(propagated_cells.get() + 1);
+                            observer.propagate((i, j), value, Reason::NakedSingle);
+                            progress = true;
+                            total_progress = true;
+                        }
+                    }
+                }
+            }
+            
+            // 🔍 HIDDEN SINGLES DETECTION - values with only one possible position
+            // Check rows for hidden singles, finding the values with one place for the whole row at once
+            for i in (0..size).filter(|_| hidden_singles) {
+                let row_places = |domains: &Domains<S>| once_and_twice((0..size).map(|j| domains.of(i, j)));
+                let (mut once, mut twice) = row_places(domains);
+                for val in 1..=size {
+                    let bit = 1u32 << (val - 1);
+                    if (domains.used[i] & bit) == 0 { // Value not yet in this row
+                        if once & bit == 0 {
+                            return Err(()); // Contradiction: value can't be placed anywhere
+                        } else if twice & bit == 0 {
+                            // Hidden single found
+                            let j = (0..size).find(|&j| domains.of(i, j) & bit != 0).expect("a place of the value");
+                            domains.assign(i, j, bit);
+                            (once, twice) = row_places(domains);
+                            propagated_cells.set(propagated_cells.get() + 1);
+                            observer.propagate((i, j), val, Reason::HiddenSingleRow);
+                            progress = true;
+                            total_progress = true;
+                        }
+                    }
+                }
+            }
+            
+            // Check columns for hidden singles
+            for j in (0..size).filter(|_| hidden_singles) {
+                let column_places = |domains: &Domains<S>| once_and_twice((0..size).map(|i| domains.of(i, j)));
+                let (mut once, mut twice) = column_places(domains);
+                for val in 1..=size {
+                    let bit = 1u32 << (val - 1);
+                    if (domains.used[size + j] & bit) == 0 { // Value not yet in this column
+                        if once & bit == 0 {
+                            return Err(()); // Contradiction: value can't be placed anywhere
+                        } else if twice & bit == 0 {
+                            // Hidden single found
+                            let i = (0..size).find(|&i| domains.of(i, j) & bit != 0).expect("a place of the value");
+                            domains.assign(i, j, bit);
+                            (once, twice) = column_places(domains);
+                            propagated_cells.set(propagated_cells.get() + 1);
+                            observer.propagate((i, j), val, Reason::HiddenSingleColumn);
+                            progress = true;
+                            total_progress = true;
+                        }
+                    }
+                }
+            }
+
+            // Check boxes for hidden singles (Sudoku puzzles only)
+            let boxes = if constraints.variant == Variant::Sudoku { size } else { 0 };
+            let shape = domains.boxes;
+            for box_index in (0..boxes).filter(|_| hidden_singles) {
+                let cells = || box_cells(size, shape, box_index);
+                let box_places = |domains: &Domains<S>| once_and_twice(cells().map(|(i, j)| domains.of(i, j)));
+                let (mut once, mut twice) = box_places(domains);
+                for val in 1..=size {
+                    let bit = 1u32 << (val - 1);
+                    if (domains.used[2 * size + box_index] & bit) == 0 {
+                        if once & bit == 0 {
+                            return Err(()); // Contradiction: value can't be placed anywhere
+                        } else if twice & bit == 0 {
+                            let (i, j) =
+                                cells().find(|&(i, j)| domains.of(i, j) & bit != 0).expect("a place of the value");
+                            domains.assign(i, j, bit);
+                            (once, twice) = box_places(domains);
+                            propagated_cells.set(propagated_cells.get() + 1);
+                            observer.propagate((i, j), val, Reason::HiddenSingleBox);
+                            progress = true;
+                            total_progress = true;
+                        }
+                    }
+                }
+            }
+
+            // 🧩 SUBSETS AND X-WINGS - only once singles are exhausted
+            if !progress && techniques.intersects(TechniqueSet::ELIMINATIONS) {
+                let mut candidates = eliminated.borrow_mut();
+                candidates.clone_from(&domains.candidates);
+                if techniques::eliminate(&mut candidates, size, techniques) {
+                    // Fill the cells left with a single candidate
+                    for i in 0..size {
+                        for j in 0..size {
+                            let mask = candidates[i * size + j];
+                            if domains.value(i, j) != 0 || mask & (mask.wrapping_sub(1)) != 0 {
+                                continue;
+                            }
+                            if mask & !(domains.used[i] | domains.used[size + j]) == 0 {
+                                return Err(()); // Contradiction: no candidate left
+                            }
+                            let value = mask.trailing_zeros() as usize + 1;
+                            domains.assign(i, j, mask);
+                            propagated_cells.set(propagated_cells.get() + 1);
+                            observer.propagate((i, j), value, Reason::Elimination);
+                            progress = true;
+                            total_progress = true;
+                        }
+                    }
+                }
+            }
+        }
+        
+        Ok(total_progress)
+    };
+
+    // Helper function to get available values for cell (i, j)
+    let get_available_values = |domains: &Domains<S>,
+                               i: usize, 
+                               j: usize,
+                               temp_candidates: &mut [usize; MAX_SIZE]| -> usize {
+        // Cached candidates: 0 for filled cells and cells without any candidate left
+        let avail_mask = domains.of(i, j);
+
+        if avail_mask == 0 {
+            return 0; // No candidates available
+        }
+
+        // Build list of available values using bit manipulation
+        let mut m = avail_mask;
+        let mut count = 0;
+        while m != 0 {
+            let bit = m & m.wrapping_neg(); // Get lowest set bit
+            m ^= bit; // Clear the bit
+            let v = bit.trailing_zeros() as usize + 1; // Convert back to 1-based
+            temp_candidates[count] = v;
+            count += 1;
+        }
+
+        count
+    };
+
+    // Helper function to find most constrained cell
+    let find_most_constrained_cell = |domains: &Domains<S>| -> (Option<(usize, usize)>, usize) {
+        let mut best_cell = None;
+        let mut min_choices = size + 1;
+        let mut best_degree = 0;
+        // Number of empty cells sharing a row or column with (i, j)
+        let degree = |i: usize, j: usize| {
+            (0..size).filter(|&k| k != j && domains.value(i, k) == 0).count()
+                + (0..size).filter(|&k| k != i && domains.value(k, j) == 0).count()
+        };
+
+        // Count the candidates of four cells at a time, in the lanes of a u64
+        for start in (0..size * size).step_by(4) {
+            let end = (start + 4).min(size * size);
+            let counts = popcounts(&domains.candidates[start..end]);
+            for cell in start..end {
+                let (i, j) = (cell / size, cell % size);
+                if domains.value(i, j) == 0 { // Empty cell
+                    let choices = (counts >> (16 * (cell - start)) & 0xFF) as usize;
+                    if choices == 0 {
+                        return (Some((i, j)), 0); // Dead end - return immediately
+                    }
+                    if choices < min_choices {
+                        min_choices = choices;
+                        best_cell = Some((i, j));
+                        if choices == 1 {
+                            return (best_cell, 1); // Can't get better than 1 choice
+                        }
+                        if options.degree_tie_break {
+                            best_degree = degree(i, j);
+                        }
+                    } else if choices == min_choices && options.degree_tie_break {
+                        // 🕸️ DEGREE HEURISTIC - prefer the cell constraining the most empty peers
+                        let cell_degree = degree(i, j);
+                        if cell_degree > best_degree {
+                            best_degree = cell_degree;
+                            best_cell = Some((i, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        (best_cell, min_choices)
+    };
+
+
+
+    // Order the candidates of cell (i, j) according to the value ordering heuristic
+    let order_values = |domains: &Domains<S>,
+                        i: usize,
+                        j: usize,
+                        candidates: &mut [usize],
+                        node: usize| {
+        match options.value_order {
+            ValueOrder::Ascending => {}
+            ValueOrder::LeastConstraining => {
+                // A stable sort of at most 16 values, allocating nothing
+                candidates.sort_by_key(|&value| {
+                    // Number of empty peers that would lose this value as a candidate
+                    let bit = 1u32 << (value - 1);
+                    (0..size).filter(|&k| k != j).map(|k| (i, k))
+                        .chain((0..size).filter(|&k| k != i).map(|k| (k, j)))
+                        .filter(|&(r, c)| domains.of(r, c) & bit != 0)
+                        .count()
+                });
+            }
+            ValueOrder::Random => shuffle_values(candidates, options.seed, node),
+        }
+    };
+
+    // 🚀 ENHANCED BACKTRACKING with optimized constraint propagation
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn backtrack<S: Storage, O: SearchObserver + ?Sized>(
+        domains: &mut Domains<S>,
+        saved: &mut Vec<Domains<S>>,
+        solutions: &mut SolutionSink,
+        stats: &mut SolveStats,
+        depth: usize,
+        max_solutions: Option<usize>,
+        node_budget: Option<usize>,
+        get_available_values: &dyn Fn(&Domains<S>, usize, usize, &mut [usize; MAX_SIZE]) -> usize,
+        find_most_constrained_cell: &dyn Fn(&Domains<S>) -> (Option<(usize, usize)>, usize),
+        order_values: &dyn Fn(&Domains<S>, usize, usize, &mut [usize], usize),
+        apply_constraint_propagation: &dyn Fn(&mut Domains<S>) -> Result<bool, ()>,
+        techniques: TechniqueSet,
+        propagate_below: usize,
+        hooks: SolveHooks,
+        observer: &O,
+    ) {
+        stats.backtrack_nodes += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        // Solutions found so far, by this search or all those sharing the count
+        let found = |solutions: &SolutionSink| {
+            hooks.shared_solutions.map_or(solutions.found, |shared| shared.load(Ordering::Relaxed))
+        };
+
+        // Check if we've found enough solutions or were asked to stop
+        if let Some(max) = max_solutions {
+            if found(solutions) >= max {
+                return;
+            }
+        }
+        if stats.timed_out || solutions.stopped || hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+            return;
+        }
+        // Reading the clock is comparatively slow, so only check the deadline now and then
+        if let Some(deadline) = hooks.deadline {
+            if stats.backtrack_nodes.is_multiple_of(64) && Instant::now() >= deadline {
+                stats.timed_out = true;
+                return;
+            }
+        }
+        if node_budget.is_some_and(|budget| stats.backtrack_nodes > budget) {
+            stats.timed_out = true;
+            return;
+        }
+
+        // Find the most constrained empty cell
+        let (cell, num_choices) = find_most_constrained_cell(domains);
+
+        if let Some((i, j)) = cell {
+            if num_choices == 0 {
+                observer.contradiction(Reason::NoCandidates);
+                return; // Dead end
+            }
+
+            let mut candidates = [0; MAX_SIZE];
+            let choices = get_available_values(domains, i, j, &mut candidates);
+            let candidates = &mut candidates[..choices];
+            order_values(domains, i, j, candidates, stats.backtrack_nodes);
+            if choices > 1 {
+                stats.guesses += 1;
+            } else {
+                stats.forced_cells += 1;
+            }
+
+            // Save the state of the node before trying its values, in the buffers of the nodes
+            // previously searched at this depth
+            if saved.len() <= depth {
+                saved.push(domains.clone());
+            } else {
+                saved[depth].clone_from(domains);
+            }
+
+            // Try each candidate value with proper state management
+            for &value in candidates.iter() {
+                // Early termination check
+                if let Some(max) = max_solutions {
+                    if found(solutions) >= max {
+                        return;
+                    }
+                }
+                if stats.timed_out || solutions.stopped || hooks.cancel.is_some_and(CancellationToken::is_cancelled) {
+                    return;
+                }
+
+                let bit = 1u32 << (value - 1);
+
+                // Place the value
+                domains.assign(i, j, bit);
+                let reason = if choices > 1 { Reason::Guess } else { Reason::Forced };
+                observer.enter_node((i, j), value, depth + 1, reason);
+
+                // Apply constraint propagation after placing value
+                let mut should_continue = true;
+                let empty_cells = domains.empty;
+                // Singles only pay off once enough of the puzzle is filled
+                if (empty_cells < propagate_below || techniques.intersects(TechniqueSet::ELIMINATIONS))
+                    && apply_constraint_propagation(domains).is_err()
+                {
+                    should_continue = false; // Contradiction found
+                }
+
+                // 🛡️ Enhanced validity check before deeper recursion
+                if should_continue && domains.is_consistent() {
+                    backtrack(
+                        domains,
+                        saved,
+                        solutions,
+                        stats,
+                        depth + 1,
+                        max_solutions,
+                        node_budget,
+                        get_available_values,
+                        find_most_constrained_cell,
+                        order_values,
+                        apply_constraint_propagation,
+                        techniques,
+                        propagate_below,
+                        hooks,
+                        observer,
+                    );
+                } else {
+                    observer.contradiction(Reason::Contradiction);
+                }
+                observer.exit_node(depth + 1);
+
+                // Restore complete state
+                domains.clone_from(&saved[depth]);
+            }
+        } else {
+            // All cells filled successfully - save this solution
+            domains.fill(&mut solutions.grid);
+            observer.solution(&solutions.grid);
+            if let Some(shared) = hooks.shared_solutions {
+                shared.fetch_add(1, Ordering::Relaxed);
+            }
+            solutions.found += 1;
+            if (solutions.on_solution)(&solutions.grid).is_break() {
+                solutions.stopped = true;
+            }
+        }
+    }
+
+    // Known values that break the rules of Latin squares have no completion
+    if let Err(violation) = validate_partial_latin_square(&constraints.known) {
+        tracing::trace!(%violation, "clues are not a partial Latin square");
+        return stats;
+    }
+    if breaks_variant(&constraints.known, constraints.variant) {
+        tracing::trace!(variant = ?constraints.variant, "clues break the rules of the variant");
+        return stats;
+    }
+
+    // 🚀 INITIAL PREPROCESSING - solve obvious cells only if puzzle is sufficiently constrained
+    let initial_filled = size * size - domains.empty;
+    // Only preprocess if we have enough initial constraints (or stronger techniques)
+    if (initial_filled > size || techniques.intersects(TechniqueSet::ELIMINATIONS))
+        && apply_constraint_propagation(&mut domains).is_err()
+    {
+        // Contradiction in initial state
+        observer.contradiction(Reason::Contradiction);
+        collect_propagation_counters(&mut stats);
+        return stats;
+    }
+
+    // Final validity check after preprocessing
+    if !domains.is_consistent() {
+        observer.contradiction(Reason::Contradiction);
+        collect_propagation_counters(&mut stats);
+        return stats;
+    }
+
+    // Try to find all completions with enhanced backtracking
+    backtrack(
+        &mut domains,
+        &mut Vec::new(),
+        &mut solutions,
+        &mut stats,
+        0,
+        max_solutions,
+        node_budget,
+        &get_available_values,
+        &find_most_constrained_cell,
+        &order_values,
+        &apply_constraint_propagation,
+        techniques,
+        propagate_below,
+        hooks,
+        observer,
+    );
+
+    collect_propagation_counters(&mut stats);
+    stats
+}
+
+/// Same as [`solve_constraints`], with the branches of the first
+/// search node explored in parallel.
+///
+/// After filling the cells that have a single candidate, the most constrained
+/// empty cell is fixed to each of its candidates in turn
+/// and each resulting puzzle is solved on a separate rayon task. The tasks
+/// share their solution count, so all of them stop once `max_solutions`
+/// solutions have been found overall. Meant for proving the uniqueness of
+/// single hard instances; return order of the solutions is not deterministic.
+/// All tasks stop early if `cancel` is cancelled.
+pub fn complete_latin_square_parallel(
+    constraints: &Constraints,
+    max_solutions: Option<usize>,
+    options: &SolverOptions,
+    cancel: Option<&CancellationToken>,
+) -> (Vec<Grid>, SolveStats) {
+    let max_solutions = options.solution_limit(max_solutions);
+    let size = constraints.size;
+    let candidates_of = |known: &Grid, i: usize, j: usize| -> Vec<usize> {
+        (1..=size)
+            .filter(|&value| {
+                (0..size).all(|k| usize::from(known[(i, k)]) != value && usize::from(known[(k, j)]) != value)
+                    && constraints.wrong[i * size + j] & (1 << (value - 1)) == 0
+            })
+            .collect()
+    };
+
+    // Fill forced cells until reaching a cell with several candidates to split on
+    let mut constraints = constraints.clone();
+    let mut forced_cells = 0;
+    let ((i, j), candidates) = loop {
+        let root = (0..size)
+            .flat_map(|i| (0..size).map(move |j| (i, j)))
+            .filter(|&(i, j)| constraints.known[(i, j)] == 0)
+            .map(|(i, j)| ((i, j), candidates_of(&constraints.known, i, j)))
+            .min_by_key(|(_, candidates)| candidates.len());
+        match root {
+            Some((cell, candidates)) if candidates.len() == 1 => {
+                constraints.set_known(cell, candidates[0]);
+                forced_cells += 1;
+            }
+            Some(root) if root.1.len() > 1 => break root,
+            _ => {
+                // Nothing to split: the grid is full or contradictory
+                let hooks = SolveHooks { cancel, ..SolveHooks::default() };
+                let (solutions, mut stats) = solve_constraints(&constraints, max_solutions, options, hooks);
+                stats.forced_cells += forced_cells;
+                return (solutions, stats);
+            }
+        }
+    };
+
+    let shared_solutions = AtomicUsize::new(0);
+    let branches: Vec<(Vec<Grid>, SolveStats)> = candidates
+        .par_iter()
+        .map(|&value| {
+            let mut branch = constraints.clone();
+            branch.set_known((i, j), value);
+            let hooks = SolveHooks { shared_solutions: Some(&shared_solutions), cancel, ..SolveHooks::default() };
+            solve_constraints(&branch, max_solutions, options, hooks)
+        })
+        .collect();
+
+    // The root node and its guess belong to the combined search tree
+    let mut stats = SolveStats { backtrack_nodes: 1, guesses: 1, forced_cells, ..SolveStats::default() };
+    let mut solutions = Vec::new();
+    for (branch_solutions, branch_stats) in branches {
+        solutions.extend(branch_solutions);
+        stats.backtrack_nodes += branch_stats.backtrack_nodes;
+        stats.guesses += branch_stats.guesses;
+        stats.max_depth = stats.max_depth.max(branch_stats.max_depth + 1);
+        stats.forced_cells += branch_stats.forced_cells;
+        stats.propagation_passes += branch_stats.propagation_passes;
+        stats.timed_out |= branch_stats.timed_out;
+    }
+    if let Some(max) = max_solutions {
+        solutions.truncate(max);
+    }
+    (solutions, stats)
+}
+
+/// Generate the basic cyclic Latin square of order N.
+///
+/// A cyclic Latin square is constructed using the formula: L[i][j] = (i + j) mod N + 1
+/// This is guaranteed to be a valid Latin square for any positive integer N.
+///
+/// # Parameters
+/// - `n`: Order of the Latin square (number of rows/columns).
+///
+/// # Returns
+/// An N×N cyclic Latin square with values 1..N.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::cyclic_latin_square;
+/// let square = cyclic_latin_square(3);
+/// // Rows are [1, 2, 3], [2, 3, 1] and [3, 1, 2]
+/// assert_eq!(square.rows().next(), Some(&[1, 2, 3][..]));
+/// ```
+///
+/// Note: This is often used as a starting point for generating more random
+/// Latin squares through transformations.
+pub fn cyclic_latin_square(n: usize) -> Grid {
+    let mut square = Grid::new(n);
+    for i in 0..n {
+        for j in 0..n {
+            square[(i, j)] = ((i + j) % n + 1) as u8;
+        }
+    }
+    square
+}
+
+/// Generate a row-complete Latin square of order N, in which every ordered
+/// pair of distinct values is side by side in exactly one row (and, for this
+/// construction, in exactly one column too).
+///
+/// Williams' construction orders the residues as 0, 1, N-1, 2, N-2, ..., so
+/// that the differences of successive terms are all distinct, and sets
+/// L[i][j] = (a_i + a_j) mod N + 1. There is no such ordering for odd N > 1,
+/// and no row-complete square at all of order 3, 5 or 7: the function returns
+/// `None` for odd orders above 1.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::row_complete_latin_square;
+/// let square = row_complete_latin_square(4).unwrap();
+/// // Rows are [1, 2, 4, 3], [2, 3, 1, 4], [4, 1, 3, 2] and [3, 4, 2, 1]
+/// assert_eq!(square.rows().next(), Some(&[1, 2, 4, 3][..]));
+/// assert!(row_complete_latin_square(5).is_none());
+/// ```
+pub fn row_complete_latin_square(n: usize) -> Option<Grid> {
+    if n > 1 && n % 2 == 1 {
+        return None;
+    }
+    let terms: Vec<usize> = (0..n).map(|k| if k % 2 == 1 { k.div_ceil(2) } else { (n - k / 2) % n }).collect();
+    let mut square = Grid::new(n);
+    for i in 0..n {
+        for j in 0..n {
+            square[(i, j)] = ((terms[i] + terms[j]) % n + 1) as u8;
+        }
+    }
+    Some(square)
+}
+
+/// Generate a symmetric Latin square of order N, with L[i][j] = L[j][i].
+///
+/// The diagonal of a symmetric square of odd order holds every value once,
+/// and this is the idempotent square of [`idempotent_latin_square`]. For even
+/// orders the square is the schedule of a round-robin tournament of N teams
+/// (team i plays team j in round L[i][j]), with the value N all along its
+/// diagonal: teams 0 to N-2 meet in round (i + j) mod (N-1) + 1, and the last
+/// team meets team i in round 2i mod (N-1) + 1.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::symmetric_latin_square;
+/// let square = symmetric_latin_square(4);
+/// // Rows are [4, 2, 3, 1], [2, 4, 1, 3], [3, 1, 4, 2] and [1, 3, 2, 4]
+/// assert_eq!(square.rows().next(), Some(&[4, 2, 3, 1][..]));
+/// ```
+pub fn symmetric_latin_square(n: usize) -> Grid {
+    if n % 2 == 1 {
+        return idempotent_latin_square(n).expect("odd orders have an idempotent square");
+    }
+    let rounds = n - 1;
+    let mut square = Grid::new(n);
+    for i in 0..n {
+        for j in 0..n {
+            let round = if i == j {
+                rounds
+            } else if j == rounds {
+                2 * i % rounds
+            } else if i == rounds {
+                2 * j % rounds
+            } else {
+                (i + j) % rounds
+            };
+            square[(i, j)] = (round + 1) as u8;
+        }
+    }
+    square
+}
+
+/// Generate an idempotent Latin square of order N, with L[i][i] = i + 1.
+///
+/// For odd N, L[i][j] = (i + j)(N + 1)/2 mod N + 1, which is symmetric too.
+/// For even N, the square of order N-1 is prolonged: on its transversal
+/// j = i + 1 (mod N-1), each value moves to the last column of its row and
+/// the last row of its column, and the new value N takes its place and the
+/// last cell of the diagonal. There is no idempotent square of order 2: the
+/// function returns `None` for it.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::idempotent_latin_square;
+/// let square = idempotent_latin_square(3).unwrap();
+/// // Rows are [1, 3, 2], [3, 2, 1] and [2, 1, 3]
+/// assert_eq!(square.rows().next(), Some(&[1, 3, 2][..]));
+/// assert!(idempotent_latin_square(2).is_none());
+/// ```
+pub fn idempotent_latin_square(n: usize) -> Option<Grid> {
+    if n == 2 {
+        return None;
+    }
+    let odd = if n % 2 == 1 { n } else { n - 1 };
+    let half = odd.div_ceil(2);
+    let mut square = Grid::new(n);
+    for i in 0..odd {
+        for j in 0..odd {
+            square[(i, j)] = ((i + j) * half % odd + 1) as u8;
+        }
+    }
+    if odd < n {
+        for i in 0..odd {
+            let j = (i + 1) % odd;
+            square[(i, odd)] = square[(i, j)];
+            square[(odd, j)] = square[(i, j)];
+            square[(i, j)] = n as u8;
+        }
+        square[(odd, odd)] = n as u8;
+    }
+    Some(square)
+}
+
+/// Generate a pandiagonal Latin square of order N, in which every broken
+/// diagonal and anti-diagonal holds each value once, as rows and columns do.
+///
+/// L[i][j] = (2i + j) mod N + 1 moves along a broken diagonal by 3 and along
+/// an anti-diagonal by 1 at each row, so it is pandiagonal whenever N has no
+/// factor 2 or 3. No pandiagonal square has another order: the function
+/// returns `None` for them.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::pandiagonal_latin_square;
+/// let square = pandiagonal_latin_square(5).unwrap();
+/// // Rows are [1, 2, 3, 4, 5], [3, 4, 5, 1, 2], [5, 1, 2, 3, 4], ...
+/// assert_eq!(square.rows().nth(1), Some(&[3, 4, 5, 1, 2][..]));
+/// assert!(pandiagonal_latin_square(6).is_none());
+/// ```
+pub fn pandiagonal_latin_square(n: usize) -> Option<Grid> {
+    if n.is_multiple_of(2) || n.is_multiple_of(3) {
+        return None;
+    }
+    let mut square = Grid::new(n);
+    for i in 0..n {
+        for j in 0..n {
+            square[(i, j)] = ((2 * i + j) % n + 1) as u8;
+        }
+    }
+    Some(square)
+}
+
+/// Generate a Sudoku solution of order N, whose boxes (see [`box_shape`])
+/// each hold every value once.
+///
+/// With boxes of h rows and w columns, L[i][j] = ((i mod h)·w + i / h + j)
+/// mod N + 1: the rows of a band of boxes are shifted by w from one another,
+/// so they fill each box together, and the bands are shifted by 1.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::{sudoku_latin_square, Variant};
+/// let square = sudoku_latin_square(4);
+/// // Rows are [1, 2, 3, 4], [3, 4, 1, 2], [2, 3, 4, 1], [4, 1, 2, 3]
+/// assert_eq!(square.rows().nth(1), Some(&[3, 4, 1, 2][..]));
+/// assert!(Variant::Sudoku.allows(&sudoku_latin_square(16)));
+/// ```
+pub fn sudoku_latin_square(n: usize) -> Grid {
+    let (height, width) = box_shape(n);
+    let mut square = Grid::new(n);
+    for i in 0..n {
+        for j in 0..n {
+            square[(i, j)] = (((i % height) * width + i / height + j) % n + 1) as u8;
+        }
+    }
+    square
+}
+
+/// Generate a non-consecutive Latin square of order N, in which orthogonally
+/// adjacent cells never hold consecutive values.
+///
+/// For odd N, L[i][j] = 2(i + j) mod N + 1: neighbours differ by 2 or N - 2.
+/// Even orders have no such formula, and the square is the first one found
+/// by the solver. There is none of order 2, 3 or 4 (a 2 or a 3 of a 4x4
+/// square would need all its neighbours to hold the same value): the
+/// function returns `None` for them.
+///
+/// # Example
+/// ```
+/// # use puzzle_finder::non_consecutive_latin_square;
+/// let square = non_consecutive_latin_square(5).unwrap();
+/// // Rows are [1, 3, 5, 2, 4], [3, 5, 2, 4, 1], ...
+/// assert_eq!(square.rows().next(), Some(&[1, 3, 5, 2, 4][..]));
+/// assert!(non_consecutive_latin_square(4).is_none());
+/// ```
+pub fn non_consecutive_latin_square(n: usize) -> Option<Grid> {
+    if (2..=4).contains(&n) {
+        return None;
+    }
+    if n % 2 == 1 {
+        let mut square = Grid::new(n);
+        for i in 0..n {
+            for j in 0..n {
+                square[(i, j)] = (2 * (i + j) % n + 1) as u8;
+            }
+        }
+        return Some(square);
+    }
+    let constraints = Constraints { variant: Variant::NonConsecutive, ..Constraints::new(n) };
+    solve_constraints(&constraints, Some(1), &SolverOptions::default(), SolveHooks::default()).0.pop()
+}
+
+/// Standardize a tuple of tile coordinates to avoid counting equivalent puzzles multiple times.
+/// This function sorts the coordinates to create a canonical representation.
+pub fn standardize_tile_tuple(tiles: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut standardized = tiles.to_vec();
+    standardized.sort();
+    standardized
+}
+
+/// A single-solution puzzle discovered by the search.
+#[derive(Clone)]
+pub struct FoundPuzzle {
+    /// Tiles revealed as correctly placed.
+    pub tiles: Vec<(usize, usize)>,
+    /// The unique completion of the puzzle, unless the search ran with
+    /// [`SearchOptions::no_solutions`] (see [`FoundPuzzle::unique_solution`]).
+    pub solution: Option<Grid>,
+    /// Solver statistics collected while proving uniqueness.
+    pub stats: SolveStats,
+    /// Wall time spent proving uniqueness.
+    pub solve_time: Duration,
+    /// Whether the puzzle can be finished with naked and hidden singles alone.
+    pub singles_solvable: bool,
+    /// The steps by which singles finish the puzzle, proving its uniqueness
+    /// without a search, see [`certificate`].
+    pub certificate: Option<Certificate>,
+    /// Guesses simulated players need to solve the puzzle, when requested.
+    pub expected_guesses: Option<simulate::ExpectedGuesses>,
+}
+
+impl FoundPuzzle {
+    /// The unique completion of the puzzle on the N×N cyclic square: the one
+    /// kept by the search, or else solved again from the tiles on demand.
+    pub fn unique_solution(&self, size: usize, semantics: ClueSemantics) -> Grid {
+        self.unique_solution_on(&cyclic_latin_square(size), semantics)
+    }
+
+    /// The unique completion of the puzzle placing tiles of `grid`, as
+    /// [`FoundPuzzle::unique_solution`] for searches of another base grid.
+    pub fn unique_solution_on(&self, grid: &Grid, semantics: ClueSemantics) -> Grid {
+        self.solution.clone().unwrap_or_else(|| {
+            let size = grid.size();
+            let tile_coordinates = all_tiles(size);
+            let constraints = puzzle_constraints_with(&self.tiles, grid, &tile_coordinates, semantics);
+            let (mut solutions, _) = solve_constraints(&constraints, Some(1), &SolverOptions::default(), SolveHooks::default());
+            solutions.pop().expect("found puzzles have a solution")
+        })
+    }
+}
+
+/// Settings of a puzzle search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// If Some(count), randomly sample this many combinations instead of exhaustive search.
+    pub random_tries: Option<usize>,
+    /// Stop as soon as one single-solution puzzle has been found.
+    pub first_only: bool,
+    /// Search strategy of the solver.
+    pub solver: SolverOptions,
+    /// Discard puzzles that cannot be solved with singles alone.
+    pub require_singles_solvable: bool,
+    /// Abandon combinations whose uniqueness check takes longer than this.
+    pub per_puzzle_timeout: Option<Duration>,
+    /// In exhaustive mode, enumerate combinations in revolving-door order and
+    /// update the constraints of each combination from the previous one.
+    pub incremental: bool,
+    /// Skip the combinations containing one of these unique clue sets.
+    pub drop_supersets: Option<superset::SupersetFilter>,
+    /// In random mode, only draw combinations with at most this many tiles in
+    /// any row or column.
+    pub max_per_line: Option<usize>,
+    /// How candidate clue sets are built; constructors other than
+    /// [`Generator::BottomUp`] run once per random try.
+    pub generator: Generator,
+    /// Seed of the constructor runs; run `r` of seed `s` always gives the same clue set.
+    pub generator_seed: u64,
+    /// Settings of [`Generator::Anneal`].
+    pub anneal: AnnealOptions,
+    /// What the tiles that are not placed say about the solution.
+    pub semantics: ClueSemantics,
+    /// Square whose tiles the puzzles place; it must exist at the size searched.
+    pub base_grid: BaseGrid,
+    /// Rules of the solutions of the puzzles; unless [`Variant::Latin`], the
+    /// base grid must follow them and the generator be [`Generator::BottomUp`].
+    pub variant: Variant,
+    /// Play this many simulated games of each puzzle found to record its
+    /// [`FoundPuzzle::expected_guesses`].
+    pub expected_guess_games: Option<usize>,
+    /// Record the cells of the trades that make clue sets non-unique and skip
+    /// the clue sets leaving one of them empty; only valid with
+    /// [`ClueSemantics::GivensOnly`].
+    pub nogoods: bool,
+    /// Count the solutions of the combinations with two, up to
+    /// [`MAX_CLASSIFIED_SOLUTIONS`], and record how the closest two differ in
+    /// [`RunSummary::duplicate_structure`].
+    pub duplicate_structure: bool,
+    /// Skip the clue sets this index records as evaluated, by this run or
+    /// previous ones, and record the outcome of the others in it.
+    pub results_index: Option<Arc<index::ResultsIndex>>,
+    /// In random mode, continue the run saved in this checkpoint: same
+    /// sampling seed and constructor runs, skipping the combinations it tried.
+    pub resume: Option<Checkpoint>,
+    /// In random mode, save the state of the run to this file every few
+    /// batches, to continue it later with [`SearchOptions::resume`].
+    pub checkpoint: Option<std::path::PathBuf>,
+    /// In random mode, share of the combinations never drawn that the filter
+    /// of tried combinations takes for tried ones and skips (defaults to
+    /// [`bloom::DEFAULT_FALSE_POSITIVE_RATE`]).
+    pub tried_false_positive_rate: Option<f64>,
+    /// Keep the approximate memory held by the search (puzzles found, the
+    /// sets of known and tried clue sets, batches) under this many bytes, by
+    /// moving puzzles found out of memory and shrinking batches.
+    pub max_memory_bytes: Option<usize>,
+    /// Drop the solution of each puzzle found once it is checked, keeping
+    /// only its tiles; [`FoundPuzzle::unique_solution`] solves it again.
+    pub no_solutions: bool,
+    /// Rewrite the counters of the search to this file after every batch, in
+    /// the Prometheus text format.
+    pub metrics_file: Option<std::path::PathBuf>,
+    /// Write the puzzles found in the order of their combinations rather than
+    /// as the workers find them, and with zero solve times, so that runs with
+    /// the same settings write the same files byte for byte. Random bottom-up
+    /// searches draw their combinations from [`SearchOptions::generator_seed`].
+    pub deterministic_output: bool,
+    /// Only evaluate part of the search: the combinations of these ranks
+    /// (in the order of [`CombinationIterator`]) in exhaustive mode, or the
+    /// constructor runs of these numbers in random mode with a constructor,
+    /// `random_tries` being the length of the range. Used by the workers of
+    /// [`cluster`]; not for incremental or bottom-up random searches.
+    pub range: Option<std::ops::Range<u64>>,
+}
+
+/// Most solutions of the combinations classified by
+/// [`SearchOptions::duplicate_structure`].
+pub const MAX_CLASSIFIED_SOLUTIONS: usize = 4;
+
+/// Number of successive combinations processed on the same thread in incremental mode.
+const INCREMENTAL_RUN_LENGTH: usize = 1000;
+
+/// Number of successive combinations evaluated together with
+/// [`SearchOptions::deterministic_output`], which bounds the results waiting
+/// for those of earlier combinations to be written.
+const REORDER_WINDOW: usize = 16 * INCREMENTAL_RUN_LENGTH;
+
+/// Mutable state carried from one batch to the next during a search.
+struct SearchState {
+    /// Puzzles found and still held in memory.
+    found: Vec<FoundPuzzle>,
+    /// Puzzles found, including those moved out of memory.
+    unique_found: usize,
+    singles_solvable: usize,
+    difficulty_counts: BTreeMap<usize, usize>,
+    /// Spill file of the puzzles moved out of memory when there are no outputs to find them in.
+    spill: Option<(std::path::PathBuf, output::PuzzleWriter)>,
+    spilled: usize,
+    /// Puzzles found so far (and by previous runs), shared by the workers.
+    seen_standardized_puzzles: ConcurrentTileSet,
+    sinks: Vec<Box<dyn PuzzleSink>>,
+    processed_count: usize,
+    dedup_hits: usize,
+    superset_skips: usize,
+    nogood_skips: usize,
+    index_hits: usize,
+    /// Nogoods found so far, shared by the workers of a batch.
+    nogoods: RwLock<NogoodSet>,
+    prefilter_rejections: usize,
+    timed_out: usize,
+    /// Combinations evaluated and unique puzzles found, by line load.
+    strata: BTreeMap<usize, StratumCounts>,
+    /// Ambiguous combinations by number of solutions and how they differ,
+    /// shared by the workers of a batch.
+    duplicate_structure: Mutex<BTreeMap<usize, DifferenceCounts>>,
+    /// Time spent evaluating combinations by each rayon worker, in nanoseconds.
+    thread_busy_ns: Vec<AtomicU64>,
+    /// Backtracking nodes of the combinations solved.
+    solver_nodes: metrics::Histogram,
+    started: Instant,
+    options: SearchOptions,
+}
+
+/// Largest number of `tiles` in a single row or column.
+pub fn line_load(size: usize, tiles: &[(usize, usize)]) -> usize {
+    let mut rows = vec![0; size];
+    let mut cols = vec![0; size];
+    for &(i, j) in tiles {
+        rows[i] += 1;
+        cols[j] += 1;
+    }
+    rows.into_iter().chain(cols).max().unwrap_or(0)
+}
+
+/// Draw `k` distinct tiles of an N×N grid with at most `max_per_line` tiles in
+/// any row or column, one at a time among the tiles whose row and column are
+/// not full yet (starting over when no such tile is left).
+fn choose_balanced(rng: &mut impl Rng, n: usize, k: usize, max_per_line: usize) -> Vec<(usize, usize)> {
+    assert!(k <= n * max_per_line, "{} tiles don't fit with {} per line", k, max_per_line);
+    loop {
+        let mut rows = vec![0; n];
+        let mut cols = vec![0; n];
+        let mut tiles = Vec::with_capacity(k);
+        while tiles.len() < k {
+            let available: Vec<(usize, usize)> = (0..n)
+                .flat_map(|i| (0..n).map(move |j| (i, j)))
+                .filter(|&(i, j)| rows[i] < max_per_line && cols[j] < max_per_line && !tiles.contains(&(i, j)))
+                .collect();
+            let Some(&(i, j)) = available.choose(rng) else { break };
+            rows[i] += 1;
+            cols[j] += 1;
+            tiles.push((i, j));
+        }
+        if tiles.len() == k {
+            return tiles;
+        }
+    }
+}
+
+/// Every tile of an N×N grid, row by row: the `tile_coordinates` of
+/// [`puzzle_constraints`].
+pub fn all_tiles(size: usize) -> Vec<(usize, usize)> {
+    (0..size).flat_map(|i| (0..size).map(move |j| (i, j))).collect()
+}
+
+/// Constraints of the puzzle revealing `selected_tiles` of `grid`.
+///
+/// Selected tiles are known to be correct; every other tile is known to be wrong.
+pub fn puzzle_constraints(
+    selected_tiles: &[(usize, usize)],
+    grid: &Grid,
+    tile_coordinates: &[(usize, usize)],
+) -> Constraints {
+    puzzle_constraints_with(selected_tiles, grid, tile_coordinates, ClueSemantics::Sudodle)
+}
+
+/// Same as [`puzzle_constraints`], reading the tiles that are not selected with
+/// the given `semantics`.
+pub fn puzzle_constraints_with(
+    selected_tiles: &[(usize, usize)],
+    grid: &Grid,
+    tile_coordinates: &[(usize, usize)],
+    semantics: ClueSemantics,
+) -> Constraints {
+    let mut constraints = Constraints::new(grid.size());
+    for &(i, j) in tile_coordinates {
+        if selected_tiles.contains(&(i, j)) {
+            constraints.set_known((i, j), grid[(i, j)].into());
+        } else if semantics == ClueSemantics::Sudodle {
+            constraints.set_wrong((i, j), grid[(i, j)].into());
+        }
+    }
+    constraints
+}
+
+/// Check whether revealing `selected_tiles` gives a puzzle with exactly one
+/// solution, `constraints` being the clues of that puzzle.
+///
+/// Returns the puzzle if it is unique, or the statistics of the solve and the
+/// solutions found otherwise (including when the solve was abandoned after `timeout`).
+fn solve_combination(
+    selected_tiles: &[(usize, usize)],
+    constraints: &Constraints,
+    options: &SolverOptions,
+    timeout: Option<Duration>,
+) -> Result<FoundPuzzle, (SolveStats, Vec<Grid>)> {
+    // Find solutions with max of 2 to check if exactly 1 exists
+    let start = Instant::now();
+    let hooks = SolveHooks {
+        deadline: timeout.map(|timeout| start + timeout),
+        ..SolveHooks::default()
+    };
+    let (mut solutions, stats) = solve_constraints(constraints, Some(2), options, hooks);
+    let solve_time = start.elapsed();
+    
+    // Only return if this is a single-solution puzzle
+    if solutions.len() == 1 && !stats.timed_out {
+        let singles_solvable = techniques::solvable_by_deduction(constraints, TechniqueSet::SINGLES);
+        Ok(FoundPuzzle {
+            tiles: selected_tiles.to_vec(),
+            solution: solutions.pop(),
+            stats,
+            solve_time,
+            singles_solvable,
+            certificate: if singles_solvable { certificate::find(constraints) } else { None },
+            expected_guesses: None,
+        })
+    } else {
+        Err((stats, solutions))
+    }
+}
+
+/// Check whether `puzzle`, placing tiles of the cyclic square, has a unique
+/// solution, and return it with its solver statistics if so.
+pub fn check_puzzle(puzzle: &PuzzleSpec, options: &SolverOptions) -> Result<FoundPuzzle, SolveStats> {
+    let tiles = puzzle.tile_pairs();
+    let constraints = puzzle_constraints(&tiles, &cyclic_latin_square(puzzle.size), &all_tiles(puzzle.size));
+    solve_combination(&tiles, &constraints, options, None).map_err(|(stats, _)| stats)
+}
+
+/// Largest grid size accepted by [`solve_report`], and written by
+/// [`output::format_grid`], whose values above 9 are the letters A to G.
+pub const MAX_SIZE: usize = 16;
+
+/// Solve `puzzle` and describe the outcome: whether it is unique, its
+/// solution if so, and solver statistics.
+///
+/// The search is abandoned at `deadline`, which is reported as `timed_out`.
+pub fn solve_report(
+    puzzle: &PuzzleSpec,
+    options: &SolverOptions,
+    deadline: Option<Instant>,
+) -> Result<SolveReport, String> {
+    let mut grids = HashMap::new();
+    report_with(puzzle, options, deadline, &mut grids)
+}
+
+/// Cyclic squares and their tile coordinates, by size, kept between the
+/// solves of a thread by [`solve_batch`].
+type GridCache = HashMap<usize, (Grid, Vec<(usize, usize)>)>;
+
+/// [`solve_report`], taking the square of the puzzle's size from `grids`.
+fn report_with(
+    puzzle: &PuzzleSpec,
+    options: &SolverOptions,
+    deadline: Option<Instant>,
+    grids: &mut GridCache,
+) -> Result<SolveReport, String> {
+    puzzle.check()?;
+    let size = puzzle.size;
+    let tiles = &puzzle.tile_pairs();
+    let (grid, tile_coordinates) = grids.entry(size).or_insert_with(|| {
+        let tile_coordinates = all_tiles(size);
+        (cyclic_latin_square(size), tile_coordinates)
+    });
+    let constraints = puzzle_constraints(tiles, grid, tile_coordinates);
+    let start = Instant::now();
+    let hooks = SolveHooks { deadline, ..SolveHooks::default() };
+    let (solutions, stats) = solve_constraints(&constraints, Some(2), options, hooks);
+    let solve_time = start.elapsed();
+    let solution = match solutions.as_slice() {
+        [solution] if !stats.timed_out => Some(solution.clone()),
+        _ => None,
+    };
+    Ok(SolveReport {
+        size,
+        clues: puzzle.tiles.clone(),
+        unique: solution.is_some(),
+        solutions: solutions.len(),
+        solution,
+        difficulty: stats.difficulty(),
+        backtrack_nodes: stats.backtrack_nodes,
+        timed_out: stats.timed_out,
+        solve_time_us: solve_time.as_micros() as u64,
+        content_id: output::FileHeader::new(size, ClueSemantics::Sudodle).content_id(tiles),
+        rules_version: puzzle.rules_version,
+    })
+}
+
+/// [`solve_report`] for each of `puzzles`, in the same order, solving them
+/// in parallel on the rayon thread pool.
+///
+/// Each thread builds the cyclic square of each size once for all its
+/// puzzles. [`SolverOptions::timeout`] applies to each puzzle.
+pub fn solve_batch(puzzles: &[PuzzleSpec], options: &SolverOptions) -> Vec<Result<SolveReport, String>> {
+    puzzles.par_iter().map_init(GridCache::new, |grids, puzzle| report_with(puzzle, options, None, grids)).collect()
+}
+
+/// Process a batch of tile combinations to find single-solution puzzles.
+///
+/// In first-only mode, the batch is abandoned as soon as one puzzle is found.
+/// In incremental mode, the batch must come from a [`RevolvingDoorIterator`]:
+/// it is split into runs of successive combinations, and each run builds the
+/// constraints of its first combination once, then applies the single tile
+/// swap leading to each following combination.
+fn process_batch(
+    batch: &[Vec<(usize, usize)>],
+    grid: &Arc<Grid>,
+    tile_coordinates: &[(usize, usize)],
+    state: &mut SearchState,
+) {
+    let batch_span = tracing::info_span!("batch", first = state.processed_count, combinations = batch.len());
+    let _entered = batch_span.enter();
+    let seen = &state.seen_standardized_puzzles;
+    let drop_supersets = state.options.drop_supersets.as_ref();
+    let thread_busy_ns = &state.thread_busy_ns;
+    let solver_nodes = &state.solver_nodes;
+    let solver_options = state.options.solver;
+    let require_singles_solvable = state.options.require_singles_solvable;
+    let timeout = state.options.per_puzzle_timeout;
+    let semantics = state.options.semantics;
+    let variant = state.options.variant;
+    let expected_guess_games = state.options.expected_guess_games;
+    let no_solutions = state.options.no_solutions;
+    let deterministic = state.options.deterministic_output;
+    let nogoods = (state.options.nogoods && semantics == ClueSemantics::GivensOnly).then_some(&state.nogoods);
+    let duplicate_structure = state.options.duplicate_structure.then_some(&state.duplicate_structure);
+    let results_index = state.options.results_index.as_deref();
+    let attempted = AtomicUsize::new(0);
+    let skipped = AtomicUsize::new(0);
+    let supersets = AtomicUsize::new(0);
+    let ruled_out = AtomicUsize::new(0);
+    let indexed = AtomicUsize::new(0);
+    let prefiltered = AtomicUsize::new(0);
+    let timed_out = AtomicUsize::new(0);
+    let size = grid.size();
+    let loads: Vec<AtomicUsize> = (0..=size).map(|_| AtomicUsize::new(0)).collect();
+    let evaluate = |selected_tiles: &[(usize, usize)], constraints: &Constraints| {
+        let start = Instant::now();
+        // Rayon workers have no current span, so attach to the batch explicitly
+        let _span = tracing::debug_span!(parent: &batch_span, "puzzle", tiles = %output::format_tiles(selected_tiles))
+            .entered();
+        attempted.fetch_add(1, Ordering::Relaxed);
+        loads[line_load(size, selected_tiles)].fetch_add(1, Ordering::Relaxed);
+        // Puzzles already found (e.g. by a previous run) don't need solving again
+        let outcome = if seen.contains(selected_tiles) {
+            skipped.fetch_add(1, Ordering::Relaxed);
+            None
+        } else if drop_supersets.is_some_and(|filter| filter.contains_subset_of(selected_tiles)) {
+            supersets.fetch_add(1, Ordering::Relaxed);
+            None
+        } else if nogoods.is_some_and(|nogoods| nogoods.read().unwrap().rules_out(selected_tiles)) {
+            ruled_out.fetch_add(1, Ordering::Relaxed);
+            None
+        } else if results_index.is_some_and(|index| index.get(selected_tiles).is_some()) {
+            indexed.fetch_add(1, Ordering::Relaxed);
+            None
+        } else {
+            let solved = solve_combination(selected_tiles, constraints, &solver_options, timeout);
+            if let Some(index) = results_index {
+                let outcome = match &solved {
+                    Ok(_) => Some(index::Outcome::Unique),
+                    Err((stats, solutions)) => {
+                        (!stats.timed_out).then(|| index::Outcome::of_solutions(solutions.len()))
+                    }
+                };
+                if let Some(outcome) = outcome {
+                    index.record(selected_tiles, outcome).expect("Failed to write the results index");
+                }
+            }
+            let nodes = match &solved {
+                Ok(puzzle) => puzzle.stats.backtrack_nodes,
+                Err((stats, _)) => stats.backtrack_nodes,
+            };
+            solver_nodes.observe(nodes as f64);
+            match solved {
+                Ok(puzzle) if require_singles_solvable && !puzzle.singles_solvable => {
+                    tracing::debug!("unique but not solvable with singles alone");
+                    None
+                }
+                Ok(_) if !seen.insert(selected_tiles) => {
+                    // Found by another worker in the meantime
+                    skipped.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+                Ok(mut puzzle) => {
+                    tracing::debug!(difficulty = puzzle.stats.difficulty(), "unique");
+                    if let Some(games) = expected_guess_games {
+                        let solution = puzzle.solution.as_ref().expect("solved puzzles have their solution");
+                        puzzle.expected_guesses = Some(simulate::expected_guesses(constraints, solution, games));
+                    }
+                    if no_solutions {
+                        puzzle.solution = None;
+                    }
+                    if deterministic {
+                        puzzle.solve_time = Duration::ZERO;
+                    }
+                    Some(puzzle)
+                }
+                Err((stats, solutions)) => {
+                    if let Some(nogoods) = nogoods.filter(|_| solutions.len() > 1 && !stats.timed_out) {
+                        let mut nogoods = nogoods.write().unwrap();
+                        for solution in &solutions {
+                            nogoods.record(solution, grid);
+                        }
+                    }
+                    if let Some(counts) = duplicate_structure.filter(|_| solutions.len() == 2 && !stats.timed_out) {
+                        let hooks = SolveHooks {
+                            deadline: timeout.map(|timeout| Instant::now() + timeout),
+                            ..SolveHooks::default()
+                        };
+                        let limit = Some(MAX_CLASSIFIED_SOLUTIONS + 1);
+                        let (solutions, stats) = solve_constraints(constraints, limit, &solver_options, hooks);
+                        if solutions.len() <= MAX_CLASSIFIED_SOLUTIONS && !stats.timed_out {
+                            let difference = cycles::closest_difference(&solutions).expect("two solutions or more");
+                            counts.lock().unwrap().entry(solutions.len()).or_default().add(difference);
+                        }
+                    }
+                    if stats.timed_out {
+                        timed_out.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            search_nodes = stats.backtrack_nodes,
+                            tiles = %output::format_tiles(selected_tiles),
+                            "timed out"
+                        );
+                    } else if stats.backtrack_nodes == 0 {
+                        prefiltered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None
+                }
+            }
+        };
+        if let Some(index) = rayon::current_thread_index() {
+            thread_busy_ns[index].fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        }
+        outcome
+    };
+    
+    // Evaluate the batch on the rayon workers, which send each new puzzle to
+    // this thread as soon as it is found, to be written while they go on,
+    // tagged with the rank of its combination in the batch. For deterministic
+    // output they also send the ranks of the other combinations, so that the
+    // puzzles are written in rank order, and evaluate the batch one window at
+    // a time, so that few results wait for the earlier ones
+    let first_only = state.options.first_only;
+    let incremental = state.options.incremental;
+    let window_length = if deterministic { REORDER_WINDOW } else { batch.len().max(1) };
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            let send = |rank: usize, puzzle: Option<FoundPuzzle>| {
+                if deterministic || puzzle.is_some() {
+                    sender.send((rank, puzzle)).expect("the batch outlives its workers");
+                }
+            };
+            for (window_index, window) in batch.chunks(window_length).enumerate() {
+                let first_rank = window_index * window_length;
+                // The first puzzle found, if the search stops there: the one
+                // of the lowest rank for deterministic output
+                let found = if incremental {
+                    let evaluate_run = |(run_index, run): (usize, &[Vec<(usize, usize)>])| {
+                        let mut constraints = puzzle_constraints_with(&run[0], grid, tile_coordinates, semantics);
+                        constraints.variant = variant;
+                        for (index, selected_tiles) in run.iter().enumerate() {
+                            if let Some(previous) = index.checked_sub(1).map(|previous| &run[previous]) {
+                                let removed = previous.iter().find(|tile| !selected_tiles.contains(tile));
+                                let added = selected_tiles.iter().find(|tile| !previous.contains(tile));
+                                if let (Some(&removed), Some(&added)) = (removed, added) {
+                                    constraints.swap_tiles(grid, removed, added, semantics);
+                                }
+                            }
+                            let puzzle = evaluate(selected_tiles, &constraints);
+                            if first_only && puzzle.is_some() {
+                                return puzzle;
+                            }
+                            send(first_rank + run_index * INCREMENTAL_RUN_LENGTH + index, puzzle);
+                        }
+                        None
+                    };
+                    let runs = window.par_chunks(INCREMENTAL_RUN_LENGTH).enumerate();
+                    match (first_only, deterministic) {
+                        (true, true) => runs.find_map_first(evaluate_run),
+                        (true, false) => runs.find_map_any(evaluate_run),
+                        (false, _) => {
+                            runs.for_each(|run| {
+                                evaluate_run(run);
+                            });
+                            None
+                        }
+                    }
+                } else {
+                    let evaluate = |(index, selected_tiles): (usize, &Vec<(usize, usize)>)| {
+                        let mut constraints =
+                            puzzle_constraints_with(selected_tiles, grid, tile_coordinates, semantics);
+                        constraints.variant = variant;
+                        let puzzle = evaluate(selected_tiles, &constraints);
+                        if first_only {
+                            return puzzle;
+                        }
+                        send(first_rank + index, puzzle);
+                        None
+                    };
+                    let combinations = window.par_iter().enumerate();
+                    match (first_only, deterministic) {
+                        (true, true) => combinations.find_map_first(evaluate),
+                        (true, false) => combinations.find_map_any(evaluate),
+                        (false, _) => {
+                            combinations.for_each(|combination| {
+                                evaluate(combination);
+                            });
+                            None
+                        }
+                    }
+                };
+                if let Some(puzzle) = found {
+                    sender.send((0, Some(puzzle))).expect("the batch outlives its workers");
+                    break;
+                }
+            }
+        });
+        
+        // Record the new puzzles in the output files and databases, writing
+        // together those that arrived while the previous ones were written
+        let reorder = deterministic && !first_only;
+        let mut pending = BTreeMap::new();
+        let mut next_rank = 0;
+        while let Ok(result) = receiver.recv() {
+            let mut puzzles = Vec::new();
+            for (rank, puzzle) in std::iter::once(result).chain(receiver.try_iter()) {
+                if !reorder {
+                    puzzles.extend(puzzle);
+                    continue;
+                }
+                pending.insert(rank, puzzle);
+                while let Some(puzzle) = pending.remove(&next_rank) {
+                    puzzles.extend(puzzle);
+                    next_rank += 1;
+                }
+            }
+            if puzzles.is_empty() {
+                continue;
+            }
+            for puzzle in &puzzles {
+                state.strata.entry(line_load(size, &puzzle.tiles)).or_default().unique_puzzles += 1;
+                *state.difficulty_counts.entry(puzzle.stats.difficulty()).or_insert(0) += 1;
+                state.singles_solvable += puzzle.singles_solvable as usize;
+            }
+            state.unique_found += puzzles.len();
+            for sink in &mut state.sinks {
+                sink.write_puzzles(&puzzles).expect("Failed to write puzzles");
+            }
+            state.found.extend(puzzles);
+        }
+    });
+    let attempted = attempted.into_inner();
+    state.dedup_hits += skipped.into_inner();
+    state.superset_skips += supersets.into_inner();
+    state.nogood_skips += ruled_out.into_inner();
+    state.index_hits += indexed.into_inner();
+    if let Some(index) = &state.options.results_index {
+        index.flush().expect("Failed to write the results index");
+    }
+    state.prefilter_rejections += prefiltered.into_inner();
+    state.timed_out += timed_out.into_inner();
+    for (load, count) in loads.into_iter().enumerate() {
+        let count = count.into_inner();
+        if count > 0 {
+            state.strata.entry(load).or_default().combinations += count;
+        }
+    }
+    
+    state.processed_count += attempted;
+    if let Some(path) = &state.options.metrics_file {
+        if let Err(e) = metrics::write_file(path, &search_metrics(state, size)) {
+            tracing::warn!(error = %e, path = %path.display(), "cannot write metrics");
+        }
+    }
+}
+
+/// Counters of a search of `size` in the Prometheus text format.
+fn search_metrics(state: &SearchState, size: usize) -> String {
+    let elapsed = state.started.elapsed().as_secs_f64();
+    let labels = format!("size=\"{}\"", size);
+    let mut text = String::new();
+    let counters = [
+        ("sudodle_search_combinations_total", "Tile combinations evaluated.", state.processed_count),
+        ("sudodle_search_puzzles_found_total", "Unique puzzles found.", state.unique_found),
+        ("sudodle_search_dedup_hits_total", "Combinations skipped as already found.", state.dedup_hits),
+        ("sudodle_search_superset_skips_total", "Supersets of known unique clue sets skipped.", state.superset_skips),
+        ("sudodle_search_nogood_skips_total", "Combinations ruled out by nogoods.", state.nogood_skips),
+        ("sudodle_search_index_hits_total", "Combinations skipped as already evaluated.", state.index_hits),
+        ("sudodle_search_timed_out_total", "Combinations abandoned after the per-puzzle timeout.", state.timed_out),
+    ];
+    for (name, help, value) in counters {
+        metrics::write_header(&mut text, name, "counter", help);
+        metrics::write_sample(&mut text, name, &labels, value);
+    }
+    let rate = state.processed_count as f64 / elapsed.max(f64::EPSILON);
+    let gauges = [
+        ("sudodle_search_combinations_per_second", "Combinations evaluated per second since the start.", rate),
+        ("sudodle_search_elapsed_seconds", "Time since the start of the search.", elapsed),
+    ];
+    for (name, help, value) in gauges {
+        metrics::write_header(&mut text, name, "gauge", help);
+        metrics::write_sample(&mut text, name, &labels, value);
+    }
+    let name = "sudodle_search_solver_nodes";
+    metrics::write_header(&mut text, name, "histogram", "Backtracking nodes of the combinations solved.");
+    state.solver_nodes.write(&mut text, name, &labels);
+    text
+}
+
+/// Smallest batch a search shrinks its batches to under a memory cap.
+const MIN_BATCH_SIZE: usize = 1000;
+
+/// Approximate bytes held by a search between batches: the puzzles found
+/// still in memory, the clue sets known, the next batch of `batch_size`
+/// combinations of `placed` tiles and `other_bytes` more.
+fn memory_estimate(state: &SearchState, size: usize, placed: usize, batch_size: usize, other_bytes: usize) -> usize {
+    let tiles_bytes = placed * std::mem::size_of::<(usize, usize)>();
+    let puzzle_bytes = std::mem::size_of::<FoundPuzzle>() + tiles_bytes + size * size;
+    // A hash set entry is a 16-byte key, with its control byte and spare capacity
+    let known_bytes = 32;
+    state.found.len() * puzzle_bytes
+        + state.seen_standardized_puzzles.len() * known_bytes
+        + batch_size * (std::mem::size_of::<Vec<(usize, usize)>>() + tiles_bytes)
+        + other_bytes
+}
+
+/// Keep a search under [`SearchOptions::max_memory_bytes`], if set: move the
+/// puzzles found out of memory (writing them to a spill file if the search
+/// has no outputs to find them in), then halve `batch_size` as long as the
+/// search is still over the cap, down to [`MIN_BATCH_SIZE`].
+fn enforce_memory_cap(state: &mut SearchState, size: usize, placed: usize, batch_size: &mut usize, other_bytes: usize) {
+    let Some(cap) = state.options.max_memory_bytes else {
+        return;
+    };
+    if memory_estimate(state, size, placed, *batch_size, other_bytes) <= cap {
+        return;
+    }
+    if !state.found.is_empty() {
+        if state.sinks.is_empty() {
+            let (path, writer) = state.spill.get_or_insert_with(|| {
+                let path = std::env::temp_dir()
+                    .join(format!("find_puzzles-{}-{}x{}-{}.txt", std::process::id(), size, size, placed));
+                let grid = state.options.base_grid.grid(size).expect("the base grid exists at the size searched");
+                let header = output::FileHeader::new(size, state.options.semantics)
+                    .with_base_grid(&grid)
+                    .with_variant(state.options.variant);
+                let writer =
+                    output::PuzzleWriter::create(&path.to_string_lossy(), output::OutputFormat::Text, None, false, &header)
+                        .expect("Failed to create spill file");
+                (path, writer)
+            });
+            writer.write_puzzles(&state.found).expect("Failed to write spill file");
+            tracing::info!(puzzles = state.found.len(), path = %path.display(), "spilled puzzles to disk");
+        }
+        state.spilled += state.found.len();
+        state.found = Vec::new();
+    }
+    let initial_batch_size = *batch_size;
+    while *batch_size > MIN_BATCH_SIZE && memory_estimate(state, size, placed, *batch_size, other_bytes) > cap {
+        *batch_size = (*batch_size / 2).max(MIN_BATCH_SIZE);
+    }
+    if *batch_size < initial_batch_size {
+        tracing::info!(batch_size = *batch_size, "shrank batches to stay under the memory cap");
+        let estimate = memory_estimate(state, size, placed, *batch_size, other_bytes);
+        if estimate > cap {
+            tracing::warn!(
+                estimate_mb = estimate >> 20,
+                "the search takes more memory than the cap even with the smallest batches"
+            );
+        }
+    }
+}
+
+/// Outcome of a puzzle search.
+pub struct SearchResult {
+    /// Puzzles with exactly one solution, in discovery order, except those
+    /// moved out of memory to stay under [`SearchOptions::max_memory_bytes`].
+    pub puzzles: Vec<FoundPuzzle>,
+    /// Puzzles moved out of memory: they are in the outputs of the search,
+    /// or in `spill_file` if it had none.
+    pub spilled: usize,
+    /// Text file of the puzzles moved out of memory by a search without outputs.
+    pub spill_file: Option<std::path::PathBuf>,
+    /// Counters describing the run.
+    pub summary: RunSummary,
+}
+
+/// Find puzzles that have exactly one solution.
+///
+/// This function generates the base grid of the search (the cyclic Latin
+/// square unless [`SearchOptions::base_grid`] says otherwise) and then tests
+/// combinations of n_well_placed tiles to find configurations that result in
+/// puzzles with exactly one valid solution.
+///
+/// # Parameters
+/// - `n`: Size of the Latin square (N×N)
+/// - `n_well_placed`: Number of tiles to place as "correct" values
+/// - `options`: Sampling mode, solver strategy and puzzle filters
+/// - `sinks`: Output files or databases to write puzzles to as they are discovered
+/// - `known_puzzles`: Standardized tile sets found by previous runs, which are skipped
+///
+/// # Returns
+/// The puzzles that have exactly one solution, with their unique solution and
+/// solver statistics, and a summary of the run.
+pub fn find_single_solution_puzzles(
+    n: usize,
+    n_well_placed: usize,
+    options: SearchOptions,
+    sinks: Vec<Box<dyn PuzzleSink>>,
+    known_puzzles: HashSet<Vec<(usize, usize)>>,
+) -> SearchResult {
+    let start_time = Instant::now();
+    let mut options = options;
+    let resume = options.resume.take();
+    let checkpoint_path = options.checkpoint.clone();
+    let range = options.range.clone();
+    let SearchOptions {
+        random_tries,
+        first_only,
+        incremental,
+        max_per_line,
+        generator,
+        generator_seed,
+        anneal,
+        semantics,
+        base_grid,
+        variant,
+        ..
+    } = options;
+    let grid = Arc::new(base_grid.grid(n).expect("the base grid exists at the size searched"));
+    
+    // Generate all tile coordinates
+    let tile_coordinates = all_tiles(n);
+    
+    // Collection for final results and set to track seen standardized puzzles
+    let mut state = SearchState {
+        found: Vec::new(),
+        unique_found: 0,
+        singles_solvable: 0,
+        difficulty_counts: BTreeMap::new(),
+        spill: None,
+        spilled: 0,
+        seen_standardized_puzzles: {
+            let seen = ConcurrentTileSet::new(n);
+            for tiles in &known_puzzles {
+                seen.insert(tiles);
+            }
+            seen
+        },
+        sinks,
+        processed_count: 0,
+        dedup_hits: 0,
+        superset_skips: 0,
+        nogood_skips: 0,
+        index_hits: 0,
+        nogoods: RwLock::new(NogoodSet::new(n)),
+        prefilter_rejections: 0,
+        timed_out: 0,
+        strata: BTreeMap::new(),
+        duplicate_structure: Mutex::new(BTreeMap::new()),
+        thread_busy_ns: (0..rayon::current_num_threads()).map(|_| AtomicU64::new(0)).collect(),
+        solver_nodes: metrics::Histogram::new(&metrics::NODE_BUCKETS),
+        started: start_time,
+        options,
+    };
+    
+    let mut batch_count = 0;
+    let mut chunk_size = match (generator, random_tries) {
+        // Constructor runs are long, keep batches to a few runs per thread
+        (Generator::Anneal | Generator::TopDown, _) => 4 * rayon::current_num_threads(),
+        (Generator::BottomUp, Some(_)) => 10000, // Smaller batches for random mode
+        (_, None) => 100000,
+    };
+    let progress_interval = 5; // Report progress every 5 batches
+    
+    // Choose iteration strategy based on random_tries parameter
+    if let Some(num_random) = random_tries {
+        tracing::info!(combinations = num_random, batch_size = chunk_size, "processing random combinations in batches");
+        
+        let (sample_seed, mut sample_batch, tries_done, mut tried_combinations) = match resume {
+            Some(resume) => {
+                tracing::info!(tries = resume.tries, tried = resume.tried.len(), "resuming from checkpoint");
+                (resume.seed, resume.batches, resume.tries, resume.tried)
+            }
+            None => {
+                let rate = state.options.tried_false_positive_rate.unwrap_or(bloom::DEFAULT_FALSE_POSITIVE_RATE);
+                let seed = match state.options.deterministic_output {
+                    true => generator_seed,
+                    false => thread_rng().gen(),
+                };
+                (seed, 0, 0, BloomFilter::new(num_random, rate))
+            }
+        };
+        tracing::info!(
+            memory_bytes = tried_combinations.memory_bytes(),
+            hashes = tried_combinations.hashes(),
+            "tracking tried combinations in a Bloom filter"
+        );
+        let save_checkpoint = |tried: &BloomFilter, batches: u64, tries: usize| {
+            if let Some(path) = &checkpoint_path {
+                let checkpoint = Checkpoint {
+                    size: n,
+                    placed: n_well_placed,
+                    generator,
+                    seed: sample_seed,
+                    batches,
+                    tries,
+                    tried: tried.clone(),
+                };
+                checkpoint.save(path).expect("Failed to write checkpoint");
+            }
+        };
+        let mut remaining_tries = num_random.saturating_sub(tries_done);
+        let mut failed_runs = 0;
+        let solver = state.options.solver;
+        let construct = |run: u64| match generator {
+            Generator::Anneal => {
+                generators::anneal(&grid, n_well_placed, run, generator_seed, &anneal, &solver, semantics, variant)
+            }
+            Generator::TopDown => {
+                generators::top_down(&grid, n_well_placed, run, generator_seed, &solver, semantics, variant)
+            }
+            Generator::BottomUp => unreachable!("bottom-up combinations are drawn below"),
+        };
+        
+        while remaining_tries > 0 && generator != Generator::BottomUp {
+            // Each try is a constructor run, whose result is checked like a random combination
+            let runs = chunk_size.min(remaining_tries);
+            let first_run = range.as_ref().map_or(0, |range| range.start) + (num_random - remaining_tries) as u64;
+            let constructed: Vec<_> = (first_run..first_run + runs as u64)
+                .into_par_iter()
+                .map(construct)
+                .collect();
+            failed_runs += constructed.iter().filter(|tiles| tiles.is_none()).count();
+            let batch: Vec<_> = constructed
+                .into_iter()
+                .flatten()
+                .filter(|tiles| tried_combinations.insert(mask::TileMask::of_tiles(n, tiles)))
+                .collect();
+            remaining_tries -= runs;
+            process_batch(&batch, &grid, &tile_coordinates, &mut state);
+            enforce_memory_cap(&mut state, n, n_well_placed, &mut chunk_size, tried_combinations.memory_bytes());
+            if first_only && state.unique_found > 0 {
+                break;
+            }
+            
+            batch_count += 1;
+            if batch_count % progress_interval == 0 {
+                save_checkpoint(&tried_combinations, sample_batch, num_random - remaining_tries);
+                tracing::info!(
+                    runs = num_random - remaining_tries,
+                    failed_runs,
+                    found = state.unique_found,
+                    "progress"
+                );
+            }
+        }
+        if generator != Generator::BottomUp {
+            tracing::info!(runs = num_random - remaining_tries, failed_runs, "constructor runs finished");
+        }
+        
+        while remaining_tries > 0 && generator == Generator::BottomUp {
+            // Collect a batch of random combinations, drawn from the batch's own generator
+            let mut rng = checkpoint::batch_rng(sample_seed, sample_batch);
+            sample_batch += 1;
+            let mut batch = Vec::with_capacity(chunk_size.min(remaining_tries));
+            for _ in 0..chunk_size.min(remaining_tries) {
+                // Generate random combination
+                let mut selected_tiles: Vec<(usize, usize)> = match max_per_line {
+                    Some(max_per_line) => choose_balanced(&mut rng, n, n_well_placed, max_per_line),
+                    None => tile_coordinates.choose_multiple(&mut rng, n_well_placed).cloned().collect(),
+                };
+                selected_tiles.sort(); // Normalize for deduplication
+                
+                if tried_combinations.insert(mask::TileMask::of_tiles(n, &selected_tiles)) {
+                    batch.push(selected_tiles);
+                }
+            }
+            
+            if batch.is_empty() {
+                break; // No more unique combinations possible
+            }
+            
+            remaining_tries = remaining_tries.saturating_sub(batch.len());
+            process_batch(&batch, &grid, &tile_coordinates, &mut state);
+            enforce_memory_cap(&mut state, n, n_well_placed, &mut chunk_size, tried_combinations.memory_bytes());
+            if first_only && state.unique_found > 0 {
+                break;
+            }
+            
+            batch_count += 1;
+            
+            // Progress reporting every X batches
+            if batch_count % progress_interval == 0 {
+                save_checkpoint(&tried_combinations, sample_batch, num_random - remaining_tries);
+                tracing::info!(
+                    batches = batch_count,
+                    combinations = state.processed_count,
+                    found = state.unique_found,
+                    "progress"
+                );
+            }
+        }
+        save_checkpoint(&tried_combinations, sample_batch, num_random - remaining_tries);
+    } else {
+        tracing::info!(batch_size = chunk_size, "processing all combinations in batches");
+        
+        // Process combinations in batches without collecting all into memory
+        let mut combinations: Box<dyn Iterator<Item = Vec<(usize, usize)>>> = if incremental {
+            Box::new(RevolvingDoorIterator::new(tile_coordinates.clone(), n_well_placed))
+        } else {
+            match &range {
+                Some(range) => Box::new(
+                    CombinationIterator::starting_at(tile_coordinates.clone(), n_well_placed, range.start as u128)
+                        .take((range.end - range.start) as usize),
+                ),
+                None => Box::new(CombinationIterator::new(tile_coordinates.clone(), n_well_placed)),
+            }
+        };
+        
+        // Process combinations in batches
+        loop {
+            // Collect a batch of combinations
+            let batch: Vec<_> = combinations.by_ref().take(chunk_size).collect();
+            
+            if batch.is_empty() {
+                break; // No more combinations
+            }
+            
+            process_batch(&batch, &grid, &tile_coordinates, &mut state);
+            enforce_memory_cap(&mut state, n, n_well_placed, &mut chunk_size, 0);
+            if first_only && state.unique_found > 0 {
+                break;
+            }
+            
+            batch_count += 1;
+            
+            // Progress reporting every X batches
+            if batch_count % progress_interval == 0 {
+                tracing::info!(
+                    batches = batch_count,
+                    combinations = state.processed_count,
+                    found = state.unique_found,
+                    "progress"
+                );
+            }
+        }
+    }
+    
+    for sink in &mut state.sinks {
+        sink.finish().expect("Failed to finalize output");
+    }
+    
+    let wall_time = start_time.elapsed();
+    tracing::info!(
+        combinations = state.processed_count,
+        found = state.unique_found,
+        wall_time = ?wall_time,
+        "finished processing"
+    );
+    
+    let spill_file = state.spill.map(|(path, mut writer)| {
+        writer.finish().expect("Failed to finalize spill file");
+        path
+    });
+    let summary = RunSummary {
+        size: n,
+        placed: n_well_placed,
+        combinations_processed: state.processed_count,
+        unique_puzzles: state.unique_found,
+        singles_solvable_puzzles: state.singles_solvable,
+        dedup_hits: state.dedup_hits,
+        superset_skips: state.superset_skips,
+        nogood_skips: state.nogood_skips,
+        index_hits: state.index_hits,
+        prefilter_rejections: state.prefilter_rejections,
+        timed_out: state.timed_out,
+        difficulty_counts: state.difficulty_counts,
+        strata: state.strata.into_iter()
+            .map(|(load, counts)| {
+                let hit_rate = counts.unique_puzzles as f64 / counts.combinations.max(1) as f64;
+                (load, StratumCounts { hit_rate, ..counts })
+            })
+            .collect(),
+        duplicate_structure: state.duplicate_structure.into_inner().unwrap(),
+        wall_time_seconds: wall_time.as_secs_f64(),
+        thread_utilization: state.thread_busy_ns.iter()
+            .map(|busy| busy.load(Ordering::Relaxed) as f64 / wall_time.as_nanos().max(1) as f64)
+            .collect(),
+    };
+    
+    SearchResult {
+        puzzles: state.found,
+        spilled: state.spilled,
+        spill_file,
+        summary,
+    }
+}
+
+/// Number of ways to choose `k` items among `n`, saturating at `u128::MAX`.
+pub fn binomial(n: usize, k: usize) -> u128 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    let mut result: u128 = 1;
+    for i in 0..k {
+        // result * (n - i) is always divisible by (i + 1)
+        result = match result.checked_mul((n - i) as u128) {
+            Some(product) => product / (i as u128 + 1),
+            None => return u128::MAX,
+        };
+    }
+    result
+}
+
+/// Generate combinations iteratively to avoid storing all in memory
+pub struct CombinationIterator<T: Clone> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    k: usize,
+    first: bool,
+    exhausted: bool,
+}
+
+impl<T: Clone> CombinationIterator<T> {
+    pub fn new(items: Vec<T>, k: usize) -> Self {
+        if k == 0 || k > items.len() {
+            return Self {
+                items,
+                indices: Vec::new(),
+                k,
+                first: true,
+                exhausted: true,
+            };
+        }
+        
+        Self {
+            items,
+            indices: (0..k).collect(),
+            k,
+            first: true,
+            exhausted: false,
+        }
+    }
+
+    /// The combinations from the one of rank `rank` in the order of
+    /// [`CombinationIterator::new`] on, none if there are not that many.
+    pub fn starting_at(items: Vec<T>, k: usize, rank: u128) -> Self {
+        let n = items.len();
+        let mut iterator = Self::new(items, k);
+        if iterator.exhausted || rank >= binomial(n, k) {
+            iterator.exhausted = true;
+            return iterator;
+        }
+        // Each first index leaves the combinations of the following items
+        let mut rank = rank;
+        let mut next = 0;
+        for position in 0..k {
+            loop {
+                let with_next = binomial(n - next - 1, k - position - 1);
+                if rank < with_next {
+                    break;
+                }
+                rank -= with_next;
+                next += 1;
+            }
+            iterator.indices[position] = next;
+            next += 1;
+        }
+        iterator
+    }
+}
+
+impl<T: Clone> Iterator for CombinationIterator<T> {
+    type Item = Vec<T>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+        
+        if self.first {
+            self.first = false;
+            return Some(self.indices.iter().map(|&i| self.items[i].clone()).collect());
+        }
+        
+        let n = self.items.len();
+        let k = self.k;
+        
+        // Find the rightmost index that can be incremented
+        let mut i = k;
+        while i > 0 {
+            i -= 1;
+            if self.indices[i] < n - k + i {
+                self.indices[i] += 1;
+                // Reset all indices to the right
+                for j in (i + 1)..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+                return Some(self.indices.iter().map(|&idx| self.items[idx].clone()).collect());
+            }
+        }
+        
+        self.exhausted = true;
+        None
+    }
+}
+
+/// Generate combinations in revolving-door order: each combination differs
+/// from the previous one by a single swap (one item removed, one added).
+///
+/// Implements Knuth's Algorithm R (TAOCP 7.2.1.3).
+pub struct RevolvingDoorIterator<T: Clone> {
+    items: Vec<T>,
+    /// `c[1..=k]` are the indices of the current combination in increasing
+    /// order, with the sentinel `c[k + 1] = n`; `c[0]` is unused.
+    c: Vec<usize>,
+    k: usize,
+    first: bool,
+    exhausted: bool,
+}
+
+impl<T: Clone> RevolvingDoorIterator<T> {
+    pub fn new(items: Vec<T>, k: usize) -> Self {
+        let n = items.len();
+        let exhausted = k == 0 || k > n;
+        let c = std::iter::once(0).chain(0..k).chain(std::iter::once(n)).collect();
+        Self { items, c, k, first: true, exhausted }
+    }
+
+    /// Move to the next combination, or return false after the last one.
+    fn advance(&mut self) -> bool {
+        let (c, k) = (&mut self.c, self.k);
+        // Easy case: only the smallest index moves
+        if k % 2 == 1 && c[1] + 1 < c[2] {
+            c[1] += 1;
+            return true;
+        }
+        if k % 2 == 0 && c[1] > 0 {
+            c[1] -= 1;
+            return true;
+        }
+        // Otherwise alternately try to decrease and increase the next indices
+        let mut increase = k % 2 == 0;
+        for j in 2..=k {
+            if increase && c[j] + 1 < c[j + 1] {
+                c[j - 1] = c[j];
+                c[j] += 1;
+                return true;
+            }
+            if !increase && c[j] >= j {
+                c[j] = c[j - 1];
+                c[j - 1] = j - 2;
+                return true;
+            }
+            increase = !increase;
+        }
+        false
+    }
+}
+
+impl<T: Clone> Iterator for RevolvingDoorIterator<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+        if self.first {
+            self.first = false;
+        } else if !self.advance() {
+            self.exhausted = true;
+            return None;
+        }
+        Some(self.c[1..=self.k].iter().map(|&i| self.items[i].clone()).collect())
+    }
+}
+
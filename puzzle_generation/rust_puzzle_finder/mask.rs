@@ -0,0 +1,76 @@
+//! Bitmasks of the tiles of a grid, with bit `row * size + col` set for each
+//! tile, which are the same for every order of the tiles.
+//!
+//! Masks have 256 bits, enough for the cells of the largest grids
+//! ([`crate::MAX_SIZE`]). The clue sets of grids up to 11×11 only use the low
+//! 128 bits, and masks whose high half is zero hash like their low 128 bits,
+//! so that the bloom filters written by older versions stay valid.
+
+/// Set of the cells of an N×N grid (N ≤ 16).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileMask {
+    /// Bits 0 to 127, then bits 128 to 255.
+    halves: [u128; 2],
+}
+
+impl TileMask {
+    /// The mask of `tiles` of an N×N grid.
+    pub fn of_tiles(size: usize, tiles: &[(usize, usize)]) -> Self {
+        Self::of_cells(tiles.iter().map(|&(r, c)| r * size + c))
+    }
+
+    /// The mask of the cells numbered `row * size + col`.
+    pub fn of_cells(cells: impl IntoIterator<Item = usize>) -> Self {
+        let mut mask = Self::default();
+        for cell in cells {
+            mask.halves[cell / 128] |= 1 << (cell % 128);
+        }
+        mask
+    }
+
+    /// The mask with these 128-bit halves, low bits first.
+    pub fn from_halves(halves: [u128; 2]) -> Self {
+        Self { halves }
+    }
+
+    /// The 128-bit halves of the mask, low bits first.
+    pub fn halves(&self) -> [u128; 2] {
+        self.halves
+    }
+
+    /// Number of cells in the mask.
+    pub fn len(&self) -> usize {
+        self.halves.iter().map(|half| half.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.halves == [0, 0]
+    }
+
+    /// Whether every cell of the mask is in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.halves.iter().zip(&other.halves).all(|(half, other)| half & !other == 0)
+    }
+
+    /// Whether the mask and `other` have no cell in common.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.halves.iter().zip(&other.halves).all(|(half, other)| half & other == 0)
+    }
+
+    /// Whether the cell numbered `row * size + col` is in the mask.
+    pub fn contains(&self, cell: usize) -> bool {
+        self.halves[cell / 128] & 1 << (cell % 128) != 0
+    }
+
+    /// Which of `shards` (up to 64) independently locked shards of a set of
+    /// masks holds this one, from a multiplicative hash of its bits.
+    pub fn shard(&self, shards: usize) -> usize {
+        let folded = self.halves.iter().fold(0, |folded, &half| folded ^ half as u64 ^ (half >> 64) as u64);
+        (folded.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 58) as usize % shards
+    }
+
+    /// The cells of the mask, in increasing order.
+    pub fn cells(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..256).filter(|&cell| self.halves[cell / 128] & 1 << (cell % 128) != 0)
+    }
+}
@@ -0,0 +1,83 @@
+//! Counters and histograms in the Prometheus text format, to monitor long
+//! searches and the server with standard tooling.
+//!
+//! A search rewrites its metrics to a file after every batch, for the
+//! textfile collector of node_exporter; the server answers them on its
+//! `/metrics` endpoint.
+
+use std::fmt::{Display, Write as _};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bucket bounds of the solver nodes of a solve.
+pub const NODE_BUCKETS: [f64; 8] = [0.0, 1.0, 10.0, 100.0, 1e3, 1e4, 1e5, 1e6];
+/// Bucket bounds of request latencies, in seconds.
+pub const LATENCY_BUCKETS: [f64; 10] = [0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0];
+
+/// Histogram of observed values, updated from any thread.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    /// Observations by bucket, the last one above every bound.
+    counts: Vec<AtomicU64>,
+    /// Sum of the observations, as the bits of an `f64`.
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    /// An empty histogram with buckets up to each of `bounds`, increasing.
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(), sum: AtomicU64::new(0) }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let bucket = self.bounds.iter().position(|&bound| value <= bound).unwrap_or(self.bounds.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| Some((f64::from_bits(bits) + value).to_bits()));
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Append the samples of the histogram `name` with `labels` (such as
+    /// `path="/solve"`, or empty) to `text`.
+    pub fn write(&self, text: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            let bound = self.bounds.get(index).map_or("+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(text, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, cumulative);
+        }
+        let sum = f64::from_bits(self.sum.load(Ordering::Relaxed));
+        write_sample(text, &format!("{}_sum", name), labels, sum);
+        write_sample(text, &format!("{}_count", name), labels, cumulative);
+    }
+}
+
+/// Append the help and type lines of the metric `name` to `text`.
+pub fn write_header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+/// Append a sample of the metric `name` with `labels` (or none) to `text`.
+pub fn write_sample(text: &mut String, name: &str, labels: &str, value: impl Display) {
+    if labels.is_empty() {
+        let _ = writeln!(text, "{} {}", name, value);
+    } else {
+        let _ = writeln!(text, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// Write `text` to `path` through a temporary file renamed over it, so that
+/// collectors never read a partly written file.
+pub fn write_file(path: &Path, text: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, text)?;
+    std::fs::rename(&temporary, path)
+}
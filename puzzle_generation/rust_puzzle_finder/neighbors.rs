@@ -0,0 +1,72 @@
+//! Clue sets near a known puzzle: those placing the same tiles but for up to
+//! `distance` of them added or removed, i.e. within that Hamming distance of
+//! its tile mask.
+//!
+//! Puzzles near a unique one share most of its tiles and often its solving
+//! path, so its unique neighbors grow a family of related puzzles, e.g. for
+//! a themed pack, far faster than searching for them: a 6x6 puzzle has 36
+//! neighbors at distance 1 and 630 at distance 2.
+
+use rayon::prelude::*;
+
+use crate::{
+    all_tiles, puzzle_constraints_with, solve_combination, ClueSemantics, CombinationIterator, FoundPuzzle, Grid,
+    SolverOptions,
+};
+
+/// What a clue set's solutions make of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// Exactly one solution.
+    Unique,
+    /// Several solutions.
+    Multiple,
+    /// No solution.
+    Invalid,
+}
+
+/// A clue set near the puzzle.
+#[derive(Clone)]
+pub struct Neighbor {
+    /// Its sorted tiles.
+    pub tiles: Vec<(usize, usize)>,
+    /// Tiles added to or removed from the puzzle.
+    pub distance: usize,
+    pub class: Class,
+    /// The clue set as a found puzzle, if it is unique.
+    pub puzzle: Option<FoundPuzzle>,
+}
+
+/// Every clue set placing tiles of `grid` within `distance` of `tiles`
+/// (excluding `tiles` itself), closest first, each classified by its
+/// solutions with `semantics`.
+pub fn neighbors(
+    grid: &Grid,
+    tiles: &[(usize, usize)],
+    distance: usize,
+    semantics: ClueSemantics,
+    options: &SolverOptions,
+) -> Vec<Neighbor> {
+    let size = grid.size();
+    let tile_coordinates = all_tiles(size);
+    // Each set of cells to toggle gives one neighbor
+    let toggles: Vec<Vec<(usize, usize)>> = (1..=distance.min(tile_coordinates.len()))
+        .flat_map(|changed| CombinationIterator::new(tile_coordinates.clone(), changed))
+        .collect();
+    toggles
+        .par_iter()
+        .map(|toggled| {
+            let mut neighbor: Vec<(usize, usize)> =
+                tiles.iter().copied().filter(|tile| !toggled.contains(tile)).collect();
+            neighbor.extend(toggled.iter().copied().filter(|tile| !tiles.contains(tile)));
+            neighbor.sort_unstable();
+            let constraints = puzzle_constraints_with(&neighbor, grid, &tile_coordinates, semantics);
+            let (class, puzzle) = match solve_combination(&neighbor, &constraints, options, None) {
+                Ok(puzzle) => (Class::Unique, Some(puzzle)),
+                Err((_, solutions)) if solutions.is_empty() => (Class::Invalid, None),
+                Err(_) => (Class::Multiple, None),
+            };
+            Neighbor { tiles: neighbor, distance: toggled.len(), class, puzzle }
+        })
+        .collect()
+}
@@ -0,0 +1,69 @@
+//! Skipping the clue sets that fail for the same reason as an earlier one.
+//!
+//! With [`crate::ClueSemantics::GivensOnly`], the base grid always completes a
+//! clue set, so a clue set has another solution exactly when some other
+//! Latin square agrees with the base grid on all its tiles. When the solver
+//! finds such a square, the cells where it differs from the base grid (a
+//! trade, such as an intercalate) are a nogood: every clue set placing no tile
+//! on them has both squares as solutions, and is skipped without solving.
+//!
+//! This doesn't carry over to the game's semantics, where the tiles that are
+//! not placed are wrong where they stand: a solution then agrees with the
+//! base grid exactly on the placed tiles, so it completes no other clue set.
+
+use crate::mask::TileMask;
+use crate::Grid;
+
+/// Largest number of nogoods kept, so that checking a clue set stays cheap.
+pub const MAX_NOGOODS: usize = 4096;
+
+/// Nogoods found so far, as the masks of their cells.
+#[derive(Debug, Clone, Default)]
+pub struct NogoodSet {
+    size: usize,
+    masks: Vec<TileMask>,
+}
+
+impl NogoodSet {
+    /// An empty set for N×N grids.
+    pub fn new(size: usize) -> Self {
+        Self { size, masks: Vec::new() }
+    }
+
+    fn mask(&self, tiles: &[(usize, usize)]) -> TileMask {
+        TileMask::of_tiles(self.size, tiles)
+    }
+
+    /// Record the cells where `solution` differs from `grid`, both solutions
+    /// of a clue set. Nogoods containing another are redundant (every clue
+    /// set they rule out, the smaller one rules out too) and are not kept.
+    pub fn record(&mut self, solution: &Grid, grid: &Grid) {
+        let size = self.size;
+        let trade = TileMask::of_cells(
+            (0..size * size).filter(|&cell| solution[(cell / size, cell % size)] != grid[(cell / size, cell % size)]),
+        );
+        if trade.is_empty() || self.masks.iter().any(|known| known.is_subset(&trade)) {
+            return;
+        }
+        self.masks.retain(|known| !trade.is_subset(known));
+        if self.masks.len() < MAX_NOGOODS {
+            self.masks.push(trade);
+        }
+    }
+
+    /// Whether placing `tiles` leaves every cell of a recorded nogood empty,
+    /// so that the clue set has several solutions.
+    pub fn rules_out(&self, tiles: &[(usize, usize)]) -> bool {
+        let mask = self.mask(tiles);
+        self.masks.iter().any(|known| known.is_disjoint(&mask))
+    }
+
+    /// Number of nogoods recorded.
+    pub fn len(&self) -> usize {
+        self.masks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+}
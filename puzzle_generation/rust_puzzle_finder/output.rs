@@ -0,0 +1,705 @@
+//! Writers for the puzzle records produced by the search.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::certificate::Certificate;
+use crate::{ClueSemantics, FoundPuzzle, Grid, Variant};
+
+#[cfg(feature = "parquet")]
+mod parquet;
+
+/// Format of the records written to the output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One line of placed tile coordinates per puzzle, e.g. `(0,1), (2,3)`.
+    Text,
+    /// One CSV row per puzzle with solver metrics, preceded by a header row.
+    Csv,
+    /// One JSON object per line with the same fields as the CSV format.
+    Jsonl,
+    /// Columnar Parquet file with the same columns as the CSV format.
+    Parquet,
+}
+
+/// Compression applied to line-based output files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// Destination for the puzzles discovered by a search.
+pub trait PuzzleSink {
+    /// Record a batch of newly discovered, already deduplicated puzzles.
+    fn write_puzzles(&mut self, puzzles: &[FoundPuzzle]) -> io::Result<()>;
+
+    /// Complete the output once the search is over.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Version of the rules by which puzzles are read: the feedback of the game
+/// and the constraints it puts on solutions. It is written with every puzzle
+/// (in file headers, records, database rows and server responses) and bumped
+/// whenever the rules change, so that puzzles written under other rules are
+/// refused rather than misread. Puzzles written before it was recorded
+/// follow the rules of version 1.
+pub const RULES_VERSION: u32 = 1;
+
+/// Rules version of puzzles that don't record one.
+pub fn first_rules_version() -> u32 {
+    1
+}
+
+/// Check that puzzles of `rules_version` can be read by this version,
+/// naming `source` in the error message.
+pub fn check_rules_version(rules_version: u32, source: &str) -> Result<(), String> {
+    if rules_version == RULES_VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} follows rules version {}, but this version of the program reads rules version {}",
+            source, rules_version, RULES_VERSION
+        ))
+    }
+}
+
+/// Description of the puzzles of an output file, written at its top so that
+/// the file can be read without repeating the settings of the search: a
+/// `# ` comment line holding it as JSON in text and CSV files, a first
+/// `{"header": ...}` record in JSON lines files, and the `sudodle_header`
+/// key of the metadata of Parquet files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHeader {
+    pub size: usize,
+    /// Grid whose tiles the puzzles place, as written by [`format_grid`].
+    pub base_grid: String,
+    /// What the tiles that are not placed say, as the `--semantics` value.
+    pub semantics: String,
+    /// Rules of the solutions, as the `--variant` value, unless they are plain
+    /// Latin squares.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Generator of the clue sets, as the `--generator` value, if they were searched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    /// Seed of the constructor runs, if they were randomized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Version of the rules of the puzzles, see [`RULES_VERSION`].
+    #[serde(default = "first_rules_version")]
+    pub rules_version: u32,
+    /// Version of the program that wrote the file.
+    pub version: String,
+}
+
+impl FileHeader {
+    /// Header of puzzles placing tiles of the N×N cyclic square.
+    pub fn new(size: usize, semantics: ClueSemantics) -> Self {
+        Self {
+            size,
+            base_grid: format_grid(&crate::cyclic_latin_square(size)),
+            semantics: semantics.to_possible_value().expect("no skipped semantics").get_name().to_string(),
+            variant: None,
+            generator: None,
+            seed: None,
+            rules_version: RULES_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// The same header for puzzles placing tiles of `grid` instead.
+    pub fn with_base_grid(self, grid: &Grid) -> Self {
+        Self { base_grid: format_grid(grid), ..self }
+    }
+
+    /// The same header for puzzles whose solutions follow `variant`.
+    pub fn with_variant(self, variant: Variant) -> Self {
+        let name = variant.to_possible_value().expect("no skipped variant").get_name().to_string();
+        Self { variant: (variant != Variant::Latin).then_some(name), ..self }
+    }
+
+    /// The variant of the puzzles, or `None` if this version doesn't know it.
+    pub fn puzzle_variant(&self) -> Option<Variant> {
+        match &self.variant {
+            None => Some(Variant::Latin),
+            Some(name) => Variant::from_str(name, true).ok(),
+        }
+    }
+
+    /// The semantics of the puzzles, or `None` if this version doesn't know it.
+    pub fn clue_semantics(&self) -> Option<ClueSemantics> {
+        ClueSemantics::from_str(&self.semantics, true).ok()
+    }
+
+    /// Parse a header line of a text, CSV or JSON lines file.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        match line.strip_prefix('#') {
+            Some(comment) => serde_json::from_str(comment.trim()).ok(),
+            None => {
+                let record: serde_json::Value = serde_json::from_str(line).ok()?;
+                serde_json::from_value(record.get("header")?.clone()).ok()
+            }
+        }
+    }
+
+    /// Content ID of the puzzle placing `tiles`, see [`content_id`]; the
+    /// semantics of puzzles of a variant are keyed as `<semantics>+<variant>`.
+    pub fn content_id(&self, tiles: &[(usize, usize)]) -> String {
+        match &self.variant {
+            Some(variant) => content_id(self.size, &format!("{}+{}", self.semantics, variant), &self.base_grid, tiles),
+            None => content_id(self.size, &self.semantics, &self.base_grid, tiles),
+        }
+    }
+
+    /// The header line of a file of `format`.
+    pub fn line(&self, format: OutputFormat) -> String {
+        let json = serde_json::to_string(self).expect("headers serialize");
+        match format {
+            OutputFormat::Jsonl => format!("{{\"header\":{}}}", json),
+            _ => format!("# {}", json),
+        }
+    }
+}
+
+/// Stable ID of a puzzle, the same in every file, database and run: 16 hex
+/// digits of the 64-bit FNV-1a hash of its size, clue semantics (as in a
+/// [`FileHeader`]), base grid (as written by [`format_grid`]) and sorted
+/// tiles, e.g. `9c3e0b81f27d4a65`.
+///
+/// Unlike the web game's puzzle IDs ([`encode_puzzle_id`]), content IDs tell
+/// apart the same tiles read with other semantics or placed on another grid.
+pub fn content_id(size: usize, semantics: &str, base_grid: &str, tiles: &[(usize, usize)]) -> String {
+    let mut sorted = tiles.to_vec();
+    sorted.sort_unstable();
+    let key = format!("{}|{}|{}|{}", size, semantics, base_grid, encode_clues(&sorted));
+    let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+const CSV_HEADER: &str = "size,placed,clues,difficulty,backtrack_nodes,max_depth,forced_cells,propagation_passes,\
+solve_time_us,singles_solvable,mean_guesses,median_guesses,p90_guesses,content_id,rules_version,certificate";
+
+/// Create the file at `path` and return a sink writing puzzles to it in the
+/// given format, starting with `header`.
+///
+/// With `append`, records are added after the existing content of the file
+/// instead of truncating it.
+pub fn create_file_sink(
+    path: &str,
+    format: OutputFormat,
+    compression: Option<Compression>,
+    append: bool,
+    header: &FileHeader,
+) -> io::Result<Box<dyn PuzzleSink>> {
+    match format {
+        OutputFormat::Parquet if compression.is_some() => Err(io::Error::other(
+            "--compress only applies to line-based output",
+        )),
+        OutputFormat::Parquet if append => Err(io::Error::other(
+            "Parquet files cannot be appended to",
+        )),
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => Ok(Box::new(parquet::ParquetWriter::create(path, header)?)),
+        #[cfg(not(feature = "parquet"))]
+        OutputFormat::Parquet => Err(io::Error::other(
+            "this build does not include Parquet support (rebuild with `--features parquet`)",
+        )),
+        OutputFormat::Text | OutputFormat::Csv | OutputFormat::Jsonl => {
+            Ok(Box::new(PuzzleWriter::create(path, format, compression, append, header)?))
+        }
+    }
+}
+
+/// Wrap `file` in an encoder for the requested compression.
+///
+/// Encoders write their trailer when dropped. Appending to a compressed file
+/// adds a new gzip member or zstd frame, which decoders read back seamlessly.
+fn compressed_writer(file: File, compression: Option<Compression>) -> io::Result<Box<dyn Write>> {
+    match compression {
+        None => Ok(Box::new(file)),
+        #[cfg(feature = "gzip")]
+        Some(Compression::Gzip) => Ok(Box::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()))),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Ok(Box::new(zstd::Encoder::new(file, 0)?.auto_finish())),
+        #[allow(unreachable_patterns)]
+        Some(compression) => Err(io::Error::other(format!(
+            "this build does not include {:?} support (rebuild with `--features {}`)",
+            compression,
+            format!("{:?}", compression).to_lowercase(),
+        ))),
+    }
+}
+
+/// Streams discovered puzzles to a file, one record per puzzle.
+///
+/// The file is flushed after every batch of puzzles so that results survive
+/// an interrupted run.
+pub struct PuzzleWriter {
+    writer: BufWriter<Box<dyn Write>>,
+    format: OutputFormat,
+    header: FileHeader,
+}
+
+impl PuzzleWriter {
+    /// Create (or truncate) the file at `path` and write `header`, then the
+    /// format's column names, if any.
+    ///
+    /// With `append`, the file is extended instead and the headers are only
+    /// written if the file was empty. Appending CSV records fails if the file
+    /// has other columns, e.g. those of an earlier version.
+    /// Only the line-based formats are supported; see [`create_file_sink`].
+    pub fn create(
+        path: &str,
+        format: OutputFormat,
+        compression: Option<Compression>,
+        append: bool,
+        header: &FileHeader,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let is_empty = file.metadata()?.len() == 0;
+        if append && !is_empty && format == OutputFormat::Csv {
+            if let Some(columns) = read_csv_columns(path)?.filter(|columns| columns != CSV_HEADER) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("its CSV columns ({}) are not those written now ({})", columns, CSV_HEADER),
+                ));
+            }
+        }
+        Self::new(compressed_writer(file, compression)?, format, header, is_empty)
+    }
+
+    /// Write records of the puzzles described by `header` to `writer`,
+    /// starting with `header` and the format's column names if `write_header`
+    /// is set.
+    pub fn new(writer: Box<dyn Write>, format: OutputFormat, header: &FileHeader, write_header: bool) -> io::Result<Self> {
+        if format == OutputFormat::Parquet {
+            return Err(io::Error::other("Parquet output is not line-based"));
+        }
+        let mut writer = Self { writer: BufWriter::new(writer), format, header: header.clone() };
+        if write_header {
+            writeln!(writer.writer, "{}", header.line(format))?;
+            if format == OutputFormat::Csv {
+                writeln!(writer.writer, "{}", CSV_HEADER)?;
+            }
+            writer.writer.flush()?;
+        }
+        Ok(writer)
+    }
+
+    /// Write the record of a single puzzle.
+    pub fn write_puzzle(&mut self, puzzle: &FoundPuzzle) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Text => {
+                writeln!(self.writer, "{}", format_tiles(&puzzle.tiles))?;
+            }
+            OutputFormat::Csv => {
+                // Left empty when the puzzle was not played
+                let guesses = puzzle.expected_guesses.map_or(",,".to_string(), |guesses| {
+                    format!("{},{},{}", guesses.mean, guesses.median, guesses.p90)
+                });
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                    self.header.size,
+                    puzzle.tiles.len(),
+                    encode_clues(&puzzle.tiles),
+                    puzzle.stats.difficulty(),
+                    puzzle.stats.backtrack_nodes,
+                    puzzle.stats.max_depth,
+                    puzzle.stats.forced_cells,
+                    puzzle.stats.propagation_passes,
+                    puzzle.solve_time.as_micros(),
+                    puzzle.singles_solvable,
+                    guesses,
+                    self.header.content_id(&puzzle.tiles),
+                    RULES_VERSION,
+                    // Left empty when singles do not solve the puzzle
+                    puzzle.certificate.as_ref().map(Certificate::encode).unwrap_or_default(),
+                )?;
+            }
+            OutputFormat::Jsonl => {
+                let record = serde_json::json!({
+                    "size": self.header.size,
+                    "placed": puzzle.tiles.len(),
+                    "clues": encode_clues(&puzzle.tiles),
+                    "difficulty": puzzle.stats.difficulty(),
+                    "backtrack_nodes": puzzle.stats.backtrack_nodes,
+                    "max_depth": puzzle.stats.max_depth,
+                    "forced_cells": puzzle.stats.forced_cells,
+                    "propagation_passes": puzzle.stats.propagation_passes,
+                    "solve_time_us": puzzle.solve_time.as_micros() as u64,
+                    "singles_solvable": puzzle.singles_solvable,
+                    "mean_guesses": puzzle.expected_guesses.map(|guesses| guesses.mean),
+                    "median_guesses": puzzle.expected_guesses.map(|guesses| guesses.median),
+                    "p90_guesses": puzzle.expected_guesses.map(|guesses| guesses.p90),
+                    "content_id": self.header.content_id(&puzzle.tiles),
+                    "rules_version": RULES_VERSION,
+                    "certificate": puzzle.certificate,
+                });
+                writeln!(self.writer, "{}", record)?;
+            }
+            OutputFormat::Parquet => unreachable!("rejected by PuzzleWriter::new"),
+        }
+        Ok(())
+    }
+}
+
+impl PuzzleSink for PuzzleWriter {
+    fn write_puzzles(&mut self, puzzles: &[FoundPuzzle]) -> io::Result<()> {
+        for puzzle in puzzles {
+            self.write_puzzle(puzzle)?;
+        }
+        self.writer.flush()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Encode tile coordinates as `row:col` pairs separated by semicolons, e.g. `0:1;2:3`.
+///
+/// The encoding contains no commas so it can be stored in a single CSV field.
+pub fn encode_clues(tiles: &[(usize, usize)]) -> String {
+    tiles.iter()
+        .map(|(r, c)| format!("{}:{}", r, c))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Decode tile coordinates written by [`encode_clues`], skipping malformed pairs.
+pub fn decode_clues(encoded: &str) -> Vec<(usize, usize)> {
+    encoded.split(';')
+        .filter_map(|pair| {
+            let (r, c) = pair.split_once(':')?;
+            Some((r.parse().ok()?, c.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Format tile coordinates as a line of the text format, e.g. `(0,1), (2,3)`.
+pub fn format_tiles(tiles: &[(usize, usize)]) -> String {
+    tiles.iter()
+        .map(|(r, c)| format!("({},{})", r, c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a line of the text format back into tile coordinates.
+pub fn parse_tiles(line: &str) -> Option<Vec<(usize, usize)>> {
+    line.split("),")
+        .map(|coord| {
+            let coord = coord.trim().trim_start_matches('(').trim_end_matches(')');
+            let (r, c) = coord.split_once(',')?;
+            Some((r.trim().parse().ok()?, c.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse a puzzle in dot notation: the N×N tiles of the cyclic square row by
+/// row, with the value of the placed tiles and `.` for the others, e.g.
+/// `12...|2....|..5..|.....|.....` for `(0,0), (0,1), (1,0), (2,2)`.
+///
+/// `|`, `/` and whitespace between rows are ignored. Placed values must be
+/// those of the cyclic square, as in the game.
+pub fn parse_dot_notation(line: &str) -> Result<(usize, Vec<(usize, usize)>), String> {
+    let cells: Vec<char> = line.chars().filter(|c| !matches!(c, '|' | '/' | ' ' | '\t')).collect();
+    let size = (1..=crate::MAX_SIZE)
+        .find(|size| size * size == cells.len())
+        .ok_or_else(|| format!("expected the N×N tiles of a grid, got {} characters", cells.len()))?;
+    let grid = crate::cyclic_latin_square(size);
+    let mut tiles = Vec::new();
+    for (index, &cell) in cells.iter().enumerate() {
+        let (r, c) = (index / size, index % size);
+        match cell {
+            '.' => {}
+            _ if parse_value(cell) == Some(grid[(r, c)]) => tiles.push((r, c)),
+            _ => {
+                return Err(format!(
+                    "tile ({},{}) holds {} in the cyclic square, not '{}'",
+                    r,
+                    c,
+                    value_char(grid[(r, c)]),
+                    cell
+                ))
+            }
+        }
+    }
+    Ok((size, tiles))
+}
+
+/// Character of `value` in grids: its digit up to 9, then the letters `A` for
+/// 10 to `G` for 16, as in 16×16 Sudoku.
+pub fn value_char(value: u8) -> char {
+    match value {
+        0..=9 => char::from(b'0' + value),
+        _ => char::from(b'A' + value - 10),
+    }
+}
+
+/// Value of a character written by [`value_char`] (lowercase letters too).
+pub fn parse_value(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        '1'..='9' => Some(c as u8 - b'0'),
+        letter @ 'A'..='G' => Some(letter as u8 - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Format a full grid row by row, with `/` between rows, e.g. `123/231/312`,
+/// writing each value with [`value_char`].
+pub fn format_grid(grid: &Grid) -> String {
+    grid.rows()
+        .map(|row| row.iter().map(|&value| value_char(value)).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Parse a grid written by [`format_grid`] (`|` and whitespace between rows
+/// are also accepted), checking that it is a Latin square.
+pub fn parse_grid(text: &str) -> Result<Grid, String> {
+    let cells: Vec<char> = text.chars().filter(|c| !matches!(c, '|' | '/' | ' ' | '\t')).collect();
+    let size = (1..=crate::MAX_SIZE)
+        .find(|size| size * size == cells.len())
+        .ok_or_else(|| format!("expected the N×N values of a grid, got {} characters", cells.len()))?;
+    let mut grid = Grid::new(size);
+    for (index, &cell) in cells.iter().enumerate() {
+        let (r, c) = (index / size, index % size);
+        match parse_value(cell) {
+            Some(value) if usize::from(value) <= size => grid[(r, c)] = value,
+            _ => {
+                return Err(format!(
+                    "tile ({},{}) holds '{}', not a value between 1 and {}",
+                    r,
+                    c,
+                    cell,
+                    value_char(size as u8)
+                ))
+            }
+        }
+    }
+    crate::validate_latin_square(&grid).map_err(|violation| violation.to_string())?;
+    Ok(grid)
+}
+
+/// Largest size of the web game's puzzle IDs, which write it as one digit.
+pub const MAX_PUZZLE_ID_SIZE: usize = 9;
+
+/// Characters of the web game's puzzle IDs, indexed by tile number.
+const PUZZLE_ID_DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Encode a puzzle as a puzzle ID of the web game: the size, then one base-62
+/// digit per placed tile, numbering tiles row by row, e.g. `5015C`.
+///
+/// Returns `None` if the size or a tile number has no digit.
+pub fn encode_puzzle_id(size: usize, tiles: &[(usize, usize)]) -> Option<String> {
+    let mut indices: Vec<usize> = tiles.iter().map(|&(r, c)| r * size + c).collect();
+    indices.sort_unstable();
+    let mut id = String::new();
+    for index in std::iter::once(size).chain(indices) {
+        if size > MAX_PUZZLE_ID_SIZE || index >= PUZZLE_ID_DIGITS.len() {
+            return None;
+        }
+        id.push(PUZZLE_ID_DIGITS[index] as char);
+    }
+    Some(id)
+}
+
+/// Decode a puzzle ID written by [`encode_puzzle_id`] into the size and placed tiles.
+pub fn decode_puzzle_id(id: &str) -> Option<(usize, Vec<(usize, usize)>)> {
+    let mut digits = id.bytes().map(|digit| PUZZLE_ID_DIGITS.iter().position(|&d| d == digit));
+    let size = digits.next()??;
+    if !(1..=MAX_PUZZLE_ID_SIZE).contains(&size) {
+        return None;
+    }
+    let tiles = digits
+        .map(|index| index.filter(|&index| index < size * size).map(|index| (index / size, index % size)))
+        .collect::<Option<Vec<_>>>()?;
+    Some((size, tiles))
+}
+
+/// Open a puzzle file for reading, transparently decompressing gzip and zstd files.
+pub fn open_puzzle_file(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))));
+        #[cfg(not(feature = "gzip"))]
+        return Err(io::Error::other(format!("{}: this build does not include gzip support", path)));
+    }
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(io::Error::other(format!("{}: this build does not include zstd support", path)));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Read the placed tiles of every puzzle in a text, CSV or JSON lines puzzle file.
+///
+/// The format is detected from the CSV header line and from each JSON record;
+/// compressed files are decompressed transparently.
+pub fn read_puzzle_tiles(path: &str) -> io::Result<Vec<Vec<(usize, usize)>>> {
+    parse_puzzle_records(open_puzzle_file(path)?, path)
+}
+
+/// The column names line of a CSV puzzle file, if it has one.
+fn read_csv_columns(path: &str) -> io::Result<Option<String>> {
+    for line in open_puzzle_file(path)?.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            return Ok(line.starts_with("size,placed,clues,").then(|| line.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Read the header of a text, CSV or JSON lines puzzle file, if it has one.
+pub fn read_file_header(path: &str) -> io::Result<Option<FileHeader>> {
+    for line in open_puzzle_file(path)?.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            return Ok(FileHeader::parse_line(line.trim()));
+        }
+    }
+    Ok(None)
+}
+
+/// Parse the placed tiles of every puzzle in text, CSV or JSON lines records
+/// read from `reader`, naming `source` in error messages.
+///
+/// JSON records hold the tiles either as `tiles` pairs, e.g.
+/// `{"tiles": [[0, 1], [2, 3]]}`, or as `clues` in the CSV encoding.
+pub fn parse_puzzle_records(reader: impl BufRead, source: &str) -> io::Result<Vec<Vec<(usize, usize)>>> {
+    let mut puzzles = Vec::new();
+    visit_puzzle_records(reader, source, |tiles, _| puzzles.push(tiles))?;
+    Ok(puzzles)
+}
+
+/// Puzzle records of a file, kept as written.
+#[derive(Debug, Clone)]
+pub struct PuzzleLines {
+    /// Column names line of a CSV file.
+    pub columns: Option<String>,
+    /// Placed tiles of each puzzle, with the line of its record.
+    pub records: Vec<(Vec<(usize, usize)>, String)>,
+}
+
+impl PuzzleLines {
+    /// Format of the records: CSV if there is a column names line, JSON lines
+    /// if they are JSON objects, text otherwise.
+    pub fn format(&self) -> OutputFormat {
+        match (&self.columns, self.records.first()) {
+            (Some(_), _) => OutputFormat::Csv,
+            (None, Some((_, line))) if line.starts_with('{') => OutputFormat::Jsonl,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+/// Read the records of a text, CSV or JSON lines puzzle file with their
+/// lines, leaving out its header and comments.
+pub fn read_puzzle_lines(path: &str) -> io::Result<PuzzleLines> {
+    let mut records = Vec::new();
+    let columns = visit_puzzle_records(open_puzzle_file(path)?, path, |tiles, line| {
+        records.push((tiles, line.to_string()))
+    })?;
+    Ok(PuzzleLines { columns, records })
+}
+
+/// Call `visit` with the tiles and the trimmed line of each puzzle record
+/// read from `reader`, returning the column names line if the records are CSV.
+///
+/// Records of another [`RULES_VERSION`] are refused.
+fn visit_puzzle_records(
+    reader: impl BufRead,
+    source: &str,
+    mut visit: impl FnMut(Vec<(usize, usize)>, &str),
+) -> io::Result<Option<String>> {
+    let mut columns = None;
+    let mut rules_column = None;
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        // Skip comments, and the file headers of JSON lines files
+        if line.is_empty() || line.starts_with('#') || line.starts_with("{\"header\":") {
+            continue;
+        }
+        // Match on the leading columns so files written before columns were added still load
+        if line.starts_with("size,placed,clues,") {
+            columns = Some(line.to_string());
+            rules_column = line.split(',').position(|column| column == "rules_version");
+            continue;
+        }
+        let record = if line.starts_with('{') {
+            parse_json_record(line)
+        } else if columns.is_some() {
+            let rules_version = match rules_column {
+                Some(column) => line.split(',').nth(column).and_then(|value| value.parse().ok()),
+                None => Some(first_rules_version()),
+            };
+            line.split(',').nth(2).map(decode_clues).zip(rules_version)
+        } else {
+            parse_tiles(line).map(|tiles| (tiles, first_rules_version()))
+        };
+        match record {
+            Some((tiles, rules_version)) if !tiles.is_empty() => {
+                check_rules_version(rules_version, &format!("the record at {}:{}", source, line_number + 1))
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                visit(tiles, line)
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: could not parse puzzle record '{}'", source, line_number + 1, line),
+                ))
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// The certificate stored in a CSV or JSON lines puzzle record, if any, see
+/// [`crate::certificate`]. `columns` is the column names line of CSV records.
+pub fn record_certificate(columns: Option<&str>, line: &str) -> Option<Result<Certificate, String>> {
+    let text = if line.starts_with('{') {
+        let record: serde_json::Value = serde_json::from_str(line).ok()?;
+        record.get("certificate")?.as_str()?.to_string()
+    } else {
+        let column = columns?.split(',').position(|column| column == "certificate")?;
+        line.split(',').nth(column)?.to_string()
+    };
+    (!text.is_empty()).then(|| Certificate::parse(&text))
+}
+
+/// Read the tiles and rules version of a JSON puzzle record, see
+/// [`parse_puzzle_records`].
+fn parse_json_record(line: &str) -> Option<(Vec<(usize, usize)>, u32)> {
+    let record: serde_json::Value = serde_json::from_str(line).ok()?;
+    let tiles = match (record.get("tiles"), record.get("clues")) {
+        (Some(tiles), _) => serde_json::from_value(tiles.clone()).ok()?,
+        (None, Some(clues)) => decode_clues(clues.as_str()?),
+        (None, None) => return None,
+    };
+    let rules_version = match record.get("rules_version") {
+        Some(version) => u32::try_from(version.as_u64()?).ok()?,
+        None => first_rules_version(),
+    };
+    Some((tiles, rules_version))
+}
@@ -0,0 +1,107 @@
+//! Columnar Parquet output, for loading large result sets into Polars/DuckDB.
+
+use std::fs::File;
+use std::io;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+
+use super::{encode_clues, FileHeader, PuzzleSink, RULES_VERSION};
+use crate::certificate::Certificate;
+use crate::FoundPuzzle;
+
+/// Writes puzzles to a Parquet file with the same columns as the CSV format.
+///
+/// Rows are buffered into row groups by the underlying writer; the file footer
+/// is written by [`PuzzleSink::finish`] (or when the writer is dropped), so an
+/// interrupted run leaves an unreadable file.
+pub struct ParquetWriter {
+    writer: Option<ArrowWriter<File>>,
+    schema: Arc<Schema>,
+    header: FileHeader,
+}
+
+impl ParquetWriter {
+    /// Create (or truncate) the Parquet file at `path`, with `header` in its
+    /// metadata.
+    pub fn create(path: &str, header: &FileHeader) -> io::Result<Self> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("size", DataType::UInt32, false),
+            Field::new("placed", DataType::UInt32, false),
+            Field::new("clues", DataType::Utf8, false),
+            Field::new("difficulty", DataType::UInt64, false),
+            Field::new("backtrack_nodes", DataType::UInt64, false),
+            Field::new("max_depth", DataType::UInt64, false),
+            Field::new("forced_cells", DataType::UInt64, false),
+            Field::new("propagation_passes", DataType::UInt64, false),
+            Field::new("solve_time_us", DataType::UInt64, false),
+            Field::new("singles_solvable", DataType::Boolean, false),
+            Field::new("mean_guesses", DataType::Float64, true),
+            Field::new("median_guesses", DataType::UInt64, true),
+            Field::new("p90_guesses", DataType::UInt64, true),
+            Field::new("content_id", DataType::Utf8, false),
+            Field::new("rules_version", DataType::UInt32, false),
+            Field::new("certificate", DataType::Utf8, true),
+        ]));
+        let file = File::create(path)?;
+        let metadata = KeyValue::new("sudodle_header".to_string(), serde_json::to_string(header)?);
+        let properties = WriterProperties::builder().set_key_value_metadata(Some(vec![metadata])).build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(properties)).map_err(io::Error::other)?;
+        Ok(Self {
+            writer: Some(writer),
+            schema,
+            header: header.clone(),
+        })
+    }
+}
+
+impl PuzzleSink for ParquetWriter {
+    fn write_puzzles(&mut self, puzzles: &[FoundPuzzle]) -> io::Result<()> {
+        if puzzles.is_empty() {
+            return Ok(());
+        }
+        let u64_column = |f: &dyn Fn(&FoundPuzzle) -> u64| -> ArrayRef {
+            Arc::new(UInt64Array::from_iter_values(puzzles.iter().map(f)))
+        };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt32Array::from(vec![self.header.size as u32; puzzles.len()])),
+            Arc::new(UInt32Array::from_iter_values(puzzles.iter().map(|p| p.tiles.len() as u32))),
+            Arc::new(StringArray::from_iter_values(puzzles.iter().map(|p| encode_clues(&p.tiles)))),
+            u64_column(&|p| p.stats.difficulty() as u64),
+            u64_column(&|p| p.stats.backtrack_nodes as u64),
+            u64_column(&|p| p.stats.max_depth as u64),
+            u64_column(&|p| p.stats.forced_cells as u64),
+            u64_column(&|p| p.stats.propagation_passes as u64),
+            u64_column(&|p| p.solve_time.as_micros() as u64),
+            Arc::new(BooleanArray::from_iter(puzzles.iter().map(|p| Some(p.singles_solvable)))),
+            Arc::new(Float64Array::from_iter(puzzles.iter().map(|p| p.expected_guesses.map(|g| g.mean)))),
+            Arc::new(UInt64Array::from_iter(puzzles.iter().map(|p| p.expected_guesses.map(|g| g.median as u64)))),
+            Arc::new(UInt64Array::from_iter(puzzles.iter().map(|p| p.expected_guesses.map(|g| g.p90 as u64)))),
+            Arc::new(StringArray::from_iter_values(puzzles.iter().map(|p| self.header.content_id(&p.tiles)))),
+            Arc::new(UInt32Array::from(vec![RULES_VERSION; puzzles.len()])),
+            Arc::new(StringArray::from_iter(puzzles.iter().map(|p| p.certificate.as_ref().map(Certificate::encode)))),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(io::Error::other)?;
+        match self.writer.as_mut() {
+            Some(writer) => writer.write(&batch).map_err(io::Error::other),
+            None => Err(io::Error::other("Parquet writer already closed")),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.close().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ParquetWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,263 @@
+//! Pool of verified puzzles of each size and level of the web game, kept
+//! full by a background thread so that `serve` hands out puzzles instantly.
+//!
+//! The thread builds clue sets with greedy removal runs
+//! ([`generators::top_down`]), rates the unique ones by their difficulty
+//! and adds them to the pool of their level until it holds the target number
+//! of puzzles. It waits while every pool is full, and resumes as soon as a
+//! puzzle is taken. A puzzle is never added twice, so none is served twice.
+//!
+//! Greedy runs give puzzles of some levels more often than others: the pools
+//! of rare levels fill slowly, and the thread keeps searching until they do.
+//! With [`PoolOptions::families`], each puzzle found also brings its family
+//! ([`symmetry::family`]) to the pool of its level, every image served with
+//! its lineage: the puzzle it comes from and the symmetry mapping it there.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crate::mask::TileMask;
+use crate::symmetry::{self, Symmetry};
+use crate::{
+    check_puzzle, cyclic_latin_square, generators, output, ClueSemantics, FoundPuzzle, PuzzleSpec, SolverOptions,
+    Variant,
+};
+
+/// Levels of the web game, by increasing difficulty.
+pub const LEVELS: [&str; 3] = ["normal", "hard", "expert"];
+
+/// Settings of a puzzle pool.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Sizes of the puzzles kept.
+    pub sizes: Vec<usize>,
+    /// Puzzles kept ready for each size and level.
+    pub target: usize,
+    /// Smallest difficulty (number of guesses) rated `hard`.
+    pub hard_from: usize,
+    /// Smallest difficulty rated `expert`.
+    pub expert_from: usize,
+    /// Seed of the greedy removal runs.
+    pub seed: u64,
+    /// Add the family of each puzzle found along with it.
+    pub families: bool,
+    pub solver: SolverOptions,
+}
+
+impl PoolOptions {
+    /// Level of a puzzle of `difficulty`.
+    pub fn level(&self, difficulty: usize) -> &'static str {
+        if difficulty >= self.expert_from {
+            LEVELS[2]
+        } else if difficulty >= self.hard_from {
+            LEVELS[1]
+        } else {
+            LEVELS[0]
+        }
+    }
+}
+
+/// Where a puzzle of a family comes from.
+#[derive(Debug, Clone)]
+pub struct Lineage {
+    /// Content ID of the puzzle found by the search.
+    pub parent: String,
+    /// The symmetry mapping that puzzle to this one.
+    pub symmetry: Symmetry,
+}
+
+/// A puzzle of the pool, with its lineage if it was added as part of a family.
+#[derive(Clone)]
+pub struct PooledPuzzle {
+    pub puzzle: FoundPuzzle,
+    pub lineage: Option<Lineage>,
+}
+
+/// Counters of one size and level of a pool.
+#[derive(Default)]
+struct LevelPool {
+    puzzles: VecDeque<PooledPuzzle>,
+    served: u64,
+    /// Puzzles found while the pool was full.
+    discarded: u64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    levels: BTreeMap<(usize, &'static str), LevelPool>,
+    /// Tile masks of the puzzles added so far, by size.
+    seen: BTreeMap<usize, HashSet<TileMask>>,
+    runs: u64,
+    /// Runs ending without a unique puzzle, or with one found before.
+    failed_runs: u64,
+    /// Images of puzzles added as part of their families.
+    family_puzzles: u64,
+}
+
+impl PoolState {
+    /// Add `puzzle` to the pool of its level unless it was added before or
+    /// the pool is full; returns whether it was new.
+    fn add(&mut self, options: &PoolOptions, size: usize, puzzle: FoundPuzzle, lineage: Option<Lineage>) -> bool {
+        if !self.seen.entry(size).or_default().insert(TileMask::of_tiles(size, &puzzle.tiles)) {
+            return false;
+        }
+        let level = options.level(puzzle.stats.difficulty());
+        let level_pool = self.levels.get_mut(&(size, level)).expect("every level of every size has a pool");
+        if level_pool.puzzles.len() < options.target {
+            level_pool.puzzles.push_back(PooledPuzzle { puzzle, lineage });
+        } else {
+            level_pool.discarded += 1;
+        }
+        true
+    }
+}
+
+/// Puzzles ready to be served, shared between the connections and the
+/// thread refilling it.
+pub struct PuzzlePool {
+    options: PoolOptions,
+    state: Mutex<PoolState>,
+    /// Signalled when a puzzle is taken, to wake the refilling thread.
+    taken: Condvar,
+}
+
+impl PuzzlePool {
+    /// An empty pool; [`PuzzlePool::start`] fills it.
+    pub fn new(options: PoolOptions) -> Arc<Self> {
+        let mut state = PoolState::default();
+        for &size in &options.sizes {
+            for level in LEVELS {
+                state.levels.insert((size, level), LevelPool::default());
+            }
+        }
+        Arc::new(Self { options, state: Mutex::new(state), taken: Condvar::new() })
+    }
+
+    pub fn options(&self) -> &PoolOptions {
+        &self.options
+    }
+
+    /// Start the thread refilling the pool, which runs until the process ends.
+    pub fn start(self: &Arc<Self>) -> JoinHandle<()> {
+        let pool = Arc::clone(self);
+        std::thread::spawn(move || pool.refill())
+    }
+
+    /// Take a puzzle of `size` and `level`, or `None` if none is ready.
+    pub fn take(&self, size: usize, level: &str) -> Option<PooledPuzzle> {
+        let mut state = self.state.lock().unwrap();
+        let level_pool = state.levels.iter_mut().find(|((s, l), _)| *s == size && *l == level)?.1;
+        let puzzle = level_pool.puzzles.pop_front()?;
+        level_pool.served += 1;
+        self.taken.notify_one();
+        Some(puzzle)
+    }
+
+    /// Counters of the pool in the Prometheus text format.
+    pub fn metrics(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut text = String::new();
+        let series = [
+            ("sudodle_pool_puzzles", "gauge"),
+            ("sudodle_pool_served_total", "counter"),
+            ("sudodle_pool_discarded_total", "counter"),
+        ];
+        for (index, (name, kind)) in series.into_iter().enumerate() {
+            let _ = writeln!(text, "# TYPE {} {}", name, kind);
+            for ((size, level), level_pool) in &state.levels {
+                let values = [level_pool.puzzles.len() as u64, level_pool.served, level_pool.discarded];
+                let _ = writeln!(text, "{}{{size=\"{}\",level=\"{}\"}} {}", name, size, level, values[index]);
+            }
+        }
+        let _ = writeln!(text, "# TYPE sudodle_pool_target gauge\nsudodle_pool_target {}", self.options.target);
+        let _ = writeln!(text, "# TYPE sudodle_pool_runs_total counter\nsudodle_pool_runs_total {}", state.runs);
+        let _ = writeln!(
+            text,
+            "# TYPE sudodle_pool_family_puzzles_total counter\nsudodle_pool_family_puzzles_total {}",
+            state.family_puzzles
+        );
+        let _ = writeln!(
+            text,
+            "# TYPE sudodle_pool_failed_runs_total counter\nsudodle_pool_failed_runs_total {}",
+            state.failed_runs
+        );
+        text
+    }
+
+    /// Size with a level below target, taking sizes in turn after `previous`,
+    /// waiting while every pool is full.
+    fn next_size(&self, previous: usize) -> usize {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let sizes = &self.options.sizes;
+            let start = sizes.iter().position(|&size| size == previous).map_or(0, |index| index + 1);
+            let needed = (0..sizes.len()).map(|offset| sizes[(start + offset) % sizes.len()]).find(|&size| {
+                LEVELS.iter().any(|&level| state.levels[&(size, level)].puzzles.len() < self.options.target)
+            });
+            match needed {
+                Some(size) => return size,
+                None => state = self.taken.wait(state).unwrap(),
+            }
+        }
+    }
+
+    fn refill(&self) {
+        if self.options.sizes.is_empty() || self.options.target == 0 {
+            return;
+        }
+        let grids: BTreeMap<usize, _> =
+            self.options.sizes.iter().map(|&size| (size, cyclic_latin_square(size))).collect();
+        let mut size = self.options.sizes[self.options.sizes.len() - 1];
+        let mut run = 0;
+        loop {
+            size = self.next_size(size);
+            let tiles = generators::top_down(
+                &grids[&size],
+                0,
+                run,
+                self.options.seed,
+                &self.options.solver,
+                ClueSemantics::Sudodle,
+                Variant::Latin,
+            );
+            run += 1;
+            let puzzle =
+                tiles.and_then(|tiles| check_puzzle(&PuzzleSpec::new(size, &tiles), &self.options.solver).ok());
+            let mut state = self.state.lock().unwrap();
+            state.runs += 1;
+            let Some(puzzle) = puzzle else {
+                state.failed_runs += 1;
+                continue;
+            };
+            if !state.add(&self.options, size, puzzle.clone(), None) {
+                state.failed_runs += 1;
+                continue;
+            }
+            if !self.options.families {
+                continue;
+            }
+            drop(state);
+            let family = self.family(size, &puzzle);
+            let mut state = self.state.lock().unwrap();
+            for (image, lineage) in family {
+                state.family_puzzles += state.add(&self.options, size, image, Some(lineage)) as u64;
+            }
+        }
+    }
+
+    /// The images of the unique puzzle `puzzle` of `size`, each with its
+    /// lineage. They are checked again to rate them, as the solver may need
+    /// other guesses for an image, branching on the cells in a fixed order.
+    fn family(&self, size: usize, puzzle: &FoundPuzzle) -> Vec<(FoundPuzzle, Lineage)> {
+        let parent = output::FileHeader::new(size, ClueSemantics::Sudodle).content_id(&puzzle.tiles);
+        symmetry::family(size, puzzle)
+            .into_iter()
+            .filter_map(|(symmetry, image)| {
+                let image = check_puzzle(&PuzzleSpec::new(size, &image.tiles), &self.options.solver).ok()?;
+                Some((image, Lineage { parent: parent.clone(), symmetry }))
+            })
+            .collect()
+    }
+}
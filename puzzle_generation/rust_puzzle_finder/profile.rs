@@ -0,0 +1,198 @@
+//! Where the solver spends its time: a [`SearchObserver`] timing the steps of
+//! its search by depth and by the technique taking them.
+//!
+//! Each span of time between two steps of the search is charged to the depth
+//! of the search when the span ends, and to the reason of the step ending it:
+//! the time spent before a guess is charged to [`Reason::Guess`], the time
+//! spent before a naked single is placed to [`Reason::NakedSingle`], and so
+//! on. Spans ending with a return to the parent node, or with a solution, are
+//! only charged to their depth.
+//!
+//! Reading the clock at every step slows the solver down, so the times are
+//! those of the observed solver; their shares are what matters.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::trace::{Reason, SearchObserver};
+use crate::{solve_constraints, Constraints, Grid, SolveHooks, SolveStats, SolverOptions};
+
+/// Steps of one kind and the time spent taking them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub steps: u64,
+    pub time: Duration,
+}
+
+impl Counters {
+    fn add(&mut self, other: &Counters) {
+        self.steps += other.steps;
+        self.time += other.time;
+    }
+}
+
+/// The search at one depth: the root node, where propagation runs on the
+/// clues, is depth 0 and each decision opens a node one deeper.
+#[derive(Debug, Clone, Default)]
+pub struct DepthProfile {
+    /// Nodes opened at this depth.
+    pub nodes: u64,
+    /// Nodes at this depth found not to lead to a solution.
+    pub dead_ends: u64,
+    pub solutions: u64,
+    /// Time spent at this depth, excluding the nodes below.
+    pub time: Duration,
+    /// Steps taken at this depth, by reason, with the time spent before each.
+    pub reasons: BTreeMap<Reason, Counters>,
+}
+
+/// Profile of the solves of one or more puzzles.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub puzzles: usize,
+    /// By depth, from the root.
+    pub depths: Vec<DepthProfile>,
+}
+
+impl Profile {
+    /// Add the solves profiled in `other`.
+    pub fn merge(&mut self, other: &Profile) {
+        self.puzzles += other.puzzles;
+        if self.depths.len() < other.depths.len() {
+            self.depths.resize_with(other.depths.len(), DepthProfile::default);
+        }
+        for (depth, other) in self.depths.iter_mut().zip(&other.depths) {
+            depth.nodes += other.nodes;
+            depth.dead_ends += other.dead_ends;
+            depth.solutions += other.solutions;
+            depth.time += other.time;
+            for (reason, counters) in &other.reasons {
+                depth.reasons.entry(*reason).or_default().add(counters);
+            }
+        }
+    }
+
+    /// Time spent at every depth.
+    pub fn time(&self) -> Duration {
+        self.depths.iter().map(|depth| depth.time).sum()
+    }
+
+    /// Steps and time by reason, over every depth.
+    pub fn reasons(&self) -> BTreeMap<Reason, Counters> {
+        let mut reasons: BTreeMap<Reason, Counters> = BTreeMap::new();
+        for depth in &self.depths {
+            for (reason, counters) in &depth.reasons {
+                reasons.entry(*reason).or_default().add(counters);
+            }
+        }
+        reasons
+    }
+
+    /// The profile as folded stacks, one `depth 0;depth 1;…;reason nanoseconds`
+    /// line per depth and reason, the input of flame graph tools such as
+    /// `flamegraph.pl` or `inferno-flamegraph`.
+    pub fn folded(&self) -> String {
+        let mut text = String::new();
+        let mut stack = String::new();
+        for (index, depth) in self.depths.iter().enumerate() {
+            if index > 0 {
+                stack.push(';');
+            }
+            let _ = write!(stack, "depth {}", index);
+            let mut untracked = depth.time;
+            for (reason, counters) in &depth.reasons {
+                let _ = writeln!(text, "{};{} {}", stack, reason.name(), counters.time.as_nanos());
+                untracked = untracked.saturating_sub(counters.time);
+            }
+            if !untracked.is_zero() {
+                let _ = writeln!(text, "{} {}", stack, untracked.as_nanos());
+            }
+        }
+        text
+    }
+}
+
+/// Observer timing the steps of one solve; see the module documentation.
+pub struct Profiler {
+    profile: RefCell<Profile>,
+    depth: Cell<usize>,
+    last: Cell<Instant>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    /// A profiler whose first span starts now.
+    pub fn new() -> Self {
+        let root = DepthProfile { nodes: 1, ..DepthProfile::default() };
+        let profile = Profile { puzzles: 1, depths: vec![root] };
+        Self { profile: RefCell::new(profile), depth: Cell::new(0), last: Cell::new(Instant::now()) }
+    }
+
+    /// End the current span, charging it to the current depth and `reason`.
+    fn charge(&self, reason: Option<Reason>) {
+        let now = Instant::now();
+        let elapsed = now - self.last.replace(now);
+        let mut profile = self.profile.borrow_mut();
+        let depth = &mut profile.depths[self.depth.get()];
+        depth.time += elapsed;
+        if let Some(reason) = reason {
+            let counters = depth.reasons.entry(reason).or_default();
+            counters.steps += 1;
+            counters.time += elapsed;
+        }
+    }
+
+    /// The profile of the solve, charging the time since the last step to
+    /// the current depth.
+    pub fn into_profile(self) -> Profile {
+        self.charge(None);
+        self.profile.into_inner()
+    }
+}
+
+impl SearchObserver for Profiler {
+    fn enter_node(&self, _cell: (usize, usize), _value: usize, depth: usize, reason: Reason) {
+        self.charge(Some(reason));
+        self.depth.set(depth);
+        let mut profile = self.profile.borrow_mut();
+        if profile.depths.len() <= depth {
+            profile.depths.resize_with(depth + 1, DepthProfile::default);
+        }
+        profile.depths[depth].nodes += 1;
+    }
+
+    fn exit_node(&self, depth: usize) {
+        self.charge(None);
+        self.depth.set(depth.saturating_sub(1));
+    }
+
+    fn propagate(&self, _cell: (usize, usize), _value: usize, reason: Reason) {
+        self.charge(Some(reason));
+    }
+
+    fn contradiction(&self, reason: Reason) {
+        self.charge(Some(reason));
+        self.profile.borrow_mut().depths[self.depth.get()].dead_ends += 1;
+    }
+
+    fn solution(&self, _solution: &Grid) {
+        self.charge(None);
+        self.profile.borrow_mut().depths[self.depth.get()].solutions += 1;
+    }
+}
+
+/// Solve `constraints` for up to two solutions, as the uniqueness checks do,
+/// profiling the search.
+pub fn profile_solve(constraints: &Constraints, options: &SolverOptions) -> (Vec<Grid>, SolveStats, Profile) {
+    let profiler = Profiler::new();
+    let hooks = SolveHooks { observer: Some(&profiler), ..SolveHooks::default() };
+    let (solutions, stats) = solve_constraints(constraints, Some(2), options, hooks);
+    (solutions, stats, profiler.into_profile())
+}
@@ -0,0 +1,121 @@
+//! Serializable descriptions of puzzles and of their solutions, shared by the
+//! output files, `solve --stdin` and the HTTP server so that they all read
+//! and write the same JSON.
+//!
+//! A puzzle places tiles of the cyclic Latin square: [`PuzzleSpec`] gives its
+//! size and placed tiles, [`SolveReport`] the outcome of solving it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, RULES_VERSION};
+use crate::{Grid, MAX_SIZE};
+
+/// A tile placed at its position in the cyclic square, serialized as its
+/// `[row, col]` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "(usize, usize)", into = "(usize, usize)")]
+pub struct CluePlacement {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl From<(usize, usize)> for CluePlacement {
+    fn from((row, col): (usize, usize)) -> Self {
+        Self { row, col }
+    }
+}
+
+impl From<CluePlacement> for (usize, usize) {
+    fn from(placement: CluePlacement) -> Self {
+        (placement.row, placement.col)
+    }
+}
+
+/// A puzzle of the web game, e.g. `{"size": 5, "tiles": [[0, 0], [0, 1]]}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PuzzleSpec {
+    pub size: usize,
+    /// Tiles revealed as correctly placed.
+    pub tiles: Vec<CluePlacement>,
+    /// Rules by which the puzzle is read, see [`RULES_VERSION`]; the current
+    /// ones if left out.
+    #[serde(default = "current_rules_version")]
+    pub rules_version: u32,
+}
+
+fn current_rules_version() -> u32 {
+    RULES_VERSION
+}
+
+impl PuzzleSpec {
+    /// A puzzle placing `tiles`, read by the current rules.
+    pub fn new(size: usize, tiles: &[(usize, usize)]) -> Self {
+        Self { size, tiles: tiles.iter().copied().map(CluePlacement::from).collect(), rules_version: RULES_VERSION }
+    }
+
+    /// The puzzle of a shareable ID, see [`output::encode_puzzle_id`].
+    pub fn from_id(id: &str) -> Option<Self> {
+        output::decode_puzzle_id(id).map(|(size, tiles)| Self::new(size, &tiles))
+    }
+
+    /// The placed tiles as `(row, col)` pairs, as the solver takes them.
+    pub fn tile_pairs(&self) -> Vec<(usize, usize)> {
+        self.tiles.iter().copied().map(<(usize, usize)>::from).collect()
+    }
+
+    /// Check that the size and rules are supported and that every tile is in
+    /// the grid.
+    pub fn check(&self) -> Result<(), String> {
+        output::check_rules_version(self.rules_version, "the puzzle")?;
+        if !(1..=MAX_SIZE).contains(&self.size) {
+            return Err(format!("size must be between 1 and {}", MAX_SIZE));
+        }
+        match self.tiles.iter().find(|tile| tile.row >= self.size || tile.col >= self.size) {
+            Some(tile) => {
+                Err(format!("tile ({},{}) is outside of a {}x{} grid", tile.row, tile.col, self.size, self.size))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Outcome of solving a puzzle, see [`crate::solve_report`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolveReport {
+    pub size: usize,
+    /// The placed tiles, encoded as in the `clues` column of output files.
+    #[serde(with = "encoded_clues")]
+    pub clues: Vec<CluePlacement>,
+    pub unique: bool,
+    /// Solutions found, stopping at 2.
+    pub solutions: usize,
+    /// The unique completion of the puzzle, if it is unique.
+    pub solution: Option<Grid>,
+    pub difficulty: usize,
+    pub backtrack_nodes: usize,
+    /// Whether the search was abandoned at its deadline.
+    pub timed_out: bool,
+    pub solve_time_us: u64,
+    /// Stable ID of the puzzle, see [`output::content_id`].
+    pub content_id: String,
+    /// Rules by which the puzzle was read, see [`RULES_VERSION`].
+    pub rules_version: u32,
+}
+
+/// (De)serialization of placed tiles as `row:col` pairs, e.g. `0:1;2:3`.
+mod encoded_clues {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::CluePlacement;
+    use crate::output;
+
+    pub fn serialize<S: Serializer>(tiles: &[CluePlacement], serializer: S) -> Result<S::Ok, S::Error> {
+        let tiles: Vec<(usize, usize)> = tiles.iter().copied().map(<(usize, usize)>::from).collect();
+        serializer.serialize_str(&output::encode_clues(&tiles))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<CluePlacement>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Ok(output::decode_clues(&encoded).into_iter().map(CluePlacement::from).collect())
+    }
+}
@@ -0,0 +1,167 @@
+//! Quasigroup completion benchmark instances: the partial Latin squares that
+//! constraint and SAT solvers are usually compared on.
+//!
+//! A QCP instance (quasigroup completion problem) gives a share of the cells,
+//! filled one at a time with values that forward checking finds consistent
+//! with the earlier ones; it may still have no completion. A QWH instance
+//! (quasigroup with holes) punches holes in a random Latin square, so it
+//! always has one. Its holes are balanced, spread as evenly as possible over
+//! the rows and columns, which makes the hardest instances of a given size.
+
+use clap::ValueEnum;
+use rand::prelude::*;
+
+use crate::{all_tiles, cyclic_latin_square, Constraints, Grid};
+
+/// Largest order of the instances: the solver keeps candidate values as
+/// bitmasks of 32 bits.
+pub const MAX_ORDER: usize = 31;
+
+/// Family of benchmark instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Family {
+    /// Partial Latin squares filled with forward checking, satisfiable or not.
+    Qcp,
+    /// Random Latin squares with balanced holes, always satisfiable.
+    #[default]
+    Qwh,
+}
+
+/// A random Latin square of `size`, drawn uniformly by the Markov chain of
+/// Jacobson and Matthews, started from the cyclic square.
+///
+/// The chain moves on the incidence cube of the square, whose cell
+/// `(row, col, value)` is 1 when the square holds `value` at `(row, col)`.
+/// A move may leave one cell at -1 (an improper square), which the next moves
+/// resolve; the square is taken after `size³` moves, at the first proper one.
+pub fn random_latin_square(size: usize, rng: &mut impl Rng) -> Grid {
+    let start = cyclic_latin_square(size);
+    if size < 2 {
+        return start;
+    }
+    let index = |x: usize, y: usize, z: usize| (x * size + y) * size + z;
+    let mut cube = vec![0i8; size * size * size];
+    for x in 0..size {
+        for y in 0..size {
+            cube[index(x, y, start[(x, y)] as usize - 1)] = 1;
+        }
+    }
+    let mut improper: Option<(usize, usize, usize)> = None;
+    let mut moves = 0;
+    while moves < size.pow(3) || improper.is_some() {
+        moves += 1;
+        let (x, y, z) = improper.unwrap_or_else(|| loop {
+            let cell = (rng.gen_range(0..size), rng.gen_range(0..size), rng.gen_range(0..size));
+            if cube[index(cell.0, cell.1, cell.2)] == 0 {
+                break cell;
+            }
+        });
+        // A proper square has one 1 on each line through the cell, an
+        // improper one two, of which the move takes either
+        let mut pick = |ones: Vec<usize>| *ones.choose(rng).expect("each line holds a 1");
+        let x1 = pick((0..size).filter(|&a| cube[index(a, y, z)] == 1).collect());
+        let y1 = pick((0..size).filter(|&b| cube[index(x, b, z)] == 1).collect());
+        let z1 = pick((0..size).filter(|&c| cube[index(x, y, c)] == 1).collect());
+        for (a, b, c) in [(x, y, z), (x, y1, z1), (x1, y, z1), (x1, y1, z)] {
+            cube[index(a, b, c)] += 1;
+        }
+        for (a, b, c) in [(x, y, z1), (x, y1, z), (x1, y, z), (x1, y1, z1)] {
+            cube[index(a, b, c)] -= 1;
+        }
+        improper = (cube[index(x1, y1, z1)] < 0).then_some((x1, y1, z1));
+    }
+    let mut square = Grid::new(size);
+    for x in 0..size {
+        for y in 0..size {
+            let z = (0..size).find(|&z| cube[index(x, y, z)] == 1).expect("a proper square fills every cell");
+            square[(x, y)] = z as u8 + 1;
+        }
+    }
+    square
+}
+
+/// A QWH instance: a random Latin square of `size` with `holes` cells left
+/// empty, at most one more in a row or column than in any other.
+///
+/// The holes are the cells holding the smallest values of a second random
+/// Latin square, which has each value once in every row and column: a full
+/// value per `size` holes, and random cells of the next value for the rest.
+pub fn qwh(size: usize, holes: usize, rng: &mut impl Rng) -> Constraints {
+    let square = random_latin_square(size, rng);
+    let pattern = random_latin_square(size, rng);
+    let holes = holes.min(size * size);
+    let (full, rest) = ((holes / size) as u8, holes % size);
+    let mut partial: Vec<(usize, usize)> = (0..size)
+        .flat_map(|i| (0..size).map(move |j| (i, j)))
+        .filter(|&cell| pattern[cell] == full + 1)
+        .collect();
+    partial.shuffle(rng);
+    partial.truncate(rest);
+    let mut constraints = Constraints::new(size);
+    for i in 0..size {
+        for j in 0..size {
+            if pattern[(i, j)] > full && !partial.contains(&(i, j)) {
+                constraints.set_known((i, j), square[(i, j)] as usize);
+            }
+        }
+    }
+    constraints
+}
+
+/// A QCP instance: `filled` cells of a grid of `size`, drawn in a random
+/// order, each given a random value that leaves a candidate to every empty
+/// cell of its row and column. Cells left with no such value are skipped, so
+/// that high fill ratios may give fewer cells than asked.
+pub fn qcp(size: usize, filled: usize, rng: &mut impl Rng) -> Constraints {
+    let full_mask = (1u32 << size) - 1;
+    let mut cells = all_tiles(size);
+    cells.shuffle(rng);
+    let mut constraints = Constraints::new(size);
+    let (mut rows, mut cols) = (vec![0u32; size], vec![0u32; size]);
+    let mut given = 0;
+    for (i, j) in cells {
+        if given == filled {
+            break;
+        }
+        let mut values: Vec<usize> = (0..size).filter(|&v| (rows[i] | cols[j]) & 1 << v == 0).collect();
+        values.shuffle(rng);
+        let forward_checks = |v: usize| {
+            let (row, col) = (rows[i] | 1 << v, cols[j] | 1 << v);
+            (0..size).all(|k| {
+                (k == j || constraints.known[(i, k)] != 0 || row | cols[k] != full_mask)
+                    && (k == i || constraints.known[(k, j)] != 0 || rows[k] | col != full_mask)
+            })
+        };
+        if let Some(v) = values.into_iter().find(|&v| forward_checks(v)) {
+            rows[i] |= 1 << v;
+            cols[j] |= 1 << v;
+            constraints.set_known((i, j), v + 1);
+            given += 1;
+        }
+    }
+    constraints
+}
+
+/// An instance of `family` of `size` whose given cells are the share `fill`
+/// of the grid, rounded to the nearest cell.
+pub fn instance(family: Family, size: usize, fill: f64, rng: &mut impl Rng) -> Constraints {
+    let given = ((size * size) as f64 * fill.clamp(0.0, 1.0)).round() as usize;
+    match family {
+        Family::Qcp => qcp(size, given, rng),
+        Family::Qwh => qwh(size, size * size - given, rng),
+    }
+}
+
+/// `constraints` in the `.pls` format of the `lsencode` generator of QCP
+/// instances: a line `order N`, then a line per row with the values counted
+/// from 0, and -1 for the empty cells.
+pub fn format_pls(constraints: &Constraints) -> String {
+    let size = constraints.size;
+    let mut text = format!("order {}\n", size);
+    for i in 0..size {
+        let row: Vec<String> = (0..size).map(|j| (constraints.known[(i, j)] as i32 - 1).to_string()).collect();
+        text.push_str(&row.join(" "));
+        text.push('\n');
+    }
+    text
+}
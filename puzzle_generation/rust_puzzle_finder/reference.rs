@@ -0,0 +1,65 @@
+//! Slow reference solver used to cross-check the verdicts of the optimized one.
+//!
+//! It fills the cells in row-major order, trying every value in ascending order
+//! and checking the row, the column, the rules of the variant (the broken
+//! diagonals, the neighbours or the box) and the clues of the cell, with no
+//! propagation, cell ordering or other heuristic that could hide a bug.
+
+use crate::{Constraints, Grid, Variant};
+
+/// Find the completions of `constraints`, stopping after `max_solutions`.
+pub fn solve(constraints: &Constraints, max_solutions: usize) -> Vec<Grid> {
+    let mut square = Grid::new(constraints.size);
+    let mut solutions = Vec::new();
+    if max_solutions > 0 {
+        fill(constraints, &mut square, 0, max_solutions, &mut solutions);
+    }
+    solutions
+}
+
+/// Whether `value` can go at `(i, j)` given the cells already filled.
+fn allowed(constraints: &Constraints, square: &Grid, (i, j): (usize, usize), value: u8) -> bool {
+    let size = constraints.size;
+    let known = constraints.known[(i, j)];
+    if known != 0 && known != value {
+        return false;
+    }
+    if constraints.wrong[i * size + j] & (1 << (value - 1)) != 0 {
+        return false;
+    }
+    let variant_allowed = match constraints.variant {
+        Variant::Latin => true,
+        Variant::Pandiagonal => (0..size).all(|k| {
+            square[(k, (k + size + j - i) % size)] != value && square[(k, (i + j + size - k) % size)] != value
+        }),
+        Variant::NonConsecutive => [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)]
+            .into_iter()
+            .filter(|&(r, c)| r < size && c < size)
+            .all(|cell| square[cell] == 0 || square[cell].abs_diff(value) != 1),
+        Variant::Sudoku => {
+            let (height, width) = crate::box_shape(size);
+            let (top, left) = (i / height * height, j / width * width);
+            (top..top + height).all(|r| (left..left + width).all(|c| square[(r, c)] != value))
+        }
+    };
+    variant_allowed && (0..size).all(|k| square[(i, k)] != value && square[(k, j)] != value)
+}
+
+fn fill(constraints: &Constraints, square: &mut Grid, cell: usize, max_solutions: usize, solutions: &mut Vec<Grid>) {
+    let size = constraints.size;
+    if cell == size * size {
+        solutions.push(square.clone());
+        return;
+    }
+    let (i, j) = (cell / size, cell % size);
+    for value in 1..=size as u8 {
+        if allowed(constraints, square, (i, j), value) {
+            square[(i, j)] = value;
+            fill(constraints, square, cell + 1, max_solutions, solutions);
+            square[(i, j)] = 0;
+            if solutions.len() >= max_solutions {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Images of puzzles, drawn as the web game shows them: the cyclic square,
+//! with the placed tiles on a green background and the other tiles, known to
+//! be wrong where they stand, in dark orange. Classic puzzles only show their
+//! placed tiles. The solution can be drawn over the tiles that are not placed.
+//!
+//! Images are SVG documents; building with the `png` feature also converts
+//! them to PNG images with resvg.
+
+use std::fmt::Write;
+
+use crate::{cyclic_latin_square, ClueSemantics, Grid};
+
+/// Settings of the images of puzzles.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    /// Width and height of a cell, in pixels.
+    pub cell_size: u32,
+    /// What the tiles that are not placed say, deciding whether they are drawn.
+    pub semantics: ClueSemantics,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { cell_size: 60, semantics: ClueSemantics::Sudodle }
+    }
+}
+
+const PLACED_BACKGROUND: &str = "#28a745";
+const PLACED_COLOR: &str = "#ffffff";
+const WRONG_BACKGROUND: &str = "#fff3cd";
+const WRONG_COLOR: &str = "#856404";
+const SOLUTION_COLOR: &str = "#1f6feb";
+const LINE_COLOR: &str = "#495057";
+
+/// Width and height of the image of an N×N puzzle, in pixels.
+pub fn image_size(size: usize, options: &RenderOptions) -> u32 {
+    size as u32 * options.cell_size + 2 * margin(options)
+}
+
+fn margin(options: &RenderOptions) -> u32 {
+    options.cell_size.div_ceil(10)
+}
+
+/// SVG document of the N×N puzzle placing `tiles`, with `solution` drawn in
+/// the cells that are not placed if there is one.
+pub fn puzzle_svg(size: usize, tiles: &[(usize, usize)], solution: Option<&Grid>, options: &RenderOptions) -> String {
+    let side = image_size(size, options);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{side}\" height=\"{side}\" viewBox=\"0 0 {side} {side}\">\n\
+         <rect width=\"{side}\" height=\"{side}\" fill=\"#ffffff\"/>\n"
+    );
+    write_puzzle(&mut svg, 0, 0, size, tiles, solution, options);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// An element of the image of a puzzle, in pixels from its top-left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    /// A filled square.
+    Cell { x: u32, y: u32, side: u32, fill: &'static str },
+    /// A straight line.
+    Line { from: (u32, u32), to: (u32, u32), width: u32, color: &'static str },
+    /// The outline of a square.
+    Frame { x: u32, y: u32, side: u32, width: u32, color: &'static str },
+    /// A digit in bold, centered on `x`, with its baseline at `y`.
+    Digit { x: u32, y: u32, font_size: u32, color: &'static str, value: u8 },
+}
+
+/// The shapes of the image of the N×N puzzle placing `tiles`, with
+/// `solution` drawn in the cells that are not placed if there is one, drawn
+/// in order within a square of side [`image_size`].
+pub fn puzzle_shapes(
+    size: usize,
+    tiles: &[(usize, usize)],
+    solution: Option<&Grid>,
+    options: &RenderOptions,
+) -> Vec<Shape> {
+    let grid = cyclic_latin_square(size);
+    let cell = options.cell_size;
+    let margin = margin(options);
+    let font_size = cell * 11 / 20;
+    let mut shapes = Vec::new();
+    for r in 0..size {
+        for c in 0..size {
+            let placed = tiles.contains(&(r, c));
+            let shown_wrong = !placed && options.semantics == ClueSemantics::Sudodle;
+            let (left, top) = (margin + c as u32 * cell, margin + r as u32 * cell);
+            let fill = match (placed, shown_wrong) {
+                (true, _) => PLACED_BACKGROUND,
+                (false, true) => WRONG_BACKGROUND,
+                (false, false) => "#ffffff",
+            };
+            shapes.push(Shape::Cell { x: left, y: top, side: cell, fill });
+            let (x, y) = (left + cell / 2, top + cell / 2 + font_size * 7 / 20);
+            match (placed, solution) {
+                (true, _) => shapes.push(Shape::Digit { x, y, font_size, color: PLACED_COLOR, value: grid[(r, c)] }),
+                (false, Some(solution)) => {
+                    shapes.push(Shape::Digit { x, y, font_size, color: SOLUTION_COLOR, value: solution[(r, c)] });
+                    // Keep the wrong value of the square in the corner
+                    if shown_wrong {
+                        let small = font_size * 2 / 5;
+                        let (x, y) = (left + small, top + small + small / 4);
+                        shapes.push(Shape::Digit { x, y, font_size: small, color: WRONG_COLOR, value: grid[(r, c)] });
+                    }
+                }
+                (false, None) if shown_wrong => {
+                    shapes.push(Shape::Digit { x, y, font_size, color: WRONG_COLOR, value: grid[(r, c)] })
+                }
+                (false, None) => {}
+            }
+        }
+    }
+    let (start, end) = (margin, margin + size as u32 * cell);
+    for line in 1..size as u32 {
+        let at = margin + line * cell;
+        shapes.push(Shape::Line { from: (at, start), to: (at, end), width: 1, color: LINE_COLOR });
+        shapes.push(Shape::Line { from: (start, at), to: (end, at), width: 1, color: LINE_COLOR });
+    }
+    shapes.push(Shape::Frame { x: start, y: start, side: end - start, width: margin.max(2), color: LINE_COLOR });
+    shapes
+}
+
+/// Append the SVG elements of the puzzle to `svg`, as a group whose top-left
+/// corner is at (`x`, `y`) and whose size is [`image_size`].
+pub fn write_puzzle(
+    svg: &mut String,
+    x: u32,
+    y: u32,
+    size: usize,
+    tiles: &[(usize, usize)],
+    solution: Option<&Grid>,
+    options: &RenderOptions,
+) {
+    let _ = writeln!(
+        svg,
+        "<g transform=\"translate({x},{y})\" font-family=\"Helvetica, Arial, sans-serif\" text-anchor=\"middle\">"
+    );
+    for shape in puzzle_shapes(size, tiles, solution, options) {
+        let _ = match shape {
+            Shape::Cell { x, y, side, fill } => {
+                writeln!(svg, "<rect x=\"{x}\" y=\"{y}\" width=\"{side}\" height=\"{side}\" fill=\"{fill}\"/>")
+            }
+            Shape::Line { from, to, width, color } => writeln!(
+                svg,
+                "<path d=\"M{} {}L{} {}\" stroke=\"{color}\" stroke-width=\"{width}\"/>",
+                from.0, from.1, to.0, to.1
+            ),
+            Shape::Frame { x, y, side, width, color } => writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{side}\" height=\"{side}\" fill=\"none\" stroke=\"{color}\" \
+                 stroke-width=\"{width}\"/>"
+            ),
+            Shape::Digit { x, y, font_size, color, value } => writeln!(
+                svg,
+                "<text x=\"{x}\" y=\"{y}\" font-size=\"{font_size}\" font-weight=\"bold\" fill=\"{color}\">{value}</text>"
+            ),
+        };
+    }
+    svg.push_str("</g>\n");
+}
+
+/// PNG image of an SVG document, drawn with the system fonts.
+#[cfg(feature = "png")]
+pub fn svg_to_png(svg: &str) -> Result<Vec<u8>, String> {
+    use resvg::{tiny_skia, usvg};
+
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("invalid SVG: {}", e))?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("empty image")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| format!("cannot encode the PNG image: {}", e))
+}
+
+#[cfg(not(feature = "png"))]
+pub fn svg_to_png(_svg: &str) -> Result<Vec<u8>, String> {
+    Err("this build does not include PNG support (rebuild with `--features png`)".to_string())
+}
@@ -0,0 +1,173 @@
+//! Repairs of puzzles without a unique solution: the fewest tiles to place
+//! in addition to theirs so that they have one.
+//!
+//! With [`ClueSemantics::GivensOnly`], the cyclic square is always a
+//! solution, and every other solution must be ruled out by a tile placed on
+//! a cell where it differs from the square. The search branches on the cells
+//! of the other solution differing on the fewest cells (the witness of
+//! [`crate::witness`], against the square), which finds every smallest
+//! repair while trying few tiles.
+//!
+//! With the game's semantics, placing a tile says that the solution agrees
+//! with the square on its cell, where every solution of the puzzle said it
+//! didn't: the repaired puzzle has none of the solutions of the original one.
+//! Clue sets with each number of extra tiles are then tested in turn, the
+//! cells on which two solutions differ first.
+
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+
+use crate::incremental::IncrementalSolver;
+use crate::witness::{self, Witness};
+use crate::{
+    all_tiles, cyclic_latin_square, puzzle_constraints_with, solve_constraints, ClueSemantics, CombinationIterator,
+    Grid, SolveHooks, SolverOptions,
+};
+
+/// Smallest repairs of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repairs {
+    /// Number of tiles added by each repair.
+    pub extra: usize,
+    /// Tiles added by each repair, sorted, with the difficulty of the
+    /// repaired puzzle, by increasing difficulty.
+    pub repairs: Vec<(Vec<(usize, usize)>, usize)>,
+}
+
+/// Settings of a repair search.
+#[derive(Debug, Clone, Copy)]
+pub struct RepairOptions {
+    /// Most tiles to add.
+    pub max_extra: usize,
+    /// Most repairs to return.
+    pub limit: usize,
+    /// Solutions compared to find the cells on which they differ.
+    pub witness_solutions: usize,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self { max_extra: 3, limit: 10, witness_solutions: witness::DEFAULT_WITNESS_SOLUTIONS }
+    }
+}
+
+/// Difficulty of the N×N puzzle placing `tiles`, or `None` if it does not
+/// have a unique solution.
+fn unique_difficulty(size: usize, tiles: &[(usize, usize)], semantics: ClueSemantics) -> Option<usize> {
+    let tile_coordinates = all_tiles(size);
+    let constraints = puzzle_constraints_with(tiles, &cyclic_latin_square(size), &tile_coordinates, semantics);
+    let (solutions, stats) = solve_constraints(&constraints, Some(2), &SolverOptions::default(), SolveHooks::default());
+    (solutions.len() == 1).then(|| stats.difficulty())
+}
+
+/// The smallest sets of tiles to add to the N×N puzzle placing `tiles` so
+/// that it has a unique solution, with up to `options.max_extra` tiles, or
+/// `None` if there are none. A unique puzzle needs no extra tile.
+pub fn repair(
+    size: usize,
+    tiles: &[(usize, usize)],
+    semantics: ClueSemantics,
+    options: &RepairOptions,
+) -> Option<Repairs> {
+    if let Some(difficulty) = unique_difficulty(size, tiles, semantics) {
+        return Some(Repairs { extra: 0, repairs: vec![(Vec::new(), difficulty)] });
+    }
+    let mut found = match semantics {
+        ClueSemantics::GivensOnly => repair_givens(size, tiles, options),
+        ClueSemantics::Sudodle => repair_sudodle(size, tiles, options),
+    }?;
+    found.repairs.sort_by_key(|(extra, difficulty)| (*difficulty, extra.clone()));
+    found.repairs.truncate(options.limit);
+    Some(found)
+}
+
+/// Repairs of a classic puzzle, found by branching on the cells where a
+/// solution differs from the square, with an increasing number of tiles.
+fn repair_givens(size: usize, tiles: &[(usize, usize)], options: &RepairOptions) -> Option<Repairs> {
+    let grid = cyclic_latin_square(size);
+    for extra in 1..=options.max_extra {
+        let mut repairs = BTreeSet::new();
+        let mut added = Vec::new();
+        let constraints = puzzle_constraints_with(tiles, &grid, &all_tiles(size), ClueSemantics::GivensOnly);
+        let mut solver = IncrementalSolver::new(constraints, SolverOptions::default());
+        branch_givens(&grid, extra, options, &mut solver, &mut added, &mut repairs);
+        if !repairs.is_empty() {
+            let repairs = repairs
+                .into_iter()
+                .filter_map(|extra_tiles: Vec<(usize, usize)>| {
+                    let all: Vec<_> = tiles.iter().chain(&extra_tiles).copied().collect();
+                    unique_difficulty(size, &all, ClueSemantics::GivensOnly).map(|difficulty| (extra_tiles, difficulty))
+                })
+                .collect();
+            return Some(Repairs { extra, repairs });
+        }
+    }
+    None
+}
+
+/// Add up to `budget` tiles to `added`, and to the puzzle of `solver`,
+/// recording the sets making the puzzle unique.
+fn branch_givens(
+    grid: &Grid,
+    budget: usize,
+    options: &RepairOptions,
+    solver: &mut IncrementalSolver,
+    added: &mut Vec<(usize, usize)>,
+    repairs: &mut BTreeSet<Vec<(usize, usize)>>,
+) {
+    if solver.solution_count(options.witness_solutions.max(2)) == 1 {
+        let mut repair = added.clone();
+        repair.sort_unstable();
+        repairs.insert(repair);
+        return;
+    }
+    if budget == 0 {
+        return;
+    }
+    // Some tile added must fall on a cell where each other solution differs from the square
+    let Some(witness) = solver
+        .solutions()
+        .iter()
+        .filter(|solution| *solution != grid)
+        .map(|solution| Witness::between(grid, solution))
+        .min_by_key(Witness::len)
+    else {
+        return;
+    };
+    for &(cell, _, _) in &witness.cells {
+        added.push(cell);
+        let id = solver.add_given(cell, grid[cell].into());
+        branch_givens(grid, budget - 1, options, solver, added, repairs);
+        solver.remove(id);
+        added.pop();
+    }
+}
+
+/// Repairs of a puzzle of the game, testing every set of each number of
+/// extra tiles, the cells on which two solutions differ first.
+fn repair_sudodle(size: usize, tiles: &[(usize, usize)], options: &RepairOptions) -> Option<Repairs> {
+    let tile_coordinates = all_tiles(size);
+    let constraints =
+        puzzle_constraints_with(tiles, &cyclic_latin_square(size), &tile_coordinates, ClueSemantics::Sudodle);
+    let distinguishing: Vec<(usize, usize)> = witness::find_witness(&constraints, options.witness_solutions)
+        .map(|witness| witness.cells.iter().map(|&(cell, _, _)| cell).collect())
+        .unwrap_or_default();
+    let mut candidates: Vec<(usize, usize)> = distinguishing.clone();
+    candidates.extend(tile_coordinates.iter().filter(|cell| !tiles.contains(cell) && !distinguishing.contains(cell)));
+    for extra in 1..=options.max_extra.min(candidates.len()) {
+        let repairs: Vec<(Vec<(usize, usize)>, usize)> = CombinationIterator::new(candidates.clone(), extra)
+            .par_bridge()
+            .filter_map(|mut extra_tiles| {
+                let all: Vec<_> = tiles.iter().chain(&extra_tiles).copied().collect();
+                let difficulty = unique_difficulty(size, &all, ClueSemantics::Sudodle)?;
+                extra_tiles.sort_unstable();
+                Some((extra_tiles, difficulty))
+            })
+            .collect();
+        if !repairs.is_empty() {
+            return Some(Repairs { extra, repairs });
+        }
+    }
+    None
+}
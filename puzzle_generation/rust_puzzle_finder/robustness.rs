@@ -0,0 +1,67 @@
+//! Robustness of the clues of unique puzzles: whether a puzzle stays unique
+//! when one of its placed tiles is moved to another cell of its row or of
+//! its column, as an editor adjusting a puzzle might. The share of such moves
+//! keeping the puzzle unique is its wiggle room.
+
+use rayon::prelude::*;
+
+use crate::{
+    all_tiles, cyclic_latin_square, puzzle_constraints_with, solve_constraints, ClueSemantics, SolveHooks,
+    SolverOptions,
+};
+
+/// The moves of one placed tile of a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClueRobustness {
+    pub tile: (usize, usize),
+    /// Cells of the row and column of the tile that hold no other tile.
+    pub moves: usize,
+    /// Those of them where the moved tile leaves the puzzle unique, row by row.
+    pub unique_moves: Vec<(usize, usize)>,
+}
+
+/// Robustness of every placed tile of the N×N puzzle placing `tiles`, in
+/// the order of `tiles`.
+pub fn clue_robustness(
+    size: usize,
+    tiles: &[(usize, usize)],
+    semantics: ClueSemantics,
+    solver: &SolverOptions,
+) -> Vec<ClueRobustness> {
+    let grid = cyclic_latin_square(size);
+    let tile_coordinates = all_tiles(size);
+    tiles
+        .par_iter()
+        .enumerate()
+        .map(|(index, &(r, c))| {
+            let targets: Vec<(usize, usize)> = tile_coordinates
+                .iter()
+                .copied()
+                .filter(|&(i, j)| (i == r) != (j == c) && !tiles.contains(&(i, j)))
+                .collect();
+            let unique_moves = targets
+                .iter()
+                .copied()
+                .filter(|&target| {
+                    let mut moved = tiles.to_vec();
+                    moved[index] = target;
+                    let constraints = puzzle_constraints_with(&moved, &grid, &tile_coordinates, semantics);
+                    solve_constraints(&constraints, Some(2), solver, SolveHooks::default()).0.len() == 1
+                })
+                .collect();
+            ClueRobustness { tile: (r, c), moves: targets.len(), unique_moves }
+        })
+        .collect()
+}
+
+/// Share of the moves of all tiles that leave the puzzle unique, 0 if no
+/// tile can move.
+pub fn wiggle_room(clues: &[ClueRobustness]) -> f64 {
+    let moves: usize = clues.iter().map(|clue| clue.moves).sum();
+    let unique: usize = clues.iter().map(|clue| clue.unique_moves.len()).sum();
+    if moves == 0 {
+        0.0
+    } else {
+        unique as f64 / moves as f64
+    }
+}
@@ -0,0 +1,326 @@
+//! Minimal HTTP server answering uniqueness checks of single puzzles.
+//!
+//! Endpoints:
+//! - `GET /solve?size=5&clues=0:0;0:1;1:0;2:2` (clues in the CSV encoding) or
+//!   `GET /solve?id=5015C` (a puzzle ID of the web game) returns the solver's
+//!   verdict and statistics as JSON;
+//! - `GET /puzzle?size=5&level=hard` returns a puzzle of the pool of
+//!   [`crate::pool`], with its ID, clues, difficulty and content ID as JSON
+//!   (and its lineage, for the puzzles of a family), or
+//!   `503 Service Unavailable` if none is ready yet;
+//! - `GET /metrics` returns the requests answered, their latencies, the
+//!   solver nodes of the checks and the counters of the pool in the
+//!   Prometheus text format;
+//! - `GET /health` returns `ok`.
+//!
+//! Each connection is answered on a thread of its own, up to a maximum number
+//! at a time, beyond which connections are turned away with `503 Service
+//! Unavailable`. Reading the request and writing the response time out after
+//! [`IO_TIMEOUT`], and the solver's timeout bounds each `/solve` check.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::metrics::{self, Histogram};
+use crate::output;
+use crate::pool::{Lineage, PooledPuzzle, PuzzlePool, LEVELS};
+use crate::{solve_report, ClueSemantics, PuzzleSpec, SolverOptions};
+
+/// Paths of the endpoints, to label their metrics; requests to any other
+/// path are counted as `other`.
+const PATHS: [&str; 5] = ["/solve", "/puzzle", "/metrics", "/health", "other"];
+
+/// Time allowed to read a request, and to write its response.
+pub const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Counters of the requests answered by the server.
+struct ServerMetrics {
+    /// Requests answered, by path and status code.
+    requests: Mutex<BTreeMap<(&'static str, u16), u64>>,
+    /// Time to answer requests, by path.
+    latencies: BTreeMap<&'static str, Histogram>,
+    /// Backtracking nodes of the uniqueness checks of `/solve`.
+    solver_nodes: Histogram,
+}
+
+impl ServerMetrics {
+    fn new() -> Self {
+        Self {
+            requests: Mutex::new(BTreeMap::new()),
+            latencies: PATHS.iter().map(|&path| (path, Histogram::new(&metrics::LATENCY_BUCKETS))).collect(),
+            solver_nodes: Histogram::new(&metrics::NODE_BUCKETS),
+        }
+    }
+
+    fn record(&self, path: &str, status: &str, start: Instant) {
+        let path = PATHS.iter().copied().find(|&known| known == path).unwrap_or("other");
+        let code = status.split_whitespace().next().and_then(|code| code.parse().ok()).unwrap_or(0);
+        *self.requests.lock().unwrap().entry((path, code)).or_insert(0) += 1;
+        self.latencies[path].observe(start.elapsed().as_secs_f64());
+    }
+
+    /// The metrics of the server, then those of `pool`, in the Prometheus text format.
+    fn text(&self, pool: Option<&PuzzlePool>) -> String {
+        let mut text = String::new();
+        let name = "sudodle_http_requests_total";
+        metrics::write_header(&mut text, name, "counter", "HTTP requests answered.");
+        for ((path, code), count) in self.requests.lock().unwrap().iter() {
+            metrics::write_sample(&mut text, name, &format!("path=\"{}\",code=\"{}\"", path, code), count);
+        }
+        let name = "sudodle_http_request_duration_seconds";
+        metrics::write_header(&mut text, name, "histogram", "Time to answer HTTP requests.");
+        for (path, latencies) in &self.latencies {
+            if latencies.count() > 0 {
+                latencies.write(&mut text, name, &format!("path=\"{}\"", path));
+            }
+        }
+        let name = "sudodle_solver_nodes";
+        metrics::write_header(&mut text, name, "histogram", "Backtracking nodes of the uniqueness checks.");
+        self.solver_nodes.write(&mut text, name, "");
+        if let Some(pool) = pool {
+            text.push_str(&pool.metrics());
+        }
+        text
+    }
+}
+
+/// Serve requests on `addr` until the process is stopped, one thread per
+/// connection and up to `max_connections` at a time, handing out the puzzles
+/// of `pool` if there is one.
+pub fn serve(
+    addr: &str,
+    options: SolverOptions,
+    pool: Option<Arc<PuzzlePool>>,
+    max_connections: usize,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    tracing::info!(addr = %listener.local_addr()?, "listening");
+    if let Some(pool) = &pool {
+        pool.start();
+    }
+    let metrics = Arc::new(ServerMetrics::new());
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream.and_then(|stream| {
+            stream.set_read_timeout(Some(IO_TIMEOUT))?;
+            stream.set_write_timeout(Some(IO_TIMEOUT))?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!(error = %e, "cannot accept connection");
+                continue;
+            }
+        };
+        let Some(slot) = ConnectionSlot::take(&connections, max_connections) else {
+            tracing::warn!(max_connections, "too many connections, turning one away");
+            if let Err(e) = turn_away(stream) {
+                tracing::warn!(error = %e, "connection failed");
+            }
+            continue;
+        };
+        let pool = pool.clone();
+        let metrics = Arc::clone(&metrics);
+        std::thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle_connection(stream, &options, pool.as_deref(), &metrics) {
+                tracing::warn!(error = %e, "connection failed");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// One of the connections answered at a time, given back when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Take a slot of `connections`, unless all `max` are taken.
+    fn take(connections: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| (taken < max).then_some(taken + 1))
+            .ok()
+            .map(|_| Self(Arc::clone(connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Answer a connection beyond the maximum without reading its request.
+fn turn_away(mut stream: TcpStream) -> io::Result<()> {
+    let body = json!({ "error": "too many connections, retry later" }).to_string();
+    write!(
+        stream,
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Answer the single request of a connection.
+fn handle_connection(
+    mut stream: TcpStream,
+    options: &SolverOptions,
+    pool: Option<&PuzzlePool>,
+    metrics: &ServerMetrics,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, requests have no body
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let start = Instant::now();
+    let mut parts = request_line.split_whitespace();
+    let mut path = "other";
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => {
+            let query;
+            (path, query) = target.split_once('?').unwrap_or((target, ""));
+            let _span = tracing::debug_span!("request", path, query).entered();
+            match path {
+                "/health" => ("200 OK", "text/plain", "ok".to_string()),
+                "/solve" => match solve(query, options, start, metrics) {
+                    Ok(body) => ("200 OK", "application/json", body),
+                    Err(e) => ("400 Bad Request", "application/json", json!({ "error": e }).to_string()),
+                },
+                "/puzzle" if pool.is_none() => {
+                    ("404 Not Found", "text/plain", "no puzzle pool (start with --pool-size)".to_string())
+                }
+                "/puzzle" => match take_puzzle(query, pool.expect("checked above")) {
+                    Ok(Some(body)) => ("200 OK", "application/json", body),
+                    Ok(None) => (
+                        "503 Service Unavailable",
+                        "application/json",
+                        json!({ "error": "no puzzle ready, retry later" }).to_string(),
+                    ),
+                    Err(e) => ("400 Bad Request", "application/json", json!({ "error": e }).to_string()),
+                },
+                "/metrics" => ("200 OK", "text/plain; version=0.0.4", metrics.text(pool)),
+                _ => ("404 Not Found", "text/plain", "not found".to_string()),
+            }
+        }
+        _ => ("405 Method Not Allowed", "text/plain", "only GET is supported".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    metrics.record(path, status, start);
+    Ok(())
+}
+
+/// Check the uniqueness of the puzzle described by the query string, giving up
+/// once the timeout of `options` has passed since the request `start`ed.
+fn solve(query: &str, options: &SolverOptions, start: Instant, metrics: &ServerMetrics) -> Result<String, String> {
+    let mut size = None;
+    let mut tiles = None;
+    let mut rules_version = output::RULES_VERSION;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value).ok_or_else(|| format!("malformed value of `{}`", key))?;
+        match key {
+            "size" => size = Some(value.parse::<usize>().map_err(|_| format!("invalid size '{}'", value))?),
+            "clues" => tiles = Some(output::decode_clues(&value)),
+            "rules_version" => {
+                rules_version = value.parse().map_err(|_| format!("invalid rules version '{}'", value))?;
+            }
+            "id" => {
+                let (id_size, id_tiles) =
+                    output::decode_puzzle_id(&value).ok_or_else(|| format!("invalid puzzle ID '{}'", value))?;
+                size = Some(id_size);
+                tiles = Some(id_tiles);
+            }
+            _ => return Err(format!("unknown parameter `{}`", key)),
+        }
+    }
+    let size = size.ok_or("missing `size` (or `id`)")?;
+    let tiles = tiles.ok_or("missing `clues` (or `id`)")?;
+    let puzzle = PuzzleSpec { rules_version, ..PuzzleSpec::new(size, &tiles) };
+    let report = solve_report(&puzzle, options, options.timeout.map(|timeout| start + timeout))?;
+    metrics.solver_nodes.observe(report.backtrack_nodes as f64);
+    serde_json::to_string(&report).map_err(|e| e.to_string())
+}
+
+/// Take a puzzle of the size and level of the query string from the pool,
+/// `None` if none is ready.
+fn take_puzzle(query: &str, pool: &PuzzlePool) -> Result<Option<String>, String> {
+    let mut size = None;
+    let mut level = None;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value).ok_or_else(|| format!("malformed value of `{}`", key))?;
+        match key {
+            "size" => size = Some(value.parse::<usize>().map_err(|_| format!("invalid size '{}'", value))?),
+            "level" => level = Some(value),
+            _ => return Err(format!("unknown parameter `{}`", key)),
+        }
+    }
+    let size = size.ok_or("missing `size`")?;
+    if !pool.options().sizes.contains(&size) {
+        return Err(format!("no pool of {}x{} puzzles", size, size));
+    }
+    let level = level.unwrap_or_else(|| LEVELS[0].to_string());
+    if !LEVELS.contains(&level.as_str()) {
+        return Err(format!("unknown level '{}', expected one of {}", level, LEVELS.join(", ")));
+    }
+    let Some(PooledPuzzle { puzzle, lineage }) = pool.take(size, &level) else {
+        return Ok(None);
+    };
+    let mut body = json!({
+        "id": output::encode_puzzle_id(size, &puzzle.tiles),
+        "size": size,
+        "level": level,
+        "difficulty": puzzle.stats.difficulty(),
+        "clues": output::encode_clues(&puzzle.tiles),
+        "content_id": output::FileHeader::new(size, ClueSemantics::Sudodle).content_id(&puzzle.tiles),
+        "rules_version": output::RULES_VERSION,
+    });
+    if let Some(Lineage { parent, symmetry }) = lineage {
+        body["lineage"] = json!({
+            "parent": parent,
+            "multiplier": symmetry.multiplier,
+            "row_shift": symmetry.row_shift,
+            "column_shift": symmetry.column_shift,
+            "transpose": symmetry.transpose,
+        });
+    }
+    Ok(Some(body.to_string()))
+}
+
+/// Decode `%XX` escapes and `+` (space) in a query string value.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        bytes.push(match byte {
+            b'%' => {
+                let hex = [input.next()?, input.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            b'+' => b' ',
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,150 @@
+//! Printable sheets of puzzles: PDF documents laying out several numbered
+//! puzzles per A4 page, drawn with the shapes of [`crate::render`], followed
+//! by pages of their solutions in the same layout.
+//!
+//! The PDF is written directly, with the standard Helvetica fonts that every
+//! reader provides. Pages are drawn with the y axis pointing down, as in the
+//! SVG images, by flipping the coordinates of each page once.
+
+use std::fmt::Write;
+
+use crate::render::{self, RenderOptions, Shape};
+use crate::{ClueSemantics, Grid};
+
+/// Width and height of an A4 page, in points.
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const PAGE_MARGIN: f64 = 40.0;
+/// Top of the puzzles, below the heading of the page.
+const PUZZLES_TOP: f64 = 90.0;
+/// Width of the digits of Helvetica, in ems.
+const DIGIT_WIDTH: f64 = 0.556;
+
+/// A puzzle of a sheet, with the solution drawn on the answer pages.
+#[derive(Debug, Clone)]
+pub struct SheetPuzzle {
+    pub size: usize,
+    pub tiles: Vec<(usize, usize)>,
+    pub solution: Grid,
+}
+
+/// PDF document of the puzzles, `per_page` on each page, numbered from 1,
+/// followed by pages of their solutions if `answers` is set.
+pub fn puzzle_sheet(puzzles: &[SheetPuzzle], per_page: usize, semantics: ClueSemantics, answers: bool) -> Vec<u8> {
+    let per_page = per_page.max(1);
+    let options = RenderOptions { semantics, ..RenderOptions::default() };
+    let rules = match semantics {
+        ClueSemantics::Sudodle => {
+            "Swap tiles until each row and column holds every number once. Green tiles are in place."
+        }
+        ClueSemantics::GivensOnly => "Fill the grid so that every row and column holds each number once.",
+    };
+    let mut pages = Vec::new();
+    for with_solutions in [false, true] {
+        if with_solutions && !answers {
+            break;
+        }
+        for (page, chunk) in puzzles.chunks(per_page).enumerate() {
+            let mut content = format!("1 0 0 -1 0 {} cm\n", PAGE_HEIGHT);
+            let heading = if with_solutions { "Answers" } else { "Sudodle" };
+            write_text(&mut content, "F1", 18.0, PAGE_MARGIN, PAGE_MARGIN + 18.0, heading);
+            if !with_solutions {
+                write_text(&mut content, "F2", 10.0, PAGE_MARGIN, PAGE_MARGIN + 36.0, rules);
+            }
+            let columns = ((per_page as f64 * PAGE_WIDTH / PAGE_HEIGHT).sqrt().round() as usize).max(1);
+            let rows = per_page.div_ceil(columns);
+            let slot_width = (PAGE_WIDTH - 2.0 * PAGE_MARGIN) / columns as f64;
+            let slot_height = (PAGE_HEIGHT - PAGE_MARGIN - PUZZLES_TOP) / rows as f64;
+            for (index, puzzle) in chunk.iter().enumerate() {
+                let left = PAGE_MARGIN + (index % columns) as f64 * slot_width;
+                let top = PUZZLES_TOP + (index / columns) as f64 * slot_height;
+                let number = page * per_page + index + 1;
+                write_text(&mut content, "F1", 12.0, left + 8.0, top + 12.0, &format!("{}.", number));
+                let side = (slot_width - 16.0).min(slot_height - 24.0);
+                let scale = side / render::image_size(puzzle.size, &options) as f64;
+                let solution = with_solutions.then_some(&puzzle.solution);
+                let shapes = render::puzzle_shapes(puzzle.size, &puzzle.tiles, solution, &options);
+                let _ = writeln!(
+                    content,
+                    "q {scale:.4} 0 0 {scale:.4} {:.2} {:.2} cm",
+                    left + (slot_width - side) / 2.0,
+                    top + 18.0
+                );
+                for shape in shapes {
+                    write_shape(&mut content, &shape);
+                }
+                content.push_str("Q\n");
+            }
+            pages.push(content);
+        }
+    }
+    write_pdf(&pages)
+}
+
+/// Append the PDF operators drawing `shape`.
+fn write_shape(content: &mut String, shape: &Shape) {
+    let _ = match *shape {
+        Shape::Cell { x, y, side, fill } => writeln!(content, "{} rg {x} {y} {side} {side} re f", rgb(fill)),
+        Shape::Line { from, to, width, color } => {
+            writeln!(content, "{} RG {width} w {} {} m {} {} l S", rgb(color), from.0, from.1, to.0, to.1)
+        }
+        Shape::Frame { x, y, side, width, color } => {
+            writeln!(content, "{} RG {width} w {x} {y} {side} {side} re S", rgb(color))
+        }
+        Shape::Digit { x, y, font_size, color, value } => {
+            let left = x as f64 - DIGIT_WIDTH * font_size as f64 / 2.0;
+            writeln!(content, "BT /F1 {font_size} Tf {} rg 1 0 0 -1 {left:.2} {y} Tm ({value}) Tj ET", rgb(color))
+        }
+    };
+}
+
+/// Append the PDF operators writing `text` in black, starting at `x` with its
+/// baseline at `y`.
+fn write_text(content: &mut String, font: &str, font_size: f64, x: f64, y: f64, text: &str) {
+    let escaped = text.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+    let _ = writeln!(content, "BT /{font} {font_size} Tf 0 g 1 0 0 -1 {x:.2} {y:.2} Tm ({escaped}) Tj ET");
+}
+
+/// PDF color operands of a `#rrggbb` color.
+fn rgb(color: &str) -> String {
+    let channel = |at: usize| u8::from_str_radix(&color[at..at + 2], 16).unwrap_or(0) as f64 / 255.0;
+    format!("{:.3} {:.3} {:.3}", channel(1), channel(3), channel(5))
+}
+
+/// PDF document of pages with the content streams `pages`, the fonts F1
+/// (Helvetica-Bold) and F2 (Helvetica).
+fn write_pdf(pages: &[String]) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len()).map(|page| format!("{} 0 R", 5 + 2 * page)).collect::<Vec<_>>().join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (page, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> \
+             /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            6 + 2 * page
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = writeln!(pdf, "{} 0 obj\n{}\nendobj", index + 1, object);
+    }
+    let xref = pdf.len();
+    let _ = writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = writeln!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF", objects.len() + 1, xref);
+    pdf.into_bytes()
+}
@@ -0,0 +1,364 @@
+//! Simulated games of Sudodle, to evaluate guessing strategies.
+//!
+//! A game is played against a secret Latin square: every guess is a full
+//! Latin square (or any grid in the relaxed variant), and the feedback is the
+//! set of its tiles that match the secret. The other tiles are then known to be wrong where they stand.
+//! Strategies only make guesses consistent with all the feedback so far, so a
+//! game always ends, with the secret as its last guess.
+
+use std::collections::{BTreeMap, HashMap};
+
+use clap::ValueEnum;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+
+use crate::completions;
+use crate::mask::TileMask;
+use crate::{solve_constraints, Constraints, Grid, SolveHooks, SolverOptions, ValueOrder};
+
+/// How a simulated player picks its next guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Strategy {
+    /// A random grid consistent with the feedback so far.
+    #[default]
+    RandomConsistent,
+    /// The sampled consistent grid whose feedback splits the other samples
+    /// into the most even groups (the highest entropy).
+    EntropyGreedy,
+    /// The sampled consistent grid closest to the previous guess, as a player
+    /// swapping a few tiles at a time would make.
+    HumanHeuristic,
+}
+
+/// Settings of a simulated player.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationOptions {
+    pub strategy: Strategy,
+    /// Consistent grids drawn to choose from at each guess, with
+    /// [`Strategy::EntropyGreedy`] and [`Strategy::HumanHeuristic`].
+    pub samples: usize,
+    /// Play the relaxed variant of the game: guesses don't have to be Latin
+    /// squares, and are only consistent with the feedback row by row.
+    pub relaxed: bool,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self { strategy: Strategy::default(), samples: 32, relaxed: false }
+    }
+}
+
+/// The guesses of a finished game and their feedback.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    /// Guesses in the order they were made; the last one is the secret.
+    pub guesses: Vec<Grid>,
+    /// Number of correct tiles of each guess.
+    pub correct: Vec<usize>,
+}
+
+/// Tiles of `guess` that match `secret`, as a mask of the cells `row * size + col`.
+pub fn feedback(guess: &Grid, secret: &Grid) -> TileMask {
+    let size = secret.size();
+    TileMask::of_cells(
+        (0..size * size).filter(|&cell| guess[(cell / size, cell % size)] == secret[(cell / size, cell % size)]),
+    )
+}
+
+/// Add what the `correct` tiles of `guess` tell about the secret to `knowledge`.
+pub fn learn(knowledge: &mut Constraints, guess: &Grid, correct: TileMask) {
+    let size = knowledge.size;
+    for cell in 0..size * size {
+        let tile = (cell / size, cell % size);
+        if correct.contains(cell) {
+            knowledge.set_known(tile, guess[tile].into());
+        } else {
+            knowledge.forbid(tile, guess[tile].into());
+        }
+    }
+}
+
+/// A random grid consistent with `knowledge`, or `None` if there is none.
+///
+/// Grids are drawn by completing `knowledge` with a random value order, which
+/// is quick but not uniform over the consistent grids.
+pub fn random_consistent(knowledge: &Constraints, rng: &mut impl Rng) -> Option<Grid> {
+    let options = SolverOptions { value_order: ValueOrder::Random, seed: rng.gen(), ..SolverOptions::default() };
+    solve_constraints(knowledge, Some(1), &options, SolveHooks::default()).0.pop()
+}
+
+/// A grid whose rows are random permutations consistent with the known and
+/// wrong values of `knowledge`, each row drawn on its own, or `None` if a row
+/// has no such permutation. Columns may hold a value several times.
+pub fn random_row_consistent(knowledge: &Constraints, rng: &mut impl Rng) -> Option<Grid> {
+    let size = knowledge.size;
+    let mut grid = Grid::new(size);
+    for row in 0..size {
+        let candidates: Vec<u32> = (0..size)
+            .map(|col| match knowledge.known[(row, col)] {
+                0 => ((1u32 << size) - 1) & !knowledge.wrong[row * size + col],
+                known => 1 << (known - 1),
+            })
+            .collect();
+        if !fill_row(&mut grid, row, 0, &candidates, 0, rng) {
+            return None;
+        }
+    }
+    Some(grid)
+}
+
+/// Fill the cells of `row` from `col` on with distinct values among their
+/// `candidates` and not in `used`, trying values in a random order.
+fn fill_row(grid: &mut Grid, row: usize, col: usize, candidates: &[u32], used: u32, rng: &mut impl Rng) -> bool {
+    if col == candidates.len() {
+        return true;
+    }
+    let mut values: Vec<u8> = (1..=candidates.len() as u8)
+        .filter(|&value| (candidates[col] & !used) & 1 << (value - 1) != 0)
+        .collect();
+    values.shuffle(rng);
+    for value in values {
+        grid[(row, col)] = value;
+        if fill_row(grid, row, col + 1, candidates, used | 1 << (value - 1), rng) {
+            return true;
+        }
+    }
+    false
+}
+
+/// A random guess consistent with `knowledge` under the rules of `options`.
+fn draw_guess(knowledge: &Constraints, options: &SimulationOptions, rng: &mut impl Rng) -> Option<Grid> {
+    if options.relaxed {
+        random_row_consistent(knowledge, rng)
+    } else {
+        random_consistent(knowledge, rng)
+    }
+}
+
+/// Up to `samples` distinct guesses consistent with `knowledge`.
+fn sample_consistent(
+    knowledge: &Constraints,
+    samples: usize,
+    options: &SimulationOptions,
+    rng: &mut impl Rng,
+) -> Vec<Grid> {
+    let mut grids: Vec<Grid> = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        match draw_guess(knowledge, options, rng) {
+            Some(grid) if !grids.contains(&grid) => grids.push(grid),
+            Some(_) => {}
+            None => break,
+        }
+    }
+    grids
+}
+
+/// Entropy of the feedback of `guess` when the secret is one of `secrets`.
+fn feedback_entropy(guess: &Grid, secrets: &[Grid]) -> f64 {
+    let mut groups: HashMap<TileMask, usize> = HashMap::new();
+    for secret in secrets {
+        *groups.entry(feedback(guess, secret)).or_insert(0) += 1;
+    }
+    // Summed in a fixed order, so that ties between guesses break the same way in every run
+    let mut counts: Vec<usize> = groups.into_values().collect();
+    counts.sort_unstable();
+    let total = secrets.len() as f64;
+    counts.into_iter().map(|count| count as f64 / total).map(|p| -p * p.log2()).sum()
+}
+
+/// Number of tiles where `a` and `b` hold the same value.
+fn agreement(a: &Grid, b: &Grid) -> usize {
+    feedback(a, b).len()
+}
+
+/// The next guess of a player using `options` who knows `knowledge` and
+/// played `previous` last.
+pub fn next_guess(
+    knowledge: &Constraints,
+    previous: Option<&Grid>,
+    options: &SimulationOptions,
+    rng: &mut impl Rng,
+) -> Option<Grid> {
+    match (options.strategy, previous) {
+        (Strategy::RandomConsistent, _) | (Strategy::HumanHeuristic, None) => draw_guess(knowledge, options, rng),
+        (Strategy::EntropyGreedy, _) => {
+            let candidates = sample_consistent(knowledge, options.samples.max(1), options, rng);
+            candidates
+                .iter()
+                .map(|guess| (feedback_entropy(guess, &candidates), guess))
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(_, guess)| guess.clone())
+        }
+        (Strategy::HumanHeuristic, Some(previous)) => sample_consistent(knowledge, options.samples.max(1), options, rng)
+            .into_iter()
+            .max_by_key(|guess| agreement(guess, previous)),
+    }
+}
+
+/// Play a game against `secret` starting from `knowledge` (no clues for a new
+/// game, or the clues of a puzzle), until a guess matches the secret.
+///
+/// # Panics
+/// If `secret` doesn't satisfy `knowledge`.
+pub fn play(secret: &Grid, knowledge: Constraints, options: &SimulationOptions, rng: &mut impl Rng) -> Transcript {
+    let mut knowledge = knowledge;
+    let mut guesses: Vec<Grid> = Vec::new();
+    let mut correct = Vec::new();
+    let size = secret.size();
+    loop {
+        let guess = next_guess(&knowledge, guesses.last(), options, rng)
+            .expect("the secret is always consistent with the feedback");
+        let matches = feedback(&guess, secret);
+        learn(&mut knowledge, &guess, matches);
+        guesses.push(guess);
+        correct.push(matches.len());
+        if matches.len() == size * size {
+            return Transcript { guesses, correct };
+        }
+    }
+}
+
+/// Random number generator of trial `trial` of `seed`.
+pub fn trial_rng(seed: u64, trial: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ trial.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Where the candidate secrets of [`hardest_secrets`] come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SecretSource {
+    /// Random Latin squares.
+    #[default]
+    Random,
+    /// Reduced Latin squares (first row and column in order), in the solver's
+    /// order. Every Latin square is one of them with its rows and values
+    /// relabelled, which the strategies are (nearly) indifferent to.
+    Enumerated,
+    /// Latin squares drawn nearly uniformly, with
+    /// [`completions::sample_completions`]; random ones favour some squares.
+    Uniform,
+}
+
+/// Up to `count` distinct candidate secrets of size `size`.
+pub fn candidate_secrets(size: usize, count: usize, source: SecretSource, seed: u64) -> Vec<Grid> {
+    match source {
+        SecretSource::Random => {
+            let mut rng = trial_rng(seed, u64::MAX);
+            sample_consistent(&Constraints::new(size), count, &SimulationOptions::default(), &mut rng)
+        }
+        SecretSource::Enumerated => {
+            let mut reduced = Constraints::new(size);
+            for k in 0..size {
+                reduced.set_known((0, k), k + 1);
+                reduced.set_known((k, 0), k + 1);
+            }
+            solve_constraints(&reduced, Some(count), &SolverOptions::default(), SolveHooks::default()).0
+        }
+        SecretSource::Uniform => {
+            let mut secrets: Vec<Grid> = Vec::with_capacity(count);
+            for secret in completions::sample_completions(&Constraints::new(size), count, seed) {
+                if !secrets.contains(&secret) {
+                    secrets.push(secret);
+                }
+            }
+            secrets
+        }
+    }
+}
+
+/// Distribution of the number of guesses of `games` games against `secret`,
+/// starting from `knowledge`.
+///
+/// Game `g` uses the random numbers of trial `g` of `seed` whatever the
+/// secret, so that secrets are compared on the same draws.
+pub fn guess_counts(
+    secret: &Grid,
+    knowledge: &Constraints,
+    games: usize,
+    options: &SimulationOptions,
+    seed: u64,
+) -> GuessCounts {
+    let mut counts = GuessCounts::default();
+    for game in 0..games as u64 {
+        let transcript = play(secret, knowledge.clone(), options, &mut trial_rng(seed, game));
+        counts.add(transcript.guesses.len());
+    }
+    counts
+}
+
+/// Player-facing difficulty of a puzzle: the guesses simulated players need
+/// to find its solution once they know its clues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpectedGuesses {
+    pub mean: f64,
+    pub median: usize,
+    pub p90: usize,
+}
+
+/// Play `games` games of the puzzle of `constraints` and `solution` with the
+/// random-consistent strategy, with the same draws for every puzzle.
+///
+/// The games are relaxed: a player whose guesses are consistent Latin squares
+/// finds the solution of a unique puzzle at the first guess, whereas players
+/// checking the feedback row by row need more guesses for harder puzzles.
+pub fn expected_guesses(constraints: &Constraints, solution: &Grid, games: usize) -> ExpectedGuesses {
+    let options = SimulationOptions { strategy: Strategy::RandomConsistent, relaxed: true, ..SimulationOptions::default() };
+    let counts = guess_counts(solution, constraints, games, &options, 0);
+    ExpectedGuesses { mean: counts.mean(), median: counts.percentile(0.5), p90: counts.percentile(0.9) }
+}
+
+/// The `top` secrets among `candidates` that take the most guesses on average
+/// over `games` games each, hardest first, with their distributions.
+pub fn hardest_secrets(
+    candidates: Vec<Grid>,
+    top: usize,
+    games: usize,
+    options: &SimulationOptions,
+    seed: u64,
+) -> Vec<(Grid, GuessCounts)> {
+    let mut rated: Vec<(Grid, GuessCounts)> = candidates
+        .into_par_iter()
+        .map(|secret| {
+            let counts = guess_counts(&secret, &Constraints::new(secret.size()), games, options, seed);
+            (secret, counts)
+        })
+        .collect();
+    rated.sort_by(|(_, a), (_, b)| b.mean().total_cmp(&a.mean()));
+    rated.truncate(top);
+    rated
+}
+
+/// Distribution of the number of guesses needed over simulated games.
+#[derive(Debug, Clone, Default)]
+pub struct GuessCounts {
+    /// Games won with each number of guesses.
+    pub counts: BTreeMap<usize, usize>,
+}
+
+impl GuessCounts {
+    pub fn add(&mut self, guesses: usize) {
+        *self.counts.entry(guesses).or_insert(0) += 1;
+    }
+
+    pub fn games(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total: usize = self.counts.iter().map(|(guesses, count)| guesses * count).sum();
+        total as f64 / self.games().max(1) as f64
+    }
+
+    /// Smallest number of guesses within which at least `fraction` of the games were won.
+    pub fn percentile(&self, fraction: f64) -> usize {
+        let target = (fraction * self.games() as f64).ceil() as usize;
+        let mut won = 0;
+        for (&guesses, &count) in &self.counts {
+            won += count;
+            if won >= target.max(1) {
+                return guesses;
+            }
+        }
+        0
+    }
+}
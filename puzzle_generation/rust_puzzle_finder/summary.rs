@@ -0,0 +1,117 @@
+//! Machine-readable summary of a search run.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use serde::Serialize;
+
+use crate::cycles::Difference;
+
+/// Counters describing a finished search, printed and optionally written as JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub size: usize,
+    pub placed: usize,
+    /// Tile combinations evaluated (including those skipped as already known).
+    pub combinations_processed: usize,
+    /// Distinct single-solution puzzles found by this run.
+    pub unique_puzzles: usize,
+    /// Puzzles among them that can be solved with naked and hidden singles alone.
+    pub singles_solvable_puzzles: usize,
+    /// Single-solution combinations discarded because the puzzle was already known.
+    pub dedup_hits: usize,
+    /// Combinations skipped because they contain a known unique clue set.
+    pub superset_skips: usize,
+    /// Combinations skipped because they leave the cells of a recorded nogood empty.
+    pub nogood_skips: usize,
+    /// Combinations skipped because the results index records them as evaluated.
+    pub index_hits: usize,
+    /// Combinations rejected by the solver's validity checks before any backtracking.
+    pub prefilter_rejections: usize,
+    /// Combinations abandoned because they exceeded the per-puzzle timeout.
+    pub timed_out: usize,
+    /// Number of puzzles found for each difficulty rating.
+    pub difficulty_counts: BTreeMap<usize, usize>,
+    /// Combinations and puzzles found for each line load (the largest number of
+    /// placed tiles in one row or column).
+    pub strata: BTreeMap<usize, StratumCounts>,
+    /// Combinations with 2 to 4 solutions, by number of solutions, counted
+    /// by how their two closest solutions differ (only with
+    /// `SearchOptions::duplicate_structure`).
+    pub duplicate_structure: BTreeMap<usize, DifferenceCounts>,
+    pub wall_time_seconds: f64,
+    /// Fraction of the wall time each worker thread spent evaluating combinations.
+    pub thread_utilization: Vec<f64>,
+}
+
+/// Outcome of the combinations of one stratum of a search.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StratumCounts {
+    pub combinations: usize,
+    pub unique_puzzles: usize,
+    /// Fraction of the combinations that were new unique puzzles.
+    pub hit_rate: f64,
+}
+
+/// Ambiguous combinations by how their two closest solutions differ.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DifferenceCounts {
+    pub intercalate: usize,
+    pub three_cycle: usize,
+    pub longer_cycle: usize,
+    pub other: usize,
+}
+
+impl DifferenceCounts {
+    pub fn add(&mut self, difference: Difference) {
+        match difference {
+            Difference::Intercalate => self.intercalate += 1,
+            Difference::ThreeCycle => self.three_cycle += 1,
+            Difference::LongerCycle => self.longer_cycle += 1,
+            Difference::Other => self.other += 1,
+        }
+    }
+}
+
+impl RunSummary {
+    /// Render the summary as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("summary is always serializable")
+    }
+
+    /// Write the summary as JSON to the file at `path`.
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", self.to_json())?;
+        writer.flush()
+    }
+}
+
+/// Summaries of the searches of several sizes run by one invocation.
+#[derive(Debug, Clone, Serialize)]
+pub struct CombinedSummary {
+    /// Unique puzzles found over all sizes.
+    pub unique_puzzles: usize,
+    pub wall_time_seconds: f64,
+    /// Summary of each size, in the order they were searched.
+    pub runs: Vec<RunSummary>,
+}
+
+impl CombinedSummary {
+    pub fn new(runs: Vec<RunSummary>) -> Self {
+        Self {
+            unique_puzzles: runs.iter().map(|run| run.unique_puzzles).sum(),
+            wall_time_seconds: runs.iter().map(|run| run.wall_time_seconds).sum(),
+            runs,
+        }
+    }
+
+    /// Write the summaries as pretty-printed JSON to the file at `path`.
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let json = serde_json::to_string_pretty(self).expect("summary is always serializable");
+        writeln!(writer, "{}", json)?;
+        writer.flush()
+    }
+}
@@ -0,0 +1,60 @@
+//! Skipping the clue sets that contain a smaller unique clue set.
+//!
+//! A clue set containing all the tiles of a known unique one gives the player
+//! that puzzle's tiles and more, so it makes no new puzzle for the game
+//! (whether or not it is unique itself: the other tiles of the smaller puzzle
+//! are known to be wrong, so most such supersets have no solution at all).
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::mask::TileMask;
+use crate::{binomial, CombinationIterator};
+
+/// Clue sets known to have a unique solution, stored as the masks of their
+/// tiles grouped by number of tiles.
+#[derive(Debug, Clone, Default)]
+pub struct SupersetFilter {
+    size: usize,
+    by_len: BTreeMap<usize, HashSet<TileMask>>,
+}
+
+impl SupersetFilter {
+    /// An empty filter for N×N grids.
+    pub fn new(size: usize) -> Self {
+        Self { size, by_len: BTreeMap::new() }
+    }
+
+    fn mask(&self, tiles: &[(usize, usize)]) -> TileMask {
+        TileMask::of_tiles(self.size, tiles)
+    }
+
+    /// Record a clue set with a unique solution.
+    pub fn insert(&mut self, tiles: &[(usize, usize)]) {
+        let mask = self.mask(tiles);
+        self.by_len.entry(mask.len()).or_default().insert(mask);
+    }
+
+    /// Number of clue sets recorded.
+    pub fn len(&self) -> usize {
+        self.by_len.values().map(HashSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_len.is_empty()
+    }
+
+    /// Whether `tiles` contain all the tiles of one of the recorded clue sets.
+    pub fn contains_subset_of(&self, tiles: &[(usize, usize)]) -> bool {
+        let mask = self.mask(tiles);
+        let len = mask.len();
+        self.by_len.range(..=len).any(|(&subset_len, masks)| {
+            // Look the subsets of the combination up when there are fewer of them than known sets
+            if binomial(len, subset_len) <= masks.len() as u128 {
+                CombinationIterator::new(mask.cells().collect(), subset_len)
+                    .any(|subset| masks.contains(&TileMask::of_cells(subset)))
+            } else {
+                masks.iter().any(|known| known.is_subset(&mask))
+            }
+        })
+    }
+}
@@ -0,0 +1,145 @@
+//! Symmetries of the cyclic square, mapping its puzzles to equivalent ones.
+//!
+//! The cyclic square holds `(r + c) mod N` at row `r` and column `c`
+//! (0-based, values counted from 0). Sending every cell `(r, c)` to
+//! `(a·r + b, a·c + d) mod N`, with `a` coprime with N, sends the value `v`
+//! to `a·v + b + d`, a relabelling of the values; transposing leaves every
+//! value in place. Either way the image of the square is the square with its
+//! values renamed, so a clue set and its image have the same number of
+//! solutions, and the same difficulty, with every clue semantics.
+//!
+//! The images of a unique puzzle, its family, are unique puzzles of the same
+//! square whose solutions are the images of its solution: [`family`] turns
+//! one puzzle into up to 2·φ(N)·N² distinct-looking ones.
+
+use std::collections::HashSet;
+
+use crate::certificate::{Certificate, Deduction, Step};
+use crate::{output, FoundPuzzle, Grid};
+
+/// A symmetry `(r, c) → (a·r + b, a·c + d) mod N`, after transposing the
+/// grid if `transpose` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symmetry {
+    pub multiplier: usize,
+    pub row_shift: usize,
+    pub column_shift: usize,
+    pub transpose: bool,
+}
+
+impl Symmetry {
+    /// Image of the tile `(r, c)` of an N×N grid.
+    pub fn apply(&self, size: usize, (r, c): (usize, usize)) -> (usize, usize) {
+        let (r, c) = if self.transpose { (c, r) } else { (r, c) };
+        ((self.multiplier * r + self.row_shift) % size, (self.multiplier * c + self.column_shift) % size)
+    }
+
+    /// Image of the value `value` (from 1 to N) of the cyclic square.
+    pub fn relabel(&self, size: usize, value: u8) -> u8 {
+        ((self.multiplier * (value as usize - 1) + self.row_shift + self.column_shift) % size + 1) as u8
+    }
+
+    /// Image of a grid filled with values from 1 to N, e.g. a solution:
+    /// every value moved to the image of its cell and relabelled.
+    pub fn apply_grid(&self, grid: &Grid) -> Grid {
+        let size = grid.size();
+        let mut image = Grid::new(size);
+        for r in 0..size {
+            for c in 0..size {
+                image[self.apply(size, (r, c))] = self.relabel(size, grid[(r, c)]);
+            }
+        }
+        image
+    }
+
+    /// Image of a unique puzzle: its tiles, solution and certificate mapped.
+    /// Its statistics are those of `puzzle`, although the solver may need
+    /// other guesses for the image, branching on the cells in a fixed order.
+    pub fn apply_puzzle(&self, size: usize, puzzle: &FoundPuzzle) -> FoundPuzzle {
+        let mut tiles: Vec<(usize, usize)> = puzzle.tiles.iter().map(|&tile| self.apply(size, tile)).collect();
+        tiles.sort_unstable();
+        let certificate = puzzle.certificate.as_ref().map(|certificate| Certificate {
+            steps: certificate
+                .steps
+                .iter()
+                .map(|step| Step {
+                    cell: self.apply(size, step.cell),
+                    value: self.relabel(size, step.value),
+                    deduction: match (step.deduction, self.transpose) {
+                        (Deduction::HiddenSingleRow, true) => Deduction::HiddenSingleColumn,
+                        (Deduction::HiddenSingleColumn, true) => Deduction::HiddenSingleRow,
+                        (deduction, _) => deduction,
+                    },
+                })
+                .collect(),
+        });
+        FoundPuzzle {
+            tiles,
+            solution: puzzle.solution.as_ref().map(|solution| self.apply_grid(solution)),
+            certificate,
+            ..puzzle.clone()
+        }
+    }
+}
+
+/// Every symmetry of the N×N cyclic square of this kind, the identity first.
+pub fn symmetries(size: usize) -> Vec<Symmetry> {
+    let multipliers = (1..size.max(2)).filter(|&a| gcd(a, size) == 1);
+    multipliers
+        .flat_map(|multiplier| {
+            (0..size).flat_map(move |row_shift| {
+                (0..size).flat_map(move |column_shift| {
+                    [false, true].map(|transpose| Symmetry { multiplier, row_shift, column_shift, transpose })
+                })
+            })
+        })
+        .collect()
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Representative of the clue sets equivalent to `tiles`: the smallest of
+/// their sorted images under `symmetries`, as listed by [`symmetries`].
+pub fn canonical_tiles(size: usize, tiles: &[(usize, usize)], symmetries: &[Symmetry]) -> Vec<(usize, usize)> {
+    let mut smallest: Option<Vec<(usize, usize)>> = None;
+    let mut image = Vec::with_capacity(tiles.len());
+    for symmetry in symmetries {
+        image.clear();
+        image.extend(tiles.iter().map(|&tile| symmetry.apply(size, tile)));
+        image.sort_unstable();
+        if smallest.as_ref().is_none_or(|smallest| image < *smallest) {
+            smallest = Some(image.clone());
+        }
+    }
+    smallest.unwrap_or_else(|| {
+        let mut sorted = tiles.to_vec();
+        sorted.sort_unstable();
+        sorted
+    })
+}
+
+/// Content ID shared by every clue set equivalent to `tiles` under the
+/// symmetries: the [`output::content_id`] of their representative.
+pub fn canonical_id(header: &output::FileHeader, tiles: &[(usize, usize)], symmetries: &[Symmetry]) -> String {
+    header.content_id(&canonical_tiles(header.size, tiles, symmetries))
+}
+
+/// The distinct images of the unique puzzle `puzzle` of the N×N cyclic
+/// square under [`symmetries`], other than itself, each with the first
+/// symmetry giving it.
+pub fn family(size: usize, puzzle: &FoundPuzzle) -> Vec<(Symmetry, FoundPuzzle)> {
+    let mut sorted = puzzle.tiles.clone();
+    sorted.sort_unstable();
+    let mut seen = HashSet::from([sorted]);
+    symmetries(size)
+        .into_iter()
+        .map(|symmetry| (symmetry, symmetry.apply_puzzle(size, puzzle)))
+        .filter(|(_, image)| seen.insert(image.tiles.clone()))
+        .collect()
+}
@@ -0,0 +1,310 @@
+//! Candidate elimination techniques used by the solver's propagation cascade
+//! on top of naked and hidden singles.
+//!
+//! Candidates are stored as one bitmask per cell, indexed `row * size + col`,
+//! with bit `v - 1` set iff value `v` is still possible; filled cells have no
+//! candidates. The units of a Latin square are its rows and columns.
+
+use bitflags::bitflags;
+
+use crate::{Constraints, Grid, MAX_SIZE};
+
+bitflags! {
+    /// Techniques the propagation cascade may apply before the solver guesses.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct TechniqueSet: u32 {
+        /// Fill cells with a single candidate left.
+        const NAKED_SINGLES = 1 << 0;
+        /// Fill the only cell of a row or column where a value fits.
+        const HIDDEN_SINGLES = 1 << 1;
+        /// Two cells of a unit sharing the same two candidates.
+        const NAKED_PAIRS = 1 << 2;
+        /// Three cells of a unit whose candidates are three values.
+        const NAKED_TRIPLES = 1 << 3;
+        /// Two values of a unit that only fit in the same two cells.
+        const HIDDEN_PAIRS = 1 << 4;
+        /// Three values of a unit that only fit in the same three cells.
+        const HIDDEN_TRIPLES = 1 << 5;
+        /// A value confined to the same two columns in two rows (or vice versa).
+        const X_WINGS = 1 << 6;
+
+        /// The original cascade: naked and hidden singles.
+        const SINGLES = Self::NAKED_SINGLES.bits() | Self::HIDDEN_SINGLES.bits();
+        /// Singles plus naked and hidden pairs and triples.
+        const SUBSETS = Self::SINGLES.bits()
+            | Self::NAKED_PAIRS.bits()
+            | Self::NAKED_TRIPLES.bits()
+            | Self::HIDDEN_PAIRS.bits()
+            | Self::HIDDEN_TRIPLES.bits();
+        /// Every technique.
+        const FULL = Self::SUBSETS.bits() | Self::X_WINGS.bits();
+        /// Techniques that remove candidates instead of filling cells directly.
+        const ELIMINATIONS = Self::FULL.bits() & !Self::SINGLES.bits();
+    }
+}
+
+impl Default for TechniqueSet {
+    fn default() -> Self {
+        Self::SINGLES
+    }
+}
+
+impl std::str::FromStr for TechniqueSet {
+    type Err = String;
+
+    /// Parse a comma-separated list of technique names or presets, e.g.
+    /// `singles,naked-pairs`. `none` disables propagation entirely.
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        let mut set = Self::empty();
+        for name in list.split(',').map(str::trim) {
+            set |= match name {
+                "none" => Self::empty(),
+                "singles" => Self::SINGLES,
+                "subsets" => Self::SUBSETS,
+                "full" => Self::FULL,
+                "naked-singles" => Self::NAKED_SINGLES,
+                "hidden-singles" => Self::HIDDEN_SINGLES,
+                "naked-pairs" => Self::NAKED_PAIRS,
+                "naked-triples" => Self::NAKED_TRIPLES,
+                "hidden-pairs" => Self::HIDDEN_PAIRS,
+                "hidden-triples" => Self::HIDDEN_TRIPLES,
+                "x-wings" => Self::X_WINGS,
+                _ => {
+                    return Err(format!(
+                        "unknown technique '{}' (expected none, singles, subsets, full, naked-singles, \
+                         hidden-singles, naked-pairs, naked-triples, hidden-pairs, hidden-triples or x-wings)",
+                        name
+                    ))
+                }
+            };
+        }
+        Ok(set)
+    }
+}
+
+/// Remove candidates using the elimination techniques of `techniques`, until
+/// no more can be removed. Returns true if any candidate was removed.
+pub fn eliminate(candidates: &mut [u32], size: usize, techniques: TechniqueSet) -> bool {
+    if !techniques.intersects(TechniqueSet::ELIMINATIONS) {
+        return false;
+    }
+    let naked = [(2, TechniqueSet::NAKED_PAIRS), (3, TechniqueSet::NAKED_TRIPLES)];
+    let hidden = [
+        (1, TechniqueSet::HIDDEN_SINGLES),
+        (2, TechniqueSet::HIDDEN_PAIRS),
+        (3, TechniqueSet::HIDDEN_TRIPLES),
+    ];
+    let mut any_removed = false;
+    // Called at every node of the search, so its scratch space lives on the stack
+    let mut unit = [0; MAX_SIZE];
+    loop {
+        let mut removed = false;
+        for index in 0..2 * size {
+            let unit = unit_cells(size, index, &mut unit);
+            for &(k, technique) in &naked {
+                if techniques.contains(technique) {
+                    removed |= naked_subsets(candidates, unit, k);
+                }
+            }
+            for &(k, technique) in &hidden {
+                if techniques.contains(technique) {
+                    removed |= hidden_subsets(candidates, unit, size, k);
+                }
+            }
+        }
+        if techniques.contains(TechniqueSet::X_WINGS) {
+            removed |= x_wings(candidates, size);
+        }
+        if !removed {
+            return any_removed;
+        }
+        any_removed = true;
+    }
+}
+
+/// Fill as many cells of the puzzle as `techniques` allow, without guessing.
+///
+/// Returns the resulting grid, with 0 in the cells that could not be deduced.
+pub fn deduce(constraints: &Constraints, techniques: TechniqueSet) -> Grid {
+    let size = constraints.size;
+    let mut square = constraints.known.clone();
+    let full_mask = (1u32 << size) - 1;
+    let units = units(size);
+    // Fill one cell at a time, recomputing the candidates from scratch after each
+    loop {
+        let mut candidates = vec![0u32; size * size];
+        for i in 0..size {
+            for j in 0..size {
+                if square[(i, j)] != 0 {
+                    continue;
+                }
+                let used = (0..size)
+                    .flat_map(|k| [square[(i, k)], square[(k, j)]])
+                    .filter(|&v| v != 0)
+                    .fold(0, |acc, v| acc | 1 << (v - 1));
+                candidates[i * size + j] = full_mask & !used & !constraints.wrong[i * size + j];
+            }
+        }
+        eliminate(&mut candidates, size, techniques);
+
+        let mut single = None;
+        if techniques.contains(TechniqueSet::NAKED_SINGLES) {
+            single = (0..size * size)
+                .find(|&cell| candidates[cell].count_ones() == 1)
+                .map(|cell| (cell, candidates[cell]));
+        }
+        if single.is_none() && techniques.contains(TechniqueSet::HIDDEN_SINGLES) {
+            single = units.iter().find_map(|unit| {
+                (0..size).find_map(|value| {
+                    let bit = 1u32 << value;
+                    let mut cells = unit.iter().filter(|&&cell| candidates[cell] & bit != 0);
+                    match (cells.next(), cells.next()) {
+                        (Some(&cell), None) => Some((cell, bit)),
+                        _ => None,
+                    }
+                })
+            });
+        }
+        match single {
+            Some((cell, bit)) => square[(cell / size, cell % size)] = bit.trailing_zeros() as u8 + 1,
+            None => return square,
+        }
+    }
+}
+
+/// Whether the puzzle can be completed with `techniques` alone, without guessing.
+pub fn solvable_by_deduction(constraints: &Constraints, techniques: TechniqueSet) -> bool {
+    deduce(constraints, techniques).empty_cells() == 0
+}
+
+/// Cell indices of every row, then every column.
+fn units(size: usize) -> Vec<Vec<usize>> {
+    let mut unit = [0; MAX_SIZE];
+    (0..2 * size).map(|index| unit_cells(size, index, &mut unit).to_vec()).collect()
+}
+
+/// Cell indices of unit `index` of [`units`], written to `cells`.
+fn unit_cells(size: usize, index: usize, cells: &mut [usize; MAX_SIZE]) -> &[usize] {
+    for (k, cell) in cells[..size].iter_mut().enumerate() {
+        *cell = if index < size { index * size + k } else { k * size + index - size };
+    }
+    &cells[..size]
+}
+
+/// The items of `items` passing `keep`, collected on the stack.
+fn stack_filter(items: impl Iterator<Item = usize>, keep: impl Fn(usize) -> bool) -> ([usize; MAX_SIZE], usize) {
+    let mut kept = [0; MAX_SIZE];
+    let mut count = 0;
+    for item in items.filter(|&item| keep(item)) {
+        kept[count] = item;
+        count += 1;
+    }
+    (kept, count)
+}
+
+/// Call `f` with every `k`-element subset of `items`.
+fn for_each_subset(items: &[usize], k: usize, f: &mut dyn FnMut(&[usize])) {
+    fn recurse(items: &[usize], k: usize, chosen: &mut [usize], len: usize, f: &mut dyn FnMut(&[usize])) {
+        if len == k {
+            f(&chosen[..k]);
+            return;
+        }
+        for (index, &item) in items.iter().enumerate() {
+            chosen[len] = item;
+            recurse(&items[index + 1..], k, chosen, len + 1, f);
+        }
+    }
+    recurse(items, k, &mut [0; MAX_SIZE], 0, f);
+}
+
+/// `k` cells of a unit whose candidates together are exactly `k` values: those
+/// values can be removed from the other cells of the unit.
+fn naked_subsets(candidates: &mut [u32], unit: &[usize], k: usize) -> bool {
+    let (cells, count) = stack_filter(unit.iter().copied(), |cell| {
+        candidates[cell] != 0 && candidates[cell].count_ones() as usize <= k
+    });
+    let mut removed = false;
+    for_each_subset(&cells[..count], k, &mut |subset| {
+        let values = subset.iter().fold(0, |acc, &cell| acc | candidates[cell]);
+        if values.count_ones() as usize != k {
+            return;
+        }
+        for &cell in unit {
+            if !subset.contains(&cell) && candidates[cell] & values != 0 {
+                candidates[cell] &= !values;
+                removed = true;
+            }
+        }
+    });
+    removed
+}
+
+/// `k` values of a unit that together fit in exactly `k` cells: the other
+/// candidates of those cells can be removed.
+fn hidden_subsets(candidates: &mut [u32], unit: &[usize], size: usize, k: usize) -> bool {
+    let positions = |candidates: &[u32], value: usize| -> u32 {
+        unit.iter()
+            .enumerate()
+            .filter(|&(_, &cell)| candidates[cell] & (1 << value) != 0)
+            .fold(0, |acc, (index, _)| acc | 1 << index)
+    };
+    let (values, count) = stack_filter(0..size, |value| {
+        let count = positions(candidates, value).count_ones() as usize;
+        count > 0 && count <= k
+    });
+    let mut removed = false;
+    for_each_subset(&values[..count], k, &mut |subset| {
+        let cells = subset.iter().fold(0, |acc, &value| acc | positions(candidates, value));
+        if cells.count_ones() as usize != k {
+            return;
+        }
+        let keep = subset.iter().fold(0, |acc, &value| acc | 1 << value);
+        for (index, &cell) in unit.iter().enumerate() {
+            if cells & (1 << index) != 0 && candidates[cell] & !keep != 0 {
+                candidates[cell] &= keep;
+                removed = true;
+            }
+        }
+    });
+    removed
+}
+
+/// A value confined to the same two columns in two rows must occupy those
+/// columns in these rows, so it can be removed from the other cells of the two
+/// columns (and likewise with rows and columns swapped).
+fn x_wings(candidates: &mut [u32], size: usize) -> bool {
+    let mut removed = false;
+    for transposed in [false, true] {
+        let cell = |line: usize, position: usize| {
+            if transposed { position * size + line } else { line * size + position }
+        };
+        for value in 0..size {
+            let bit = 1u32 << value;
+            let mut positions = [0u32; MAX_SIZE];
+            for (line, positions) in positions[..size].iter_mut().enumerate() {
+                *positions = (0..size)
+                    .filter(|&position| candidates[cell(line, position)] & bit != 0)
+                    .fold(0, |acc, position| acc | 1 << position);
+            }
+            for first in 0..size {
+                if positions[first].count_ones() != 2 {
+                    continue;
+                }
+                for second in first + 1..size {
+                    if positions[second] != positions[first] {
+                        continue;
+                    }
+                    for line in (0..size).filter(|&line| line != first && line != second) {
+                        for position in (0..size).filter(|&position| positions[first] & (1 << position) != 0) {
+                            if candidates[cell(line, position)] & bit != 0 {
+                                candidates[cell(line, position)] &= !bit;
+                                removed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    removed
+}
@@ -0,0 +1,3371 @@
+//! Properties every solver configuration must satisfy, checked on random
+//! Latin squares and random clues consistent with them.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::{ControlFlow, RangeInclusive};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use puzzle_finder::bloom::BloomFilter;
+use puzzle_finder::bounds;
+use puzzle_finder::certificate::{self, Certificate};
+use puzzle_finder::checkpoint::Checkpoint;
+use puzzle_finder::classes;
+use puzzle_finder::cluster::{self, CoordinatorOptions, Job};
+use puzzle_finder::completions;
+use puzzle_finder::critical;
+use puzzle_finder::cycles::{self, Difference, LineKind};
+use puzzle_finder::dedup::ConcurrentTileSet;
+use puzzle_finder::export::{self, ModelFormat};
+use puzzle_finder::first_guess::{self, FirstGuessSettings};
+use puzzle_finder::generators::{self, AnnealOptions};
+use puzzle_finder::hints::{self, format_hints, parse_hints};
+use puzzle_finder::incremental::{self, IncrementalSolver};
+use puzzle_finder::index::{Outcome, ResultsIndex};
+use puzzle_finder::mask::TileMask;
+use puzzle_finder::metrics::{self, Histogram};
+use puzzle_finder::neighbors::{self, Class};
+use puzzle_finder::nogoods::NogoodSet;
+use puzzle_finder::output::{self, Compression, FileHeader, OutputFormat, PuzzleSink, PuzzleWriter};
+use puzzle_finder::pool::{PoolOptions, PuzzlePool};
+use puzzle_finder::profile;
+use puzzle_finder::qcp::{self, Family};
+use puzzle_finder::reference;
+use puzzle_finder::repair::{self, RepairOptions};
+use puzzle_finder::robustness;
+use puzzle_finder::render::{self, RenderOptions, Shape};
+use puzzle_finder::server;
+use puzzle_finder::sheet::{self, SheetPuzzle};
+use puzzle_finder::simulate::{self, SimulationOptions};
+use puzzle_finder::superset::SupersetFilter;
+use puzzle_finder::symmetry;
+use puzzle_finder::techniques::{self, TechniqueSet};
+use puzzle_finder::trace::{self, Reason, SearchObserver, TraceEvent, Tracer};
+use puzzle_finder::witness::{self, Witness};
+use puzzle_finder::{
+    all_tiles, binomial, check_puzzle, complete_latin_square_parallel, complete_latin_square_with,
+    cyclic_latin_square, find_single_solution_puzzles, for_each_solution, idempotent_latin_square, line_load,
+    non_consecutive_latin_square, normalize, pandiagonal_latin_square, puzzle_constraints, puzzle_constraints_with,
+    row_complete_latin_square, solve_batch, solve_constraints, solve_report, standardize_tile_tuple,
+    sudoku_latin_square, symmetric_latin_square, validate_latin_square, ClueSemantics, CluePlacement,
+    CombinationIterator, Constraints, FoundPuzzle, Grid, Kernel, PropagationThreshold, PuzzleSpec, SearchOptions,
+    SolveHooks, SolveReport, SolveStats, SolverOptions, ValueOrder, Variant, MAX_SIZE, CancellationToken,
+    RevolvingDoorIterator,
+};
+
+/// Stop counting solutions beyond this; counts are compared capped.
+const MAX_SOLUTIONS: usize = 50;
+
+/// A random Latin square: the cyclic square with its rows, columns and symbols permuted.
+fn latin_square() -> impl Strategy<Value = Grid> {
+    latin_square_of_size(2..=5)
+}
+
+/// A random Latin square of a size in `sizes`, as in [`latin_square`].
+fn latin_square_of_size(sizes: RangeInclusive<usize>) -> impl Strategy<Value = Grid> {
+    sizes
+        .prop_flat_map(|size| {
+            let permutation = || Just((0..size).collect::<Vec<_>>()).prop_shuffle();
+            (permutation(), permutation(), permutation())
+        })
+        .prop_map(|(rows, cols, symbols)| {
+            let size = rows.len();
+            let mut grid = Grid::new(size);
+            for i in 0..size {
+                for j in 0..size {
+                    grid[(i, j)] = (symbols[(rows[i] + cols[j]) % size] + 1) as u8;
+                }
+            }
+            grid
+        })
+}
+
+/// A clue about one cell of a grid.
+#[derive(Debug, Clone, Copy)]
+enum Clue {
+    None,
+    /// The cell holds its value in the grid.
+    Known,
+    /// The cell does not hold its value in the grid plus this offset (modulo the size).
+    Wrong(usize),
+}
+
+fn clue() -> impl Strategy<Value = Clue> {
+    prop_oneof![Just(Clue::None), Just(Clue::Known), (1..5usize).prop_map(Clue::Wrong)]
+}
+
+/// A Latin square and clues about some of its cells, which it satisfies.
+fn puzzle() -> impl Strategy<Value = (Grid, Vec<Clue>)> {
+    puzzle_of_size(2..=5)
+}
+
+/// A puzzle as in [`puzzle`], of a size in `sizes`.
+fn puzzle_of_size(sizes: RangeInclusive<usize>) -> impl Strategy<Value = (Grid, Vec<Clue>)> {
+    latin_square_of_size(sizes).prop_flat_map(|grid| {
+        let cells = grid.size() * grid.size();
+        (Just(grid), prop::collection::vec(clue(), cells))
+    })
+}
+
+/// A puzzle of the game of size 3 to 6 placing random tiles.
+fn spec() -> impl Strategy<Value = PuzzleSpec> {
+    (3..=6usize).prop_flat_map(|size| {
+        let tiles = all_tiles(size);
+        prop::sample::subsequence(tiles, 0..=size * size).prop_map(move |tiles| PuzzleSpec::new(size, &tiles))
+    })
+}
+
+fn options() -> impl Strategy<Value = SolverOptions> {
+    let techniques = prop_oneof![
+        Just(TechniqueSet::empty()),
+        Just(TechniqueSet::SINGLES),
+        Just(TechniqueSet::SUBSETS),
+        Just(TechniqueSet::FULL),
+    ];
+    let value_order = prop_oneof![
+        Just(ValueOrder::Ascending),
+        Just(ValueOrder::LeastConstraining),
+        Just(ValueOrder::Random),
+    ];
+    (techniques, value_order, any::<u64>(), any::<bool>()).prop_map(
+        |(techniques, value_order, seed, degree_tie_break)| {
+            SolverOptions::builder()
+                .techniques(techniques)
+                .value_order(value_order)
+                .seed(seed)
+                .degree_tie_break(degree_tie_break)
+                .build()
+        },
+    )
+}
+
+/// Apply the clue of `cell` about `grid` on top of `constraints`.
+fn add_clue(constraints: &mut Constraints, grid: &Grid, cell: usize, clue: Clue) {
+    let size = grid.size();
+    let (i, j) = (cell / size, cell % size);
+    match clue {
+        Clue::None => {}
+        Clue::Known => constraints.known[(i, j)] = grid[(i, j)],
+        Clue::Wrong(offset) => {
+            let value = (usize::from(grid[(i, j)]) - 1 + offset) % size + 1;
+            if value != usize::from(grid[(i, j)]) {
+                constraints.wrong[cell] |= 1 << (value - 1);
+            }
+        }
+    }
+}
+
+fn constraints(grid: &Grid, clues: &[Clue]) -> Constraints {
+    let mut constraints = Constraints::new(grid.size());
+    for (cell, &clue) in clues.iter().enumerate() {
+        add_clue(&mut constraints, grid, cell, clue);
+    }
+    constraints
+}
+
+/// The clue of `cell` about `grid` for the incremental solver, if it says something.
+fn incremental_clue(grid: &Grid, cell: usize, clue: Clue) -> Option<incremental::Clue> {
+    let size = grid.size();
+    let (i, j) = (cell / size, cell % size);
+    match clue {
+        Clue::None => None,
+        Clue::Known => Some(incremental::Clue::Given((i, j), grid[(i, j)].into())),
+        Clue::Wrong(offset) => {
+            let value = (usize::from(grid[(i, j)]) - 1 + offset) % size + 1;
+            (value != usize::from(grid[(i, j)])).then_some(incremental::Clue::Forbidden((i, j), value))
+        }
+    }
+}
+
+fn solve(constraints: &Constraints, options: &SolverOptions) -> Vec<Grid> {
+    solve_constraints(constraints, Some(MAX_SOLUTIONS), options, SolveHooks::default()).0
+}
+
+/// Check that the solver with `engine_options` finds the solutions it finds
+/// with `options`, in any order.
+fn assert_same_solutions(
+    constraints: &Constraints,
+    engine_options: &SolverOptions,
+    options: &SolverOptions,
+) -> Result<(), TestCaseError> {
+    let (mut solutions, stats) =
+        solve_constraints(constraints, Some(MAX_SOLUTIONS), engine_options, SolveHooks::default());
+    let mut expected = solve(constraints, options);
+    prop_assert!(!stats.timed_out);
+    prop_assert_eq!(solutions.len(), expected.len());
+    if expected.len() < MAX_SOLUTIONS {
+        solutions.sort_by_key(|solution| format!("{:?}", solution));
+        expected.sort_by_key(|solution| format!("{:?}", solution));
+        prop_assert_eq!(solutions, expected);
+    }
+    Ok(())
+}
+
+/// Check that the solver finds the solutions of the `clues` about `grid`
+/// under the rules of `variant` that the reference solver finds, and that
+/// dancing links find them too.
+fn assert_variant_agrees(
+    grid: &Grid,
+    variant: Variant,
+    clues: &[Clue],
+    options: &SolverOptions,
+) -> Result<(), TestCaseError> {
+    let mut constraints = constraints(grid, clues);
+    constraints.variant = variant;
+    let mut solutions = solve(&constraints, options);
+    let mut reference_solutions = reference::solve(&constraints, MAX_SOLUTIONS);
+    prop_assert!(solutions.len() == MAX_SOLUTIONS || solutions.contains(grid));
+    prop_assert!(solutions.iter().all(|solution| variant.allows(solution)));
+    prop_assert_eq!(solutions.len(), reference_solutions.len());
+    if solutions.len() < MAX_SOLUTIONS {
+        solutions.sort_by_key(|solution| format!("{:?}", solution));
+        reference_solutions.sort_by_key(|solution| format!("{:?}", solution));
+        prop_assert_eq!(solutions, reference_solutions);
+    }
+    assert_same_solutions(&constraints, &SolverOptions { dlx: true, ..*options }, options)
+}
+
+/// Check the general and specialized kernels search the puzzle of `grid` and
+/// `clues` the same way, down to their statistics.
+fn assert_kernels_search_alike(grid: &Grid, clues: &[Clue], options: SolverOptions) -> Result<(), TestCaseError> {
+    let constraints = constraints(grid, clues);
+    let search = |kernel| {
+        let options = SolverOptions { kernel, ..options };
+        let (solutions, stats) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, SolveHooks::default());
+        (solutions, stats.backtrack_nodes, stats.guesses, stats.forced_cells, stats.propagation_passes)
+    };
+    prop_assert_eq!(search(Kernel::General), search(Kernel::Specialized));
+    Ok(())
+}
+
+/// A puzzle placing `tiles`, as the search would record it without its solution.
+fn found_puzzle(tiles: Vec<(usize, usize)>) -> FoundPuzzle {
+    FoundPuzzle {
+        tiles,
+        solution: None,
+        stats: SolveStats::default(),
+        solve_time: std::time::Duration::ZERO,
+        singles_solvable: false,
+        certificate: None,
+        expected_guesses: None,
+    }
+}
+
+/// A path of the temporary directory ending in `name`, different at each call
+/// so that tests running at the same time don't share files.
+fn temp_path(name: &str) -> std::path::PathBuf {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let call = CALLS.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("solver-properties-{}-{}-{}", std::process::id(), call, name))
+}
+
+/// Run `find_single_solution_puzzles` on the N×N cyclic square with one sink
+/// writing `format`, returning the puzzles found and the bytes it wrote.
+fn search_output(
+    size: usize,
+    placed: usize,
+    options: &SearchOptions,
+    format: OutputFormat,
+) -> (Vec<FoundPuzzle>, Vec<u8>) {
+    let path = temp_path("search.out");
+    let path = path.to_str().unwrap();
+    let header = FileHeader::new(size, ClueSemantics::Sudodle);
+    let sink = output::create_file_sink(path, format, None, false, &header).unwrap();
+    let result = find_single_solution_puzzles(size, placed, options.clone(), vec![sink], HashSet::new());
+    let bytes = std::fs::read(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    (result.puzzles, bytes)
+}
+
+/// The records of a CSV puzzle file, by column name, after its header.
+fn csv_records(text: &str) -> Vec<HashMap<String, String>> {
+    let mut lines = text.lines().skip_while(|line| FileHeader::parse_line(line).is_some());
+    let columns: Vec<&str> = lines.next().map(|line| line.split(',').collect()).unwrap_or_default();
+    lines
+        .map(|line| columns.iter().map(|column| column.to_string()).zip(line.split(',').map(str::to_string)).collect())
+        .collect()
+}
+
+/// Counts the nodes a solve enters and exits, and keeps the solutions it reports.
+#[derive(Default)]
+struct CountingObserver {
+    entered: Cell<usize>,
+    exited: Cell<usize>,
+    solutions: RefCell<Vec<Grid>>,
+}
+
+impl SearchObserver for CountingObserver {
+    fn enter_node(&self, _cell: (usize, usize), _value: usize, _depth: usize, _reason: Reason) {
+        self.entered.set(self.entered.get() + 1);
+    }
+
+    fn exit_node(&self, _depth: usize) {
+        self.exited.set(self.exited.get() + 1);
+    }
+
+    fn solution(&self, solution: &Grid) {
+        self.solutions.borrow_mut().push(solution.clone());
+    }
+}
+
+/// Words of a bloom filter of `len` words holding the 128-bit `keys`, set as
+/// in the filters written before tile masks had 256 bits.
+fn bloom_words_of_128_bit_keys(keys: &[u128], hashes: u32, len: usize) -> Vec<u64> {
+    fn mix(mut x: u64) -> u64 {
+        x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^ (x >> 31)
+    }
+    let mut words = vec![0; len];
+    let bits = len as u64 * 64;
+    for &key in keys {
+        let first = mix(key as u64 ^ mix((key >> 64) as u64));
+        let step = mix(first ^ 0x9E37_79B9_7F4A_7C15) | 1;
+        for i in 0..hashes as u64 {
+            let position = first.wrapping_add(i.wrapping_mul(step)) % bits;
+            words[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+    words
+}
+
+/// Run `find_puzzles` with `args`, feeding it `input`, and return what it printed.
+fn run_command_line(args: &[&str], input: &str) -> Result<String, TestCaseError> {
+    run_command_line_with_logs(args, input).map(|(printed, _)| printed)
+}
+
+/// Same as [`run_command_line`], also returning what it logged to standard error.
+fn run_command_line_with_logs(args: &[&str], input: &str) -> Result<(String, String), TestCaseError> {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_find_puzzles"))
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    std::io::Write::write_all(&mut stdin, input.as_bytes()).map_err(|e| TestCaseError::fail(e.to_string()))?;
+    drop(stdin);
+    let output = child.wait_with_output().map_err(|e| TestCaseError::fail(e.to_string()))?;
+    let logs = String::from_utf8_lossy(&output.stderr).into_owned();
+    prop_assert!(output.status.success(), "find_puzzles {} failed:\n{}", args.join(" "), logs);
+    let printed = String::from_utf8(output.stdout).map_err(|e| TestCaseError::fail(e.to_string()))?;
+    Ok((printed, logs))
+}
+
+/// Start a server with `options`, `pool` and `max_connections` on a free port
+/// of this machine, returning its address and a first connection to it.
+fn start_server(
+    options: SolverOptions,
+    pool: Option<Arc<PuzzlePool>>,
+    max_connections: usize,
+) -> Result<(std::net::SocketAddr, std::net::TcpStream), TestCaseError> {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").and_then(|listener| listener.local_addr()).unwrap();
+    std::thread::spawn(move || server::serve(&addr.to_string(), options, pool, max_connections));
+    for _ in 0..100 {
+        if let Ok(stream) = std::net::TcpStream::connect(addr) {
+            return Ok((addr, stream));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    Err(TestCaseError::fail(format!("the server on {} did not start", addr)))
+}
+
+/// Send `GET path` on `stream` and return the whole response.
+fn http_get(mut stream: std::net::TcpStream, path: &str) -> Result<String, TestCaseError> {
+    let mut response = String::new();
+    std::io::Write::write_all(&mut stream, format!("GET {} HTTP/1.1\r\n\r\n", path).as_bytes())
+        .and_then(|()| std::io::Read::read_to_string(&mut stream, &mut response))
+        .map_err(|e| TestCaseError::fail(e.to_string()))?;
+    Ok(response)
+}
+
+/// Value of the sample `series` (a metric name and its labels) of metrics in
+/// the Prometheus text format.
+fn metric(text: &str, series: &str) -> Option<f64> {
+    text.lines().find_map(|line| line.strip_prefix(series)?.strip_prefix(' ')?.parse().ok())
+}
+
+fn is_latin_square(grid: &Grid) -> bool {
+    let size = grid.size();
+    let full = (1u32 << size) - 1;
+    (0..size).all(|line| {
+        let row = (0..size).fold(0, |acc, k| acc | 1u32 << (grid[(line, k)].max(1) - 1));
+        let col = (0..size).fold(0, |acc, k| acc | 1u32 << (grid[(k, line)].max(1) - 1));
+        row == full && col == full && (0..size).all(|k| grid[(line, k)] != 0)
+    })
+}
+
+proptest! {
+    #[test]
+    fn completions_satisfy_every_constraint((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let solutions = solve(&constraints, &options);
+        // The grid the clues were taken from is always a solution
+        prop_assert!(!solutions.is_empty());
+        for solution in &solutions {
+            prop_assert!(is_latin_square(solution));
+            let size = grid.size();
+            for cell in 0..size * size {
+                let (i, j) = (cell / size, cell % size);
+                let known = constraints.known[(i, j)];
+                prop_assert!(known == 0 || solution[(i, j)] == known);
+                prop_assert_eq!(constraints.wrong[cell] & (1 << (solution[(i, j)] - 1)), 0);
+            }
+        }
+        let mut sorted = solutions.iter().map(|solution| format!("{:?}", solution)).collect::<Vec<_>>();
+        sorted.sort();
+        sorted.dedup();
+        prop_assert_eq!(sorted.len(), solutions.len(), "duplicate solutions");
+    }
+
+    #[test]
+    fn full_grid_is_its_own_solution(grid in latin_square(), options in options()) {
+        let mut constraints = Constraints::new(grid.size());
+        constraints.known = grid.clone();
+        prop_assert_eq!(solve(&constraints, &options), vec![grid]);
+    }
+
+    #[test]
+    fn adding_a_clue_never_adds_solutions(
+        (grid, clues) in puzzle(),
+        extra in (any::<prop::sample::Index>(), clue()),
+        options in options(),
+    ) {
+        let constraints = constraints(&grid, &clues);
+        let mut more_constraints = constraints.clone();
+        add_clue(&mut more_constraints, &grid, extra.0.index(clues.len()), extra.1);
+        prop_assert!(solve(&more_constraints, &options).len() <= solve(&constraints, &options).len());
+    }
+
+    #[test]
+    fn agrees_with_the_reference_solver((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let mut solutions = solve(&constraints, &options);
+        let mut reference_solutions = reference::solve(&constraints, MAX_SOLUTIONS);
+        prop_assert_eq!(solutions.len(), reference_solutions.len());
+        // Past the cap both solvers may have stopped on different completions
+        if solutions.len() < MAX_SOLUTIONS {
+            solutions.sort_by_key(|solution| format!("{:?}", solution));
+            reference_solutions.sort_by_key(|solution| format!("{:?}", solution));
+            prop_assert_eq!(solutions, reference_solutions);
+        }
+    }
+
+    #[test]
+    fn exact_count_matches_the_enumeration((grid, clues) in puzzle()) {
+        let constraints = constraints(&grid, &clues);
+        let count = completions::count_exact(&constraints).expect("small squares are counted exactly");
+        let enumerated = reference::solve(&constraints, MAX_SOLUTIONS).len();
+        prop_assert!(count >= 1);
+        prop_assert_eq!((count as usize).min(MAX_SOLUTIONS), enumerated);
+    }
+
+    #[test]
+    fn estimated_completions_approach_the_exact_count((grid, clues) in puzzle(), seed in any::<u64>()) {
+        let constraints = constraints(&grid, &clues);
+        let count = completions::count_exact(&constraints).expect("small squares are counted exactly") as f64;
+        let estimate = completions::estimate_completions(&constraints, 2000, seed);
+        prop_assert_eq!(estimate, completions::estimate_completions(&constraints, 2000, seed));
+        prop_assert_eq!(estimate.probes, 2000);
+        prop_assert!(estimate.success_rate > 0.0 && estimate.success_rate <= 1.0);
+        prop_assert!(estimate.tree_nodes >= estimate.completions);
+        // Knuth's estimates are unbiased, so their mean lies within a few standard errors of the count
+        let error = (estimate.completions - count).abs();
+        prop_assert!(error <= 6.0 * estimate.completions_std_error + 1e-9 * count, "{:?} for {}", estimate, count);
+    }
+
+    #[test]
+    fn sampled_completions_are_completions((grid, clues) in puzzle(), seed in any::<u64>()) {
+        let constraints = constraints(&grid, &clues);
+        let samples = completions::sample_completions(&constraints, 3, seed);
+        // The grid the clues were taken from is a completion, but probes may miss it
+        prop_assert!(samples.len() == 3 || samples.is_empty());
+        let solutions = solve(&constraints, &SolverOptions::default());
+        for sample in &samples {
+            prop_assert!(is_latin_square(sample));
+            if solutions.len() < MAX_SOLUTIONS {
+                prop_assert!(solutions.contains(sample));
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_counts_match_fresh_solves((grid, clues) in puzzle(), options in options(), limit in 1..4usize) {
+        let mut solver = IncrementalSolver::new(Constraints::new(grid.size()), options);
+        let mut ids = Vec::new();
+        for (cell, &clue) in clues.iter().enumerate() {
+            if let Some(incremental_clue) = incremental_clue(&grid, cell, clue) {
+                ids.push((cell, solver.add(incremental_clue)));
+            }
+            let fresh = constraints(&grid, &clues[..=cell]);
+            prop_assert_eq!(solver.solution_count(limit), solve(&fresh, &options).len().min(limit));
+        }
+        // Remove every other clue, then add them back
+        let mut kept = clues.clone();
+        for &(cell, id) in ids.iter().step_by(2) {
+            solver.remove(id);
+            kept[cell] = Clue::None;
+            prop_assert_eq!(solver.solution_count(limit), solve(&constraints(&grid, &kept), &options).len().min(limit));
+        }
+        for &(cell, _) in ids.iter().step_by(2) {
+            solver.add(incremental_clue(&grid, cell, clues[cell]).expect("only clues saying something were added"));
+            kept[cell] = clues[cell];
+            prop_assert_eq!(solver.solution_count(limit), solve(&constraints(&grid, &kept), &options).len().min(limit));
+        }
+    }
+
+    #[test]
+    fn validation_agrees_with_the_definition(grid in latin_square(), cell in any::<prop::sample::Index>(), value in 0..=6u8) {
+        prop_assert!(validate_latin_square(&grid).is_ok());
+        let mut changed = grid.clone();
+        let size = grid.size();
+        changed[(cell.index(size * size) / size, cell.index(size * size) % size)] = value;
+        prop_assert_eq!(validate_latin_square(&changed).is_ok(), is_latin_square(&changed));
+    }
+
+    #[test]
+    fn normalized_squares_start_in_order(grid in latin_square()) {
+        let normalized = normalize(&grid);
+        prop_assert!(validate_latin_square(&normalized).is_ok());
+        prop_assert!((0..grid.size()).all(|j| usize::from(normalized[(0, j)]) == j + 1));
+        prop_assert_eq!(normalize(&normalized), normalized);
+    }
+
+    #[test]
+    fn grids_round_trip_through_json(grid in latin_square()) {
+        let json = serde_json::to_string(&grid).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Grid>(&json).unwrap(), grid);
+    }
+
+    #[test]
+    fn node_budget_bounds_the_search((grid, clues) in puzzle(), options in options(), budget in 1..50usize) {
+        let constraints = constraints(&grid, &clues);
+        let budgeted = SolverOptions { node_budget: Some(budget), ..options };
+        let (solutions, stats) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &budgeted, SolveHooks::default());
+        prop_assert!(stats.backtrack_nodes <= budget + 1);
+        if !stats.timed_out {
+            prop_assert_eq!(solutions, solve(&constraints, &options));
+        }
+    }
+
+    #[test]
+    fn restarts_find_the_same_solutions((grid, clues) in puzzle(), options in options(), unit in 1..20usize) {
+        let constraints = constraints(&grid, &clues);
+        let restarting = SolverOptions { restarts: Some(unit), ..options };
+        assert_same_solutions(&constraints, &restarting, &options)?;
+    }
+
+    #[test]
+    fn learning_finds_the_same_solutions((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let learning = SolverOptions { learning: true, ..options };
+        assert_same_solutions(&constraints, &learning, &options)?;
+    }
+
+    #[test]
+    fn dlx_finds_the_same_solutions((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let dlx = SolverOptions { dlx: true, ..options };
+        assert_same_solutions(&constraints, &dlx, &options)?;
+    }
+
+    #[test]
+    fn portfolio_finds_the_same_solutions((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let portfolio = SolverOptions { portfolio: true, ..options };
+        assert_same_solutions(&constraints, &portfolio, &options)?;
+    }
+
+    #[test]
+    fn batches_report_like_single_solves(puzzles in prop::collection::vec(spec(), 0..8)) {
+        let options = SolverOptions::default();
+        // Solve times differ from one solve to the next
+        let timeless =
+            |report: Result<SolveReport, String>| report.map(|report| SolveReport { solve_time_us: 0, ..report });
+        let reports: Vec<_> = solve_batch(&puzzles, &options).into_iter().map(timeless).collect();
+        let expected: Vec<_> = puzzles.iter().map(|puzzle| timeless(solve_report(puzzle, &options, None))).collect();
+        prop_assert_eq!(reports, expected);
+    }
+
+    #[test]
+    fn certificates_prove_the_unique_solution((grid, clues) in puzzle()) {
+        let constraints = constraints(&grid, &clues);
+        if let Some(found) = certificate::find(&constraints) {
+            let solution = certificate::check(&constraints, &found).unwrap();
+            prop_assert_eq!(solve(&constraints, &SolverOptions::default()), vec![solution]);
+            prop_assert_eq!(Certificate::parse(&found.encode()), Ok(found));
+        }
+    }
+
+    #[test]
+    fn unique_classic_puzzles_place_the_lower_bound((grid, clues) in puzzle()) {
+        // Every cell with a clue becomes a given
+        let mut constraints = Constraints::new(grid.size());
+        let givens: Vec<usize> = (0..clues.len()).filter(|&cell| !matches!(clues[cell], Clue::None)).collect();
+        for &cell in &givens {
+            add_clue(&mut constraints, &grid, cell, Clue::Known);
+        }
+        if solve(&constraints, &SolverOptions::default()).len() == 1 {
+            let bound = bounds::lower_bound(&grid, ClueSemantics::GivensOnly, Variant::Latin, 10_000).unwrap();
+            prop_assert!(givens.len() >= bound.tiles);
+            for trade in &bound.trades {
+                prop_assert!(trade.cells.iter().any(|&(i, j)| constraints.known[(i, j)] != 0));
+            }
+        }
+    }
+
+    #[test]
+    fn critical_sets_force_the_cyclic_square_and_lose_it_without_any_tile(
+        size in 2..=6usize,
+        runs in 1..=4usize,
+        seed in any::<u64>(),
+        limit in 1..=5usize,
+    ) {
+        let grid = cyclic_latin_square(size);
+        let solver = SolverOptions::default();
+        let solutions = |tiles: &[(usize, usize)]| {
+            solve(&puzzle_constraints_with(tiles, &grid, &all_tiles(size), ClueSemantics::GivensOnly), &solver)
+        };
+        let greedy = critical::greedy_critical_sets(size, runs, seed, limit, &solver);
+        let exact = (size <= 4).then(|| critical::smallest_critical_sets(size, limit, &solver));
+        for found in exact.iter().chain([&greedy]) {
+            prop_assert!(!found.sets.is_empty() && found.sets.len() <= limit);
+            prop_assert!(found.sets.windows(2).all(|pair| pair[0] < pair[1]));
+            for set in &found.sets {
+                prop_assert_eq!(set.len(), found.tiles);
+                prop_assert_eq!(solutions(set), vec![grid.clone()]);
+                for removed in set {
+                    let fewer: Vec<_> = set.iter().copied().filter(|tile| tile != removed).collect();
+                    prop_assert!(solutions(&fewer).len() > 1);
+                }
+            }
+        }
+        prop_assert!(!greedy.exact);
+        if let Some(exact) = exact {
+            prop_assert!(exact.exact);
+            prop_assert!(exact.tiles <= greedy.tiles);
+        }
+    }
+
+    #[test]
+    fn minimum_descent_finds_fewer_tiles_at_each_level(size in 3..=5usize, seed in any::<u64>()) {
+        let grid = cyclic_latin_square(size);
+        let options = AnnealOptions { steps: 50, ..AnnealOptions::default() };
+        let solver = SolverOptions::default();
+        let levels =
+            generators::find_minimum(&grid, 0, 2, seed, &options, &solver, ClueSemantics::GivensOnly, Variant::Latin);
+        prop_assert!(!levels.is_empty());
+        for (index, level) in levels.iter().enumerate() {
+            prop_assert_eq!(level.placed + index, levels[0].placed);
+            prop_assert_eq!(level.example.is_none(), index > 0 && index + 1 == levels.len());
+            if let Some(tiles) = &level.example {
+                prop_assert_eq!(tiles.len(), level.placed);
+                let mut constraints = Constraints::new(size);
+                for &cell in tiles {
+                    constraints.known[cell] = grid[cell];
+                }
+                prop_assert_eq!(solve(&constraints, &solver), vec![grid.clone()]);
+            }
+        }
+    }
+
+    #[test]
+    fn cycles_split_each_pair_of_lines_and_bound_unique_clue_sets(
+        (grid, tiles) in latin_square().prop_flat_map(|grid| {
+            let size = grid.size();
+            (Just(grid), prop::sample::subsequence(all_tiles(size), 0..=size * size))
+        }),
+    ) {
+        let size = grid.size();
+        for kind in [LineKind::Row, LineKind::Column, LineKind::Value] {
+            let mut cells_of_pairs: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+            for cycle in cycles::cycles(&grid, kind) {
+                prop_assert!(cycle.len() >= 2);
+                cells_of_pairs.entry(cycle.lines).or_default().extend(&cycle.cells);
+            }
+            prop_assert_eq!(cells_of_pairs.len() as u128, binomial(size, 2));
+            for cells in cells_of_pairs.values() {
+                prop_assert_eq!(cells.iter().collect::<HashSet<_>>().len(), 2 * size);
+            }
+            let types = cycles::cycle_types(&grid, kind);
+            prop_assert_eq!(types.values().sum::<usize>() as u128, binomial(size, 2));
+            prop_assert!(types.keys().all(|lengths| lengths.iter().sum::<usize>() == size));
+        }
+        let subsquares = (0..size)
+            .flat_map(|r| (r + 1..size).map(move |s| (r, s)))
+            .flat_map(|rows| (0..size).flat_map(move |c| (c + 1..size).map(move |d| (rows, (c, d)))))
+            .filter(|&((r, s), (c, d))| grid[(r, c)] == grid[(s, d)] && grid[(r, d)] == grid[(s, c)])
+            .count();
+        prop_assert_eq!(cycles::intercalates(&grid), subsquares);
+
+        // A unique classic clue set places a tile on each of the disjoint cycles
+        let constraints = puzzle_constraints_with(&tiles, &grid, &all_tiles(size), ClueSemantics::GivensOnly);
+        if solve(&constraints, &SolverOptions::default()).len() == 1 {
+            prop_assert!(tiles.len() >= cycles::givens_lower_bound(&grid));
+        }
+    }
+
+    #[test]
+    fn swapped_row_cycles_differ_by_their_length(grid in latin_square()) {
+        for cycle in cycles::cycles(&grid, LineKind::Row) {
+            let (first, second) = cycle.lines;
+            let mut swapped = grid.clone();
+            for &(_, col) in &cycle.cells {
+                swapped[(first, col)] = grid[(second, col)];
+                swapped[(second, col)] = grid[(first, col)];
+            }
+            let expected = match cycle.len() {
+                2 => Difference::Intercalate,
+                3 => Difference::ThreeCycle,
+                _ => Difference::LongerCycle,
+            };
+            prop_assert!(is_latin_square(&swapped));
+            prop_assert_eq!(cycles::difference(&grid, &swapped), expected);
+            prop_assert_eq!(cycles::closest_difference(&[grid.clone(), swapped]), Some(expected));
+        }
+    }
+
+    #[test]
+    fn results_indexes_keep_their_records(
+        sets in prop::collection::vec((prop::sample::subsequence(all_tiles(4), 0..=16), 0..3usize), 0..20),
+        cut in 1..33usize,
+        last in prop::sample::subsequence(all_tiles(4), 0..=16),
+    ) {
+        let grid = cyclic_latin_square(4);
+        let path = temp_path("results-index.bin");
+        let _ = std::fs::remove_file(&path);
+        let index = ResultsIndex::open(&path, &grid, ClueSemantics::Sudodle, Variant::Latin).unwrap();
+        let mut expected = std::collections::HashMap::new();
+        for (tiles, solutions) in &sets {
+            index.record(tiles, Outcome::of_solutions(*solutions)).unwrap();
+            expected.entry(tiles.clone()).or_insert(Outcome::of_solutions(*solutions));
+        }
+        index.flush().unwrap();
+        drop(index);
+        // A record cut short by an interrupted run is dropped, and the next one lines up
+        let length = std::fs::metadata(&path).unwrap().len();
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, &vec![0xFF; cut]).unwrap();
+        drop(file);
+        let reopened = ResultsIndex::open(&path, &grid, ClueSemantics::Sudodle, Variant::Latin).unwrap();
+        prop_assert_eq!(std::fs::metadata(&path).unwrap().len(), length);
+        prop_assert_eq!(reopened.loaded(), expected.len());
+        for (tiles, outcome) in &expected {
+            prop_assert_eq!(reopened.get(tiles), Some(*outcome));
+        }
+        reopened.record(&last, Outcome::Unique).unwrap();
+        expected.entry(last).or_insert(Outcome::Unique);
+        reopened.flush().unwrap();
+        drop(reopened);
+        let reopened = ResultsIndex::open(&path, &grid, ClueSemantics::Sudodle, Variant::Latin).unwrap();
+        prop_assert_eq!(reopened.loaded(), expected.len());
+        for (tiles, outcome) in &expected {
+            prop_assert_eq!(reopened.get(tiles), Some(*outcome));
+        }
+        drop(reopened);
+        // The index of another search is refused
+        prop_assert!(ResultsIndex::open(&path, &grid, ClueSemantics::GivensOnly, Variant::Latin).is_err());
+        prop_assert!(ResultsIndex::open(&path, &grid, ClueSemantics::Sudodle, Variant::Sudoku).is_err());
+        let other = sudoku_latin_square(4);
+        prop_assert!(ResultsIndex::open(&path, &other, ClueSemantics::Sudodle, Variant::Latin).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn neighbors_are_classified_by_their_solutions(
+        tiles in prop::sample::subsequence(all_tiles(4), 0..=16),
+        distance in 0..=2usize,
+        givens_only in any::<bool>(),
+    ) {
+        let grid = cyclic_latin_square(4);
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let options = SolverOptions::default();
+        let found = neighbors::neighbors(&grid, &tiles, distance, semantics, &options);
+        prop_assert_eq!(found.len(), [0, 16, 16 + 120][distance]);
+        for neighbor in &found {
+            let differing = neighbor.tiles.iter().filter(|tile| !tiles.contains(tile)).count()
+                + tiles.iter().filter(|tile| !neighbor.tiles.contains(tile)).count();
+            prop_assert_eq!(differing, neighbor.distance);
+            let constraints = puzzle_constraints_with(&neighbor.tiles, &grid, &all_tiles(4), semantics);
+            let class = match solve(&constraints, &options).len() {
+                0 => Class::Invalid,
+                1 => Class::Unique,
+                _ => Class::Multiple,
+            };
+            prop_assert_eq!(neighbor.class, class);
+            prop_assert_eq!(neighbor.puzzle.is_some(), class == Class::Unique);
+        }
+    }
+
+    #[test]
+    fn families_are_unique_puzzles_with_the_images_of_the_solution(
+        (size, tiles) in (4..=5usize)
+            .prop_flat_map(|size| (Just(size), prop::sample::subsequence(all_tiles(size), 0..=size * size))),
+    ) {
+        let options = SolverOptions::default();
+        let Ok(puzzle) = check_puzzle(&PuzzleSpec::new(size, &tiles), &options) else {
+            return Ok(());
+        };
+        let family = symmetry::family(size, &puzzle);
+        let distinct: HashSet<&Vec<(usize, usize)>> = family.iter().map(|(_, image)| &image.tiles).collect();
+        prop_assert_eq!(distinct.len(), family.len());
+        prop_assert!(!distinct.contains(&puzzle.tiles));
+        for (symmetry, image) in &family {
+            let checked = check_puzzle(&PuzzleSpec::new(size, &image.tiles), &options);
+            prop_assert!(checked.is_ok(), "image {:?} is not unique", symmetry);
+            prop_assert_eq!(&checked.unwrap().solution, &image.solution);
+            if let Some(certificate) = &image.certificate {
+                let constraints = puzzle_constraints_with(
+                    &image.tiles,
+                    &cyclic_latin_square(size),
+                    &all_tiles(size),
+                    ClueSemantics::Sudodle,
+                );
+                prop_assert_eq!(certificate::check(&constraints, certificate).ok(), image.solution.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn propagation_thresholds_do_not_change_the_solutions(
+        (grid, clues) in puzzle(),
+        options in options(),
+        threshold in prop_oneof![(0..=100u8).prop_map(PropagationThreshold::Percent), Just(PropagationThreshold::Auto)],
+    ) {
+        let constraints = constraints(&grid, &clues);
+        let thresholded = SolverOptions { propagation_threshold: threshold, ..options };
+        let mut solutions = solve(&constraints, &thresholded);
+        let mut expected = solve(&constraints, &options);
+        prop_assert_eq!(solutions.len(), expected.len());
+        if expected.len() < MAX_SOLUTIONS {
+            solutions.sort_by_key(|solution| format!("{:?}", solution));
+            expected.sort_by_key(|solution| format!("{:?}", solution));
+            prop_assert_eq!(solutions, expected);
+        }
+    }
+
+    #[test]
+    fn kernels_search_alike((grid, clues) in puzzle(), options in options()) {
+        assert_kernels_search_alike(&grid, &clues, options)?;
+    }
+
+    #[test]
+    fn fixed_size_kernels_search_alike((grid, clues) in puzzle_of_size(6..=9), options in options()) {
+        assert_kernels_search_alike(&grid, &clues, options)?;
+    }
+
+    #[test]
+    fn games_of_large_grids_end_on_the_secret(
+        size in 12..=MAX_SIZE,
+        hidden in prop::collection::vec(any::<prop::sample::Index>(), 0..48),
+        seed in any::<u64>(),
+    ) {
+        let secret = cyclic_latin_square(size);
+        let hidden: HashSet<usize> = hidden.iter().map(|cell| cell.index(size * size)).collect();
+        let mut knowledge = Constraints::new(size);
+        for cell in (0..size * size).filter(|cell| !hidden.contains(cell)) {
+            let tile = (cell / size, cell % size);
+            knowledge.set_known(tile, secret[tile].into());
+        }
+        let options = SimulationOptions { relaxed: true, ..SimulationOptions::default() };
+        let transcript = simulate::play(&secret, knowledge, &options, &mut simulate::trial_rng(seed, 0));
+        prop_assert_eq!(transcript.guesses.last(), Some(&secret));
+        prop_assert_eq!(transcript.correct.last().copied(), Some(size * size));
+    }
+
+    #[test]
+    fn csv_files_of_other_columns_are_not_appended_to(dropped in 1..=6usize) {
+        let path = temp_path("append-columns.csv");
+        let path = path.to_str().unwrap();
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        drop(PuzzleWriter::create(path, OutputFormat::Csv, None, false, &header).unwrap());
+        prop_assert!(PuzzleWriter::create(path, OutputFormat::Csv, None, true, &header).is_ok());
+        // The columns of an earlier version, which had fewer of them
+        let text = std::fs::read_to_string(path).unwrap();
+        let columns = text.lines().find(|line| line.starts_with("size,")).unwrap();
+        let fewer = columns.split(',').collect::<Vec<_>>();
+        let fewer = fewer[..fewer.len() - dropped].join(",");
+        std::fs::write(path, text.replace(columns, &fewer)).unwrap();
+        prop_assert!(PuzzleWriter::create(path, OutputFormat::Csv, None, true, &header).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn deterministic_searches_write_the_same_bytes(
+        placed in 3..=6usize,
+        random in any::<bool>(),
+        generator_seed in any::<u64>(),
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+    ) {
+        let options = SearchOptions {
+            random_tries: random.then_some(500),
+            generator_seed,
+            deterministic_output: true,
+            ..SearchOptions::default()
+        };
+        let (puzzles, bytes) = search_output(4, placed, &options, format);
+        let (again, again_bytes) = search_output(4, placed, &options, format);
+        let tiles = |puzzles: &[FoundPuzzle]| puzzles.iter().map(|puzzle| puzzle.tiles.clone()).collect::<Vec<_>>();
+        prop_assert_eq!(tiles(&puzzles), tiles(&again));
+        prop_assert_eq!(bytes, again_bytes);
+    }
+
+    #[test]
+    fn first_only_searches_find_the_lowest_rank_puzzle(placed in 3..=6usize) {
+        let grid = cyclic_latin_square(4);
+        let lowest = CombinationIterator::new(all_tiles(4), placed).find(|tiles| {
+            solve(&puzzle_constraints(tiles, &grid, &all_tiles(4)), &SolverOptions::default()).len() == 1
+        });
+        let options = SearchOptions { first_only: true, deterministic_output: true, ..SearchOptions::default() };
+        let result = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        prop_assert_eq!(result.puzzles.len(), usize::from(lowest.is_some()));
+        prop_assert_eq!(result.puzzles.first().map(|puzzle| puzzle.tiles.clone()), lowest);
+    }
+
+    #[test]
+    fn the_command_line_solves_large_puzzles_in_letter_notation(
+        size in 10..=MAX_SIZE,
+        first in any::<prop::sample::Index>(),
+        offset in any::<prop::sample::Index>(),
+    ) {
+        // Blanking two rows of the cyclic square leaves them wrong where they
+        // stand, which forces them to swap
+        let grid = cyclic_latin_square(size);
+        let first = first.index(size);
+        let rows = [first, (first + 1 + offset.index(size - 1)) % size];
+        let puzzle: Vec<String> = output::format_grid(&grid)
+            .split('/')
+            .enumerate()
+            .map(|(row, values)| if rows.contains(&row) { ".".repeat(size) } else { values.to_string() })
+            .collect();
+        let mut expected = grid.clone();
+        for col in 0..size {
+            expected[(rows[0], col)] = grid[(rows[1], col)];
+            expected[(rows[1], col)] = grid[(rows[0], col)];
+        }
+        let report = run_command_line(&["solve", "--stdin"], &format!("{}\n", puzzle.join("/")))?;
+        let report: SolveReport = serde_json::from_str(&report).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert!(report.unique);
+        prop_assert_eq!(report.solution, Some(expected));
+    }
+
+    #[test]
+    fn bloom_filters_never_forget_a_key_and_keep_to_their_false_positive_rate(
+        keys in prop::collection::hash_set(any::<u128>(), 1..400),
+        rate in prop_oneof![Just(0.1), Just(0.01), Just(0.001)],
+        fresh in prop::collection::vec(any::<u128>(), 4000),
+    ) {
+        let mut filter = BloomFilter::new(keys.len(), rate);
+        prop_assert!(filter.is_empty());
+        let mut added = 0;
+        for &key in &keys {
+            added += filter.insert(TileMask::from_halves([key, 0])) as u64;
+            prop_assert!(!filter.insert(TileMask::from_halves([key, 0])));
+        }
+        prop_assert_eq!(filter.len(), added);
+        prop_assert!(keys.iter().all(|&key| filter.contains(TileMask::from_halves([key, 0]))));
+        let saved = BloomFilter::from_parts(filter.words().to_vec(), filter.hashes(), filter.len());
+        prop_assert_eq!(&saved, &filter);
+        prop_assert!(filter.false_positive_rate() <= 2.0 * rate);
+        // Keys never inserted are rarely reported present
+        let false_positives = fresh
+            .iter()
+            .filter(|key| !keys.contains(key) && filter.contains(TileMask::from_halves([**key, 0])))
+            .count();
+        let allowed = 3.0 * rate * fresh.len() as f64 + 10.0;
+        prop_assert!(false_positives as f64 <= allowed, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn masks_of_128_cells_fill_bloom_filters_like_128_bit_keys(
+        keys in prop::collection::vec(any::<u128>(), 1..50),
+        capacity in 1..1000usize,
+    ) {
+        let mut filter = BloomFilter::new(capacity, 0.01);
+        for &key in &keys {
+            filter.insert(TileMask::from_halves([key, 0]));
+        }
+        let expected = bloom_words_of_128_bit_keys(&keys, filter.hashes(), filter.words().len());
+        prop_assert_eq!(filter.words(), &expected[..]);
+    }
+
+    #[test]
+    fn minimal_hint_sets_single_out_the_secret(solution in latin_square(), seed in any::<u64>()) {
+        let grid = cyclic_latin_square(solution.size());
+        let options = SolverOptions::default();
+        if let Some(hints) = hints::minimal_hints(&grid, &solution, 0, seed, &options) {
+            prop_assert_eq!(solve(&hints::hint_constraints(&hints, &grid), &options), vec![solution.clone()]);
+            for dropped in 0..hints.len() {
+                let mut fewer = hints.clone();
+                fewer.remove(dropped);
+                prop_assert!(solve(&hints::hint_constraints(&fewer, &grid), &options).len() > 1);
+            }
+        }
+    }
+
+    #[test]
+    fn clue_maps_give_the_solutions_of_their_constraints((grid, clues) in puzzle(), options in options()) {
+        let size = grid.size();
+        let mut known = HashMap::new();
+        let mut wrong = HashMap::new();
+        for (cell, &clue) in clues.iter().enumerate() {
+            let placement = CluePlacement { row: cell / size, col: cell % size };
+            let value = usize::from(grid[(placement.row, placement.col)]);
+            match clue {
+                Clue::None => {}
+                Clue::Known => {
+                    known.insert(placement, value);
+                }
+                Clue::Wrong(offset) if (value - 1 + offset) % size + 1 != value => {
+                    wrong.insert(placement, vec![(value - 1 + offset) % size + 1]);
+                }
+                Clue::Wrong(_) => {}
+            }
+        }
+        let hooks = SolveHooks::default();
+        let (mut solutions, _) = complete_latin_square_with(size, &known, &wrong, Some(MAX_SOLUTIONS), &options, hooks);
+        let mut expected = solve(&constraints(&grid, &clues), &options);
+        solutions.sort_by_key(|solution| format!("{:?}", solution));
+        expected.sort_by_key(|solution| format!("{:?}", solution));
+        prop_assert_eq!(solutions, expected);
+    }
+
+    #[test]
+    fn concurrent_tile_sets_report_each_clue_set_new_once(
+        (size, clue_sets) in (3..=5usize).prop_flat_map(|size| {
+            let clue_set = prop::sample::subsequence(all_tiles(size), 0..=size * size);
+            (Just(size), prop::collection::vec(clue_set, 1..40))
+        }),
+        threads in 2..=8usize,
+    ) {
+        let seen = ConcurrentTileSet::new(size);
+        // Each thread inserts every clue set, starting at a different one
+        let mut new: Vec<Vec<(usize, usize)>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|thread| {
+                    let (seen, clue_sets) = (&seen, &clue_sets);
+                    scope.spawn(move || {
+                        let start = thread * clue_sets.len() / threads;
+                        clue_sets[start..]
+                            .iter()
+                            .chain(&clue_sets[..start])
+                            .filter(|tiles| seen.insert(tiles))
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        });
+        let mut distinct: Vec<_> = clue_sets.iter().collect::<HashSet<_>>().into_iter().cloned().collect();
+        new.sort();
+        distinct.sort();
+        prop_assert_eq!(seen.len(), distinct.len());
+        prop_assert_eq!(new, distinct);
+    }
+
+    #[test]
+    fn csv_records_give_the_solver_metrics_of_their_puzzles(placed in 3..=6usize) {
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let (puzzles, bytes) = search_output(4, placed, &options, OutputFormat::Csv);
+        let text = String::from_utf8(bytes).unwrap();
+        let mut lines = text.lines();
+        let header = lines.next().and_then(FileHeader::parse_line);
+        prop_assert_eq!(header.as_ref(), Some(&FileHeader::new(4, ClueSemantics::Sudodle)));
+        let columns: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let records: Vec<HashMap<_, _>> =
+            lines.map(|line| columns.iter().copied().zip(line.split(',')).collect()).collect();
+        prop_assert_eq!(records.len(), puzzles.len());
+        for (record, puzzle) in records.iter().zip(&puzzles) {
+            prop_assert_eq!(output::decode_clues(record["clues"]), puzzle.tiles.clone());
+            prop_assert_eq!(record["size"], "4");
+            prop_assert_eq!(record["placed"], placed.to_string());
+            prop_assert_eq!(record["difficulty"], puzzle.stats.difficulty().to_string());
+            prop_assert_eq!(record["backtrack_nodes"], puzzle.stats.backtrack_nodes.to_string());
+            prop_assert_eq!(record["max_depth"], puzzle.stats.max_depth.to_string());
+            prop_assert_eq!(record["forced_cells"], puzzle.stats.forced_cells.to_string());
+            prop_assert_eq!(record["propagation_passes"], puzzle.stats.propagation_passes.to_string());
+            prop_assert_eq!(record["singles_solvable"], puzzle.singles_solvable.to_string());
+            prop_assert_eq!(record["content_id"], header.as_ref().unwrap().content_id(&puzzle.tiles));
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn databases_store_each_puzzle_once_and_sample_by_difficulty(
+        placed in 4..=6usize,
+        min_difficulty in 0..3usize,
+        width in 0..3usize,
+    ) {
+        use puzzle_finder::db::{self, PuzzleDb, PuzzleQuery};
+
+        let result = find_single_solution_puzzles(4, placed, SearchOptions::default(), Vec::new(), HashSet::new());
+        let path = temp_path("puzzles.db");
+        let path = path.to_str().unwrap();
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        let insert = || PuzzleDb::open(path, &header).and_then(|mut db| db.insert_puzzles(&result.puzzles)).unwrap();
+        prop_assert_eq!(insert(), result.puzzles.len());
+        // Another run finding the same puzzles adds nothing
+        prop_assert_eq!(insert(), 0);
+
+        let max_difficulty = min_difficulty + width;
+        let query = PuzzleQuery {
+            size: 4,
+            placed: Some(placed),
+            min_difficulty: Some(min_difficulty),
+            max_difficulty: Some(max_difficulty),
+            limit: usize::MAX >> 1,
+        };
+        let mut sampled = db::sample_puzzles(path, &query).unwrap();
+        let mut expected: Vec<_> = result
+            .puzzles
+            .iter()
+            .filter(|puzzle| (min_difficulty..=max_difficulty).contains(&puzzle.stats.difficulty()))
+            .map(|puzzle| standardize_tile_tuple(&puzzle.tiles))
+            .collect();
+        sampled.sort();
+        expected.sort();
+        std::fs::remove_file(path).unwrap();
+        prop_assert_eq!(sampled, expected);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_files_hold_the_header_and_clues_of_the_search(placed in 3..=6usize) {
+        use arrow_array::{Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = temp_path("puzzles.parquet");
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        let sink = output::create_file_sink(path.to_str().unwrap(), OutputFormat::Parquet, None, false, &header);
+        let sink = sink.unwrap();
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let puzzles = find_single_solution_puzzles(4, placed, options, vec![sink], HashSet::new()).puzzles;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let metadata = reader.metadata().file_metadata().key_value_metadata().cloned().unwrap_or_default();
+        let written = metadata.iter().find(|pair| pair.key == "sudodle_header").and_then(|pair| pair.value.clone());
+        let written: Option<FileHeader> = written.and_then(|header| serde_json::from_str(&header).ok());
+        prop_assert_eq!(written.as_ref(), Some(&header));
+        let mut clues = Vec::new();
+        for batch in reader.build().unwrap() {
+            let batch = batch.unwrap();
+            let column = batch.column_by_name("clues").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+            clues.extend((0..column.len()).map(|row| output::decode_clues(column.value(row))));
+        }
+        prop_assert_eq!(clues, puzzles.iter().map(|puzzle| puzzle.tiles.clone()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn compressed_files_read_back_their_puzzles_across_appends(
+        puzzles in prop::collection::vec(prop::sample::subsequence(all_tiles(5), 1..=25), 0..20),
+        appended in any::<prop::sample::Index>(),
+        compression in prop_oneof![Just(Compression::Gzip), Just(Compression::Zstd)],
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+    ) {
+        let path = temp_path("puzzles.compressed");
+        let path = path.to_str().unwrap();
+        let header = FileHeader::new(5, ClueSemantics::Sudodle);
+        let puzzles: Vec<FoundPuzzle> = puzzles.into_iter().map(found_puzzle).collect();
+        // The appended puzzles go to a new gzip member or zstd frame
+        let (first, second) = puzzles.split_at(appended.index(puzzles.len() + 1));
+        for (part, append) in [(first, false), (second, true)] {
+            let mut writer = PuzzleWriter::create(path, format, Some(compression), append, &header).unwrap();
+            writer.write_puzzles(part).unwrap();
+        }
+        let magic = match compression {
+            Compression::Gzip => &[0x1f, 0x8b][..],
+            Compression::Zstd => &[0x28, 0xb5, 0x2f, 0xfd][..],
+        };
+        prop_assert!(std::fs::read(path).unwrap().starts_with(magic));
+        prop_assert_eq!(output::read_file_header(path).unwrap(), Some(header));
+        let tiles = output::read_puzzle_tiles(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        prop_assert_eq!(tiles, puzzles.iter().map(|puzzle| puzzle.tiles.clone()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn file_headers_read_back_and_tell_verify_how_to_read_the_puzzles(
+        grid in latin_square_of_size(3..=6),
+        givens_only in any::<bool>(),
+        seed in any::<Option<u64>>(),
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+        placed in 5..=6usize,
+    ) {
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let generator = Some("anneal".to_string());
+        let header = FileHeader { seed, generator, ..FileHeader::new(grid.size(), semantics) }.with_base_grid(&grid);
+        let path = temp_path("header.out");
+        let path = path.to_str().unwrap();
+        drop(PuzzleWriter::create(path, format, None, false, &header).unwrap());
+        prop_assert_eq!(output::read_file_header(path).unwrap(), Some(header));
+
+        // Without --semantics, verify reads the puzzles of a givens-only search as such
+        let semantics = if givens_only { "givens-only" } else { "sudodle" };
+        let placed = placed.to_string();
+        let search = ["search", "--size", "4", "--placed", &placed, "--semantics", semantics, "--out-file", path];
+        run_command_line(&search, "")?;
+        let verified = run_command_line(&["verify", path], "")?;
+        std::fs::remove_file(path).unwrap();
+        prop_assert!(verified.lines().any(|line| line.starts_with("All ") && line.contains("have a unique solution")));
+    }
+
+    #[test]
+    fn content_ids_ignore_tile_order_and_tell_apart_semantics_grids_and_tiles(
+        grid in latin_square_of_size(3..=6),
+        picks in prop::collection::vec(any::<prop::sample::Index>(), 1..=12),
+        rotation in any::<usize>(),
+    ) {
+        let size = grid.size();
+        let mut tiles: Vec<(usize, usize)> = picks.iter().map(|pick| *pick.get(&all_tiles(size))).collect();
+        tiles.sort_unstable();
+        tiles.dedup();
+        let mut reordered = tiles.clone();
+        reordered.rotate_left(rotation % tiles.len());
+        reordered.reverse();
+
+        let header = FileHeader::new(size, ClueSemantics::Sudodle).with_base_grid(&grid);
+        let id = header.content_id(&tiles);
+        prop_assert_eq!(id.len(), 16);
+        prop_assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        prop_assert_eq!(&header.content_id(&reordered), &id);
+        prop_assert_eq!(&output::content_id(size, &header.semantics, &header.base_grid, &tiles), &id);
+
+        let givens_only = FileHeader::new(size, ClueSemantics::GivensOnly).with_base_grid(&grid);
+        prop_assert_ne!(&givens_only.content_id(&tiles), &id);
+        let variant = FileHeader { variant: Some("diagonal".to_string()), ..header.clone() };
+        prop_assert_ne!(&variant.content_id(&tiles), &id);
+        if grid != cyclic_latin_square(size) {
+            prop_assert_ne!(&FileHeader::new(size, ClueSemantics::Sudodle).content_id(&tiles), &id);
+        }
+        prop_assert_ne!(&header.content_id(&tiles[1..]), &id);
+    }
+
+    #[test]
+    fn puzzles_of_other_rules_versions_are_refused(
+        placed in 5..=6usize,
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+        other in any::<u32>().prop_filter("another version", |&version| version != output::RULES_VERSION),
+        in_header in any::<bool>(),
+    ) {
+        let options = SearchOptions { random_tries: Some(300), ..SearchOptions::default() };
+        let (puzzles, bytes) = search_output(4, placed, &options, format);
+        prop_assume!(!puzzles.is_empty() || in_header);
+        let text = String::from_utf8(bytes).unwrap();
+        let path = temp_path("rules.out");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &text).unwrap();
+        prop_assert_eq!(output::read_puzzle_tiles(path).unwrap().len(), puzzles.len());
+
+        // Either the header or the last record follows other rules; text records carry no version
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let header = FileHeader { rules_version: other, ..FileHeader::new(4, ClueSemantics::Sudodle) };
+        let last = lines.len() - 1;
+        let in_header = in_header || format == OutputFormat::Text;
+        match format {
+            _ if in_header => lines[0] = header.line(format),
+            OutputFormat::Csv => {
+                let column = lines[1].split(',').position(|column| column == "rules_version").unwrap();
+                let mut values: Vec<&str> = lines[last].split(',').collect();
+                let version = other.to_string();
+                values[column] = &version;
+                lines[last] = values.join(",");
+            }
+            _ => {
+                let mut record: serde_json::Value = serde_json::from_str(&lines[last]).unwrap();
+                record["rules_version"] = other.into();
+                lines[last] = record.to_string();
+            }
+        }
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+        if in_header {
+            prop_assert_eq!(output::read_file_header(path).unwrap().map(|header| header.rules_version), Some(other));
+        } else {
+            let error = output::read_puzzle_tiles(path).unwrap_err();
+            prop_assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+        let verify = std::process::Command::new(env!("CARGO_BIN_EXE_find_puzzles")).args(["verify", path]).output();
+        std::fs::remove_file(path).unwrap();
+        let verify = verify.unwrap();
+        prop_assert!(!verify.status.success());
+        let refusal = format!("rules version {}", other);
+        prop_assert!(String::from_utf8_lossy(&verify.stderr).contains(&refusal));
+
+        // Puzzles to solve default to the current rules
+        let spec: PuzzleSpec = serde_json::from_str("{\"size\": 4, \"tiles\": [[0, 0]]}").unwrap();
+        prop_assert_eq!(spec.rules_version, output::RULES_VERSION);
+        let spec = PuzzleSpec { rules_version: other, ..spec };
+        prop_assert!(solve_report(&spec, &SolverOptions::default(), None).is_err());
+    }
+
+    #[test]
+    fn appended_runs_skip_the_puzzles_of_the_file(
+        placed in 4..=6usize,
+        seeds in (any::<u64>(), any::<u64>()),
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+    ) {
+        let path = temp_path("appended.out");
+        let path = path.to_str().unwrap();
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        let read = || -> Vec<Vec<(usize, usize)>> {
+            output::read_puzzle_tiles(path).unwrap().iter().map(|tiles| standardize_tile_tuple(tiles)).collect()
+        };
+        let mut found = Vec::new();
+        for (seed, append) in [(seeds.0, false), (seeds.1, true)] {
+            // As `search --append` does, skip the puzzles already in the file
+            let known: HashSet<_> = if append { read().into_iter().collect() } else { HashSet::new() };
+            let sink = output::create_file_sink(path, format, None, append, &header).unwrap();
+            let options = SearchOptions { random_tries: Some(300), generator_seed: seed, ..SearchOptions::default() };
+            let puzzles = find_single_solution_puzzles(4, placed, options, vec![sink], known.clone()).puzzles;
+            prop_assert!(puzzles.iter().all(|puzzle| !known.contains(&standardize_tile_tuple(&puzzle.tiles))));
+            found.extend(puzzles.into_iter().map(|puzzle| standardize_tile_tuple(&puzzle.tiles)));
+        }
+        let mut written = read();
+        std::fs::remove_file(path).unwrap();
+        written.sort();
+        found.sort();
+        let distinct: HashSet<_> = written.iter().collect();
+        prop_assert_eq!(distinct.len(), written.len());
+        prop_assert_eq!(written, found);
+    }
+
+    #[test]
+    fn deduped_files_hold_each_puzzle_of_their_inputs_once(
+        placed in 4..=6usize,
+        seeds in (any::<u64>(), any::<u64>()),
+        format in prop_oneof![Just(OutputFormat::Text), Just(OutputFormat::Csv), Just(OutputFormat::Jsonl)],
+        by_symmetry in any::<bool>(),
+    ) {
+        // Shards that overlap: the second also holds the puzzles of the first's run
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        let inputs = [temp_path("shard-a.out"), temp_path("shard-b.out")];
+        let runs = [(&inputs[0], seeds.0, false), (&inputs[1], seeds.0, false), (&inputs[1], seeds.1, true)];
+        let mut found = Vec::new();
+        for (path, seed, append) in runs {
+            let sink = output::create_file_sink(path.to_str().unwrap(), format, None, append, &header).unwrap();
+            let options = SearchOptions { random_tries: Some(200), generator_seed: seed, ..SearchOptions::default() };
+            let puzzles = find_single_solution_puzzles(4, placed, options, vec![sink], HashSet::new()).puzzles;
+            found.extend(puzzles.into_iter().map(|puzzle| standardize_tile_tuple(&puzzle.tiles)));
+        }
+        let merged = temp_path("merged.out");
+        let mut args = vec!["dedupe", "--inputs", inputs[0].to_str().unwrap(), inputs[1].to_str().unwrap()];
+        args.extend(["--output", merged.to_str().unwrap()]);
+        if by_symmetry {
+            args.push("--symmetry");
+        }
+        run_command_line(&args, "")?;
+        let merged_header = output::read_file_header(merged.to_str().unwrap()).unwrap();
+        let kept: Vec<Vec<(usize, usize)>> = output::read_puzzle_tiles(merged.to_str().unwrap())
+            .unwrap()
+            .iter()
+            .map(|tiles| standardize_tile_tuple(tiles))
+            .collect();
+        for path in inputs.iter().chain([&merged]) {
+            std::fs::remove_file(path).unwrap();
+        }
+        prop_assert_eq!(merged_header, Some(header));
+
+        // One puzzle of each class of the puzzles found, the class of a puzzle
+        // being itself without --symmetry
+        let symmetries = if by_symmetry { symmetry::symmetries(4) } else { Vec::new() };
+        let class = |tiles: &[(usize, usize)]| symmetry::canonical_tiles(4, tiles, &symmetries);
+        let kept_classes: HashSet<_> = kept.iter().map(|tiles| class(tiles)).collect();
+        prop_assert_eq!(kept_classes.len(), kept.len());
+        let found_classes: HashSet<_> = found.iter().map(|tiles| class(tiles)).collect();
+        prop_assert_eq!(kept_classes, found_classes);
+        prop_assert!(kept.iter().all(|tiles| found.contains(tiles)));
+    }
+
+    #[test]
+    fn web_exports_list_the_ids_of_each_size_by_level(
+        placed in (4..=6usize, 7..=9usize),
+        generator_seed in any::<u64>(),
+        (hard_from, expert_from) in (1..=3usize).prop_flat_map(|hard_from| (Just(hard_from), hard_from..=6)),
+        extension in prop_oneof![Just("json"), Just("js"), Just("ts")],
+    ) {
+        let mut levels = HashMap::new();
+        let mut paths = Vec::new();
+        for (size, placed) in [(4, placed.0), (5, placed.1)] {
+            let path = temp_path(&format!("s{}.jsonl", size));
+            let header = FileHeader::new(size, ClueSemantics::Sudodle);
+            let sink = output::create_file_sink(path.to_str().unwrap(), OutputFormat::Jsonl, None, false, &header);
+            let sink = sink.unwrap();
+            let options = SearchOptions { random_tries: Some(200), generator_seed, ..SearchOptions::default() };
+            for puzzle in find_single_solution_puzzles(size, placed, options, vec![sink], HashSet::new()).puzzles {
+                let difficulty = puzzle.stats.difficulty();
+                let level = match difficulty {
+                    _ if difficulty >= expert_from => "expert",
+                    _ if difficulty >= hard_from => "hard",
+                    _ => "normal",
+                };
+                levels.insert(output::encode_puzzle_id(size, &puzzle.tiles).unwrap(), (size.to_string(), level));
+            }
+            paths.push(path);
+        }
+        let out_file = temp_path(&format!("puzzleData.{}", extension));
+        let (hard_from, expert_from) = (hard_from.to_string(), expert_from.to_string());
+        let mut args = vec!["export-web", paths[0].to_str().unwrap(), paths[1].to_str().unwrap()];
+        args.extend(["--hard-from", &hard_from, "--expert-from", &expert_from]);
+        args.extend(["--out-file", out_file.to_str().unwrap()]);
+        run_command_line(&args, "")?;
+        let text = std::fs::read_to_string(&out_file).unwrap();
+        for path in paths.iter().chain([&out_file]) {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        // Modules export the JSON object as `puzzleData`
+        let json = match extension {
+            "json" => text.as_str(),
+            _ => text.split_once("export const puzzleData").unwrap().1.split_once(" = ").unwrap().1.trim_end(),
+        };
+        let data: serde_json::Value = serde_json::from_str(json.trim_end_matches(';')).unwrap();
+        let mut exported = HashMap::new();
+        for size in ["4", "5"] {
+            let by_level = data[size].as_object().unwrap();
+            let mut level_names: Vec<&str> = by_level.keys().map(String::as_str).collect();
+            level_names.sort_unstable();
+            prop_assert_eq!(level_names, ["expert", "hard", "normal"]);
+            for (level, ids) in by_level {
+                let ids: Vec<&str> = ids.as_array().unwrap().iter().map(|id| id.as_str().unwrap()).collect();
+                prop_assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "unsorted IDs {:?}", ids);
+                for id in ids {
+                    prop_assert!(exported.insert(id.to_string(), (size.to_string(), level.as_str())).is_none());
+                }
+            }
+        }
+        prop_assert_eq!(exported, levels);
+    }
+
+    #[test]
+    fn first_only_random_searches_stop_at_a_unique_puzzle(placed in 3..=7usize, generator_seed in any::<u64>()) {
+        // Deterministic output draws the same combinations in both searches
+        let options = SearchOptions {
+            random_tries: Some(300),
+            generator_seed,
+            deterministic_output: true,
+            ..SearchOptions::default()
+        };
+        let all = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new()).puzzles;
+        let options = SearchOptions { first_only: true, ..options };
+        let first = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new()).puzzles;
+        let tiles = |puzzles: &[FoundPuzzle]| puzzles.first().map(|puzzle| puzzle.tiles.clone());
+        prop_assert_eq!(first.len(), usize::from(!all.is_empty()));
+        prop_assert_eq!(tiles(&first), tiles(&all));
+        for puzzle in &first {
+            prop_assert!(check_puzzle(&PuzzleSpec::new(4, &puzzle.tiles), &SolverOptions::default()).is_ok());
+        }
+    }
+
+    #[test]
+    fn feasibility_tables_count_the_puzzles_of_exhaustive_searches(size in 3..=4usize, max_placed in 1..=4usize) {
+        let max_placed = max_placed.min(size);
+        let (size_arg, max_placed_arg) = (size.to_string(), max_placed.to_string());
+        let args = ["table", "--size", &size_arg, "--max-placed", &max_placed_arg, "--probes", "100000"];
+        let report = run_command_line(&args, "")?;
+        let rows: Vec<Vec<&str>> = report
+            .lines()
+            .skip_while(|line| !line.starts_with("Feasibility table"))
+            .skip(2)
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        prop_assert!(!rows.is_empty());
+        let mut expected_placed = max_placed;
+        for row in &rows {
+            let placed: usize = row[0].parse().unwrap();
+            prop_assert_eq!(placed, expected_placed);
+            let found =
+                find_single_solution_puzzles(size, placed, SearchOptions::default(), Vec::new(), HashSet::new());
+            prop_assert_eq!(row[3], found.puzzles.len().to_string());
+            prop_assert_eq!(row[5], if found.puzzles.is_empty() { "no" } else { "yes" });
+            expected_placed -= 1;
+        }
+        // The descent stops at the first placed count without unique puzzles
+        prop_assert!(rows.last().is_some_and(|row| row[3] == "0") || expected_placed == 0);
+    }
+
+    #[test]
+    fn run_summaries_account_for_every_combination(placed in 2..=6usize) {
+        let result = find_single_solution_puzzles(4, placed, SearchOptions::default(), Vec::new(), HashSet::new());
+        let summary = &result.summary;
+        prop_assert_eq!(summary.combinations_processed as u128, binomial(16, placed));
+        prop_assert_eq!(summary.unique_puzzles, result.puzzles.len());
+        prop_assert_eq!(summary.difficulty_counts.values().sum::<usize>(), summary.unique_puzzles);
+        let stratified: usize = summary.strata.values().map(|stratum| stratum.combinations).sum();
+        prop_assert_eq!(stratified, summary.combinations_processed);
+        for (&load, stratum) in &summary.strata {
+            let found = result.puzzles.iter().filter(|puzzle| line_load(4, &puzzle.tiles) == load).count();
+            prop_assert_eq!(stratum.unique_puzzles, found);
+        }
+
+        let path = temp_path("summary.json");
+        summary.write(path.to_str().unwrap()).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        prop_assert_eq!(&json["unique_puzzles"], &serde_json::json!(summary.unique_puzzles));
+        prop_assert_eq!(&json["combinations_processed"], &serde_json::json!(summary.combinations_processed));
+    }
+
+    #[test]
+    fn search_depths_and_forced_cells_are_bounded_by_the_empty_cells((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let size = grid.size();
+        let empty = (0..size * size).filter(|&cell| constraints.known[(cell / size, cell % size)] == 0).count();
+        let (solutions, stats) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, SolveHooks::default());
+        prop_assert!(stats.max_depth <= empty);
+        prop_assert!(stats.max_depth <= stats.backtrack_nodes);
+        // Without guesses the search follows a single path, forcing each empty cell once
+        if stats.guesses == 0 && solutions.len() == 1 {
+            prop_assert_eq!(stats.forced_cells, empty);
+        }
+    }
+
+    #[test]
+    fn traces_record_the_search_tree_and_read_back((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let tracer = Tracer::new();
+        let hooks = SolveHooks { observer: Some(&tracer), ..SolveHooks::default() };
+        let (solutions, _) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, hooks);
+        let events = tracer.into_events();
+
+        // Depth of each node opened so far, the initial grid being node 0
+        let mut depths: Vec<Option<usize>> = vec![None];
+        let mut found = 0;
+        for event in &events {
+            match *event {
+                TraceEvent::Decision { node, parent, depth, .. } => {
+                    prop_assert_eq!(node, depths.len());
+                    prop_assert!(parent < node);
+                    if let Some(parent_depth) = depths[parent] {
+                        prop_assert_eq!(depth, parent_depth + 1);
+                    }
+                    depths.push(Some(depth));
+                }
+                TraceEvent::Propagation { node, .. } | TraceEvent::DeadEnd { node, .. } => {
+                    prop_assert!(node < depths.len());
+                }
+                TraceEvent::Solution { node, .. } => {
+                    prop_assert!(node < depths.len());
+                    found += 1;
+                }
+            }
+        }
+        prop_assert_eq!(found, solutions.len());
+
+        let path = temp_path("trace.jsonl");
+        trace::write_jsonl(path.to_str().unwrap(), &events).unwrap();
+        let read = trace::read_jsonl(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        prop_assert_eq!(&read, &events);
+        let dot = trace::to_dot(&events);
+        prop_assert_eq!(dot.lines().filter(|line| line.contains("[label=")).count(), depths.len());
+        prop_assert_eq!(dot.lines().filter(|line| line.contains(" -> ")).count(), depths.len() - 1);
+    }
+
+    #[test]
+    fn eliminations_keep_the_values_of_the_solution((grid, clues) in puzzle()) {
+        let constraints = constraints(&grid, &clues);
+        let size = grid.size();
+        let mut candidates: Vec<u32> = (0..size * size)
+            .map(|cell| {
+                let (i, j) = (cell / size, cell % size);
+                if constraints.known[(i, j)] != 0 {
+                    return 0;
+                }
+                let used = (0..size)
+                    .flat_map(|k| [constraints.known[(i, k)], constraints.known[(k, j)]])
+                    .filter(|&v| v != 0)
+                    .fold(0, |acc, v| acc | 1 << (v - 1));
+                ((1 << size) - 1) & !used & !constraints.wrong[cell]
+            })
+            .collect();
+        techniques::eliminate(&mut candidates, size, TechniqueSet::FULL);
+        for (cell, &cell_candidates) in candidates.iter().enumerate() {
+            let (i, j) = (cell / size, cell % size);
+            if constraints.known[(i, j)] == 0 {
+                prop_assert!(cell_candidates & 1 << (grid[(i, j)] - 1) != 0);
+            }
+        }
+
+        // Deductions hold in every solution, and stronger techniques deduce at least as much
+        let deduced = techniques::deduce(&constraints, TechniqueSet::FULL);
+        for i in 0..size {
+            for j in 0..size {
+                prop_assert!(deduced[(i, j)] == 0 || deduced[(i, j)] == grid[(i, j)]);
+            }
+        }
+        if techniques::solvable_by_deduction(&constraints, TechniqueSet::SINGLES) {
+            prop_assert!(techniques::solvable_by_deduction(&constraints, TechniqueSet::SUBSETS));
+            prop_assert!(techniques::solvable_by_deduction(&constraints, TechniqueSet::FULL));
+        }
+    }
+
+    #[test]
+    fn searches_of_large_squares_restore_every_node_and_eliminate_as_small_ones(
+        (grid, clues) in latin_square_of_size(6..=16).prop_flat_map(|grid| {
+            let cells = grid.size() * grid.size();
+            (Just(grid), prop::collection::vec(prop_oneof![3 => Just(Clue::Known), 2 => clue()], cells))
+        }),
+        kernel in prop_oneof![Just(Kernel::General), Just(Kernel::Specialized)],
+        options in options(),
+    ) {
+        let constraints = constraints(&grid, &clues);
+        let size = grid.size();
+        let mut candidates: Vec<u32> = all_tiles(size)
+            .into_iter()
+            .map(|(i, j)| {
+                if constraints.known[(i, j)] != 0 {
+                    return 0;
+                }
+                let used = (0..size)
+                    .flat_map(|k| [constraints.known[(i, k)], constraints.known[(k, j)]])
+                    .filter(|&v| v != 0)
+                    .fold(0, |acc, v| acc | 1 << (v - 1));
+                ((1 << size) - 1) & !used & !constraints.wrong[i * size + j]
+            })
+            .collect();
+        techniques::eliminate(&mut candidates, size, TechniqueSet::FULL);
+        for (i, j) in all_tiles(size).into_iter().filter(|&cell| constraints.known[cell] == 0) {
+            prop_assert!(candidates[i * size + j] & 1 << (grid[(i, j)] - 1) != 0);
+        }
+
+        // Each node is restored from the state saved at its depth, whichever subtree was searched before
+        let eliminating = SolverOptions { techniques: TechniqueSet::FULL, kernel, ..options };
+        let solutions = solve(&constraints, &eliminating);
+        prop_assert!(solutions.len() == MAX_SOLUTIONS || solutions.contains(&grid));
+        prop_assert!(solutions.iter().all(is_latin_square));
+        prop_assert_eq!(solutions.iter().collect::<HashSet<_>>().len(), solutions.len());
+        let guessing = SolverOptions { techniques: TechniqueSet::empty(), ..options };
+        assert_same_solutions(&constraints, &eliminating, &guessing)?;
+    }
+
+    #[test]
+    fn any_technique_set_parses_from_its_names_and_keeps_the_solutions(
+        (grid, clues) in puzzle(),
+        chosen in prop::collection::vec(any::<bool>(), 7),
+    ) {
+        let names = [
+            ("naked-singles", TechniqueSet::NAKED_SINGLES),
+            ("hidden-singles", TechniqueSet::HIDDEN_SINGLES),
+            ("naked-pairs", TechniqueSet::NAKED_PAIRS),
+            ("naked-triples", TechniqueSet::NAKED_TRIPLES),
+            ("hidden-pairs", TechniqueSet::HIDDEN_PAIRS),
+            ("hidden-triples", TechniqueSet::HIDDEN_TRIPLES),
+            ("x-wings", TechniqueSet::X_WINGS),
+        ];
+        let chosen: Vec<_> = names.iter().zip(&chosen).filter(|(_, &keep)| keep).map(|(name, _)| name).collect();
+        let expected = chosen.iter().fold(TechniqueSet::empty(), |set, (_, technique)| set | *technique);
+        let list: Vec<&str> = chosen.iter().map(|(name, _)| *name).collect();
+        let list = if list.is_empty() { "none".to_string() } else { list.join(",") };
+        prop_assert_eq!(list.parse::<TechniqueSet>(), Ok(expected));
+        let unknown = format!("{},unknown", list);
+        prop_assert!(unknown.parse::<TechniqueSet>().is_err());
+
+        let options = SolverOptions::builder().techniques(expected).build();
+        assert_same_solutions(&constraints(&grid, &clues), &options, &SolverOptions::default())?;
+    }
+
+    #[test]
+    fn singles_solvable_searches_keep_the_puzzles_singles_complete(placed in 3..=6usize) {
+        let grid = cyclic_latin_square(4);
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let all = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        for puzzle in &all.puzzles {
+            let constraints = puzzle_constraints(&puzzle.tiles, &grid, &all_tiles(4));
+            let solvable = techniques::solvable_by_deduction(&constraints, TechniqueSet::SINGLES);
+            prop_assert_eq!(puzzle.singles_solvable, solvable);
+        }
+        let expected: Vec<_> =
+            all.puzzles.iter().filter(|puzzle| puzzle.singles_solvable).map(|puzzle| &puzzle.tiles).collect();
+        prop_assert_eq!(all.summary.singles_solvable_puzzles, expected.len());
+
+        let options = SearchOptions { require_singles_solvable: true, ..options };
+        let solvable = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new()).puzzles;
+        prop_assert_eq!(solvable.iter().map(|puzzle| &puzzle.tiles).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn value_orders_keep_the_solutions_and_random_orders_repeat_with_their_seed(
+        (grid, clues) in puzzle(),
+        options in options(),
+        seed in any::<u64>(),
+    ) {
+        let constraints = constraints(&grid, &clues);
+        for value_order in [ValueOrder::Ascending, ValueOrder::LeastConstraining, ValueOrder::Random] {
+            let ordered = SolverOptions { value_order, ..options };
+            assert_same_solutions(&constraints, &ordered, &options)?;
+        }
+        let random = SolverOptions { value_order: ValueOrder::Random, seed, ..options };
+        let first = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &random, SolveHooks::default());
+        let second = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &random, SolveHooks::default());
+        prop_assert_eq!(first.0, second.0);
+        prop_assert_eq!(first.1.backtrack_nodes, second.1.backtrack_nodes);
+    }
+
+    #[test]
+    fn degree_tie_breaks_guess_in_the_most_constraining_of_the_fewest_candidate_cells(
+        (grid, clues) in puzzle(),
+        options in options(),
+    ) {
+        let constraints = constraints(&grid, &clues);
+        let size = grid.size();
+        let empty = |i: usize, j: usize| constraints.known[(i, j)] == 0;
+        let candidates = |i: usize, j: usize| {
+            (1..=size as u8)
+                .filter(|&v| (0..size).all(|k| constraints.known[(i, k)] != v && constraints.known[(k, j)] != v))
+                .filter(|&v| constraints.wrong[i * size + j] & 1 << (v - 1) == 0)
+                .count()
+        };
+        let degree = |i: usize, j: usize| {
+            (0..size).filter(|&k| k != j && empty(i, k)).count() + (0..size).filter(|&k| k != i && empty(k, j)).count()
+        };
+        // Without propagation the first decision is taken on the grid of the clues
+        let options = SolverOptions { techniques: TechniqueSet::empty(), degree_tie_break: true, ..options };
+        let tracer = Tracer::new();
+        let hooks = SolveHooks { observer: Some(&tracer), ..SolveHooks::default() };
+        solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, hooks);
+        assert_same_solutions(&constraints, &options, &SolverOptions::default())?;
+        if let Some(&TraceEvent::Decision { cell: (i, j), reason: Reason::Guess, .. }) = tracer.into_events().first() {
+            let cells: Vec<_> =
+                (0..size * size).map(|cell| (cell / size, cell % size)).filter(|&(i, j)| empty(i, j)).collect();
+            let fewest = cells.iter().map(|&(i, j)| candidates(i, j)).min().unwrap();
+            prop_assert_eq!(candidates(i, j), fewest);
+            let ties = cells.iter().filter(|&&(i, j)| candidates(i, j) == fewest);
+            prop_assert_eq!(Some(degree(i, j)), ties.map(|&(i, j)| degree(i, j)).max());
+        }
+    }
+
+    #[test]
+    fn parallel_solves_share_their_solution_limit((grid, clues) in puzzle(), options in options(), limit in 1..4usize) {
+        let constraints = constraints(&grid, &clues);
+        let (mut solutions, stats) = complete_latin_square_parallel(&constraints, Some(MAX_SOLUTIONS), &options, None);
+        let mut expected = solve(&constraints, &options);
+        prop_assert!(!stats.timed_out);
+        prop_assert_eq!(solutions.len(), expected.len());
+        if expected.len() < MAX_SOLUTIONS {
+            solutions.sort_by_key(|solution| format!("{:?}", solution));
+            expected.sort_by_key(|solution| format!("{:?}", solution));
+            prop_assert_eq!(&solutions, &expected);
+        }
+        // The branches stop together once the limit is reached overall
+        let (limited, _) = complete_latin_square_parallel(&constraints, Some(limit), &options, None);
+        prop_assert_eq!(limited.len(), expected.len().min(limit));
+        for solution in &limited {
+            prop_assert!(solutions.contains(solution) || expected.len() >= MAX_SOLUTIONS);
+        }
+    }
+
+    #[test]
+    fn timed_out_combinations_are_counted_instead_of_reported(
+        placed in 6..=9usize,
+        generator_seed in any::<u64>(),
+        timeout_ms in prop_oneof![Just(0), Just(60_000u64)],
+    ) {
+        let options = SearchOptions {
+            random_tries: Some(100),
+            generator_seed,
+            deterministic_output: true,
+            ..SearchOptions::default()
+        };
+        let all = find_single_solution_puzzles(6, placed, options.clone(), Vec::new(), HashSet::new());
+        let per_puzzle_timeout = Some(std::time::Duration::from_millis(timeout_ms));
+        let options = SearchOptions { per_puzzle_timeout, ..options };
+        let limited = find_single_solution_puzzles(6, placed, options, Vec::new(), HashSet::new());
+        let standardized = |puzzles: &[FoundPuzzle]| -> HashSet<_> {
+            puzzles.iter().map(|puzzle| standardize_tile_tuple(&puzzle.tiles)).collect()
+        };
+        prop_assert!(standardized(&limited.puzzles).is_subset(&standardized(&all.puzzles)));
+        prop_assert!(limited.puzzles.len() + limited.summary.timed_out >= all.puzzles.len());
+        if timeout_ms > 0 {
+            prop_assert_eq!(limited.summary.timed_out, 0);
+            prop_assert_eq!(limited.puzzles.len(), all.puzzles.len());
+        }
+    }
+
+    #[test]
+    fn incremental_searches_find_the_puzzles_of_fresh_searches(placed in 3..=6usize) {
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let fresh = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        let options = SearchOptions { incremental: true, ..options };
+        let incremental = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        // The combinations come in another order, so each puzzle may be found as another of its symmetric forms
+        let standardized = |puzzles: &[FoundPuzzle]| -> HashSet<_> {
+            puzzles.iter().map(|puzzle| standardize_tile_tuple(&puzzle.tiles)).collect()
+        };
+        prop_assert_eq!(incremental.puzzles.len(), fresh.puzzles.len());
+        prop_assert_eq!(standardized(&incremental.puzzles), standardized(&fresh.puzzles));
+        prop_assert_eq!(incremental.summary.combinations_processed, fresh.summary.combinations_processed);
+    }
+
+    #[test]
+    fn revolving_door_combinations_swap_one_tile_at_a_time(
+        grid in latin_square_of_size(2..=5),
+        k in 1..=4usize,
+        givens_only in any::<bool>(),
+    ) {
+        let size = grid.size();
+        let tiles = all_tiles(size);
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let combinations: Vec<_> = RevolvingDoorIterator::new(tiles.clone(), k).collect();
+        prop_assert_eq!(combinations.len() as u128, binomial(size * size, k));
+        prop_assert_eq!(combinations.iter().collect::<HashSet<_>>().len(), combinations.len());
+        let mut constraints = puzzle_constraints_with(&combinations[0], &grid, &tiles, semantics);
+        for pair in combinations.windows(2) {
+            let removed: Vec<_> = pair[0].iter().filter(|tile| !pair[1].contains(tile)).collect();
+            let added: Vec<_> = pair[1].iter().filter(|tile| !pair[0].contains(tile)).collect();
+            prop_assert_eq!((removed.len(), added.len()), (1, 1));
+            constraints.swap_tiles(&grid, *removed[0], *added[0], semantics);
+            prop_assert_eq!(&constraints, &puzzle_constraints_with(&pair[1], &grid, &tiles, semantics));
+        }
+    }
+
+    #[test]
+    fn grids_store_their_cells_row_by_row(
+        (size, values) in (1..=MAX_SIZE)
+            .prop_flat_map(|size| (Just(size), prop::collection::vec(0..=size as u8, size * size))),
+    ) {
+        let mut grid = Grid::new(size);
+        prop_assert_eq!(grid.empty_cells(), size * size);
+        for (cell, &value) in values.iter().enumerate() {
+            grid[(cell / size, cell % size)] = value;
+        }
+        let rows: Vec<&[u8]> = grid.rows().collect();
+        prop_assert_eq!(rows.concat(), values.clone());
+        prop_assert_eq!(grid.empty_cells(), values.iter().filter(|&&value| value == 0).count());
+        let json = serde_json::to_string(&grid).unwrap();
+        prop_assert_eq!(serde_json::from_str::<Grid>(&json).unwrap(), grid);
+    }
+
+    #[test]
+    fn searches_only_place_the_candidates_of_the_grid_they_reach((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let size = grid.size();
+        let candidates = |known: &Grid, (i, j): (usize, usize)| -> Vec<usize> {
+            (1..=size)
+                .filter(|&v| (0..size).all(|k| usize::from(known[(i, k)]) != v && usize::from(known[(k, j)]) != v))
+                .filter(|&v| constraints.wrong[i * size + j] & 1 << (v - 1) == 0)
+                .collect()
+        };
+        let tracer = Tracer::new();
+        let hooks = SolveHooks { observer: Some(&tracer), ..SolveHooks::default() };
+        let (solutions, _) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, hooks);
+
+        // Replay the search, checking each value against the candidates of the grid of its node
+        let mut grids = vec![constraints.known.clone()];
+        let mut found = Vec::new();
+        for event in tracer.into_events() {
+            match event {
+                TraceEvent::Decision { parent, cell, value, reason, .. } => {
+                    let mut known = grids[parent].clone();
+                    let cell_candidates = candidates(&known, cell);
+                    prop_assert_eq!(known[cell], 0);
+                    prop_assert!(cell_candidates.contains(&value));
+                    prop_assert_eq!(reason == Reason::Forced, cell_candidates.len() == 1);
+                    known[cell] = value as u8;
+                    grids.push(known);
+                }
+                TraceEvent::Propagation { node, cell, value, .. } => {
+                    prop_assert_eq!(grids[node][cell], 0);
+                    prop_assert!(candidates(&grids[node], cell).contains(&value));
+                    grids[node][cell] = value as u8;
+                }
+                TraceEvent::Solution { node, .. } => found.push(grids[node].clone()),
+                TraceEvent::DeadEnd { .. } => {}
+            }
+        }
+        prop_assert_eq!(found, solutions);
+    }
+
+    #[test]
+    fn empty_squares_complete_to_latin_squares(size in 1..=MAX_SIZE, options in options()) {
+        let (solutions, stats) = solve_constraints(&Constraints::new(size), Some(1), &options, SolveHooks::default());
+        prop_assert!(!stats.timed_out);
+        prop_assert_eq!(solutions.len(), 1);
+        prop_assert_eq!(validate_latin_square(&solutions[0]), Ok(()));
+    }
+
+    #[test]
+    fn parsers_survive_any_input_and_read_back_what_they_format(
+        input in ".*",
+        bytes in prop::collection::vec(any::<u8>(), 0..200),
+        tiles in prop::collection::vec((0..MAX_SIZE, 0..MAX_SIZE), 1..10),
+    ) {
+        // What the fuzz targets check, on arbitrary and on well-formed inputs
+        for text in [input.clone(), output::format_tiles(&tiles)] {
+            if let Some(parsed) = output::parse_tiles(&text) {
+                prop_assert_eq!(output::parse_tiles(&output::format_tiles(&parsed)), Some(parsed));
+            }
+        }
+        prop_assert_eq!(output::parse_tiles(&output::format_tiles(&tiles)), Some(tiles.clone()));
+        for clues in [output::decode_clues(&input), tiles.clone()] {
+            prop_assert_eq!(output::decode_clues(&output::encode_clues(&clues)), clues);
+        }
+        if let Some((size, mut decoded)) = output::decode_puzzle_id(&input) {
+            decoded.sort_unstable();
+            let id = output::encode_puzzle_id(size, &decoded);
+            prop_assert_eq!(id.as_deref().and_then(output::decode_puzzle_id), Some((size, decoded)));
+        }
+        if let Ok((size, placed)) = output::parse_dot_notation(&input) {
+            prop_assert!(placed.iter().all(|&(r, c)| r < size && c < size));
+        }
+        if let Some(hints) = parse_hints(&input) {
+            prop_assert_eq!(parse_hints(&format_hints(&hints)), Some(hints));
+        }
+        let _ = output::parse_puzzle_records(&bytes[..], "input");
+        if let Ok(events) = trace::parse_jsonl(&bytes[..], "input") {
+            trace::to_dot(&events);
+        }
+    }
+
+    #[test]
+    fn json_logs_report_the_puzzles_within_their_batch_spans(placed in 3..=6usize) {
+        let placed_arg = placed.to_string();
+        let args = ["--log-level", "debug", "--log-format", "json", "search", "--size", "4", "--placed", &placed_arg];
+        let (_, logs) = run_command_line_with_logs(&args, "")?;
+        let events: Vec<serde_json::Value> = logs
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|e| TestCaseError::fail(e.to_string()))?;
+        let message = |event: &serde_json::Value| event["fields"]["message"].as_str().unwrap_or_default().to_string();
+
+        let expected = find_single_solution_puzzles(4, placed, SearchOptions::default(), Vec::new(), HashSet::new());
+        let unique: Vec<_> = events.iter().filter(|event| message(event) == "unique").collect();
+        // Symmetric forms of a puzzle are each logged, and only one of them kept
+        prop_assert!(unique.len() >= expected.puzzles.len());
+        for event in unique {
+            let spans: Vec<_> = event["spans"].as_array().unwrap().iter().map(|span| &span["name"]).collect();
+            prop_assert_eq!(spans, vec!["batch", "puzzle"]);
+            let tiles = event["span"]["tiles"].as_str().and_then(output::parse_tiles);
+            prop_assert_eq!(tiles.map(|tiles| tiles.len()), Some(placed));
+        }
+        let finished = events.iter().find(|event| message(event) == "finished processing").unwrap();
+        prop_assert_eq!(&finished["fields"]["found"], &serde_json::json!(expected.puzzles.len()));
+    }
+
+    #[test]
+    fn config_files_set_the_flags_the_command_line_leaves_out(placed in 3..=6usize, file_placed in 3..=6usize) {
+        let config = temp_path("search.toml");
+        let (from_flags, from_config) = (temp_path("flags.csv"), temp_path("config.csv"));
+        let settings = format!("size = 4\nplaced = {}\nformat = \"csv\"\ndeterministic-output = true\n", file_placed);
+        std::fs::write(&config, settings).unwrap();
+        let placed = placed.to_string();
+        let flags = ["search", "--size", "4", "--placed", &placed, "--format", "csv", "--deterministic-output"];
+        run_command_line(&[&flags[..], &["--out-file", from_flags.to_str().unwrap()]].concat(), "")?;
+        let with_config = ["search", "--config", config.to_str().unwrap(), "--placed", &placed];
+        run_command_line(&[&with_config[..], &["--out-file", from_config.to_str().unwrap()]].concat(), "")?;
+        let (expected, written) = (std::fs::read(&from_flags).unwrap(), std::fs::read(&from_config).unwrap());
+        for path in [config, from_flags, from_config] {
+            std::fs::remove_file(path).unwrap();
+        }
+        prop_assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn multi_size_searches_write_the_files_of_single_size_searches(
+        placed in prop::collection::vec(3..=6usize, 1..=2),
+    ) {
+        let (out_file, summary) = (temp_path("sizes.csv"), temp_path("sizes.json"));
+        let placed: Vec<String> = placed.iter().map(usize::to_string).collect();
+        let flags = ["search", "--format", "csv", "--deterministic-output"];
+        let outputs = ["--out-file", out_file.to_str().unwrap(), "--summary", summary.to_str().unwrap()];
+        run_command_line(&[&flags[..], &["--sizes", "3,4", "--placed", &placed.join(",")], &outputs].concat(), "")?;
+        let combined: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&summary).unwrap()).unwrap();
+        prop_assert_eq!(combined["runs"].as_array().map(Vec::len), Some(2));
+        let mut total = 0;
+        for (index, size) in ["3", "4"].into_iter().enumerate() {
+            let single = temp_path("size.csv");
+            let placed = &placed[index.min(placed.len() - 1)];
+            let single_flags = ["--size", size, "--placed", placed, "--out-file", single.to_str().unwrap()];
+            run_command_line(&[&flags[..], &single_flags].concat(), "")?;
+            let per_size = out_file.with_extension(format!("s{}.csv", size));
+            let (expected, written) = (std::fs::read(&single).unwrap(), std::fs::read(&per_size).unwrap());
+            let puzzles = csv_records(&String::from_utf8_lossy(&written)).len();
+            prop_assert_eq!(&combined["runs"][index]["unique_puzzles"], &serde_json::json!(puzzles));
+            total += puzzles;
+            std::fs::remove_file(single).unwrap();
+            std::fs::remove_file(per_size).unwrap();
+            prop_assert_eq!(written, expected);
+        }
+        prop_assert_eq!(&combined["unique_puzzles"], &serde_json::json!(total));
+        std::fs::remove_file(summary).unwrap();
+    }
+
+    #[test]
+    fn piped_puzzles_get_one_report_per_line(puzzles in prop::collection::vec(spec(), 1..6), garbage in 0..6usize) {
+        let mut lines: Vec<String> = puzzles.iter().map(|puzzle| serde_json::to_string(puzzle).unwrap()).collect();
+        lines.insert(garbage.min(lines.len()), "not a puzzle".to_string());
+        lines.push(String::new());
+        let printed = run_command_line(&["solve", "--stdin"], &lines.join("\n"))?;
+        let results: Vec<serde_json::Value> = printed.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        prop_assert_eq!(results.len(), puzzles.len() + 1);
+        let mut puzzles = puzzles.iter();
+        for (line_number, (line, result)) in lines.iter().zip(&results).enumerate() {
+            prop_assert_eq!(&result["line"], &serde_json::json!(line_number + 1));
+            if line == "not a puzzle" {
+                prop_assert!(result["error"].is_string());
+                continue;
+            }
+            let mut report: SolveReport = serde_json::from_value(result.clone()).unwrap();
+            let mut expected = solve_report(puzzles.next().unwrap(), &SolverOptions::default(), None).unwrap();
+            (report.solve_time_us, expected.solve_time_us) = (0, 0);
+            prop_assert_eq!(report, expected);
+        }
+    }
+
+    #[test]
+    fn rated_files_keep_the_unique_puzzles_sorted_or_split_by_level(placed in 3..=6usize) {
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let (puzzles, mut bytes) = search_output(4, placed, &options, OutputFormat::Text);
+        // A puzzle with many solutions, which rating skips
+        bytes.extend_from_slice(b"(0,0)\n");
+        let (input, rated) = (temp_path("input.txt"), temp_path("rated.csv"));
+        std::fs::write(&input, bytes).unwrap();
+        let (input, rated) = (input.to_str().unwrap(), rated.to_str().unwrap());
+        let levels = ["--hard-from", "1", "--expert-from", "2"];
+        let level = |difficulty: usize| ["normal", "hard", "expert"][difficulty.min(2)];
+        let difficulty = |record: &HashMap<String, String>| record["difficulty"].parse::<usize>().unwrap();
+        let expected: HashMap<_, _> = puzzles
+            .iter()
+            .map(|puzzle| (output::encode_clues(&puzzle.tiles), puzzle.stats.difficulty().to_string()))
+            .collect();
+
+        run_command_line(&[&["rate", "--input", input, "--output", rated, "--sort"][..], &levels].concat(), "")?;
+        let records = csv_records(&std::fs::read_to_string(rated).unwrap());
+        std::fs::remove_file(rated).unwrap();
+        let clues: HashMap<_, _> =
+            records.iter().map(|record| (record["clues"].clone(), record["difficulty"].clone())).collect();
+        prop_assert_eq!(records.len(), puzzles.len());
+        prop_assert_eq!(clues, expected.clone());
+        prop_assert!(records.windows(2).all(|pair| difficulty(&pair[0]) <= difficulty(&pair[1])));
+
+        run_command_line(&[&["rate", "--input", input, "--output", rated, "--split"][..], &levels].concat(), "")?;
+        let mut split = 0;
+        for name in ["normal", "hard", "expert"] {
+            let path = rated.replace(".csv", &format!(".{}.csv", name));
+            let records = csv_records(&std::fs::read_to_string(&path).unwrap());
+            std::fs::remove_file(&path).unwrap();
+            prop_assert!(records.iter().all(|record| level(difficulty(record)) == name));
+            prop_assert!(records.iter().all(|record| expected.get(&record["clues"]) == Some(&record["difficulty"])));
+            split += records.len();
+        }
+        std::fs::remove_file(input).unwrap();
+        prop_assert_eq!(split, puzzles.len());
+    }
+
+    #[test]
+    fn superset_filters_find_the_recorded_sets_within_a_combination(
+        (size, known, combinations) in (3..=6usize).prop_flat_map(|size| {
+            let subsets = move |max| prop::sample::subsequence(all_tiles(size), 1..=max);
+            let known = prop::collection::vec(subsets(4), 0..40);
+            (Just(size), known, prop::collection::vec(subsets(size * size), 1..10))
+        }),
+    ) {
+        let mut filter = SupersetFilter::new(size);
+        for tiles in &known {
+            filter.insert(tiles);
+        }
+        prop_assert_eq!(filter.len(), known.iter().collect::<HashSet<_>>().len());
+        for combination in &combinations {
+            let expected = known.iter().any(|tiles| tiles.iter().all(|tile| combination.contains(tile)));
+            prop_assert_eq!(filter.contains_subset_of(combination), expected);
+        }
+    }
+
+    #[test]
+    fn stratified_samples_keep_the_line_load_under_the_cap(
+        placed in 3..=8usize,
+        max_per_line in 1..=3usize,
+        generator_seed in any::<u64>(),
+    ) {
+        let placed = placed.min(5 * max_per_line);
+        let options = SearchOptions {
+            random_tries: Some(200),
+            max_per_line: Some(max_per_line),
+            generator_seed,
+            deterministic_output: true,
+            ..SearchOptions::default()
+        };
+        let result = find_single_solution_puzzles(5, placed, options, Vec::new(), HashSet::new());
+        prop_assert!(result.puzzles.iter().all(|puzzle| line_load(5, &puzzle.tiles) <= max_per_line));
+        prop_assert!(result.summary.strata.keys().all(|&load| load <= max_per_line));
+        let stratified: usize = result.summary.strata.values().map(|stratum| stratum.combinations).sum();
+        prop_assert_eq!(stratified, result.summary.combinations_processed);
+    }
+
+    #[test]
+    fn annealing_runs_end_on_unique_puzzles_of_their_seed(
+        grid in latin_square_of_size(3..=5),
+        placed in 2..=8usize,
+        run in any::<u64>(),
+        seed in any::<u64>(),
+    ) {
+        let size = grid.size();
+        let options = AnnealOptions { steps: 200, ..AnnealOptions::default() };
+        let solver = SolverOptions::default();
+        let semantics = ClueSemantics::Sudodle;
+        let anneal = || generators::anneal(&grid, placed, run, seed, &options, &solver, semantics, Variant::Latin);
+        let tiles = anneal();
+        prop_assert_eq!(anneal(), tiles.clone());
+        if let Some(tiles) = tiles {
+            prop_assert_eq!(tiles.len(), placed);
+            prop_assert!(tiles.windows(2).all(|pair| pair[0] < pair[1]));
+            prop_assert_eq!(solve(&puzzle_constraints(&tiles, &grid, &all_tiles(size)), &solver).len(), 1);
+        }
+    }
+
+    #[test]
+    fn top_down_runs_end_on_minimal_unique_puzzles(
+        grid in latin_square_of_size(3..=5),
+        min_placed in 0..=6usize,
+        run in any::<u64>(),
+        seed in any::<u64>(),
+        givens_only in any::<bool>(),
+    ) {
+        let size = grid.size();
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let solver = SolverOptions::default();
+        let top_down = || generators::top_down(&grid, min_placed, run, seed, &solver, semantics, Variant::Latin);
+        let tiles = top_down();
+        prop_assert_eq!(top_down(), tiles.clone());
+        let count = |tiles: &[(usize, usize)]| {
+            solve(&puzzle_constraints_with(tiles, &grid, &all_tiles(size), semantics), &solver).len()
+        };
+        if let Some(tiles) = tiles {
+            prop_assert!(tiles.len() >= min_placed);
+            prop_assert_eq!(count(&tiles), 1);
+            // Each tile left was kept because removing it let a second solution in
+            if tiles.len() > min_placed {
+                for removed in &tiles {
+                    let fewer: Vec<_> = tiles.iter().copied().filter(|tile| tile != removed).collect();
+                    prop_assert!(count(&fewer) >= 2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn givens_only_puzzles_keep_every_completion_of_their_givens(
+        (grid, tiles) in latin_square().prop_flat_map(|grid| {
+            let size = grid.size();
+            (Just(grid), prop::sample::subsequence(all_tiles(size), 0..=size * size))
+        }),
+    ) {
+        let size = grid.size();
+        let options = SolverOptions::default();
+        let solutions = |semantics| {
+            solve(&puzzle_constraints_with(&tiles, &grid, &all_tiles(size), semantics), &options)
+        };
+        let given = solutions(ClueSemantics::GivensOnly);
+        let sudodle = solutions(ClueSemantics::Sudodle);
+        // The game's solutions differ from the grid on every tile that is not placed
+        for solution in &sudodle {
+            prop_assert!(all_tiles(size).iter().all(|tile| tiles.contains(tile) || solution[*tile] != grid[*tile]));
+        }
+        for solution in &given {
+            prop_assert!(is_latin_square(solution));
+            prop_assert!(tiles.iter().all(|&tile| solution[tile] == grid[tile]));
+        }
+        if given.len() < MAX_SOLUTIONS {
+            prop_assert!(given.contains(&grid));
+            prop_assert!(sudodle.iter().all(|solution| given.contains(solution)));
+        }
+    }
+
+    #[test]
+    fn givens_only_searches_find_unique_classic_puzzles(placed in 4..=5usize) {
+        let options = SearchOptions { semantics: ClueSemantics::GivensOnly, ..SearchOptions::default() };
+        let result = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        let solver = SolverOptions::default();
+        for puzzle in &result.puzzles {
+            let grid = puzzle.solution.clone().unwrap();
+            let solutions = |semantics| {
+                solve(&puzzle_constraints_with(&puzzle.tiles, &grid, &all_tiles(4), semantics), &solver)
+            };
+            prop_assert_eq!(puzzle.tiles.len(), placed);
+            prop_assert_eq!(solutions(ClueSemantics::GivensOnly), vec![grid.clone()]);
+            // The grid is the only completion of the givens, so it must be the game's answer
+            prop_assert!(solutions(ClueSemantics::Sudodle).is_empty());
+        }
+    }
+
+    #[test]
+    fn simulated_guesses_follow_the_feedback_until_the_secret(
+        secret in latin_square_of_size(2..=4),
+        strategy in prop_oneof![
+            Just(simulate::Strategy::RandomConsistent),
+            Just(simulate::Strategy::EntropyGreedy),
+            Just(simulate::Strategy::HumanHeuristic),
+        ],
+        relaxed in any::<bool>(),
+        seed in any::<u64>(),
+    ) {
+        let size = secret.size();
+        let options = SimulationOptions { strategy, samples: 8, relaxed };
+        let play = || simulate::play(&secret, Constraints::new(size), &options, &mut simulate::trial_rng(seed, 0));
+        let transcript = play();
+        prop_assert_eq!(transcript.guesses.last(), Some(&secret));
+        prop_assert_eq!(transcript.guesses.len(), transcript.correct.len());
+        for (index, guess) in transcript.guesses.iter().enumerate() {
+            prop_assert!(relaxed || is_latin_square(guess));
+            prop_assert_eq!(transcript.correct[index], simulate::feedback(guess, &secret).len());
+            // Each guess agrees with the feedback of the earlier ones, so none is made twice
+            for earlier in &transcript.guesses[..index] {
+                prop_assert_eq!(simulate::feedback(guess, earlier), simulate::feedback(&secret, earlier));
+            }
+        }
+        prop_assert_eq!(&play().guesses, &transcript.guesses);
+        let counts = simulate::guess_counts(&secret, &Constraints::new(size), 3, &options, seed);
+        prop_assert_eq!(counts.games(), 3);
+        // Game 0 draws the numbers of the game above
+        prop_assert!(counts.counts.contains_key(&transcript.guesses.len()));
+    }
+
+    #[test]
+    fn hardest_secrets_take_the_most_guesses_of_the_candidates(
+        size in 2..=4usize,
+        count in 1..=6usize,
+        source in prop_oneof![
+            Just(simulate::SecretSource::Random),
+            Just(simulate::SecretSource::Enumerated),
+            Just(simulate::SecretSource::Uniform),
+        ],
+        top in 1..=4usize,
+        seed in any::<u64>(),
+    ) {
+        let candidates = simulate::candidate_secrets(size, count, source, seed);
+        prop_assert!(!candidates.is_empty() && candidates.len() <= count);
+        prop_assert_eq!(candidates.iter().collect::<HashSet<_>>().len(), candidates.len());
+        for secret in &candidates {
+            prop_assert!(is_latin_square(secret));
+            let reduced = (0..size).all(|k| [secret[(0, k)], secret[(k, 0)]] == [k as u8 + 1; 2]);
+            prop_assert!(reduced || source != simulate::SecretSource::Enumerated);
+        }
+        let options = SimulationOptions { samples: 4, ..SimulationOptions::default() };
+        let mean = |secret: &Grid| simulate::guess_counts(secret, &Constraints::new(size), 4, &options, seed).mean();
+        let hardest = simulate::hardest_secrets(candidates.clone(), top, 4, &options, seed);
+        prop_assert_eq!(hardest.len(), top.min(candidates.len()));
+        for (index, (secret, counts)) in hardest.iter().enumerate() {
+            prop_assert!(candidates.contains(secret));
+            prop_assert_eq!(counts.games(), 4);
+            prop_assert_eq!(counts.mean(), mean(secret));
+            prop_assert!(index == 0 || hardest[index - 1].1.mean() >= counts.mean());
+        }
+        let least = hardest.last().unwrap().1.mean();
+        let left_out = candidates.iter().filter(|secret| hardest.iter().all(|(other, _)| other != *secret));
+        for secret in left_out {
+            prop_assert!(mean(secret) <= least);
+        }
+    }
+
+    #[test]
+    fn searches_record_the_expected_guesses_of_their_puzzles(placed in 5..=6usize, games in 1..=6usize) {
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let plain = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        prop_assert!(plain.puzzles.iter().all(|puzzle| puzzle.expected_guesses.is_none()));
+        let options = SearchOptions { expected_guess_games: Some(games), ..options };
+        let rated = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        prop_assert_eq!(rated.puzzles.len(), plain.puzzles.len());
+        let grid = cyclic_latin_square(4);
+        for puzzle in &rated.puzzles {
+            let solution = puzzle.solution.as_ref().unwrap();
+            let expected = puzzle.expected_guesses.unwrap();
+            let constraints = puzzle_constraints(&puzzle.tiles, &grid, &all_tiles(4));
+            prop_assert_eq!(expected, simulate::expected_guesses(&constraints, solution, games));
+            prop_assert!(expected.mean >= 1.0);
+            prop_assert!(1 <= expected.median && expected.median <= expected.p90);
+        }
+    }
+
+    #[test]
+    fn first_guesses_are_the_best_rated_reduced_squares_and_cached(
+        size in 2..=4usize,
+        candidates in 1..=8usize,
+        secrets in 1..=40usize,
+        seed in any::<u64>(),
+    ) {
+        let settings = FirstGuessSettings { candidates, secrets, seed };
+        let (rated, exhaustive) = first_guess::rating_secrets(size, &settings);
+        prop_assert!(rated.len() <= secrets);
+        prop_assert_eq!(exhaustive, solve(&Constraints::new(size), &SolverOptions::default()).len() <= secrets);
+        prop_assert!(rated.iter().all(is_latin_square));
+        let ranked = first_guess::rank_first_guesses(size, &settings, &rated);
+        for (guess, rating) in &ranked {
+            prop_assert_eq!(*rating, first_guess::rate_guess(guess, &rated));
+            prop_assert!(rating.entropy >= 0.0 && rating.entropy <= (rated.len() as f64).log2() + 1e-9);
+            prop_assert!(rating.expected_share <= rating.worst_share + 1e-9 && rating.worst_share <= 1.0);
+        }
+        prop_assert!(ranked.windows(2).all(|pair| pair[0].1.entropy >= pair[1].1.entropy));
+
+        let path = temp_path("first-guesses.json");
+        let best = first_guess::cached_first_guesses(&path, &[size], &settings).unwrap();
+        prop_assert_eq!(best.len(), 1);
+        prop_assert_eq!(&best[0].guess, &output::format_grid(&ranked[0].0));
+        prop_assert_eq!(best[0].rating, ranked[0].1);
+        prop_assert_eq!(best[0].cyclic, first_guess::rate_guess(&cyclic_latin_square(size), &rated));
+        // The second call reads the table the first one wrote
+        prop_assert_eq!(first_guess::cached_first_guesses(&path, &[size], &settings).unwrap(), best);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn zinc_models_hold_the_clues_of_their_puzzle(
+        (grid, tiles) in latin_square().prop_flat_map(|grid| {
+            let size = grid.size();
+            (Just(grid), prop::sample::subsequence(all_tiles(size), 0..=size * size))
+        }),
+        givens_only in any::<bool>(),
+    ) {
+        let size = grid.size();
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let constraints = puzzle_constraints_with(&tiles, &grid, &all_tiles(size), semantics);
+        let wrong = |(i, j): (usize, usize), value: usize| constraints.wrong[i * size + j] & 1 << (value - 1) != 0;
+        let mut placed = HashSet::new();
+        let mut forbidden = HashSet::new();
+        for (i, j) in all_tiles(size) {
+            let known = usize::from(constraints.known[(i, j)]);
+            if known != 0 {
+                placed.insert(format!("constraint grid[{}, {}] = {};", i + 1, j + 1, known));
+            }
+            for value in (1..=size).filter(|&value| wrong((i, j), value)) {
+                forbidden.insert(format!("constraint grid[{}, {}] != {};", i + 1, j + 1, value));
+            }
+        }
+        let minizinc = export::export_model(&constraints, semantics, ModelFormat::Minizinc);
+        let clues = |operator: &str| -> HashSet<String> {
+            let clue = |line: &&str| line.starts_with("constraint grid[") && line.contains(operator);
+            minizinc.lines().filter(clue).map(String::from).collect()
+        };
+        prop_assert_eq!(clues(" = "), placed);
+        prop_assert_eq!(clues(" != "), forbidden);
+
+        // FlatZinc models give each cell the values its clues allow, which hold every solution
+        let flatzinc = export::export_model(&constraints, semantics, ModelFormat::Flatzinc);
+        let solutions = solve(&constraints, &SolverOptions::default());
+        for (i, j) in all_tiles(size) {
+            let domain: Vec<usize> = match usize::from(constraints.known[(i, j)]) {
+                0 => (1..=size).filter(|&value| !wrong((i, j), value)).collect(),
+                known => vec![known],
+            };
+            let declaration = format!(
+                "var {{{}}}: x_{}_{};",
+                domain.iter().map(usize::to_string).collect::<Vec<_>>().join(","),
+                i + 1,
+                j + 1
+            );
+            prop_assert!(flatzinc.lines().any(|line| line == declaration));
+            prop_assert!(solutions.iter().all(|solution| domain.contains(&usize::from(solution[(i, j)]))));
+        }
+        let disequalities = flatzinc.lines().filter(|line| line.starts_with("constraint int_ne(")).count();
+        prop_assert_eq!(disequalities, size * size * (size - 1));
+    }
+
+    #[test]
+    fn xcsp3_solutions_read_back_as_their_grid(
+        (grid, tiles) in latin_square().prop_flat_map(|grid| {
+            let size = grid.size();
+            (Just(grid), prop::sample::subsequence(all_tiles(size), 0..=size * size))
+        }),
+        order in any::<u64>(),
+    ) {
+        let size = grid.size();
+        let constraints = puzzle_constraints(&tiles, &grid, &all_tiles(size));
+        let instance = export::export_model(&constraints, ClueSemantics::Sudodle, ModelFormat::Xcsp3);
+        let conflicts = instance.lines().filter(|line| line.contains("<conflicts>")).count();
+        prop_assert_eq!(conflicts, size * size - tiles.len());
+        prop_assert_eq!(instance.contains("<instantiation>"), !tiles.is_empty());
+        for solution in solve(&constraints, &SolverOptions::default()) {
+            let values: Vec<String> = all_tiles(size).iter().map(|&tile| solution[tile].to_string()).collect();
+            let printed = format!(
+                "s SATISFIABLE\nv <instantiation> <list> grid[][] </list>\nv <values> {} </values>\nv </instantiation>",
+                values.join(" ")
+            );
+            prop_assert_eq!(export::parse_xcsp3_solution(&printed), Ok(solution.clone()));
+            // The same cells listed one by one, in any order
+            let mut cells = all_tiles(size);
+            cells.sort_by_key(|&(i, j)| (i * size + j) as u64 ^ order);
+            let list: Vec<String> = cells.iter().map(|&(i, j)| format!("grid[{}][{}]", i, j)).collect();
+            let values: Vec<String> = cells.iter().map(|&tile| solution[tile].to_string()).collect();
+            let file = format!(
+                "<instantiation> <list> {} </list> <values> {} </values> </instantiation>",
+                list.join(" "),
+                values.join(" ")
+            );
+            prop_assert_eq!(export::parse_xcsp3_solution(&file), Ok(solution));
+        }
+        let truncated = "<instantiation> <list> grid[][] </list> <values> 1 2 </values>";
+        prop_assert!(export::parse_xcsp3_solution(truncated).is_err());
+    }
+
+    #[test]
+    fn nogoods_only_rule_out_clue_sets_with_several_solutions(
+        (grid, recorded, checked) in latin_square_of_size(2..=5).prop_flat_map(|grid| {
+            let tiles = all_tiles(grid.size());
+            let clue_sets = prop::collection::vec(prop::sample::subsequence(tiles.clone(), 0..=tiles.len()), 0..8);
+            (Just(grid), clue_sets.clone(), clue_sets)
+        }),
+    ) {
+        let size = grid.size();
+        let options = SolverOptions::default();
+        let solutions = |tiles: &[(usize, usize)]| {
+            solve(&puzzle_constraints_with(tiles, &grid, &all_tiles(size), ClueSemantics::GivensOnly), &options)
+        };
+        let mut nogoods = NogoodSet::new(size);
+        for tiles in &recorded {
+            for solution in solutions(tiles) {
+                nogoods.record(&solution, &grid);
+            }
+        }
+        prop_assert!(nogoods.len() <= recorded.len() * MAX_SOLUTIONS);
+        for tiles in recorded.iter().chain(&checked) {
+            if nogoods.rules_out(tiles) {
+                prop_assert!(solutions(tiles).len() > 1);
+            }
+        }
+    }
+
+    #[test]
+    fn nogood_searches_find_the_puzzles_of_plain_searches(placed in 4..=5usize) {
+        let options = SearchOptions {
+            semantics: ClueSemantics::GivensOnly,
+            deterministic_output: true,
+            ..SearchOptions::default()
+        };
+        let plain = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        let options = SearchOptions { nogoods: true, ..options };
+        let pruned = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        let tiles = |puzzles: &[FoundPuzzle]| -> Vec<_> { puzzles.iter().map(|puzzle| puzzle.tiles.clone()).collect() };
+        prop_assert_eq!(tiles(&pruned.puzzles), tiles(&plain.puzzles));
+        prop_assert_eq!(plain.summary.nogood_skips, 0);
+        prop_assert!(pruned.summary.nogood_skips > 0);
+    }
+
+    #[test]
+    fn resumed_random_searches_skip_the_combinations_of_their_checkpoint(
+        placed in 4..=6usize,
+        first in 1..=150usize,
+        more in 1..=150usize,
+        seed in any::<u64>(),
+    ) {
+        let (path, copy) = (temp_path("search.checkpoint"), temp_path("copy.checkpoint"));
+        let options = SearchOptions {
+            random_tries: Some(first),
+            deterministic_output: true,
+            generator_seed: seed,
+            checkpoint: Some(path.clone()),
+            ..SearchOptions::default()
+        };
+        let started = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        let saved = Checkpoint::load(&path).unwrap().unwrap();
+        prop_assert_eq!((saved.size, saved.placed, saved.seed), (4, placed, seed));
+        prop_assert_eq!(saved.tries, started.summary.combinations_processed);
+        prop_assert_eq!(saved.tried.len(), saved.tries as u64);
+        saved.save(&copy).unwrap();
+        prop_assert_eq!(Checkpoint::load(&copy).unwrap(), Some(saved.clone()));
+
+        let options = SearchOptions { random_tries: Some(first + more), resume: Some(saved.clone()), ..options };
+        let resumed = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        let continued = Checkpoint::load(&path).unwrap().unwrap();
+        prop_assert_eq!(continued.tries, saved.tries + resumed.summary.combinations_processed);
+        prop_assert!(continued.tries <= first + more && continued.batches > saved.batches);
+        for puzzle in &started.puzzles {
+            prop_assert!(continued.tried.contains(TileMask::of_tiles(4, &puzzle.tiles)));
+            prop_assert!(resumed.puzzles.iter().all(|other| other.tiles != puzzle.tiles));
+        }
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(copy).unwrap();
+    }
+
+    #[test]
+    fn capped_searches_spill_the_puzzles_they_move_out_of_memory(placed in 4..=6usize, cap_kb in 0..=64usize) {
+        let options = SearchOptions { deterministic_output: true, ..SearchOptions::default() };
+        let uncapped = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        prop_assert_eq!((uncapped.spilled, &uncapped.spill_file), (0, &None));
+        let options = SearchOptions { max_memory_bytes: Some(cap_kb << 10), ..options };
+        let capped = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        prop_assert_eq!(capped.summary.unique_puzzles, uncapped.summary.unique_puzzles);
+        prop_assert_eq!(capped.spilled + capped.puzzles.len(), uncapped.puzzles.len());
+        prop_assert_eq!(capped.spill_file.is_some(), capped.spilled > 0);
+        // Puzzles are spilled batch by batch, so the ones left in memory are the last found
+        let spilled = match &capped.spill_file {
+            Some(path) => {
+                let tiles = output::read_puzzle_tiles(&path.to_string_lossy()).unwrap();
+                std::fs::remove_file(path).unwrap();
+                tiles
+            }
+            None => Vec::new(),
+        };
+        let kept = capped.puzzles.iter().map(|puzzle| puzzle.tiles.clone());
+        let expected: Vec<_> = uncapped.puzzles.iter().map(|puzzle| puzzle.tiles.clone()).collect();
+        prop_assert_eq!(spilled.into_iter().chain(kept).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn searches_without_solutions_solve_them_again_on_demand(placed in 4..=5usize, givens_only in any::<bool>()) {
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let options = SearchOptions { semantics, deterministic_output: true, ..SearchOptions::default() };
+        let kept = find_single_solution_puzzles(4, placed, options.clone(), Vec::new(), HashSet::new());
+        let options = SearchOptions { no_solutions: true, ..options };
+        let dropped = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        prop_assert_eq!(dropped.puzzles.len(), kept.puzzles.len());
+        for (without, with) in dropped.puzzles.iter().zip(&kept.puzzles) {
+            prop_assert_eq!(&without.tiles, &with.tiles);
+            prop_assert_eq!(&without.solution, &None);
+            prop_assert_eq!(without.unique_solution(4, semantics), with.solution.clone().unwrap());
+            prop_assert_eq!(with.unique_solution(4, semantics), with.solution.clone().unwrap());
+        }
+    }
+
+    #[test]
+    fn rendered_puzzles_show_each_cell_as_the_game_does(
+        (solution, tiles) in latin_square_of_size(3..=9).prop_flat_map(|grid| {
+            let tiles = all_tiles(grid.size());
+            (Just(grid), prop::sample::subsequence(tiles.clone(), 0..=tiles.len()))
+        }),
+        with_solution in any::<bool>(),
+        givens_only in any::<bool>(),
+        cell_size in 20..=100u32,
+    ) {
+        let size = solution.size();
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let options = RenderOptions { cell_size, semantics };
+        let shown = with_solution.then_some(&solution);
+        let shapes = render::puzzle_shapes(size, &tiles, shown, &options);
+        let side = render::image_size(size, &options);
+        let margin = (side - size as u32 * cell_size) / 2;
+        let cell_at = |x: u32, y: u32| (((y - margin) / cell_size) as usize, ((x - margin) / cell_size) as usize);
+
+        // Placed tiles stand out from the other tiles, which all look alike
+        let mut fills = HashMap::new();
+        for shape in &shapes {
+            if let Shape::Cell { x, y, side: cell_side, fill } = *shape {
+                prop_assert_eq!(cell_side, cell_size);
+                prop_assert!(x + cell_side <= side && y + cell_side <= side);
+                prop_assert!(fills.insert(cell_at(x, y), fill).is_none());
+            }
+        }
+        prop_assert_eq!(fills.len(), size * size);
+        let placed_fills: HashSet<_> = tiles.iter().map(|tile| fills[tile]).collect();
+        let other_fills: HashSet<_> = fills.iter().filter(|(tile, _)| !tiles.contains(tile)).map(|(_, &f)| f).collect();
+        prop_assert!(placed_fills.len() <= 1 && other_fills.len() <= 1 && placed_fills.is_disjoint(&other_fills));
+
+        // The large digit of a cell is its value in the cyclic square if it is placed, its
+        // value in the solution if there is one, and the wrong value of the square with
+        // Sudodle semantics; Sudodle puzzles with a solution keep the wrong values small
+        let cyclic = cyclic_latin_square(size);
+        let mut large = HashMap::new();
+        let mut small = HashMap::new();
+        for shape in &shapes {
+            if let Shape::Digit { x, y, font_size, value, .. } = *shape {
+                prop_assert!(x < side && y < side);
+                let digits = if font_size == cell_size * 11 / 20 { &mut large } else { &mut small };
+                prop_assert!(digits.insert(cell_at(x, y), value).is_none());
+            }
+        }
+        for tile in all_tiles(size) {
+            let expected = match (tiles.contains(&tile), with_solution) {
+                (true, _) => Some(cyclic[tile]),
+                (false, true) => Some(solution[tile]),
+                (false, false) => (!givens_only).then_some(cyclic[tile]),
+            };
+            prop_assert_eq!(large.get(&tile).copied(), expected, "large digit of {:?}", tile);
+            let expected = (!tiles.contains(&tile) && with_solution && !givens_only).then_some(cyclic[tile]);
+            prop_assert_eq!(small.get(&tile).copied(), expected, "small digit of {:?}", tile);
+        }
+
+        let svg = render::puzzle_svg(size, &tiles, shown, &options);
+        prop_assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        let dimensions = format!("width=\"{}\" height=\"{}\"", side, side);
+        prop_assert!(svg.contains(&dimensions));
+        prop_assert_eq!(svg.matches("<text ").count(), large.len() + small.len());
+    }
+
+    #[test]
+    fn puzzle_sheets_lay_out_every_puzzle_and_its_answer(
+        placed in 4..=6usize,
+        generator_seed in any::<u64>(),
+        per_page in 1..=8usize,
+        answers in any::<bool>(),
+    ) {
+        let path = temp_path("sheet.jsonl");
+        let header = FileHeader::new(4, ClueSemantics::Sudodle);
+        let sink = output::create_file_sink(path.to_str().unwrap(), OutputFormat::Jsonl, None, false, &header).unwrap();
+        let options = SearchOptions { random_tries: Some(100), generator_seed, ..SearchOptions::default() };
+        let found = find_single_solution_puzzles(4, placed, options, vec![sink], HashSet::new()).puzzles;
+        let solutions: HashMap<_, _> = found
+            .iter()
+            .map(|puzzle| (standardize_tile_tuple(&puzzle.tiles), puzzle.unique_solution(4, ClueSemantics::Sudodle)))
+            .collect();
+        let puzzles: Vec<SheetPuzzle> = output::read_puzzle_tiles(path.to_str().unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|tiles| SheetPuzzle { size: 4, solution: solutions[&standardize_tile_tuple(&tiles)].clone(), tiles })
+            .collect();
+        let pdf = sheet::puzzle_sheet(&puzzles, per_page, ClueSemantics::Sudodle, answers);
+
+        // The print command lays out the puzzles of the file in their order
+        let out_file = temp_path("sheet.pdf");
+        let per_page_arg = per_page.to_string();
+        let mut args = vec!["print", "--input", path.to_str().unwrap(), "--per-page", &per_page_arg];
+        args.extend(["--output", out_file.to_str().unwrap()]);
+        if !answers {
+            args.push("--no-answers");
+        }
+        run_command_line(&args, "")?;
+        let printed = std::fs::read(&out_file).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&out_file).unwrap();
+        prop_assert!(printed == pdf, "print wrote another document");
+
+        let pdf = String::from_utf8(pdf).unwrap();
+        prop_assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+        let copies = if answers { 2 } else { 1 };
+        let pages = copies * puzzles.len().div_ceil(per_page);
+        let count = format!("/Count {} >>", pages);
+        prop_assert!(pdf.contains(&count));
+        prop_assert_eq!(pdf.matches("/Type /Page /Parent").count(), pages);
+        // The cross-reference table points at each object
+        let xref = pdf.rsplit_once("startxref\n").unwrap().1.lines().next().unwrap().parse::<usize>().unwrap();
+        let offsets: Vec<&str> = pdf[xref..].lines().skip(3).take_while(|line| line.ends_with(" n ")).collect();
+        prop_assert_eq!(offsets.len(), 4 + 2 * pages);
+        for (index, line) in offsets.iter().enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            let object = format!("{} 0 obj\n", index + 1);
+            prop_assert!(pdf[offset..].starts_with(&object));
+        }
+        for stream in pdf.split("/Length ").skip(1) {
+            let (length, rest) = stream.split_once(" >>\nstream\n").unwrap();
+            let length: usize = length.parse().unwrap();
+            prop_assert!(rest[length..].starts_with("endstream"));
+        }
+
+        // Each puzzle is numbered and drawn on its page, then with its solution on the answer pages
+        let options = RenderOptions::default();
+        let mut digits = 0;
+        for (index, puzzle) in puzzles.iter().enumerate() {
+            prop_assert_eq!(pdf.matches(&format!("({}.) Tj", index + 1)).count(), copies);
+            let solutions = [None, Some(&puzzle.solution)];
+            for solution in &solutions[..copies] {
+                let shapes = render::puzzle_shapes(4, &puzzle.tiles, *solution, &options);
+                digits += shapes.iter().filter(|shape| matches!(shape, Shape::Digit { .. })).count();
+            }
+        }
+        let texts = pages + puzzles.len().div_ceil(per_page) + copies * puzzles.len() + digits;
+        prop_assert_eq!(pdf.matches(" Tj ET").count(), texts);
+    }
+
+    #[test]
+    fn witnesses_are_trades_between_two_solutions_of_puzzles_that_are_not_unique(
+        (grid, tiles) in latin_square_of_size(3..=5).prop_flat_map(|grid| {
+            let tiles = all_tiles(grid.size());
+            (Just(grid), prop::sample::subsequence(tiles.clone(), 0..=tiles.len()))
+        }),
+        givens_only in any::<bool>(),
+    ) {
+        let size = grid.size();
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let constraints = puzzle_constraints_with(&tiles, &grid, &all_tiles(size), semantics);
+        let solutions = solve(&constraints, &SolverOptions::default());
+        let found = witness::find_witness(&constraints, MAX_SOLUTIONS);
+        prop_assert_eq!(found.is_some(), solutions.len() > 1);
+        let Some(found) = found else {
+            return Ok(());
+        };
+        prop_assert!(found.len() <= Witness::between(&solutions[0], &solutions[1]).len());
+
+        // Swapping the values of the cells turns a solution into another: each
+        // line of the witness holds the same values before and after
+        let cells: Vec<(usize, usize)> = found.cells.iter().map(|&(cell, _, _)| cell).collect();
+        prop_assert!(cells.windows(2).all(|pair| pair[0] < pair[1]));
+        prop_assert!(cells.iter().all(|cell| !tiles.contains(cell)));
+        for line in 0..size {
+            for axis in [0, 1] {
+                let in_line: Vec<_> =
+                    found.cells.iter().filter(|&&(cell, _, _)| [cell.0, cell.1][axis] == line).collect();
+                let mut before: Vec<u8> = in_line.iter().map(|&&(_, first, _)| first).collect();
+                let mut after: Vec<u8> = in_line.iter().map(|&&(_, _, second)| second).collect();
+                before.sort_unstable();
+                after.sort_unstable();
+                prop_assert!(before.len() != 1 && before == after);
+            }
+        }
+        prop_assert!(found.cells.iter().all(|&(_, first, second)| first != second));
+        prop_assert!(found.len() >= 4);
+        prop_assert_eq!(found.len() == 4, found.kind() == "intercalate");
+        let matches_first = |solution: &&Grid| found.cells.iter().all(|&(cell, value, _)| solution[cell] == value);
+        let mut second = solutions.iter().find(matches_first).cloned().unwrap();
+        for &(cell, _, value) in &found.cells {
+            second[cell] = value;
+        }
+        prop_assert!(solutions.contains(&second));
+    }
+
+    #[test]
+    fn repairs_are_the_smallest_sets_of_extra_tiles_making_puzzles_unique(
+        (size, tiles) in (3..=4usize)
+            .prop_flat_map(|size| (Just(size), prop::sample::subsequence(all_tiles(size), 0..=size * size))),
+        givens_only in any::<bool>(),
+        max_extra in 1..=2usize,
+    ) {
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let options = RepairOptions { max_extra, limit: usize::MAX, ..RepairOptions::default() };
+        let repairs = repair::repair(size, &tiles, semantics, &options);
+
+        // Try every set of each number of extra tiles
+        let unique_difficulty = |extra_tiles: &[(usize, usize)]| {
+            let all: Vec<_> = tiles.iter().chain(extra_tiles).copied().collect();
+            let constraints = puzzle_constraints_with(&all, &cyclic_latin_square(size), &all_tiles(size), semantics);
+            let (solutions, stats) =
+                solve_constraints(&constraints, Some(2), &SolverOptions::default(), SolveHooks::default());
+            (solutions.len() == 1).then(|| stats.difficulty())
+        };
+        let free: Vec<(usize, usize)> = all_tiles(size).into_iter().filter(|tile| !tiles.contains(tile)).collect();
+        let mut expected = None;
+        for extra in 0..=max_extra.min(free.len()) {
+            let sets: Vec<Vec<(usize, usize)>> = if extra == 0 {
+                vec![Vec::new()]
+            } else {
+                CombinationIterator::new(free.clone(), extra).collect()
+            };
+            let mut found: Vec<(Vec<(usize, usize)>, usize)> = sets
+                .into_iter()
+                .filter_map(|mut set| {
+                    set.sort_unstable();
+                    unique_difficulty(&set).map(|difficulty| (set, difficulty))
+                })
+                .collect();
+            if !found.is_empty() {
+                found.sort_by_key(|(set, difficulty)| (*difficulty, set.clone()));
+                expected = Some((extra, found));
+                break;
+            }
+        }
+        prop_assert_eq!(repairs.map(|repairs| (repairs.extra, repairs.repairs)), expected);
+    }
+
+    #[test]
+    fn robust_moves_are_the_moves_along_a_line_keeping_puzzles_unique(
+        (size, tiles) in (3..=5usize)
+            .prop_flat_map(|size| (Just(size), prop::sample::subsequence(all_tiles(size), 0..=size * size))),
+        givens_only in any::<bool>(),
+        options in options(),
+    ) {
+        let semantics = if givens_only { ClueSemantics::GivensOnly } else { ClueSemantics::Sudodle };
+        let clues = robustness::clue_robustness(size, &tiles, semantics, &options);
+        prop_assert_eq!(&clues, &robustness::clue_robustness(size, &tiles, semantics, &SolverOptions::default()));
+        prop_assert_eq!(clues.len(), tiles.len());
+        for (index, clue) in clues.iter().enumerate() {
+            let (r, c) = tiles[index];
+            prop_assert_eq!(clue.tile, (r, c));
+            let mut unique_moves = Vec::new();
+            let mut moves = 0;
+            for target in all_tiles(size) {
+                if (target.0 == r) == (target.1 == c) || tiles.contains(&target) {
+                    continue;
+                }
+                moves += 1;
+                let mut moved = tiles.clone();
+                moved[index] = target;
+                let grid = cyclic_latin_square(size);
+                let constraints = puzzle_constraints_with(&moved, &grid, &all_tiles(size), semantics);
+                if solve(&constraints, &SolverOptions::default()).len() == 1 {
+                    unique_moves.push(target);
+                }
+            }
+            prop_assert_eq!(clue.moves, moves);
+            prop_assert_eq!(&clue.unique_moves, &unique_moves);
+        }
+        let wiggle_room = robustness::wiggle_room(&clues);
+        let moves: usize = clues.iter().map(|clue| clue.moves).sum();
+        let unique: usize = clues.iter().map(|clue| clue.unique_moves.len()).sum();
+        prop_assert!((0.0..=1.0).contains(&wiggle_room));
+        prop_assert!(moves == 0 || (wiggle_room * moves as f64 - unique as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn histograms_count_each_value_in_the_buckets_at_or_above_it(
+        values in prop::collection::vec(prop_oneof![0.0..2e6f64, Just(10.0), Just(0.0)], 0..50),
+    ) {
+        let histogram = Histogram::new(&metrics::NODE_BUCKETS);
+        for &value in &values {
+            histogram.observe(value);
+        }
+        prop_assert_eq!(histogram.count(), values.len() as u64);
+        let mut text = String::new();
+        histogram.write(&mut text, "nodes", "size=\"5\"");
+        for bound in metrics::NODE_BUCKETS {
+            let at_most = values.iter().filter(|&&value| value <= bound).count() as f64;
+            prop_assert_eq!(metric(&text, &format!("nodes_bucket{{size=\"5\",le=\"{}\"}}", bound)), Some(at_most));
+        }
+        prop_assert_eq!(metric(&text, "nodes_bucket{size=\"5\",le=\"+Inf\"}"), Some(values.len() as f64));
+        prop_assert_eq!(metric(&text, "nodes_count{size=\"5\"}"), Some(values.len() as f64));
+        let sum = metric(&text, "nodes_sum{size=\"5\"}").unwrap();
+        prop_assert!((sum - values.iter().sum::<f64>()).abs() <= 1e-9 * sum.max(1.0));
+    }
+
+    #[test]
+    fn metrics_files_hold_the_counters_of_their_search(placed in 4..=6usize, generator_seed in any::<u64>()) {
+        let path = temp_path("search.prom");
+        let options = SearchOptions {
+            random_tries: Some(100),
+            generator_seed,
+            metrics_file: Some(path.clone()),
+            ..SearchOptions::default()
+        };
+        let summary = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new()).summary;
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let counter = |name: &str| metric(&text, &format!("sudodle_search_{}{{size=\"4\"}}", name));
+        prop_assert_eq!(counter("combinations_total"), Some(summary.combinations_processed as f64));
+        prop_assert_eq!(counter("puzzles_found_total"), Some(summary.unique_puzzles as f64));
+        prop_assert_eq!(counter("dedup_hits_total"), Some(summary.dedup_hits as f64));
+        // Every combination solved is in the histogram of solver nodes
+        let solved = counter("solver_nodes_count").unwrap();
+        prop_assert!(solved >= summary.unique_puzzles as f64 && solved <= summary.combinations_processed as f64);
+        prop_assert!(counter("combinations_per_second").is_some_and(|rate| rate > 0.0));
+        prop_assert!(text.contains("# TYPE sudodle_search_solver_nodes histogram\n"));
+    }
+
+    #[test]
+    fn random_latin_squares_are_latin_squares_and_reach_every_square_of_order_3(seed in any::<u64>()) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for size in 1..=9 {
+            prop_assert!(is_latin_square(&qcp::random_latin_square(size, &mut rng)));
+        }
+        // All 12 Latin squares of order 3, each drawn with probability 1/12
+        let drawn: HashSet<Grid> = (0..200).map(|_| qcp::random_latin_square(3, &mut rng)).collect();
+        prop_assert_eq!(drawn.len(), 12);
+    }
+
+    #[test]
+    fn qwh_instances_have_a_completion_and_balanced_holes(
+        size in 2..=7usize,
+        fill in 0.0..=1.0f64,
+        seed in any::<u64>(),
+    ) {
+        let constraints = qcp::instance(Family::Qwh, size, fill, &mut StdRng::seed_from_u64(seed));
+        let holes: Vec<(usize, usize)> =
+            all_tiles(size).into_iter().filter(|&cell| constraints.known[cell] == 0).collect();
+        prop_assert_eq!(size * size - holes.len(), ((size * size) as f64 * fill).round() as usize);
+        for axis in [0, 1] {
+            let counts: Vec<usize> =
+                (0..size).map(|line| holes.iter().filter(|cell| [cell.0, cell.1][axis] == line).count()).collect();
+            prop_assert!(counts.iter().max().unwrap() - counts.iter().min().unwrap() <= 1, "{:?}", counts);
+        }
+        prop_assert!(!solve(&constraints, &SolverOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn qcp_instances_leave_a_candidate_to_every_empty_cell(
+        size in 2..=7usize,
+        filled in 0..=49usize,
+        seed in any::<u64>(),
+    ) {
+        let constraints = qcp::qcp(size, filled, &mut StdRng::seed_from_u64(seed));
+        let given = all_tiles(size).into_iter().filter(|&cell| constraints.known[cell] != 0).count();
+        prop_assert!(given <= filled.min(size * size));
+        let values_in = |cells: Vec<(usize, usize)>| -> Vec<u8> {
+            cells.into_iter().map(|cell| constraints.known[cell]).filter(|&value| value != 0).collect()
+        };
+        for line in 0..size {
+            let row = values_in((0..size).map(|k| (line, k)).collect());
+            let column = values_in((0..size).map(|k| (k, line)).collect());
+            for values in [row, column] {
+                prop_assert_eq!(values.iter().collect::<HashSet<_>>().len(), values.len());
+            }
+        }
+        for (i, j) in all_tiles(size).into_iter().filter(|&cell| constraints.known[cell] == 0) {
+            let mut taken = values_in((0..size).map(|k| (i, k)).collect());
+            taken.extend(values_in((0..size).map(|k| (k, j)).collect()));
+            prop_assert!((1..=size as u8).any(|value| !taken.contains(&value)), "no candidate for ({}, {})", i, j);
+        }
+
+        // The .pls format counts values from 0, with -1 for empty cells
+        let pls = qcp::format_pls(&constraints);
+        let mut lines = pls.lines();
+        let order = format!("order {}", size);
+        prop_assert_eq!(lines.next(), Some(order.as_str()));
+        let read: Vec<Vec<i32>> =
+            lines.map(|line| line.split(' ').map(|value| value.parse().unwrap()).collect()).collect();
+        let expected: Vec<Vec<i32>> =
+            (0..size).map(|i| (0..size).map(|j| constraints.known[(i, j)] as i32 - 1).collect()).collect();
+        prop_assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn lines_without_a_place_for_a_value_end_the_search_at_once(
+        (grid, clues) in puzzle(),
+        column in any::<bool>(),
+        line in any::<prop::sample::Index>(),
+        value in any::<prop::sample::Index>(),
+        options in options(),
+    ) {
+        let size = grid.size();
+        let (line, bit) = (line.index(size), 1 << value.index(size));
+        let mut constraints = constraints(&grid, &clues);
+        for k in 0..size {
+            let (i, j) = if column { (k, line) } else { (line, k) };
+            constraints.known[(i, j)] = 0;
+            constraints.wrong[i * size + j] |= bit;
+        }
+        let (solutions, stats) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, SolveHooks::default());
+        prop_assert!(solutions.is_empty());
+        prop_assert!(reference::solve(&constraints, MAX_SOLUTIONS).is_empty());
+        prop_assert_eq!((stats.backtrack_nodes, stats.guesses), (0, 0));
+    }
+
+    #[test]
+    fn any_givens_of_any_variant_agree_with_the_reference_solver(
+        variant in prop_oneof![
+            Just(Variant::Latin),
+            Just(Variant::Pandiagonal),
+            Just(Variant::NonConsecutive),
+            Just(Variant::Sudoku),
+        ],
+        givens in prop::collection::vec(prop_oneof![4 => Just(0u8), 1 => 1..=5u8], 25),
+        options in options(),
+    ) {
+        // Sudoku boxes need a size that is not prime
+        let size = if variant == Variant::Sudoku { 4 } else { 5 };
+        let mut constraints = Constraints::new(size);
+        constraints.variant = variant;
+        for (cell, &value) in all_tiles(size).into_iter().zip(&givens) {
+            constraints.known[cell] = value.min(size as u8);
+        }
+        let mut solutions = solve(&constraints, &options);
+        let mut reference_solutions = reference::solve(&constraints, MAX_SOLUTIONS);
+        prop_assert_eq!(solutions.len(), reference_solutions.len());
+        if solutions.len() < MAX_SOLUTIONS {
+            solutions.sort_by_key(|solution| format!("{:?}", solution));
+            reference_solutions.sort_by_key(|solution| format!("{:?}", solution));
+            prop_assert_eq!(solutions, reference_solutions);
+        }
+    }
+
+    #[test]
+    fn singles_are_forced_and_guesses_go_to_the_first_cell_of_fewest_candidates(
+        (grid, clues) in puzzle_of_size(2..=9),
+        techniques in prop_oneof![
+            Just(TechniqueSet::empty()),
+            Just(TechniqueSet::HIDDEN_SINGLES),
+            Just(TechniqueSet::SINGLES),
+        ],
+        kernel in prop_oneof![Just(Kernel::General), Just(Kernel::Specialized)],
+        options in options(),
+    ) {
+        let size = grid.size();
+        let constraints = constraints(&grid, &clues);
+        let options = SolverOptions { techniques, kernel, ..options };
+        let tracer = Tracer::new();
+        let hooks = SolveHooks { observer: Some(&tracer), ..SolveHooks::default() };
+        let (solutions, _) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, hooks);
+        let events = tracer.into_events();
+
+        // Cells filled before the first guess hold their value in every solution
+        let mut root = constraints.known.clone();
+        for event in &events {
+            if let TraceEvent::Propagation { node: 0, cell, value, .. } = *event {
+                prop_assert!(solutions.iter().all(|solution| usize::from(solution[cell]) == value));
+                root[cell] = value as u8;
+            }
+        }
+
+        // Singles only fill cells, so the candidates of the others are those of their row and column
+        let candidates = |(i, j): (usize, usize)| {
+            (1..=size as u8)
+                .filter(|&value| (0..size).all(|k| root[(i, k)] != value && root[(k, j)] != value))
+                .filter(|&value| constraints.wrong[i * size + j] & 1 << (value - 1) == 0)
+                .count()
+        };
+        let degree = |(i, j): (usize, usize)| {
+            (0..size).filter(|&k| k != j && root[(i, k)] == 0).count()
+                + (0..size).filter(|&k| k != i && root[(k, j)] == 0).count()
+        };
+        let empty: Vec<(usize, usize)> = all_tiles(size).into_iter().filter(|&cell| root[cell] == 0).collect();
+        let first_guess = events.iter().find_map(|event| match *event {
+            TraceEvent::Decision { parent: 0, cell, .. } => Some(cell),
+            _ => None,
+        });
+        prop_assert_eq!(first_guess.is_some(), !empty.is_empty());
+        if let Some(cell) = first_guess {
+            let fewest = empty.iter().map(|&cell| candidates(cell)).min().unwrap();
+            let ties = empty.iter().copied().filter(|&cell| candidates(cell) == fewest);
+            let expected = if options.degree_tie_break && fewest > 1 {
+                // The first cell of the highest degree among them
+                ties.rev().max_by_key(|&cell| degree(cell)).unwrap()
+            } else {
+                ties.min().unwrap()
+            };
+            prop_assert_eq!(cell, expected);
+        }
+    }
+
+    #[test]
+    fn callbacks_see_the_solutions_in_order((grid, clues) in puzzle(), options in options(), stop_after in 1..4usize) {
+        let constraints = constraints(&grid, &clues);
+        let expected = solve(&constraints, &options);
+        let mut seen = Vec::new();
+        for_each_solution(&constraints, &options, SolveHooks::default(), |solution| {
+            seen.push(solution.clone());
+            if seen.len() == stop_after { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        prop_assert_eq!(&seen[..], &expected[..stop_after.min(expected.len())]);
+    }
+
+    #[test]
+    fn cancelled_solves_stop_at_the_solutions_found_so_far(
+        (grid, clues) in puzzle(),
+        options in options(),
+        stop_after in 1..4usize,
+    ) {
+        let constraints = constraints(&grid, &clues);
+        let expected = solve(&constraints, &options);
+        let cancel = CancellationToken::new();
+        let hooks = SolveHooks { cancel: Some(&cancel), ..SolveHooks::default() };
+        let mut seen = Vec::new();
+        for_each_solution(&constraints, &options, hooks, |solution| {
+            seen.push(solution.clone());
+            if seen.len() == stop_after {
+                cancel.cancel();
+            }
+            ControlFlow::Continue(())
+        });
+        prop_assert_eq!(&seen[..], &expected[..stop_after.min(expected.len())]);
+        prop_assert_eq!(cancel.is_cancelled(), expected.len() >= stop_after);
+
+        // Solves started with a cancelled token give up at once
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let hooks = SolveHooks { cancel: Some(&cancelled), ..SolveHooks::default() };
+        prop_assert!(solve_constraints(&constraints, None, &options, hooks).0.is_empty());
+        prop_assert!(complete_latin_square_parallel(&constraints, None, &options, Some(&cancelled)).0.is_empty());
+    }
+
+    #[test]
+    fn line_feedback_pins_the_value(grid in latin_square(), cell in any::<prop::sample::Index>()) {
+        let size = grid.size();
+        let (row, col) = (cell.index(size * size) / size, cell.index(size * size) % size);
+        let value = usize::from(grid[(row, col)]);
+        let mut constraints = Constraints::new(size);
+        constraints.forbid_value_in_row(value, row, &[col]);
+        constraints.forbid_value_in_column(value, col, &[row]);
+        let solutions = solve(&constraints, &SolverOptions::default());
+        prop_assert!(solutions.len() == MAX_SOLUTIONS || solutions.contains(&grid));
+        prop_assert!(solutions.iter().all(|solution| usize::from(solution[(row, col)]) == value));
+    }
+
+    #[test]
+    fn isotopes_share_their_main_class(grid in latin_square()) {
+        let representative = classes::main_class_representative(&grid);
+        prop_assert_eq!(&representative, &classes::main_class_representative(&cyclic_latin_square(grid.size())));
+        prop_assert!(validate_latin_square(&representative).is_ok());
+    }
+
+    #[test]
+    fn row_complete_squares_have_every_pair_once(half in 1..=4usize) {
+        let size = 2 * half;
+        let grid = row_complete_latin_square(size).expect("even sizes have a row-complete square");
+        prop_assert!(validate_latin_square(&grid).is_ok());
+        let mut pairs = HashSet::new();
+        for row in grid.rows() {
+            for pair in row.windows(2) {
+                prop_assert!(pairs.insert((pair[0], pair[1])), "{:?} is side by side twice", pair);
+            }
+        }
+        prop_assert_eq!(pairs.len(), size * (size - 1));
+        prop_assert!(row_complete_latin_square(size + 1).is_none());
+    }
+
+    #[test]
+    fn symmetric_and_idempotent_squares_keep_their_shape(size in 3..=9usize) {
+        let symmetric = symmetric_latin_square(size);
+        prop_assert!(validate_latin_square(&symmetric).is_ok());
+        prop_assert!((0..size).all(|i| (0..size).all(|j| symmetric[(i, j)] == symmetric[(j, i)])));
+        let idempotent = idempotent_latin_square(size).expect("every size but 2 has an idempotent square");
+        prop_assert!(validate_latin_square(&idempotent).is_ok());
+        prop_assert!((0..size).all(|i| usize::from(idempotent[(i, i)]) == i + 1));
+    }
+
+    #[test]
+    fn pandiagonal_puzzles_agree_with_the_reference_solver(
+        clues in prop::collection::vec(clue(), 25),
+        options in options(),
+    ) {
+        let grid = pandiagonal_latin_square(5).expect("5 has no factor 2 or 3");
+        assert_variant_agrees(&grid, Variant::Pandiagonal, &clues, &options)?;
+    }
+
+    #[test]
+    fn non_consecutive_puzzles_agree_with_the_reference_solver(
+        clues in prop::collection::vec(clue(), 25),
+        options in options(),
+    ) {
+        let grid = non_consecutive_latin_square(5).expect("5 has a non-consecutive square");
+        assert_variant_agrees(&grid, Variant::NonConsecutive, &clues, &options)?;
+    }
+
+    #[test]
+    fn sudoku_puzzles_agree_with_the_reference_solver(
+        clues in prop::collection::vec(clue(), 36),
+        options in options(),
+    ) {
+        assert_variant_agrees(&sudoku_latin_square(6), Variant::Sudoku, &clues, &options)?;
+    }
+
+    #[test]
+    fn hex_sudoku_puzzles_agree_with_the_reference_solver(
+        symbols in Just((1..=16u8).collect::<Vec<_>>()).prop_shuffle(),
+        clues in prop::collection::vec(prop_oneof![7 => Just(Clue::Known), 1 => clue()], 256),
+        options in options(),
+    ) {
+        let mut grid = sudoku_latin_square(16);
+        for cell in all_tiles(16) {
+            grid[cell] = symbols[usize::from(grid[cell]) - 1];
+        }
+        // Values 10 to 16 are written A to G, and read back in either case
+        let text = output::format_grid(&grid);
+        prop_assert!(text.contains('A') && text.contains('G'));
+        prop_assert_eq!(output::parse_grid(&text), Ok(grid.clone()));
+        prop_assert_eq!(output::parse_grid(&text.to_lowercase()), Ok(grid.clone()));
+        prop_assert!((1..=16).all(|value| output::parse_value(output::value_char(value)) == Some(value)));
+        assert_variant_agrees(&grid, Variant::Sudoku, &clues, &options)?;
+    }
+
+    #[test]
+    fn profiles_count_every_node_of_the_search((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let observer = CountingObserver::default();
+        let hooks = SolveHooks { observer: Some(&observer), ..SolveHooks::default() };
+        let (solutions, _) = solve_constraints(&constraints, Some(2), &options, hooks);
+        let (profiled, _, profile) = profile::profile_solve(&constraints, &options);
+        prop_assert_eq!(&profiled, &solutions);
+        let nodes: u64 = profile.depths.iter().map(|depth| depth.nodes).sum();
+        prop_assert_eq!(nodes, observer.entered.get() as u64 + 1);
+        let found: u64 = profile.depths.iter().map(|depth| depth.solutions).sum();
+        prop_assert_eq!(found, solutions.len() as u64);
+        let reasons = profile.reasons();
+        let decisions: u64 =
+            [Reason::Guess, Reason::Forced].iter().filter_map(|reason| reasons.get(reason)).map(|c| c.steps).sum();
+        prop_assert_eq!(decisions, observer.entered.get() as u64);
+        prop_assert!(reasons.values().map(|counters| counters.time).sum::<std::time::Duration>() <= profile.time());
+    }
+
+    #[test]
+    fn observers_do_not_change_the_search((grid, clues) in puzzle(), options in options()) {
+        let constraints = constraints(&grid, &clues);
+        let observer = CountingObserver::default();
+        let hooks = SolveHooks { observer: Some(&observer), ..SolveHooks::default() };
+        let (solutions, stats) = solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, hooks);
+        let (unobserved, unobserved_stats) =
+            solve_constraints(&constraints, Some(MAX_SOLUTIONS), &options, SolveHooks::default());
+        prop_assert_eq!(&solutions, &unobserved);
+        prop_assert_eq!(stats.backtrack_nodes, unobserved_stats.backtrack_nodes);
+        prop_assert_eq!(observer.entered.get(), observer.exited.get());
+        prop_assert!(stats.backtrack_nodes <= observer.entered.get() + 1);
+        prop_assert_eq!(&*observer.solutions.borrow(), &solutions);
+    }
+}
+
+proptest! {
+    // Each case starts a server, which runs until the tests end
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn servers_turn_away_connections_beyond_the_maximum(size in 3..=6usize) {
+        let (addr, held) = start_server(SolverOptions::default(), None, 1)?;
+        let mut turned_away = String::new();
+        std::net::TcpStream::connect(addr)
+            .and_then(|mut stream| std::io::Read::read_to_string(&mut stream, &mut turned_away))
+            .map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert!(turned_away.starts_with("HTTP/1.1 503"), "{}", turned_away);
+
+        // The connection holding the slot is still answered, then gives it back
+        let clues = output::encode_clues(&all_tiles(size)[2 * size..]);
+        let solved = http_get(held, &format!("/solve?size={}&clues={}", size, clues))?;
+        prop_assert!(solved.starts_with("HTTP/1.1 200") && solved.contains("\"unique\":true"), "{}", solved);
+        // Turned away connections may be reset before their request is read
+        let mut health = None;
+        for _ in 0..100 {
+            health = std::net::TcpStream::connect(addr).ok().and_then(|stream| http_get(stream, "/health").ok());
+            if health.as_ref().is_some_and(|health| health.starts_with("HTTP/1.1 200")) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        prop_assert!(health.as_ref().is_some_and(|health| health.ends_with("ok")), "{:?}", health);
+    }
+
+    #[test]
+    fn server_metrics_count_the_requests_answered(size in 3..=6usize, solves in 1..=4usize, unknown in 0..=3usize) {
+        let (addr, first) = start_server(SolverOptions::default(), None, 8)?;
+        let get = |path: &str| http_get(std::net::TcpStream::connect(addr).unwrap(), path);
+        prop_assert!(http_get(first, "/health")?.ends_with("ok"));
+        let clues = output::encode_clues(&all_tiles(size)[2 * size..]);
+        for _ in 0..solves {
+            get(&format!("/solve?size={}&clues={}", size, clues))?;
+        }
+        get("/solve?size=0")?;
+        for index in 0..unknown {
+            get(&format!("/unknown/{}", index))?;
+        }
+
+        let metrics = get("/metrics")?;
+        let requests = |labels: &str| metric(&metrics, &format!("sudodle_http_requests_total{{{}}}", labels));
+        prop_assert_eq!(requests("path=\"/health\",code=\"200\""), Some(1.0));
+        prop_assert_eq!(requests("path=\"/solve\",code=\"200\""), Some(solves as f64));
+        prop_assert_eq!(requests("path=\"/solve\",code=\"400\""), Some(1.0));
+        prop_assert_eq!(requests("path=\"other\",code=\"404\""), (unknown > 0).then_some(unknown as f64));
+        // Requests are counted once answered, so this one is not yet
+        prop_assert_eq!(requests("path=\"/metrics\",code=\"200\""), None);
+        let latencies = "sudodle_http_request_duration_seconds_count{path=\"/solve\"}";
+        prop_assert_eq!(metric(&metrics, latencies), Some(solves as f64 + 1.0));
+        prop_assert_eq!(metric(&metrics, "sudodle_solver_nodes_count"), Some(solves as f64));
+        prop_assert!(!metrics.contains("sudodle_pool_"));
+    }
+
+    #[test]
+    fn cluster_searches_find_the_puzzles_of_single_searches_despite_failed_workers(
+        placed in 3..=5usize,
+        runs in prop_oneof![Just(None), Just(Some(40u64))],
+        generator_seed in any::<u64>(),
+        range_size in 50..=2000u64,
+        workers in 1..=3usize,
+        stalls in any::<bool>(),
+    ) {
+        let generator = if runs.is_some() { generators::Generator::TopDown } else { generators::Generator::BottomUp };
+        let job = Job {
+            size: 4,
+            placed,
+            semantics: ClueSemantics::Sudodle,
+            base_grid: Default::default(),
+            generator,
+            generator_seed,
+            runs,
+            require_singles_solvable: false,
+            rules_version: output::RULES_VERSION,
+        };
+        let range_size = if runs.is_some() { range_size / 50 } else { range_size };
+        let options = CoordinatorOptions {
+            job: job.clone(),
+            range_size,
+            lease_timeout: std::time::Duration::from_millis(100),
+        };
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").and_then(|listener| listener.local_addr()).unwrap();
+        let path = temp_path("cluster.txt");
+        let out_file = path.to_str().unwrap().to_string();
+        let coordinator = std::thread::spawn(move || {
+            let header = FileHeader::new(4, ClueSemantics::Sudodle);
+            let sink = output::create_file_sink(&out_file, OutputFormat::Text, None, false, &header).unwrap();
+            cluster::coordinate(&addr.to_string(), options, &mut [sink])
+        });
+
+        // A worker taking a range, then leaving without returning it or holding it past its lease
+        let mut failing = None;
+        for _ in 0..100 {
+            failing = std::net::TcpStream::connect(addr).ok();
+            if failing.is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let failing = failing.ok_or_else(|| TestCaseError::fail("the coordinator did not start"))?;
+        std::io::Write::write_all(&mut &failing, b"{\"type\":\"ready\"}\n").unwrap();
+        let mut work = String::new();
+        std::io::BufRead::read_line(&mut std::io::BufReader::new(&failing), &mut work).unwrap();
+        prop_assert!(work.contains("\"type\":\"work\""), "{}", work);
+        let failing = std::thread::spawn(move || {
+            if stalls {
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+            drop(failing);
+        });
+        let workers: Vec<_> = (0..workers)
+            .map(|_| std::thread::spawn(move || cluster::work(&addr.to_string(), SolverOptions::default())))
+            .collect();
+        let searched: usize = workers.into_iter().map(|worker| worker.join().unwrap().unwrap().0).sum();
+        failing.join().unwrap();
+        let summary = coordinator.join().unwrap().unwrap();
+        let mut found: Vec<_> = output::read_puzzle_tiles(path.to_str().unwrap())
+            .unwrap()
+            .iter()
+            .map(|tiles| standardize_tile_tuple(tiles))
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        let random_tries = runs.map(|runs| runs as usize);
+        let options = SearchOptions { random_tries, generator, generator_seed, ..SearchOptions::default() };
+        let single = find_single_solution_puzzles(4, placed, options, Vec::new(), HashSet::new());
+        let mut expected: Vec<_> = single.puzzles.iter().map(|puzzle| standardize_tile_tuple(&puzzle.tiles)).collect();
+        found.sort();
+        expected.sort();
+        prop_assert_eq!(found, expected);
+        prop_assert_eq!(summary.ranges, job.total().div_ceil(range_size));
+        prop_assert!(searched as u64 >= summary.ranges);
+        prop_assert_eq!(summary.combinations as usize, single.summary.combinations_processed);
+        prop_assert!(summary.reissued >= 1);
+        prop_assert_eq!(summary.unique_puzzles, single.puzzles.len());
+    }
+
+    #[test]
+    fn pooled_puzzles_are_unique_puzzles_of_their_level_served_once(target in 1..=3usize, seed in any::<u64>()) {
+        // 5x5 puzzles of greedy runs fill every level with these thresholds
+        let options = PoolOptions {
+            sizes: vec![5],
+            target,
+            hard_from: 1,
+            expert_from: 2,
+            seed,
+            families: false,
+            solver: SolverOptions::default(),
+        };
+        let (addr, first) = start_server(SolverOptions::default(), Some(PuzzlePool::new(options)), 8)?;
+        let get = |path: &str| http_get(std::net::TcpStream::connect(addr).unwrap(), path);
+        let pooled = |metrics: &str, series: &str, level: &str| -> Option<usize> {
+            let prefix = format!("{}{{size=\"5\",level=\"{}\"}} ", series, level);
+            metrics.lines().find_map(|line| line.strip_prefix(&prefix)).and_then(|value| value.parse().ok())
+        };
+        let levels = ["normal", "hard", "expert"];
+        let mut metrics = http_get(first, "/metrics")?;
+        for _ in 0..3000 {
+            if levels.iter().all(|level| pooled(&metrics, "sudodle_pool_puzzles", level) == Some(target)) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            metrics = get("/metrics")?;
+        }
+        prop_assert!(metrics.starts_with("HTTP/1.1 200"), "{}", metrics);
+
+        let mut served = HashSet::new();
+        for (expected, level) in levels.iter().enumerate() {
+            for _ in 0..target {
+                let response = get(&format!("/puzzle?size=5&level={}", level))?;
+                prop_assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+                let body: serde_json::Value = serde_json::from_str(response.split_once("\r\n\r\n").unwrap().1).unwrap();
+                let tiles = output::decode_clues(body["clues"].as_str().unwrap());
+                let checked = check_puzzle(&PuzzleSpec::new(5, &tiles), &SolverOptions::default());
+                prop_assert!(checked.is_ok(), "{} is not unique", body);
+                let difficulty = checked.unwrap().stats.difficulty();
+                prop_assert_eq!(body["difficulty"].as_u64(), Some(difficulty as u64));
+                prop_assert_eq!(difficulty.min(2), expected);
+                prop_assert_eq!(body["id"].as_str().map(str::to_string), output::encode_puzzle_id(5, &tiles));
+                let content_id = FileHeader::new(5, ClueSemantics::Sudodle).content_id(&tiles);
+                prop_assert_eq!(body["content_id"].as_str(), Some(content_id.as_str()));
+                prop_assert!(served.insert(tiles), "{} served twice", body);
+            }
+        }
+        let metrics = get("/metrics")?;
+        prop_assert!(levels.iter().all(|level| pooled(&metrics, "sudodle_pool_served_total", level) == Some(target)));
+
+        for query in ["size=4", "size=5&level=easy", "level=hard"] {
+            let response = get(&format!("/puzzle?{}", query))?;
+            prop_assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        }
+        let (_, without_pool) = start_server(SolverOptions::default(), None, 8)?;
+        prop_assert!(http_get(without_pool, "/puzzle?size=5")?.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,263 @@
+//! Instrumentation of the solver, and recording of its search tree for
+//! visualizing why some puzzles need many more search nodes than others.
+//!
+//! The solver tells a [`SearchObserver`] of each step of its search, and
+//! [`Tracer`] is the observer recording them. A trace is a sequence of [`TraceEvent`]s stored as JSON lines. Each decision
+//! of the backtracker opens a new node of the search tree; propagation events,
+//! dead ends and solutions belong to the node that was current when they happened.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Grid;
+
+/// Why a cell was filled or a branch abandoned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// One of several candidates of the most constrained cell was tried.
+    Guess,
+    /// The most constrained cell had a single candidate.
+    Forced,
+    /// Propagation: the cell had a single candidate left.
+    NakedSingle,
+    /// Propagation: the value fitted in a single cell of its row.
+    HiddenSingleRow,
+    /// Propagation: the value fitted in a single cell of its column.
+    HiddenSingleColumn,
+    /// Propagation: the value fitted in a single cell of its box, in the
+    /// Sudoku variant.
+    HiddenSingleBox,
+    /// Propagation: the cell had a single candidate left after subset or
+    /// X-wing eliminations.
+    Elimination,
+    /// Propagation: a clause learned from an earlier conflict, with
+    /// [`crate::SolverOptions::learning`], left the cell a single value.
+    Learned,
+    /// Some empty cell has no candidate left.
+    NoCandidates,
+    /// Propagation or the validity checks found the grid cannot be completed.
+    Contradiction,
+}
+
+impl Reason {
+    /// Name of the reason in traces.
+    pub fn name(self) -> &'static str {
+        match self {
+            Reason::Guess => "guess",
+            Reason::Forced => "forced",
+            Reason::NakedSingle => "naked_single",
+            Reason::HiddenSingleRow => "hidden_single_row",
+            Reason::HiddenSingleColumn => "hidden_single_column",
+            Reason::HiddenSingleBox => "hidden_single_box",
+            Reason::Elimination => "elimination",
+            Reason::Learned => "learned",
+            Reason::NoCandidates => "no_candidates",
+            Reason::Contradiction => "contradiction",
+        }
+    }
+}
+
+/// A single step of the search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// The backtracker placed `value` in `cell`, opening `node`.
+    Decision {
+        node: usize,
+        parent: usize,
+        depth: usize,
+        cell: (usize, usize),
+        value: usize,
+        reason: Reason,
+    },
+    /// Constraint propagation filled `cell` within `node`.
+    Propagation {
+        node: usize,
+        depth: usize,
+        cell: (usize, usize),
+        value: usize,
+        reason: Reason,
+    },
+    /// The search gave up on `node`.
+    DeadEnd { node: usize, depth: usize, reason: Reason },
+    /// `node` is a complete Latin square.
+    Solution { node: usize, depth: usize },
+}
+
+/// Callbacks of the solver on the steps of its search, installed with
+/// [`crate::SolveHooks::observer`].
+///
+/// Every method does nothing by default, so that observers only implement
+/// the steps they need. Calls come from the thread running the search, in
+/// its order: the steps between [`SearchObserver::enter_node`] and the
+/// matching [`SearchObserver::exit_node`] belong to that node.
+pub trait SearchObserver {
+    /// The backtracker placed `value` in `cell`, opening a node at `depth`.
+    fn enter_node(&self, _cell: (usize, usize), _value: usize, _depth: usize, _reason: Reason) {}
+
+    /// The backtracker is done with the node at `depth`, and returns to its parent.
+    fn exit_node(&self, _depth: usize) {}
+
+    /// Constraint propagation filled `cell` with `value` in the current node.
+    fn propagate(&self, _cell: (usize, usize), _value: usize, _reason: Reason) {}
+
+    /// The current node cannot be completed.
+    fn contradiction(&self, _reason: Reason) {}
+
+    /// The current node is a complete solution.
+    fn solution(&self, _solution: &Grid) {}
+}
+
+/// Collects trace events while the solver runs.
+///
+/// Node 0 is the initial grid; decisions are numbered in the order they are made.
+#[derive(Default)]
+pub struct Tracer {
+    events: RefCell<Vec<TraceEvent>>,
+    node_count: Cell<usize>,
+    current_node: Cell<usize>,
+    current_depth: Cell<usize>,
+    /// Parents of the nodes opened and not exited yet, innermost last.
+    parents: RefCell<Vec<usize>>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_events(self) -> Vec<TraceEvent> {
+        self.events.into_inner()
+    }
+}
+
+impl SearchObserver for Tracer {
+    /// Record a decision and make its node current.
+    fn enter_node(&self, cell: (usize, usize), value: usize, depth: usize, reason: Reason) {
+        let parent = self.current_node.get();
+        let node = self.node_count.get() + 1;
+        self.node_count.set(node);
+        self.events.borrow_mut().push(TraceEvent::Decision { node, parent, depth, cell, value, reason });
+        self.parents.borrow_mut().push(parent);
+        self.current_node.set(node);
+        self.current_depth.set(depth);
+    }
+
+    /// Return to the parent of the current node.
+    fn exit_node(&self, depth: usize) {
+        self.current_node.set(self.parents.borrow_mut().pop().unwrap_or(0));
+        self.current_depth.set(depth.saturating_sub(1));
+    }
+
+    fn propagate(&self, cell: (usize, usize), value: usize, reason: Reason) {
+        let (node, depth) = (self.current_node.get(), self.current_depth.get());
+        self.events.borrow_mut().push(TraceEvent::Propagation { node, depth, cell, value, reason });
+    }
+
+    fn contradiction(&self, reason: Reason) {
+        let (node, depth) = (self.current_node.get(), self.current_depth.get());
+        self.events.borrow_mut().push(TraceEvent::DeadEnd { node, depth, reason });
+    }
+
+    fn solution(&self, _solution: &Grid) {
+        let (node, depth) = (self.current_node.get(), self.current_depth.get());
+        self.events.borrow_mut().push(TraceEvent::Solution { node, depth });
+    }
+}
+
+/// Write events to `path`, one JSON object per line.
+pub fn write_jsonl(path: &str, events: &[TraceEvent]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Read events written by [`write_jsonl`].
+pub fn read_jsonl(path: &str) -> io::Result<Vec<TraceEvent>> {
+    parse_jsonl(BufReader::new(File::open(path)?), path)
+}
+
+/// Parse events written by [`write_jsonl`] from `reader`, naming `source` in error messages.
+pub fn parse_jsonl(reader: impl BufRead, source: &str) -> io::Result<Vec<TraceEvent>> {
+    let mut events = Vec::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", source, line_number + 1, e))
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Render the search tree of a trace as a Graphviz DOT graph.
+///
+/// Each node is labelled with the decision that opened it and the number of
+/// cells propagation filled in it; guesses are drawn as solid edges and forced
+/// moves as dashed ones. Dead ends are red and solutions green.
+pub fn to_dot(events: &[TraceEvent]) -> String {
+    #[derive(Default)]
+    struct Node {
+        label: String,
+        propagated: usize,
+        color: Option<&'static str>,
+    }
+    // Keyed by node number, which comes from the trace file and may be arbitrary
+    let mut nodes = BTreeMap::new();
+    nodes.insert(0, Node { label: "start".to_string(), ..Node::default() });
+    let mut edges = Vec::new();
+    for event in events {
+        match *event {
+            TraceEvent::Decision { node, parent, cell, value, reason, .. } => {
+                nodes.entry(node).or_default().label = format!("({},{})={}", cell.0, cell.1, value);
+                edges.push((parent, node, reason == Reason::Guess));
+            }
+            TraceEvent::Propagation { node, .. } => {
+                if let Some(node) = nodes.get_mut(&node) {
+                    node.propagated += 1;
+                }
+            }
+            TraceEvent::DeadEnd { node, .. } => {
+                if let Some(node) = nodes.get_mut(&node) {
+                    node.color = Some("lightcoral");
+                }
+            }
+            TraceEvent::Solution { node, .. } => {
+                if let Some(node) = nodes.get_mut(&node) {
+                    node.color = Some("palegreen");
+                }
+            }
+        }
+    }
+
+    let mut dot = String::from("digraph search {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for (id, node) in &nodes {
+        let mut label = node.label.clone();
+        if node.propagated > 0 {
+            label.push_str(&format!("\\n+{} propagated", node.propagated));
+        }
+        let style = match node.color {
+            Some(color) => format!(", style=filled, fillcolor={}", color),
+            None => String::new(),
+        };
+        let _ = writeln!(dot, "    n{} [label=\"{}\"{}];", id, label, style);
+    }
+    for (parent, node, guess) in edges {
+        let style = if guess { "" } else { " [style=dashed]" };
+        let _ = writeln!(dot, "    n{} -> n{}{};", parent, node, style);
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,82 @@
+//! Witnesses that a puzzle is not unique: two of its solutions, and the
+//! cells on which they differ. Swapping the values of these cells turns one
+//! solution into the other, so they form a trade, and a clue on any of them
+//! rules one of the two solutions out. The smallest witnesses are often
+//! intercalates (two rows and two columns holding two values) or the cycles
+//! of a pair of rows or columns, see [`crate::cycles`].
+
+use crate::{solve_constraints, Constraints, Grid, SolveHooks, SolverOptions};
+
+/// Default number of solutions compared to find a small witness.
+pub const DEFAULT_WITNESS_SOLUTIONS: usize = 200;
+
+/// Cells on which two solutions of a puzzle differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness {
+    /// The differing cells, row by row, with their values in both solutions.
+    pub cells: Vec<((usize, usize), u8, u8)>,
+}
+
+impl Witness {
+    /// Cells of the witness and their values in two grids.
+    pub fn between(first: &Grid, second: &Grid) -> Self {
+        let size = first.size();
+        let cells = (0..size)
+            .flat_map(|r| (0..size).map(move |c| (r, c)))
+            .filter(|&cell| first[cell] != second[cell])
+            .map(|cell| (cell, first[cell], second[cell]))
+            .collect();
+        Self { cells }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Shape of the witness: an intercalate, a cycle of two rows or of two
+    /// columns, or another trade.
+    pub fn kind(&self) -> &'static str {
+        let mut rows: Vec<usize> = self.cells.iter().map(|&((r, _), _, _)| r).collect();
+        let mut columns: Vec<usize> = self.cells.iter().map(|&((_, c), _, _)| c).collect();
+        for lines in [&mut rows, &mut columns] {
+            lines.sort_unstable();
+            lines.dedup();
+        }
+        match (rows.len(), columns.len()) {
+            (2, 2) => "intercalate",
+            (2, _) => "row cycle",
+            (_, 2) => "column cycle",
+            _ => "trade",
+        }
+    }
+}
+
+/// The smallest witness between two of `solutions`, or `None` if there are
+/// fewer than two.
+pub fn smallest_witness(solutions: &[Grid]) -> Option<Witness> {
+    let mut smallest: Option<Witness> = None;
+    for (index, first) in solutions.iter().enumerate() {
+        for second in &solutions[index + 1..] {
+            let witness = Witness::between(first, second);
+            if smallest.as_ref().is_none_or(|smallest| witness.len() < smallest.len()) {
+                smallest = Some(witness);
+            }
+        }
+    }
+    smallest
+}
+
+/// The smallest witness between the first `max_solutions` solutions of the
+/// puzzle of `constraints` found by the solver, or `None` if it is unique.
+///
+/// The smallest trade between two solutions may involve solutions found
+/// later, so the witness is small rather than always the smallest.
+pub fn find_witness(constraints: &Constraints, max_solutions: usize) -> Option<Witness> {
+    let (solutions, _) =
+        solve_constraints(constraints, Some(max_solutions.max(2)), &SolverOptions::default(), SolveHooks::default());
+    smallest_witness(&solutions)
+}